@@ -0,0 +1,32 @@
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Bake the git commit and build time into the binary for `/api/version`
+fn main() {
+    if let Some(sha) = git_sha() {
+        println!("cargo:rustc-env=DAVINCI3_GIT_SHA={}", sha);
+    }
+
+    // Reproducible builds pin the build time through SOURCE_DATE_EPOCH
+    let build_time = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<u64>().ok())
+        .or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs()));
+    if let Some(build_time) = build_time {
+        println!("cargo:rustc-env=DAVINCI3_BUILD_TIMESTAMP={}", build_time);
+    }
+
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+}
+
+/// Short hash of the checked out commit, when building from a git checkout
+fn git_sha() -> Option<String> {
+    let output = Command::new("git").args(["rev-parse", "--short=12", "HEAD"]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let sha = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!sha.is_empty()).then_some(sha)
+}
@@ -0,0 +1,15 @@
+#![no_main]
+
+use davinci3_wiki::parser::{clean_wiki_text, CleanOptions};
+use libfuzzer_sys::fuzz_target;
+
+// Run with `cargo +nightly fuzz run clean_wiki_text -- -max_total_time=60`;
+// libFuzzer reports any panic, and `-timeout` catches inputs that hang.
+fuzz_target!(|text: &str| {
+    let cleaned = clean_wiki_text(text);
+    // Every step only removes markup, so cleaning never grows the text
+    assert!(cleaned.len() <= text.len());
+
+    let all_steps = CleanOptions { strip_refs: true, ..Default::default() };
+    all_steps.clean(text);
+});
@@ -0,0 +1,112 @@
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use warp::filters::BoxedFilter;
+use warp::reject::{self, Rejection};
+use warp::reply::Reply;
+use warp::Filter;
+
+/// Rejection used when every request permit is in use; answered with 503
+#[derive(Debug)]
+pub struct ServerBusy;
+
+impl reject::Reject for ServerBusy {}
+
+/// Caps the number of requests being handled at once across the whole server
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLimiter {
+    semaphore: Arc<Semaphore>,
+}
+
+impl ConcurrencyLimiter {
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_concurrent)),
+        }
+    }
+
+    /// Permits not currently held by an in-flight request
+    pub fn available_permits(&self) -> usize {
+        self.semaphore.available_permits()
+    }
+
+    /// Create a warp filter that holds a permit while `route` handles the request,
+    /// rejecting with [`ServerBusy`] when none is free
+    pub fn with_concurrency_limit<T: Reply + Send + 'static>(
+        &self,
+        route: BoxedFilter<(T,)>,
+    ) -> BoxedFilter<(T,)> {
+        let semaphore = self.semaphore.clone();
+
+        warp::any()
+            .and_then(move || {
+                let semaphore = semaphore.clone();
+                async move {
+                    semaphore
+                        .try_acquire_owned()
+                        .map_err(|_| reject::custom(ServerBusy))
+                }
+            })
+            .and(route)
+            .map(|permit: OwnedSemaphorePermit, reply: T| {
+                // The permit is released once the route has produced its reply
+                drop(permit);
+                reply
+            })
+            .boxed()
+    }
+}
+
+/// Warp filter that applies a global concurrency limit to a route
+pub fn with_concurrency_limit<T: Reply + Send + 'static>(
+    limiter: &ConcurrencyLimiter,
+    route: BoxedFilter<(T,)>,
+) -> BoxedFilter<(T,)> {
+    limiter.with_concurrency_limit(route)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::error_handler::handle_rejection;
+    use warp::http::StatusCode;
+    use warp::test::request;
+
+    #[tokio::test]
+    async fn test_requests_beyond_permits_get_503() {
+        let limiter = ConcurrencyLimiter::new(2);
+        // Slow handlers block until the gate is opened
+        let gate = Arc::new(Semaphore::new(0));
+        let handler_gate = gate.clone();
+        let route = warp::any()
+            .and_then(move || {
+                let gate = handler_gate.clone();
+                async move {
+                    gate.acquire().await.unwrap().forget();
+                    Ok::<_, Rejection>("done")
+                }
+            })
+            .boxed();
+        let limited = limiter.with_concurrency_limit(route).recover(handle_rejection);
+
+        let slow: Vec<_> = (0..2)
+            .map(|_| {
+                let limited = limited.clone();
+                tokio::spawn(async move { request().path("/").reply(&limited).await })
+            })
+            .collect();
+        while limiter.available_permits() > 0 {
+            tokio::task::yield_now().await;
+        }
+
+        let busy = request().path("/").reply(&limited).await;
+        assert_eq!(busy.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        gate.add_permits(2);
+        for handle in slow {
+            assert_eq!(handle.await.unwrap().status(), StatusCode::OK);
+        }
+        assert_eq!(limiter.available_permits(), 2);
+        gate.add_permits(1);
+        assert_eq!(request().path("/").reply(&limited).await.status(), StatusCode::OK);
+    }
+}
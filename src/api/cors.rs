@@ -0,0 +1,118 @@
+use std::sync::Arc;
+use arc_swap::ArcSwap;
+use warp::filters::BoxedFilter;
+use warp::http::header::{
+    HeaderValue, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN,
+    ACCESS_CONTROL_MAX_AGE, VARY,
+};
+use warp::http::{Method, StatusCode};
+use warp::reject::{self, Reject};
+use warp::reply::Response;
+use warp::{Filter, Rejection, Reply};
+
+/// Origins allowed to make cross-origin requests, swappable while the server runs
+pub type AllowedOrigins = Arc<ArcSwap<Vec<String>>>;
+
+/// Methods a preflight may ask for
+const ALLOWED_METHODS: [&str; 3] = ["GET", "POST", "OPTIONS"];
+
+/// Request headers a preflight may ask for, lowercase
+const ALLOWED_HEADERS: [&str; 2] = ["content-type", "authorization"];
+
+/// How long browsers may cache a preflight answer (24 hours)
+const MAX_AGE_SECS: u64 = 86400;
+
+/// Cross-origin request from an origin, or asking for a method or header, that isn't allowed
+#[derive(Debug)]
+pub struct CorsForbidden;
+
+impl Reject for CorsForbidden {}
+
+/// Wrap `route` with CORS handling against the origins currently in `origins`.
+///
+/// Preflights are answered here without reaching `route`; other requests from
+/// allowed origins get `Access-Control-Allow-Origin` on their reply. Requests
+/// from other origins are rejected with [`CorsForbidden`].
+pub fn with_cors<T: Reply + Send + 'static>(
+    origins: AllowedOrigins,
+    route: BoxedFilter<(T,)>,
+) -> BoxedFilter<(Response,)> {
+    let preflight_origins = origins.clone();
+    // Other methods reject as not found rather than through `warp::options()`, whose
+    // MethodNotAllowed would outrank the routes' NotFound and turn every 404 into a 405
+    let preflight = warp::method()
+        .and_then(|method: Method| async move {
+            if method == Method::OPTIONS {
+                Ok(())
+            } else {
+                Err(reject::not_found())
+            }
+        })
+        .untuple_one()
+        .and(warp::header::<String>("origin"))
+        .and(warp::header::<String>("access-control-request-method"))
+        .and(warp::header::optional::<String>("access-control-request-headers"))
+        .map(move |origin: String, method: String, headers: Option<String>| {
+            let allowed = is_allowed_origin(&preflight_origins, &origin)
+                && ALLOWED_METHODS.contains(&method.as_str())
+                && headers.as_deref().is_none_or(headers_allowed);
+            if allowed {
+                Ok(preflight_response(&origin))
+            } else {
+                Err(reject::custom(CorsForbidden))
+            }
+        });
+
+    let request = warp::header::optional::<String>("origin")
+        .and_then(move |origin: Option<String>| {
+            let allowed = origin.as_deref().is_none_or(|origin| is_allowed_origin(&origins, origin));
+            async move {
+                if allowed {
+                    Ok(origin)
+                } else {
+                    Err(reject::custom(CorsForbidden))
+                }
+            }
+        })
+        .and(route)
+        .map(|origin: Option<String>, reply: T| {
+            let mut response = reply.into_response();
+            if let Some(origin) = origin.and_then(|origin| HeaderValue::from_str(&origin).ok()) {
+                response.headers_mut().insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+                response.headers_mut().append(VARY, HeaderValue::from_static("origin"));
+            }
+            Ok(response)
+        });
+
+    // A matched preflight is answered even when forbidden, instead of falling through to the routes
+    preflight
+        .or(request)
+        .unify()
+        .and_then(|result: Result<Response, Rejection>| async move { result })
+        .boxed()
+}
+
+fn is_allowed_origin(origins: &AllowedOrigins, origin: &str) -> bool {
+    origins.load().iter().any(|allowed| allowed == origin)
+}
+
+fn headers_allowed(requested: &str) -> bool {
+    requested
+        .split(',')
+        .map(|header| header.trim())
+        .filter(|header| !header.is_empty())
+        .all(|header| ALLOWED_HEADERS.contains(&header.to_ascii_lowercase().as_str()))
+}
+
+fn preflight_response(origin: &str) -> Response {
+    let mut response = StatusCode::OK.into_response();
+    let headers = response.headers_mut();
+    if let Ok(origin) = HeaderValue::from_str(origin) {
+        headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+    }
+    headers.insert(ACCESS_CONTROL_ALLOW_METHODS, HeaderValue::from_static("GET, POST, OPTIONS"));
+    headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, HeaderValue::from_static("content-type, authorization"));
+    headers.insert(ACCESS_CONTROL_MAX_AGE, HeaderValue::from(MAX_AGE_SECS));
+    headers.append(VARY, HeaderValue::from_static("origin"));
+    response
+}
@@ -1,20 +1,70 @@
 use std::convert::Infallible;
-use warp::{Rejection, Reply, http::StatusCode};
+use warp::{Rejection, Reply, http::{header, HeaderValue, StatusCode}, reject};
 
+use super::concurrency::ServerBusy;
+use super::cors::CorsForbidden;
 use super::validation::{ValidationError, ValidationErrorResponse};
 
+/// Rejection for a known path requested with a method it does not support
+#[derive(Debug)]
+pub struct WrongMethod {
+    /// Methods the path does support, for the `Allow` header
+    pub allow: &'static [&'static str],
+}
+
+impl reject::Reject for WrongMethod {}
+
+/// Rejection for semantic endpoints when the vector store holds no embeddings
+#[derive(Debug)]
+pub struct SemanticSearchUnavailable;
+
+impl reject::Reject for SemanticSearchUnavailable {}
+
+/// Rejection for a request the database failed to answer, as opposed to one for a missing resource
+#[derive(Debug)]
+pub struct DatabaseFailure;
+
+impl reject::Reject for DatabaseFailure {}
+
 /// Handle rejections, including custom validation errors
 pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible> {
     let code;
     let message;
     let status;
     let field;
+    let mut allow = None;
     
     if err.is_not_found() {
         code = StatusCode::NOT_FOUND;
         message = "Not Found".to_string();
         status = "error".to_string();
         field = None;
+    } else if err.find::<ServerBusy>().is_some() {
+        code = StatusCode::SERVICE_UNAVAILABLE;
+        message = "Server is busy, please retry shortly".to_string();
+        status = "error".to_string();
+        field = None;
+    } else if err.find::<CorsForbidden>().is_some() {
+        code = StatusCode::FORBIDDEN;
+        message = "CORS request forbidden".to_string();
+        status = "error".to_string();
+        field = None;
+    } else if err.find::<SemanticSearchUnavailable>().is_some() {
+        code = StatusCode::SERVICE_UNAVAILABLE;
+        message = "Semantic search unavailable; run reembed to generate embeddings".to_string();
+        status = "error".to_string();
+        field = None;
+    } else if err.find::<DatabaseFailure>().is_some() {
+        code = StatusCode::INTERNAL_SERVER_ERROR;
+        message = "Database error".to_string();
+        status = "error".to_string();
+        field = None;
+    } else if let Some(e) = err.find::<WrongMethod>() {
+        code = StatusCode::METHOD_NOT_ALLOWED;
+        message = "Method not allowed".to_string();
+        status = "error".to_string();
+        field = None;
+        allow = Some(e.allow.join(", "));
     } else if let Some(e) = err.find::<ValidationError>() {
         code = StatusCode::BAD_REQUEST;
         message = e.message.clone();
@@ -25,6 +75,21 @@ pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible>
         message = format!("Invalid request data: {}", e);
         status = "error".to_string();
         field = None;
+    } else if let Some(e) = err.find::<warp::reject::InvalidQuery>() {
+        code = StatusCode::BAD_REQUEST;
+        message = format!("Invalid query parameters: {}", e);
+        status = "error".to_string();
+        field = None;
+    } else if err.find::<warp::reject::PayloadTooLarge>().is_some() {
+        code = StatusCode::PAYLOAD_TOO_LARGE;
+        message = "Request body too large".to_string();
+        status = "error".to_string();
+        field = None;
+    } else if err.find::<warp::reject::LengthRequired>().is_some() {
+        code = StatusCode::LENGTH_REQUIRED;
+        message = "Content-Length header is required".to_string();
+        status = "error".to_string();
+        field = None;
     } else if let Some(_) = err.find::<warp::reject::MethodNotAllowed>() {
         code = StatusCode::METHOD_NOT_ALLOWED;
         message = "Method not allowed".to_string();
@@ -48,5 +113,9 @@ pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible>
         field,
     });
     
-    Ok(warp::reply::with_status(json, code))
+    let mut response = warp::reply::with_status(json, code).into_response();
+    if let Some(allow) = allow.and_then(|allow| HeaderValue::from_str(&allow).ok()) {
+        response.headers_mut().insert(header::ALLOW, allow);
+    }
+    Ok(response)
 } 
\ No newline at end of file
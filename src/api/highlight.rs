@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+
+/// FTS5 operators that are never search terms themselves
+const QUERY_OPERATORS: &[&str] = &["AND", "OR", "NOT", "NEAR"];
+
+/// A matched term in article content, as character offsets `[start, end)`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchSpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A term from a keyword query; `prefix` terms came from `term*`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct QueryTerm {
+    text: String,
+    prefix: bool,
+}
+
+/// Pull the search terms out of an FTS5 query, dropping operators, quotes and column filters
+fn query_terms(query: &str) -> Vec<QueryTerm> {
+    let mut terms: Vec<QueryTerm> = Vec::new();
+    let mut rest = query;
+    while let Some(start) = rest.find(|c: char| c.is_alphanumeric()) {
+        let word_len = rest[start..]
+            .find(|c: char| !c.is_alphanumeric())
+            .unwrap_or(rest.len() - start);
+        let word = &rest[start..start + word_len];
+        rest = &rest[start + word_len..];
+
+        let prefix = rest.starts_with('*');
+        // `title : (...)` scopes the search; the column name is not a term
+        let column_filter = rest.trim_start().starts_with(':');
+        if column_filter || QUERY_OPERATORS.contains(&word) {
+            continue;
+        }
+
+        let term = QueryTerm { text: word.to_lowercase(), prefix };
+        if !terms.contains(&term) {
+            terms.push(term);
+        }
+    }
+    terms
+}
+
+/// Character offsets of every word in `content` matched by a term of `query`.
+///
+/// Words are matched whole and case-insensitively like the FTS5 tokenizer does, or by
+/// prefix for `term*`. Spans are sorted and overlapping spans are merged, so a word
+/// matched by several terms is reported once.
+pub fn match_offsets(content: &str, query: &str) -> Vec<MatchSpan> {
+    let terms = query_terms(query);
+    if terms.is_empty() {
+        return Vec::new();
+    }
+
+    let mut spans: Vec<MatchSpan> = Vec::new();
+    let mut word_start: Option<usize> = None;
+    let mut word = String::new();
+    let mut position = 0;
+
+    let finish_word = |start: usize, end: usize, word: &str, spans: &mut Vec<MatchSpan>| {
+        let matched = terms.iter().any(|term| {
+            if term.prefix {
+                word.starts_with(&term.text)
+            } else {
+                word == term.text
+            }
+        });
+        if !matched {
+            return;
+        }
+        match spans.last_mut() {
+            Some(last) if start < last.end => last.end = last.end.max(end),
+            _ => spans.push(MatchSpan { start, end }),
+        }
+    };
+
+    for c in content.chars() {
+        if c.is_alphanumeric() {
+            word_start.get_or_insert(position);
+            word.extend(c.to_lowercase());
+        } else if let Some(start) = word_start.take() {
+            finish_word(start, position, &word, &mut spans);
+            word.clear();
+        }
+        position += 1;
+    }
+    if let Some(start) = word_start {
+        finish_word(start, position, &word, &mut spans);
+    }
+
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matched(content: &str, spans: &[MatchSpan]) -> Vec<String> {
+        spans
+            .iter()
+            .map(|span| content.chars().skip(span.start).take(span.end - span.start).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_match_offsets() {
+        // Offsets count characters, so the accented words before the matches must not shift them
+        let content = "Café crème: the Sun heats the sunny Earth; SUN again, sundial.";
+        let spans = match_offsets(content, "sun AND earth");
+        assert_eq!(spans, vec![
+            MatchSpan { start: 16, end: 19 },
+            MatchSpan { start: 36, end: 41 },
+            MatchSpan { start: 43, end: 46 },
+        ]);
+        assert_eq!(matched(content, &spans), vec!["Sun", "Earth", "SUN"]);
+
+        // A prefix term and a whole term hitting the same word produce one span
+        let spans = match_offsets(content, "\"sun\" OR sun*");
+        assert_eq!(matched(content, &spans), vec!["Sun", "sunny", "SUN", "sundial"]);
+
+        // Column filters and operators are not terms
+        assert_eq!(matched(content, &match_offsets(content, "title : (heats NOT moon)")), vec!["heats"]);
+        assert!(match_offsets(content, "\"\"").is_empty());
+    }
+}
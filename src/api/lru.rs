@@ -0,0 +1,122 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+#[derive(Debug)]
+struct Entry<V> {
+    value: V,
+    last_used: u64,
+}
+
+/// Map holding at most `capacity` entries, evicting the least recently used one
+/// to make room for a new key. A capacity of 0 keeps nothing.
+///
+/// The caches built on it wrap it in a mutex; the map itself is not shared.
+#[derive(Debug)]
+pub struct LruMap<K, V> {
+    entries: HashMap<K, Entry<V>>,
+    capacity: usize,
+    tick: u64,
+}
+
+impl<K: Eq + Hash + Clone, V> LruMap<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            capacity,
+            tick: 0,
+        }
+    }
+
+    /// Value for `key` without counting as a use
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.entries.get(key).map(|entry| &entry.value)
+    }
+
+    /// Value for `key`, marking it as the most recently used
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.tick += 1;
+        let tick = self.tick;
+        let entry = self.entries.get_mut(key)?;
+        entry.last_used = tick;
+        Some(&entry.value)
+    }
+
+    /// Store `value` under `key`, replacing any previous value and evicting the
+    /// least recently used key when a new one doesn't fit
+    pub fn insert(&mut self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.tick += 1;
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(key, Entry { value, last_used: self.tick });
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.entries.remove(key).map(|entry| entry.value)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lru_map_evicts_least_recently_used() {
+        let mut map = LruMap::new(2);
+        map.insert("alpha".to_string(), 1);
+        map.insert("beta".to_string(), 2);
+        assert_eq!(map.get("alpha"), Some(&1));
+
+        map.insert("gamma".to_string(), 3);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.peek("beta"), None);
+        assert_eq!(map.peek("alpha"), Some(&1));
+
+        // Replacing a value doesn't take another slot; peeking doesn't count as a use
+        map.insert("alpha".to_string(), 10);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.peek("gamma"), Some(&3));
+        map.insert("delta".to_string(), 4);
+        assert_eq!(map.peek("gamma"), None);
+        assert_eq!(map.remove("alpha"), Some(10));
+
+        let mut disabled = LruMap::new(0);
+        disabled.insert("alpha", 1);
+        assert!(disabled.is_empty());
+    }
+}
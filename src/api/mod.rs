@@ -1,29 +1,235 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
+use arc_swap::ArcSwap;
 use tokio::sync::Mutex;
 use warp::{Filter, Rejection, Reply, filters::BoxedFilter};
 use serde::{Deserialize, Serialize};
 use rusqlite::Connection;
+use tracing::warn;
 
 use crate::error_handling::WikiResult;
-use crate::db::DatabaseReader;
-use crate::vector::VectorStore;
+use crate::db::{ArticleSort, CategoryMatch, DatabaseReader, SearchFields, SortOrder};
+use crate::vector::{SimilarityOptions, VectorStore};
 use crate::llm::LlmService;
+use crate::parser::models::{WikiArticle, WikiImage, WikiLangLink, WikiReference, WikiSection};
 
 mod rate_limiter;
-use rate_limiter::{RateLimiter, with_rate_limiting};
+use rate_limiter::{RateLimiter, with_current_rate_limiter};
+
+mod cors;
+use cors::{with_cors, AllowedOrigins};
+
+mod reload;
+pub use reload::ReloadHandle;
 
 mod validation;
-use validation::{validate_article_title, validate_search_query};
+use validation::{validate_article_title, validate_question, validate_search_body, validate_search_query, validation_error, ValidationError};
+
+mod concurrency;
+use concurrency::{ConcurrencyLimiter, with_concurrency_limit};
+
+mod view_counter;
+pub use view_counter::ViewCounter;
+
+mod lru;
+
+mod summary_cache;
+pub use summary_cache::{SummaryCache, DEFAULT_SUMMARY_CACHE_SIZE};
+pub use crate::llm::summary_etag;
+mod semantic_cache;
+pub use semantic_cache::{SemanticCache, DEFAULT_SEMANTIC_CACHE_SIZE, DEFAULT_SEMANTIC_CACHE_TTL};
+use summary_cache::etag_matches;
+
+mod highlight;
+pub use highlight::MatchSpan;
+use highlight::match_offsets;
+
+mod sanitize;
+use sanitize::escape_html;
 
 mod error_handler;
-use error_handler::handle_rejection;
+use error_handler::{handle_rejection, DatabaseFailure, SemanticSearchUnavailable, WrongMethod};
+
+#[cfg(any(test, feature = "test-util"))]
+mod test_util;
+#[cfg(any(test, feature = "test-util"))]
+pub use test_util::TestServer;
 
 pub struct ApiServer {
     db_path: String,
     vector_store: Arc<VectorStore>,
     llm_service: Arc<LlmService>,
-    allowed_origins: Vec<String>,
-    rate_limiters: ApiRateLimiters,
+    allowed_origins: AllowedOrigins,
+    rate_limiters: Arc<ArcSwap<ApiRateLimiters>>,
+    search_config: SearchConfig,
+    limits: ApiLimits,
+    max_article_bytes: usize,
+    sanitize_html: bool,
+    view_counter: ViewCounter,
+    summary_cache: SummaryCache,
+    semantic_cache: SemanticCache,
+    concurrency_limiter: ConcurrencyLimiter,
+}
+
+/// Largest article body, in bytes, returned by the article endpoints before truncating
+pub const DEFAULT_MAX_ARTICLE_BYTES: usize = 1024 * 1024;
+
+/// Largest request body, in bytes, accepted by POST endpoints
+pub const DEFAULT_MAX_BODY_BYTES: u64 = 16 * 1024;
+
+/// Requests handled at once across the whole server before answering 503
+pub const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 256;
+
+/// Seconds between writes of buffered article view counts
+const VIEW_COUNT_FLUSH_SECS: u64 = 30;
+
+/// Page, batch and body size limits enforced by every endpoint and validator
+#[derive(Debug, Clone, Copy)]
+pub struct ApiLimits {
+    /// Number of items returned when the request does not specify a limit
+    pub default_page_size: usize,
+    /// Largest number of items a single request may return
+    pub max_page_size: usize,
+    /// Clamp limits above `max_page_size` instead of rejecting the request
+    pub clamp_page_size: bool,
+    /// Largest number of rows fetched in one internal lookup, such as semantic candidates
+    pub max_batch_size: usize,
+    /// Largest request body, in bytes, accepted by POST endpoints
+    pub max_body_bytes: u64,
+}
+
+impl Default for ApiLimits {
+    fn default() -> Self {
+        Self {
+            default_page_size: 10,
+            max_page_size: 100,
+            clamp_page_size: true,
+            max_batch_size: 500,
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+        }
+    }
+}
+
+impl ApiLimits {
+    /// Resolve the requested page size against the configured default and ceiling
+    pub fn resolve_page_size(&self, requested: Option<usize>) -> usize {
+        requested.unwrap_or(self.default_page_size).min(self.max_page_size)
+    }
+}
+
+/// Ranking settings shared by keyword and semantic search
+#[derive(Debug, Clone, Copy)]
+pub struct SearchConfig {
+    /// Multiplier applied to the limit when fetching semantic candidates, so
+    /// vectors without a matching article don't shrink the result set
+    pub semantic_overfetch: usize,
+    /// Delete vectors whose article no longer exists when they are encountered
+    pub prune_dangling_vectors: bool,
+    /// Weight of view counts in keyword search ordering; 0 ranks by BM25 alone
+    pub popularity_boost: f64,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            semantic_overfetch: 2,
+            prune_dangling_vectors: false,
+            popularity_boost: 0.0,
+        }
+    }
+}
+
+impl SearchConfig {
+    /// Number of nearest neighbours to fetch for a semantic search returning `limit` results,
+    /// capped at the batch size unless the page itself is larger
+    pub fn semantic_candidates(&self, limit: usize, limits: &ApiLimits) -> usize {
+        limit
+            .saturating_mul(self.semantic_overfetch.max(1))
+            .min(limits.max_batch_size.max(limit))
+    }
+}
+
+/// Settings for running the API server; rate limits and allowed origins can be
+/// reloaded while it runs, the rest take effect on restart
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiConfig {
+    pub port: u16,
+    pub host: String,
+    pub db_path: PathBuf,
+    pub vector_path: PathBuf,
+    pub ollama_url: String,
+    /// Origins allowed to make cross-origin requests
+    pub allowed_origins: Vec<String>,
+    pub rate_limits: RateLimitConfig,
+    /// HTML-escape article text in responses unless a request asks for `raw`
+    pub sanitize_html: bool,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            port: 8080,
+            host: "127.0.0.1".to_string(),
+            db_path: PathBuf::from("data/wiki.db"),
+            vector_path: PathBuf::from("vectors"),
+            ollama_url: crate::llm::DEFAULT_OLLAMA_URL.to_string(),
+            allowed_origins: default_allowed_origins(),
+            rate_limits: RateLimitConfig::default(),
+            sanitize_html: false,
+        }
+    }
+}
+
+/// Origins allowed to call the API unless configured otherwise
+fn default_allowed_origins() -> Vec<String> {
+    vec![
+        "http://localhost".to_string(),
+        "http://localhost:8080".to_string(),
+        "http://127.0.0.1".to_string(),
+        "http://127.0.0.1:8080".to_string(),
+    ]
+}
+
+/// Requests allowed per client in a sliding window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub max_requests: usize,
+    pub window_secs: u64,
+}
+
+/// Limits for each class of endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    /// Most endpoints
+    pub standard: RateLimit,
+    /// Computationally expensive endpoints such as semantic search
+    pub restricted: RateLimit,
+    /// Endpoints that call the LLM
+    pub llm: RateLimit,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            // 100 requests per minute
+            standard: RateLimit { max_requests: 100, window_secs: 60 },
+            // 20 requests per minute
+            restricted: RateLimit { max_requests: 20, window_secs: 60 },
+            // 5 requests per minute
+            llm: RateLimit { max_requests: 5, window_secs: 60 },
+        }
+    }
+}
+
+/// Which of the [`ApiRateLimiters`] a route counts against
+#[derive(Debug, Clone, Copy)]
+enum RateLimitTier {
+    Standard,
+    Restricted,
+    Llm,
 }
 
 /// Rate limiters for different API endpoints with different limits
@@ -39,13 +245,39 @@ pub struct ApiRateLimiters {
 
 impl Default for ApiRateLimiters {
     fn default() -> Self {
+        Self::from_config(&RateLimitConfig::default())
+    }
+}
+
+impl ApiRateLimiters {
+    /// Fresh limiters, with no request history, enforcing `config`
+    pub fn from_config(config: &RateLimitConfig) -> Self {
+        let limiter = |limit: RateLimit| RateLimiter::new(limit.max_requests, limit.window_secs);
         Self {
-            // 100 requests per minute
-            standard: RateLimiter::new(100, 60),
-            // 20 requests per minute
-            restricted: RateLimiter::new(20, 60),
-            // 5 requests per minute
-            llm: RateLimiter::new(5, 60),
+            standard: limiter(config.standard),
+            restricted: limiter(config.restricted),
+            llm: limiter(config.llm),
+        }
+    }
+
+    /// Limits currently enforced by each limiter
+    pub fn config(&self) -> RateLimitConfig {
+        let limit = |limiter: &RateLimiter| RateLimit {
+            max_requests: limiter.max_requests(),
+            window_secs: limiter.window_secs(),
+        };
+        RateLimitConfig {
+            standard: limit(&self.standard),
+            restricted: limit(&self.restricted),
+            llm: limit(&self.llm),
+        }
+    }
+
+    fn tier(&self, tier: RateLimitTier) -> &RateLimiter {
+        match tier {
+            RateLimitTier::Standard => &self.standard,
+            RateLimitTier::Restricted => &self.restricted,
+            RateLimitTier::Llm => &self.llm,
         }
     }
 }
@@ -55,6 +287,29 @@ pub struct SearchQuery {
     pub query: String,
     pub limit: Option<usize>,
     pub offset: Option<usize>,
+    /// Include redirect stubs in keyword search results (off by default)
+    #[serde(default)]
+    pub include_redirects: bool,
+    /// Return content unescaped even when the server sanitizes HTML
+    #[serde(default)]
+    pub raw: bool,
+    /// Match keyword searches against `title`, `content` or `both` (the default)
+    #[serde(default)]
+    pub fields: SearchFields,
+    /// Return the offsets of matched terms with each keyword search result
+    #[serde(default)]
+    pub highlight: bool,
+}
+
+/// Length in characters of the content excerpt returned with semantic search results
+const SEMANTIC_EXCERPT_LENGTH: usize = 300;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SemanticSearchResult {
+    pub title: String,
+    pub excerpt: String,
+    pub categories: Vec<String>,
+    pub score: f32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -64,16 +319,104 @@ pub struct ArticleResponse {
     pub categories: Vec<String>,
     pub last_modified: String,
     pub size: usize,
+    /// Whether `content` was cut short; `size` always reports the full article
+    #[serde(default)]
+    pub truncated: bool,
+    #[serde(default)]
+    pub images: Vec<ImageResponse>,
+    /// Words in the full article, not just the returned content
+    #[serde(default)]
+    pub word_count: usize,
+    /// Estimated minutes to read the full article
+    #[serde(default)]
+    pub reading_time_minutes: usize,
+    /// Character offsets of the query terms in `content`, for highlighted searches
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matches: Option<Vec<MatchSpan>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImageResponse {
+    pub filename: String,
+    pub mime_type: String,
+    pub caption: Option<String>,
+    /// Pixel dimensions, absent until the image has been downloaded
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+impl From<WikiImage> for ImageResponse {
+    fn from(image: WikiImage) -> Self {
+        Self {
+            filename: image.filename,
+            mime_type: image.mime_type,
+            caption: image.caption,
+            width: image.width,
+            height: image.height,
+        }
+    }
+}
+
+impl ArticleResponse {
+    /// Build a response, HTML-escaping its text when `escape` is set and then truncating
+    /// content longer than `max_content_bytes` at a char boundary, so the limit applies to
+    /// the content as sent and never cuts an entity in half
+    fn from_article(article: WikiArticle, max_content_bytes: usize, escape: bool) -> Self {
+        let mut content = if escape { escape_html(&article.content) } else { article.content };
+        let truncated = content.len() > max_content_bytes;
+        if truncated {
+            let mut end = max_content_bytes;
+            while !content.is_char_boundary(end) {
+                end -= 1;
+            }
+            // Every `&` of escaped text starts an entity; drop one left without its `;`
+            if escape {
+                if let Some(amp) = content[..end].rfind('&').filter(|&amp| !content[amp..end].contains(';')) {
+                    end = amp;
+                }
+            }
+            content.truncate(end);
+        }
+        let mut images: Vec<ImageResponse> = article.images.into_iter().map(ImageResponse::from).collect();
+        if escape {
+            for image in &mut images {
+                image.caption = image.caption.as_deref().map(escape_html);
+            }
+        }
+
+        Self {
+            title: article.title,
+            content,
+            categories: article.categories.into_iter().collect(),
+            last_modified: article.last_modified.to_rfc3339(),
+            size: article.size,
+            truncated,
+            images,
+            word_count: article.word_count,
+            reading_time_minutes: article.reading_minutes,
+            matches: None,
+        }
+    }
 }
 
 impl ApiServer {
     pub fn new(db_path: &str, vector_store: Arc<VectorStore>, llm_service: Arc<LlmService>) -> Self {
-        Self::with_origins(db_path, vector_store, llm_service, vec![
-            "http://localhost".to_string(),
-            "http://localhost:8080".to_string(),
-            "http://127.0.0.1".to_string(),
-            "http://127.0.0.1:8080".to_string(),
-        ])
+        Self::with_origins(db_path, vector_store, llm_service, default_allowed_origins())
+    }
+
+    /// Create a server for the database configured in `config`
+    pub fn from_config(config: &ApiConfig, vector_store: Arc<VectorStore>, llm_service: Arc<LlmService>) -> Self {
+        Self::with_origins(&config.db_path.to_string_lossy(), vector_store, llm_service, config.allowed_origins.clone())
+            .with_rate_limits(&config.rate_limits)
+            .with_html_sanitization(config.sanitize_html)
+    }
+
+    /// Create a server from `config`, opening the vector store at `config.vector_path`
+    /// and generating with the Ollama server at `config.ollama_url`
+    pub async fn open(config: &ApiConfig) -> WikiResult<Self> {
+        let vector_store = Arc::new(VectorStore::new(&config.vector_path, &config.ollama_url).await?);
+        let llm_service = Arc::new(LlmService::new(&config.ollama_url, None)?);
+        Ok(Self::from_config(config, vector_store, llm_service))
     }
 
     pub fn with_origins(
@@ -86,94 +429,345 @@ impl ApiServer {
             db_path: db_path.to_string(),
             vector_store,
             llm_service,
-            allowed_origins,
-            rate_limiters: ApiRateLimiters::default(),
+            allowed_origins: Arc::new(ArcSwap::from_pointee(allowed_origins)),
+            rate_limiters: Arc::new(ArcSwap::from_pointee(ApiRateLimiters::default())),
+            search_config: SearchConfig::default(),
+            limits: ApiLimits::default(),
+            max_article_bytes: DEFAULT_MAX_ARTICLE_BYTES,
+            sanitize_html: false,
+            view_counter: ViewCounter::new(),
+            summary_cache: SummaryCache::new(),
+            semantic_cache: SemanticCache::default(),
+            concurrency_limiter: ConcurrencyLimiter::new(DEFAULT_MAX_CONCURRENT_REQUESTS),
         }
     }
 
+    /// Override the request limits of each class of endpoint
+    pub fn with_rate_limits(self, rate_limits: &RateLimitConfig) -> Self {
+        self.rate_limiters.store(Arc::new(ApiRateLimiters::from_config(rate_limits)));
+        self
+    }
+
+    /// Override the search ranking settings
+    pub fn with_search_config(mut self, search_config: SearchConfig) -> Self {
+        self.search_config = search_config;
+        self
+    }
+
+    /// Override the page, batch and body size limits applied to every endpoint
+    pub fn with_limits(mut self, limits: ApiLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Override the maximum article body size returned before truncation
+    pub fn with_max_article_bytes(mut self, max_article_bytes: usize) -> Self {
+        self.max_article_bytes = max_article_bytes;
+        self
+    }
+
+    /// HTML-escape article content, section bodies and excerpts before serving them.
+    ///
+    /// For front ends that render content as HTML; clients wanting the text as
+    /// stored can still pass `raw=true`.
+    pub fn with_html_sanitization(mut self, enabled: bool) -> Self {
+        self.sanitize_html = enabled;
+        self
+    }
+
+    /// Override the largest request body accepted by POST endpoints; larger bodies get a 413
+    pub fn with_max_body_bytes(mut self, max_body_bytes: u64) -> Self {
+        self.limits.max_body_bytes = max_body_bytes;
+        self
+    }
+
+    /// Cap the number of requests handled at once; requests beyond it get a 503
+    pub fn with_max_concurrent_requests(mut self, max_concurrent: usize) -> Self {
+        self.concurrency_limiter = ConcurrencyLimiter::new(max_concurrent);
+        self
+    }
+
+    /// Cache up to `capacity` semantic queries for `ttl` each; a capacity of 0 disables caching
+    pub fn with_semantic_cache(mut self, capacity: usize, ttl: std::time::Duration) -> Self {
+        self.semantic_cache = SemanticCache::new(capacity, ttl);
+        self
+    }
+
+    /// Buffer of article views not yet written to the database
+    pub fn view_counter(&self) -> &ViewCounter {
+        &self.view_counter
+    }
+
+    /// Article summaries generated so far, tagged by the content they were made from
+    pub fn summary_cache(&self) -> &SummaryCache {
+        &self.summary_cache
+    }
+
+    /// Semantic search results computed so far, dropped once the vector store changes
+    pub fn semantic_cache(&self) -> &SemanticCache {
+        &self.semantic_cache
+    }
+
     pub async fn run(&self, port: u16) -> WikiResult<()> {
+        self.serve(SocketAddr::from(([127, 0, 0, 1], port))).await
+    }
+
+    /// Serve on `addr`
+    pub async fn serve(&self, addr: SocketAddr) -> WikiResult<()> {
+        let routes = self.routes().await?;
+
+        // Start the server
+        warp::serve(routes).run(addr).await;
+        
+        Ok(())
+    }
+
+    /// Build the full route tree, opening the database and starting rate limiter cleanup
+    async fn routes(
+        &self,
+    ) -> WikiResult<impl Filter<Extract = (impl Reply,), Error = std::convert::Infallible> + Clone + Send + Sync + 'static> {
         let db_path = self.db_path.clone();
         let vector_store = self.vector_store.clone();
         let llm_service = self.llm_service.clone();
         let allowed_origins = self.allowed_origins.clone();
         let rate_limiters = self.rate_limiters.clone();
+        let search_config = self.search_config;
+        let max_article_bytes = self.max_article_bytes;
+        let sanitize_html = self.sanitize_html;
+        let limits = self.limits;
+        let view_counter = self.view_counter.clone();
+        let summary_cache = self.summary_cache.clone();
+        let semantic_cache = self.semantic_cache.clone();
+        let concurrency_limiter = self.concurrency_limiter.clone();
+
+        // Start cleanup task for whichever rate limiters are in use
+        start_rate_limiter_cleanup(rate_limiters.clone(), 60);
 
-        // Start cleanup tasks for rate limiters
-        rate_limiters.standard.clone().start_cleanup(60).await;
-        rate_limiters.restricted.clone().start_cleanup(60).await;
-        rate_limiters.llm.clone().start_cleanup(60).await;
+        // Create connection pool; searches fail on every request without FTS5, so refuse to start
+        let conn = Connection::open(&db_path)?;
+        crate::db::ensure_fts5(&conn)?;
+        let db = Arc::new(Mutex::new(conn));
 
-        // Create connection pool
-        let db = Arc::new(Mutex::new(Connection::open(&db_path)?));
+        // Embeddings are skipped by some installs; semantic endpoints answer 503 until they exist
+        let semantic_available = match vector_store.is_empty() {
+            Ok(empty) => !empty,
+            Err(e) => {
+                warn!("Failed to read vector store: {}", e);
+                false
+            }
+        };
+        if !semantic_available {
+            warn!("Vector store is empty, semantic search is disabled");
+        }
+
+        // Write buffered view counts in the background
+        view_counter.clone().start_flush(db.clone(), VIEW_COUNT_FLUSH_SECS).await;
 
         // Define routes
         let api = warp::path("api");
         
-        // GET /api/articles
+        // GET /api/articles?sort=&order=
         let articles_route = api
             .and(warp::path("articles"))
+            .and(warp::path::end())
             .and(warp::get())
+            .and(warp::query::<ArticlesQuery>())
             .and(with_db(db.clone()))
+            .and(with_limits(limits))
+            .and(with_max_article_bytes(max_article_bytes))
+            .and(with_sanitize_html(sanitize_html))
             .and_then(handle_get_articles)
             .boxed();
         
         // Apply standard rate limiting
-        let articles_route = with_rate_limiting(&rate_limiters.standard, articles_route);
+        let articles_route = with_rate_limiting(&rate_limiters, RateLimitTier::Standard, articles_route);
 
         // GET /api/articles/:title
         let article_route = api
             .and(warp::path("articles"))
             .and(validate_article_title())
-            .and(warp::path::param::<String>())
+            .and(warp::path::end())
             .and(warp::get())
+            .and(warp::query::<ContentQuery>())
             .and(with_db(db.clone()))
+            .and(with_max_article_bytes(max_article_bytes))
+            .and(with_sanitize_html(sanitize_html))
+            .and(with_view_counter(view_counter.clone()))
             .and_then(handle_get_article)
             .boxed();
         
         // Apply standard rate limiting
-        let article_route = with_rate_limiting(&rate_limiters.standard, article_route);
+        let article_route = with_rate_limiting(&rate_limiters, RateLimitTier::Standard, article_route);
+
+        // GET /api/articles/:title/exists
+        let exists_route = api
+            .and(warp::path("articles"))
+            .and(validate_article_title())
+            .and(warp::path("exists"))
+            .and(warp::path::end())
+            .and(warp::get())
+            .and(with_db(db.clone()))
+            .and_then(handle_article_exists)
+            .boxed();
+
+        // Apply standard rate limiting
+        let exists_route = with_rate_limiting(&rate_limiters, RateLimitTier::Standard, exists_route);
+
+        // GET /api/articles/:title/sections/:section
+        let section_route = api
+            .and(warp::path("articles"))
+            .and(validate_article_title())
+            .and(warp::path("sections"))
+            .and(warp::path::param::<String>())
+            .and(warp::path::end())
+            .and(warp::get())
+            .and(warp::query::<ContentQuery>())
+            .and(with_db(db.clone()))
+            .and(with_sanitize_html(sanitize_html))
+            .and_then(handle_get_article_section)
+            .boxed();
+        
+        // Apply standard rate limiting
+        let section_route = with_rate_limiting(&rate_limiters, RateLimitTier::Standard, section_route);
+
+        // GET /api/articles/:title/references
+        let references_route = api
+            .and(warp::path("articles"))
+            .and(validate_article_title())
+            .and(warp::path("references"))
+            .and(warp::path::end())
+            .and(warp::get())
+            .and(warp::query::<ContentQuery>())
+            .and(with_db(db.clone()))
+            .and(with_sanitize_html(sanitize_html))
+            .and_then(handle_get_article_references)
+            .boxed();
+        
+        // Apply standard rate limiting
+        let references_route = with_rate_limiting(&rate_limiters, RateLimitTier::Standard, references_route);
+
+        // GET /api/articles/:title/langlinks
+        let langlinks_route = api
+            .and(warp::path("articles"))
+            .and(validate_article_title())
+            .and(warp::path("langlinks"))
+            .and(warp::path::end())
+            .and(warp::get())
+            .and(warp::query::<ContentQuery>())
+            .and(with_db(db.clone()))
+            .and(with_sanitize_html(sanitize_html))
+            .and_then(handle_get_article_langlinks)
+            .boxed();
+        
+        // Apply standard rate limiting
+        let langlinks_route = with_rate_limiting(&rate_limiters, RateLimitTier::Standard, langlinks_route);
 
         // GET /api/search
         let search_route = api
             .and(warp::path("search"))
             .and(warp::get())
-            .and(validate_search_query())
+            .and(validate_search_query(limits))
             .and(warp::query::<SearchQuery>())
             .and(with_db(db.clone()))
+            .and(with_search_config(search_config))
+            .and(with_limits(limits))
+            .and(with_max_article_bytes(max_article_bytes))
+            .and(with_sanitize_html(sanitize_html))
+            .and_then(handle_search)
+            .boxed();
+        
+        // Apply standard rate limiting
+        let search_route = with_rate_limiting(&rate_limiters, RateLimitTier::Standard, search_route);
+
+        // POST /api/search (same as GET, with the query as a JSON body)
+        let search_post_route = api
+            .and(warp::path("search"))
+            .and(warp::path::end())
+            .and(warp::post())
+            .and(validate_search_body(limits))
+            .and(with_db(db.clone()))
+            .and(with_search_config(search_config))
+            .and(with_limits(limits))
+            .and(with_max_article_bytes(max_article_bytes))
+            .and(with_sanitize_html(sanitize_html))
             .and_then(handle_search)
             .boxed();
         
         // Apply standard rate limiting
-        let search_route = with_rate_limiting(&rate_limiters.standard, search_route);
+        let search_post_route = with_rate_limiting(&rate_limiters, RateLimitTier::Standard, search_post_route);
 
         // GET /api/semantic-search
         let semantic_search_route = api
             .and(warp::path("semantic-search"))
             .and(warp::get())
-            .and(validate_search_query())
+            .and(require_semantic_search(semantic_available))
+            .and(validate_search_query(limits))
             .and(warp::query::<SearchQuery>())
             .and(with_db(db.clone()))
             .and(with_vector_store(vector_store.clone()))
+            .and(with_search_config(search_config))
+            .and(with_limits(limits))
+            .and(with_semantic_cache(semantic_cache.clone()))
+            .and(with_sanitize_html(sanitize_html))
             .and_then(handle_semantic_search)
             .boxed();
         
         // Apply restricted rate limiting for computationally expensive endpoint
-        let semantic_search_route = with_rate_limiting(&rate_limiters.restricted, semantic_search_route);
+        let semantic_search_route = with_rate_limiting(&rate_limiters, RateLimitTier::Restricted, semantic_search_route);
 
         // GET /api/articles/:title/summary
         let summary_route = api
             .and(warp::path("articles"))
             .and(validate_article_title())
-            .and(warp::path::param::<String>())
             .and(warp::path("summary"))
             .and(warp::get())
+            .and(warp::query::<SummaryQuery>())
+            .and(warp::header::optional::<String>("if-none-match"))
             .and(with_db(db.clone()))
             .and(with_llm(llm_service.clone()))
+            .and(with_summary_cache(summary_cache.clone()))
+            .and(with_sanitize_html(sanitize_html))
             .and_then(handle_article_summary)
             .boxed();
         
         // Apply LLM rate limiting for the most expensive endpoint
-        let summary_route = with_rate_limiting(&rate_limiters.llm, summary_route);
+        let summary_route = with_rate_limiting(&rate_limiters, RateLimitTier::Llm, summary_route);
+
+        // GET /api/articles/:title/ask?q=
+        let ask_route = api
+            .and(warp::path("articles"))
+            .and(validate_article_title())
+            .and(warp::path("ask"))
+            .and(warp::path::end())
+            .and(warp::get())
+            .and(validate_question())
+            .and(with_db(db.clone()))
+            .and(with_llm(llm_service.clone()))
+            .and(with_sanitize_html(sanitize_html))
+            .and_then(handle_ask_article)
+            .boxed();
+
+        // Questions run the LLM, so they share its rate limit
+        let ask_route = with_rate_limiting(&rate_limiters, RateLimitTier::Llm, ask_route);
+
+        // GET /api/articles/:title/related?min_score=&max_candidates=
+        let related_route = api
+            .and(warp::path("articles"))
+            .and(validate_article_title())
+            .and(warp::path("related"))
+            .and(warp::path::end())
+            .and(warp::get())
+            .and(require_semantic_search(semantic_available))
+            .and(warp::query::<RelatedQuery>())
+            .and(with_db(db.clone()))
+            .and(with_vector_store(vector_store.clone()))
+            .and(with_search_config(search_config))
+            .and(with_limits(limits))
+            .and_then(handle_related_articles)
+            .boxed();
+
+        // Scans the whole vector store, like semantic search
+        let related_route = with_rate_limiting(&rate_limiters, RateLimitTier::Restricted, related_route);
 
         // GET /api/status
         let status_route = api
@@ -184,30 +778,160 @@ impl ApiServer {
         
         // Status endpoint has no rate limiting
 
-        // Configure CORS with specific allowed origins
-        let cors = warp::cors()
-            .allow_methods(vec!["GET", "POST", "OPTIONS"])
-            .allow_headers(vec!["Content-Type", "Authorization"])
-            .max_age(86400) // 24 hours in seconds
-            .allow_origins(allowed_origins.iter().map(|s| s.as_str()).collect::<Vec<&str>>());
+        // GET /api/version
+        let version_route = api
+            .and(warp::path("version"))
+            .and(warp::path::end())
+            .and(warp::get())
+            .and(with_db(db.clone()))
+            .and_then(handle_version)
+            .boxed();
+        
+        // Apply standard rate limiting
+        let version_route = with_rate_limiting(&rate_limiters, RateLimitTier::Standard, version_route);
+
+        // GET /api/categories
+        let categories_route = api
+            .and(warp::path("categories"))
+            .and(warp::path::end())
+            .and(warp::get())
+            .and(warp::query::<CategoriesQuery>())
+            .and(with_db(db.clone()))
+            .and(with_limits(limits))
+            .and_then(handle_get_categories)
+            .boxed();
+        
+        // Apply standard rate limiting
+        let categories_route = with_rate_limiting(&rate_limiters, RateLimitTier::Standard, categories_route);
+
+        // GET /api/categories/intersect?names=a,b&mode=all
+        let category_intersect_route = api
+            .and(warp::path("categories"))
+            .and(warp::path("intersect"))
+            .and(warp::path::end())
+            .and(warp::get())
+            .and(warp::query::<CategoryIntersectQuery>())
+            .and(with_db(db.clone()))
+            .and(with_limits(limits))
+            .and_then(handle_category_intersect)
+            .boxed();
+        
+        // Apply standard rate limiting
+        let category_intersect_route = with_rate_limiting(&rate_limiters, RateLimitTier::Standard, category_intersect_route);
+
+        // GET /api/trending
+        let trending_route = api
+            .and(warp::path("trending"))
+            .and(warp::path::end())
+            .and(warp::get())
+            .and(warp::query::<TrendingQuery>())
+            .and(with_db(db.clone()))
+            .and(with_limits(limits))
+            .and_then(handle_trending)
+            .boxed();
+        
+        // Apply standard rate limiting
+        let trending_route = with_rate_limiting(&rate_limiters, RateLimitTier::Standard, trending_route);
+
+        // GET /api/redirects?limit=&offset=
+        let redirects_route = api
+            .and(warp::path("redirects"))
+            .and(warp::path::end())
+            .and(warp::get())
+            .and(warp::query::<RedirectsQuery>())
+            .and(with_db(db.clone()))
+            .and(with_limits(limits))
+            .and_then(handle_redirects)
+            .boxed();
+        
+        // Apply standard rate limiting
+        let redirects_route = with_rate_limiting(&rate_limiters, RateLimitTier::Standard, redirects_route);
+
+        // GET /api/stats
+        let stats_route = api
+            .and(warp::path("stats"))
+            .and(warp::path::end())
+            .and(warp::get())
+            .and(with_db(db.clone()))
+            .and_then(handle_stats)
+            .boxed();
+        
+        // Apply standard rate limiting
+        let stats_route = with_rate_limiting(&rate_limiters, RateLimitTier::Standard, stats_route);
 
-        // Combine all routes
+        // Combine all routes; `method_not_allowed` comes last so it only sees unmatched requests
         let routes = articles_route
+            .or(exists_route)
+            .or(section_route)
+            .or(references_route)
+            .or(langlinks_route)
+            .or(related_route)
             .or(article_route)
             .or(search_route)
+            .or(search_post_route)
             .or(semantic_search_route)
             .or(summary_route)
+            .or(ask_route)
             .or(status_route)
-            .with(cors)
+            .or(version_route)
+            .or(stats_route)
+            .or(categories_route)
+            .or(category_intersect_route)
+            .or(trending_route)
+            .or(redirects_route)
+            .or(method_not_allowed())
+            .boxed();
+
+        // Apply the global concurrency limit around every route
+        let routes = with_concurrency_limit(&concurrency_limiter, routes);
+
+        // Apply CORS with the allowed origins in effect for each request
+        let routes = with_cors(allowed_origins, routes)
             .recover(handle_rejection); // Add error handling
 
-        // Start the server
-        warp::serve(routes).run(([127, 0, 0, 1], port)).await;
-        
-        Ok(())
+        Ok(routes)
+    }
+}
+
+/// Methods supported by each API path, or `None` for paths the API does not serve
+fn allowed_methods(path: &str) -> Option<&'static [&'static str]> {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match segments.as_slice() {
+        ["api", "search"] => Some(&["GET", "POST"]),
+        ["api", "articles"]
+        | ["api", "articles", _]
+        | ["api", "articles", _, "exists"]
+        | ["api", "articles", _, "sections", _]
+        | ["api", "articles", _, "references"]
+        | ["api", "articles", _, "langlinks"]
+        | ["api", "articles", _, "related"]
+        | ["api", "articles", _, "summary"]
+        | ["api", "articles", _, "ask"]
+        | ["api", "semantic-search"]
+        | ["api", "status"]
+        | ["api", "version"]
+        | ["api", "stats"]
+        | ["api", "categories"]
+        | ["api", "categories", "intersect"]
+        | ["api", "trending"]
+        | ["api", "redirects"] => Some(&["GET"]),
+        _ => None,
     }
 }
 
+/// Reject requests that reach a known path with an unsupported method, so the
+/// error handler can answer 405 with an `Allow` header
+fn method_not_allowed() -> impl Filter<Extract = (warp::reply::Json,), Error = Rejection> + Clone {
+    warp::path::full()
+        .and(warp::method())
+        .and_then(|path: warp::path::FullPath, method: warp::http::Method| async move {
+            match allowed_methods(path.as_str()) {
+                Some(allow) if !allow.contains(&method.as_str()) => Err(warp::reject::custom(WrongMethod { allow })),
+                _ => Err(warp::reject::not_found()),
+            }
+        })
+}
+
 // Helper functions to provide context to handlers
 fn with_db(db: Arc<Mutex<Connection>>) -> impl Filter<Extract = (Arc<Mutex<Connection>>,), Error = std::convert::Infallible> + Clone {
     warp::any().map(move || db.clone())
@@ -217,66 +941,437 @@ fn with_vector_store(store: Arc<VectorStore>) -> impl Filter<Extract = (Arc<Vect
     warp::any().map(move || store.clone())
 }
 
-fn with_llm(llm: Arc<LlmService>) -> impl Filter<Extract = (Arc<LlmService>,), Error = std::convert::Infallible> + Clone {
-    warp::any().map(move || llm.clone())
+fn with_max_article_bytes(max_bytes: usize) -> impl Filter<Extract = (usize,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || max_bytes)
 }
 
-// Handler functions
-async fn handle_get_articles(db: Arc<Mutex<Connection>>) -> Result<impl Reply, Rejection> {
-    let conn = db.lock().await;
-    let reader = DatabaseReader::new(&conn);
-    
-    match reader.get_articles(100) {
-        Ok(articles) => {
-            let response: Vec<ArticleResponse> = articles.into_iter()
-                .map(|a| ArticleResponse {
-                    title: a.title,
-                    content: a.content,
-                    categories: a.categories.into_iter().collect(),
-                    last_modified: a.last_modified.to_rfc3339(),
-                    size: a.size,
-                })
-                .collect();
-            Ok(warp::reply::json(&response))
-        },
-        Err(_) => Err(warp::reject::not_found()),
-    }
+fn with_sanitize_html(enabled: bool) -> impl Filter<Extract = (bool,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || enabled)
 }
 
-async fn handle_get_article(title: String, db: Arc<Mutex<Connection>>) -> Result<impl Reply, Rejection> {
-    let conn = db.lock().await;
-    let reader = DatabaseReader::new(&conn);
-    
-    match reader.get_article(&title) {
-        Ok(Some(article)) => {
-            let response = ArticleResponse {
-                title: article.title,
-                content: article.content,
-                categories: article.categories.into_iter().collect(),
-                last_modified: article.last_modified.to_rfc3339(),
-                size: article.size,
-            };
-            Ok(warp::reply::json(&response))
-        },
+/// Deserialize a JSON body, rejecting bodies over `max_bytes` (413) or without a length (411)
+fn json_body<T: serde::de::DeserializeOwned + Send>(max_bytes: u64) -> impl Filter<Extract = (T,), Error = Rejection> + Clone {
+    warp::body::content_length_limit(max_bytes).and(warp::body::json())
+}
+
+/// Reject with [`SemanticSearchUnavailable`] when the server started without embeddings
+fn require_semantic_search(available: bool) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::any()
+        .and_then(move || async move {
+            if available {
+                Ok(())
+            } else {
+                Err(warp::reject::custom(SemanticSearchUnavailable))
+            }
+        })
+        .untuple_one()
+}
+
+fn with_search_config(config: SearchConfig) -> impl Filter<Extract = (SearchConfig,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || config)
+}
+
+fn with_limits(limits: ApiLimits) -> impl Filter<Extract = (ApiLimits,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || limits)
+}
+
+fn with_view_counter(counter: ViewCounter) -> impl Filter<Extract = (ViewCounter,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || counter.clone())
+}
+
+fn with_summary_cache(cache: SummaryCache) -> impl Filter<Extract = (SummaryCache,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || cache.clone())
+}
+
+/// Apply the rate limiter of `tier` from the limiters in effect when each request arrives
+fn with_rate_limiting<T: Reply + Send + 'static>(
+    rate_limiters: &Arc<ArcSwap<ApiRateLimiters>>,
+    tier: RateLimitTier,
+    route: BoxedFilter<(T,)>,
+) -> BoxedFilter<(impl Reply,)> {
+    let rate_limiters = rate_limiters.clone();
+    with_current_rate_limiter(route, move || rate_limiters.load().tier(tier).clone())
+}
+
+/// Periodically drop expired request history from the rate limiters in effect
+fn start_rate_limiter_cleanup(rate_limiters: Arc<ArcSwap<ApiRateLimiters>>, interval_secs: u64) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+        loop {
+            interval.tick().await;
+            let current = rate_limiters.load_full();
+            current.standard.cleanup().await;
+            current.restricted.cleanup().await;
+            current.llm.cleanup().await;
+        }
+    });
+}
+
+fn with_semantic_cache(cache: SemanticCache) -> impl Filter<Extract = (SemanticCache,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || cache.clone())
+}
+
+fn with_llm(llm: Arc<LlmService>) -> impl Filter<Extract = (Arc<LlmService>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || llm.clone())
+}
+
+// Handler functions
+async fn handle_get_articles(
+    query: ArticlesQuery,
+    db: Arc<Mutex<Connection>>,
+    limits: ApiLimits,
+    max_article_bytes: usize,
+    sanitize_html: bool,
+) -> Result<impl Reply, Rejection> {
+    let conn = db.lock().await;
+    let reader = DatabaseReader::new(&conn);
+    let limit = limits.resolve_page_size(query.limit);
+    
+    match reader.get_articles_sorted(limit, query.sort, query.order) {
+        Ok(articles) => {
+            let response: Vec<ArticleResponse> = articles.into_iter()
+                .map(|a| ArticleResponse::from_article(a, max_article_bytes, sanitize_html && !query.raw))
+                .collect();
+            Ok(warp::reply::json(&response))
+        },
+        Err(_) => Err(warp::reject::not_found()),
+    }
+}
+
+async fn handle_get_article(
+    title: String,
+    query: ContentQuery,
+    db: Arc<Mutex<Connection>>,
+    max_article_bytes: usize,
+    sanitize_html: bool,
+    view_counter: ViewCounter,
+) -> Result<impl Reply, Rejection> {
+    let conn = db.lock().await;
+    let reader = DatabaseReader::new(&conn);
+    
+    match reader.get_article(&title) {
+        Ok(Some(article)) => {
+            view_counter.record(&article.title);
+            let response = ArticleResponse::from_article(article, max_article_bytes, sanitize_html && !query.raw);
+            Ok(warp::reply::json(&response))
+        },
+        Ok(None) => Err(warp::reject::not_found()),
+        Err(_) => Err(warp::reject::not_found()),
+    }
+}
+
+/// 200 when the title (or the redirect it names) resolves to an article, 404 otherwise; never a body
+async fn handle_article_exists(
+    title: String,
+    db: Arc<Mutex<Connection>>,
+) -> Result<impl Reply, Rejection> {
+    let conn = db.lock().await;
+    let reader = DatabaseReader::new(&conn);
+
+    match reader.article_exists(&title) {
+        Ok(true) => Ok(warp::reply::with_status(warp::reply(), warp::http::StatusCode::OK)),
+        Ok(false) => Ok(warp::reply::with_status(warp::reply(), warp::http::StatusCode::NOT_FOUND)),
+        Err(_) => Err(warp::reject::not_found()),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SectionResponse {
+    pub title: String,
+    pub index: usize,
+    pub heading: String,
+    pub slug: String,
+    pub body: String,
+}
+
+async fn handle_get_article_section(
+    title: String,
+    section: String,
+    query: ContentQuery,
+    db: Arc<Mutex<Connection>>,
+    sanitize_html: bool,
+) -> Result<impl Reply, Rejection> {
+    let conn = db.lock().await;
+    let reader = DatabaseReader::new(&conn);
+
+    let article = match reader.get_article(&title) {
+        Ok(Some(article)) => article,
+        Ok(None) => return Err(warp::reject::not_found()),
+        Err(_) => return Err(warp::reject::not_found()),
+    };
+
+    match select_section(article.sections(), &section) {
+        Ok(Some((index, section))) => Ok(warp::reply::json(&SectionResponse {
+            title: article.title,
+            index,
+            slug: section.slug(),
+            heading: section.heading,
+            body: if sanitize_html && !query.raw { escape_html(&section.body) } else { section.body },
+        })),
+        Ok(None) => Err(warp::reject::not_found()),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SummaryResponse {
+    pub title: String,
+    pub summary: String,
+}
+
+/// Query parameters for `/api/articles`; title order, ascending, by default
+#[derive(Debug, Default, Deserialize)]
+pub struct ArticlesQuery {
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub sort: ArticleSort,
+    #[serde(default)]
+    pub order: SortOrder,
+    /// Return content unescaped even when the server sanitizes HTML
+    #[serde(default)]
+    pub raw: bool,
+}
+
+/// Query parameters for `/api/articles/:title` and its sections
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ContentQuery {
+    /// Return content unescaped even when the server sanitizes HTML
+    #[serde(default)]
+    pub raw: bool,
+}
+
+/// Query parameters for `/api/articles/:title/summary`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SummaryQuery {
+    /// Model to summarize with instead of the server default; must be installed in Ollama
+    pub model: Option<String>,
+    /// Return the summary unescaped even when the server sanitizes HTML
+    #[serde(default)]
+    pub raw: bool,
+}
+
+/// Query parameters for `/api/articles/:title/ask`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AskQuery {
+    /// The question to answer from the article
+    pub q: String,
+    /// Return the answer and citations unescaped even when the server sanitizes HTML
+    #[serde(default)]
+    pub raw: bool,
+}
+
+/// Answer to a question about an article, with the sentences it cites
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AskResponse {
+    pub title: String,
+    pub answer: String,
+    pub citations: Vec<crate::llm::Citation>,
+}
+
+/// Query parameters for `/api/articles/:title/related`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RelatedQuery {
+    pub limit: Option<usize>,
+    /// Leave out articles scoring below this; cosine similarity unless the store uses another metric
+    pub min_score: Option<f32>,
+    /// Nearest neighbours considered before the page is cut; defaults to the semantic search overfetch
+    pub max_candidates: Option<usize>,
+}
+
+/// Query parameters for `/api/categories`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategoriesQuery {
+    /// Only `count` (busiest first) is supported, and is the default
+    pub sort: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// Query parameters for `/api/categories/intersect`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategoryIntersectQuery {
+    /// Comma-separated category names
+    pub names: String,
+    #[serde(default)]
+    pub mode: CategoryMatch,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+impl CategoryIntersectQuery {
+    /// The non-empty names, trimmed
+    fn category_names(&self) -> Vec<String> {
+        self.names
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategoryCountResponse {
+    pub name: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrendingQuery {
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrendingResponse {
+    pub title: String,
+    pub views: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RedirectsQuery {
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RedirectResponse {
+    pub from: String,
+    pub to: String,
+}
+
+/// Corpus size reported by `/api/stats`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatsResponse {
+    pub articles: usize,
+    pub categories: usize,
+    pub images: usize,
+}
+
+/// Build and install details reported by `/api/version`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VersionResponse {
+    pub version: String,
+    /// Commit the server was built from, when built from a git checkout
+    pub git_sha: Option<String>,
+    /// RFC 3339 time the server was built
+    pub build_date: Option<String>,
+    /// Schema version of the database, which may lag the server's until it is reinitialized
+    pub schema_version: Option<i32>,
+    /// Date (YYYYMMDD) of the dump the database was installed from
+    pub dump_date: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReferencesResponse {
+    pub title: String,
+    pub references: Vec<WikiReference>,
+}
+
+async fn handle_get_article_references(
+    title: String,
+    query: ContentQuery,
+    db: Arc<Mutex<Connection>>,
+    sanitize_html: bool,
+) -> Result<impl Reply, Rejection> {
+    let conn = db.lock().await;
+    let reader = DatabaseReader::new(&conn);
+
+    match reader.get_references(&title) {
+        Ok(Some(mut references)) => {
+            if sanitize_html && !query.raw {
+                for reference in &mut references {
+                    reference.name = reference.name.as_deref().map(escape_html);
+                    reference.content = escape_html(&reference.content);
+                }
+            }
+            Ok(warp::reply::json(&ReferencesResponse { title, references }))
+        }
         Ok(None) => Err(warp::reject::not_found()),
         Err(_) => Err(warp::reject::not_found()),
     }
 }
 
-async fn handle_search(query: SearchQuery, db: Arc<Mutex<Connection>>) -> Result<impl Reply, Rejection> {
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LangLinksResponse {
+    pub title: String,
+    pub langlinks: Vec<WikiLangLink>,
+}
+
+async fn handle_get_article_langlinks(
+    title: String,
+    query: ContentQuery,
+    db: Arc<Mutex<Connection>>,
+    sanitize_html: bool,
+) -> Result<impl Reply, Rejection> {
     let conn = db.lock().await;
     let reader = DatabaseReader::new(&conn);
-    let limit = query.limit.unwrap_or(10);
+
+    match reader.get_langlinks(&title) {
+        Ok(Some(mut langlinks)) => {
+            if sanitize_html && !query.raw {
+                for langlink in &mut langlinks {
+                    langlink.target = escape_html(&langlink.target);
+                }
+            }
+            Ok(warp::reply::json(&LangLinksResponse { title, langlinks }))
+        }
+        Ok(None) => Err(warp::reject::not_found()),
+        Err(_) => Err(warp::reject::not_found()),
+    }
+}
+
+/// Pick a section by zero-based index (0 is the lead) or by heading slug
+fn select_section(
+    sections: Vec<WikiSection>,
+    selector: &str,
+) -> Result<Option<(usize, WikiSection)>, ValidationError> {
+    if let Ok(index) = selector.parse::<usize>() {
+        return Ok(sections.into_iter().nth(index).map(|s| (index, s)));
+    }
+
+    let valid_slug = !selector.is_empty()
+        && selector.len() <= 200
+        && selector.chars().all(|c| c.is_alphanumeric() || c == '-');
+    if !valid_slug {
+        return Err(validation_error(
+            "Section must be a non-negative index or a heading slug",
+            Some("section"),
+        ));
+    }
+
+    Ok(sections
+        .into_iter()
+        .enumerate()
+        .find(|(_, s)| s.level > 0 && s.slug() == selector.to_lowercase()))
+}
+
+async fn handle_search(
+    query: SearchQuery,
+    db: Arc<Mutex<Connection>>,
+    search_config: SearchConfig,
+    limits: ApiLimits,
+    max_article_bytes: usize,
+    sanitize_html: bool,
+) -> Result<impl Reply, Rejection> {
+    let conn = db.lock().await;
+    let reader = DatabaseReader::new(&conn);
+    let limit = limits.resolve_page_size(query.limit);
     
-    match reader.search_articles(&query.query, limit) {
+    match reader.search_articles_boosted(
+        &query.query,
+        limit,
+        query.include_redirects,
+        query.fields,
+        search_config.popularity_boost,
+    ) {
         Ok(articles) => {
             let response: Vec<ArticleResponse> = articles.into_iter()
-                .map(|a| ArticleResponse {
-                    title: a.title,
-                    content: a.content,
-                    categories: a.categories.into_iter().collect(),
-                    last_modified: a.last_modified.to_rfc3339(),
-                    size: a.size,
+                .map(|a| {
+                    let mut response = ArticleResponse::from_article(a, max_article_bytes, sanitize_html && !query.raw);
+                    // Offsets refer to the content as returned, after any escaping or truncation
+                    if query.highlight {
+                        response.matches = Some(match_offsets(&response.content, &query.query));
+                    }
+                    response
                 })
                 .collect();
             Ok(warp::reply::json(&response))
@@ -288,8 +1383,30 @@ async fn handle_search(query: SearchQuery, db: Arc<Mutex<Connection>>) -> Result
 async fn handle_semantic_search(
     query: SearchQuery, 
     db: Arc<Mutex<Connection>>, 
-    vector_store: Arc<VectorStore>
+    vector_store: Arc<VectorStore>,
+    search_config: SearchConfig,
+    limits: ApiLimits,
+    cache: SemanticCache,
+    sanitize_html: bool,
 ) -> Result<impl Reply, Rejection> {
+    let limit = limits.resolve_page_size(query.limit);
+    // Cached results stay unescaped, so raw and sanitized requests can share them
+    let escape = sanitize_html && !query.raw;
+    let reply = move |mut results: Vec<SemanticSearchResult>| {
+        if escape {
+            for result in &mut results {
+                result.excerpt = escape_html(&result.excerpt);
+            }
+        }
+        warp::reply::json(&results)
+    };
+
+    // Any write to the vector store, such as a reembed, invalidates cached results
+    let generation = vector_store.generation();
+    if let Some(results) = cache.get(&query.query, limit, generation) {
+        return Ok(reply(results));
+    }
+
     // Generate embedding for the query
     let embedding = match vector_store.generate_embedding(&query.query).await {
         Ok(emb) => emb,
@@ -297,62 +1414,366 @@ async fn handle_semantic_search(
     };
     
     // Find similar articles
-    let limit = query.limit.unwrap_or(10);
-    let similar = match vector_store.find_similar(&embedding, limit) {
-        Ok(results) => results,
-        Err(_) => return Err(warp::reject::not_found()),
+    match find_semantic_results(&vector_store, &db, &embedding, limit, search_config, limits).await {
+        Ok(results) => {
+            cache.insert(&query.query, limit, generation, results.clone());
+            Ok(reply(results))
+        }
+        Err(_) => Err(warp::reject::not_found()),
+    }
+}
+
+/// Fetch extra candidates from the vector store and resolve them against the
+/// database, so vectors left behind by deleted articles don't shrink the results
+async fn find_semantic_results(
+    vector_store: &VectorStore,
+    db: &Mutex<Connection>,
+    embedding: &[f32],
+    limit: usize,
+    search_config: SearchConfig,
+    limits: ApiLimits,
+) -> WikiResult<Vec<SemanticSearchResult>> {
+    let candidates = search_config.semantic_candidates(limit, &limits);
+    let similar = vector_store.find_similar_async(embedding, candidates).await?;
+    resolve_semantic_results(vector_store, db, similar, limit, search_config).await
+}
+
+async fn handle_related_articles(
+    title: String,
+    query: RelatedQuery,
+    db: Arc<Mutex<Connection>>,
+    vector_store: Arc<VectorStore>,
+    search_config: SearchConfig,
+    limits: ApiLimits,
+) -> Result<impl Reply, Rejection> {
+    let limit = limits.resolve_page_size(query.limit);
+    let max_candidates = query
+        .max_candidates
+        .unwrap_or_else(|| search_config.semantic_candidates(limit, &limits));
+    if max_candidates == 0 || max_candidates > limits.max_batch_size {
+        return Err(warp::reject::custom(validation_error(
+            &format!("max_candidates must be between 1 and {}", limits.max_batch_size),
+            Some("max_candidates"),
+        )));
+    }
+    let mut options = SimilarityOptions::new(max_candidates);
+    if let Some(min_score) = query.min_score {
+        if !min_score.is_finite() {
+            return Err(warp::reject::custom(validation_error("min_score must be a number", Some("min_score"))));
+        }
+        options = options.with_min_score(min_score);
+    }
+
+    match find_related_articles(&vector_store, &db, &title, limit, options, search_config).await {
+        Ok(Some(results)) => Ok(warp::reply::json(&results)),
+        Ok(None) => Err(warp::reject::not_found()),
+        Err(_) => Err(warp::reject::not_found()),
+    }
+}
+
+/// Articles whose embeddings are nearest to the embedding of `title`, excluding the article
+/// itself; `None` when the article has no embedding
+async fn find_related_articles(
+    vector_store: &VectorStore,
+    db: &Mutex<Connection>,
+    title: &str,
+    limit: usize,
+    options: SimilarityOptions,
+    search_config: SearchConfig,
+) -> WikiResult<Option<Vec<SemanticSearchResult>>> {
+    let embedding = match vector_store.get_embedding_async(title).await? {
+        Some(embedding) => embedding,
+        None => return Ok(None),
     };
-    
-    // Get article details
-    let conn = db.lock().await;
-    let reader = DatabaseReader::new(&conn);
-    
-    let mut articles = Vec::new();
-    for (title, _) in similar {
-        if let Ok(Some(article)) = reader.get_article(&title) {
-            articles.push(ArticleResponse {
-                title: article.title,
-                content: article.content,
-                categories: article.categories.into_iter().collect(),
-                last_modified: article.last_modified.to_rfc3339(),
-                size: article.size,
-            });
+
+    // The article is its own nearest neighbour, so look one further
+    let options = SimilarityOptions { max_candidates: options.max_candidates.saturating_add(1), ..options };
+    let mut similar = vector_store.find_similar_with_async(&embedding, options).await?;
+    similar.retain(|(candidate, _)| candidate != title);
+    similar.truncate(options.max_candidates - 1);
+
+    resolve_semantic_results(vector_store, db, similar, limit, search_config).await.map(Some)
+}
+
+/// Resolve nearest neighbours against the database, dropping (and optionally pruning)
+/// vectors whose article no longer exists
+async fn resolve_semantic_results(
+    vector_store: &VectorStore,
+    db: &Mutex<Connection>,
+    similar: Vec<(String, f32)>,
+    limit: usize,
+    search_config: SearchConfig,
+) -> WikiResult<Vec<SemanticSearchResult>> {
+    // Get article details in a single query
+    let (mut results, dangling) = {
+        let conn = db.lock().await;
+        let reader = DatabaseReader::new(&conn);
+        build_semantic_results(&reader, similar)?
+    };
+
+    if !dangling.is_empty() {
+        warn!(
+            "Semantic search found {} vectors without a matching article",
+            dangling.len()
+        );
+        if search_config.prune_dangling_vectors {
+            for title in &dangling {
+                vector_store.delete_embedding_async(title).await?;
+            }
         }
     }
-    
-    Ok(warp::reply::json(&articles))
+
+    results.truncate(limit);
+    Ok(results)
+}
+
+/// Join similarity scores with article excerpts, keeping the similarity order.
+/// Also returns the titles that have a vector but no article.
+fn build_semantic_results(
+    reader: &DatabaseReader,
+    similar: Vec<(String, f32)>,
+) -> WikiResult<(Vec<SemanticSearchResult>, Vec<String>)> {
+    let titles: Vec<String> = similar.iter().map(|(title, _)| title.clone()).collect();
+    let mut excerpts: std::collections::HashMap<String, _> = reader
+        .get_article_excerpts(&titles, SEMANTIC_EXCERPT_LENGTH)?
+        .into_iter()
+        .map(|e| (e.title.clone(), e))
+        .collect();
+
+    let mut results = Vec::with_capacity(similar.len());
+    let mut dangling = Vec::new();
+    for (title, score) in similar {
+        match excerpts.remove(&title) {
+            Some(e) => results.push(SemanticSearchResult {
+                title: e.title,
+                excerpt: e.excerpt,
+                categories: e.categories,
+                score,
+            }),
+            None => dangling.push(title),
+        }
+    }
+
+    Ok((results, dangling))
 }
 
 async fn handle_article_summary(
     title: String, 
+    query: SummaryQuery,
+    if_none_match: Option<String>,
     db: Arc<Mutex<Connection>>, 
-    llm: Arc<LlmService>
+    llm: Arc<LlmService>,
+    cache: SummaryCache,
+    sanitize_html: bool,
 ) -> Result<impl Reply, Rejection> {
-    // Get article
-    let conn = db.lock().await;
-    let reader = DatabaseReader::new(&conn);
-    
-    let article = match reader.get_article(&title) {
-        Ok(Some(article)) => article,
-        Ok(None) => return Err(warp::reject::not_found()),
-        Err(_) => return Err(warp::reject::not_found()),
+    let escape = sanitize_html && !query.raw;
+    // A one-off model must be installed; the default model is used as configured
+    let llm = match query.model {
+        Some(model) if model != llm.model() => match llm.has_model(&model).await {
+            Ok(true) => Arc::new(llm.for_model(&model)),
+            Ok(false) => {
+                return Err(warp::reject::custom(validation_error(
+                    &format!("Unknown model '{}'", model),
+                    Some("model"),
+                )))
+            }
+            Err(_) => return Err(warp::reject::not_found()),
+        },
+        _ => llm,
     };
-    
-    // Generate summary
-    let prompt = format!(
-        "Please provide a concise summary of the following Wikipedia article:\n\nTitle: {}\n\n{}",
-        article.title, article.content
-    );
-    
-    let summary = match llm.generate_text(&prompt).await {
-        Ok(text) => text,
-        Err(_) => return Err(warp::reject::not_found()),
+
+    // Get article and any stored summary, releasing the connection before waiting on the LLM
+    let (article, etag, cached) = {
+        let conn = db.lock().await;
+        let reader = DatabaseReader::new(&conn);
+        let article = match reader.get_article(&title) {
+            Ok(Some(article)) => article,
+            Ok(None) => return Err(warp::reject::not_found()),
+            Err(_) => return Err(warp::reject::not_found()),
+        };
+        let etag = summary_etag(&article.content, &llm);
+        let cached = cache.load(&conn, &article.title, &etag).unwrap_or_else(|e| {
+            warn!("Failed to read stored summary of '{}': {}", article.title, e);
+            None
+        });
+        (article, etag, cached)
     };
-    
-    Ok(warp::reply::json(&serde_json::json!({
-        "title": article.title,
-        "summary": summary,
-    })))
+
+    // The client already holds the summary for this revision
+    if cached.is_some() && if_none_match.is_some_and(|value| etag_matches(&value, &etag)) {
+        let reply = warp::reply::with_status(warp::reply(), warp::http::StatusCode::NOT_MODIFIED);
+        return Ok(warp::reply::with_header(reply, warp::http::header::ETAG, etag).into_response());
+    }
+
+    let summary = match cached {
+        Some(summary) => summary,
+        None => match llm.summarize_article(&article.title, &article.content).await {
+            Ok(text) => {
+                let conn = db.lock().await;
+                if let Err(e) = cache.save(&conn, &article.title, &etag, text.clone()) {
+                    warn!("Failed to store summary of '{}': {}", article.title, e);
+                }
+                text
+            }
+            Err(_) => return Err(warp::reject::not_found()),
+        },
+    };
+
+    // Summaries are cached unescaped, so raw and sanitized requests can share them
+    let reply = warp::reply::json(&SummaryResponse {
+        title: article.title,
+        summary: if escape { escape_html(&summary) } else { summary },
+    });
+    Ok(warp::reply::with_header(reply, warp::http::header::ETAG, etag).into_response())
+}
+
+async fn handle_ask_article(
+    title: String,
+    query: AskQuery,
+    db: Arc<Mutex<Connection>>,
+    llm: Arc<LlmService>,
+    sanitize_html: bool,
+) -> Result<impl Reply, Rejection> {
+    // Release the connection before waiting on the LLM
+    let article = {
+        let conn = db.lock().await;
+        let reader = DatabaseReader::new(&conn);
+        match reader.get_article(&title) {
+            Ok(Some(article)) => article,
+            Ok(None) => return Err(warp::reject::not_found()),
+            Err(_) => return Err(warp::reject::not_found()),
+        }
+    };
+
+    match llm.answer_question(&article.title, &article.content, &query.q).await {
+        Ok(mut answer) => {
+            if sanitize_html && !query.raw {
+                answer.answer = escape_html(&answer.answer);
+                for citation in &mut answer.citations {
+                    citation.snippet = escape_html(&citation.snippet);
+                }
+            }
+            Ok(warp::reply::json(&AskResponse {
+                title: article.title,
+                answer: answer.answer,
+                citations: answer.citations,
+            }))
+        }
+        Err(_) => Err(warp::reject::not_found()),
+    }
+}
+
+async fn handle_get_categories(
+    query: CategoriesQuery,
+    db: Arc<Mutex<Connection>>,
+    limits: ApiLimits,
+) -> Result<impl Reply, Rejection> {
+    if !matches!(query.sort.as_deref(), None | Some("count")) {
+        return Err(warp::reject::custom(validation_error(
+            "Unsupported sort order, expected 'count'",
+            Some("sort"),
+        )));
+    }
+
+    let conn = db.lock().await;
+    let reader = DatabaseReader::new(&conn);
+    let limit = limits.resolve_page_size(query.limit);
+
+    match reader.category_counts(limit) {
+        Ok(counts) => {
+            let response: Vec<CategoryCountResponse> = counts
+                .into_iter()
+                .map(|(name, count)| CategoryCountResponse { name, count })
+                .collect();
+            Ok(warp::reply::json(&response))
+        }
+        Err(_) => Err(warp::reject::not_found()),
+    }
+}
+
+async fn handle_category_intersect(
+    query: CategoryIntersectQuery,
+    db: Arc<Mutex<Connection>>,
+    limits: ApiLimits,
+) -> Result<impl Reply, Rejection> {
+    let names = query.category_names();
+    if names.is_empty() {
+        return Err(warp::reject::custom(validation_error("At least one category name is required", Some("names"))));
+    }
+    if names.len() > limits.max_batch_size {
+        return Err(warp::reject::custom(validation_error(
+            &format!("At most {} categories can be matched at once", limits.max_batch_size),
+            Some("names"),
+        )));
+    }
+
+    let conn = db.lock().await;
+    let reader = DatabaseReader::new(&conn);
+    let limit = limits.resolve_page_size(query.limit);
+
+    match reader.get_articles_in_categories(&names, query.mode, limit, query.offset.unwrap_or(0)) {
+        Ok(titles) => Ok(warp::reply::json(&titles)),
+        Err(_) => Err(warp::reject::not_found()),
+    }
+}
+
+async fn handle_trending(
+    query: TrendingQuery,
+    db: Arc<Mutex<Connection>>,
+    limits: ApiLimits,
+) -> Result<impl Reply, Rejection> {
+    let conn = db.lock().await;
+    let reader = DatabaseReader::new(&conn);
+    let limit = limits.resolve_page_size(query.limit);
+
+    match reader.most_viewed(limit) {
+        Ok(views) => {
+            let response: Vec<TrendingResponse> = views
+                .into_iter()
+                .map(|(title, views)| TrendingResponse { title, views })
+                .collect();
+            Ok(warp::reply::json(&response))
+        }
+        Err(_) => Err(warp::reject::not_found()),
+    }
+}
+
+async fn handle_redirects(
+    query: RedirectsQuery,
+    db: Arc<Mutex<Connection>>,
+    limits: ApiLimits,
+) -> Result<impl Reply, Rejection> {
+    let conn = db.lock().await;
+    let reader = DatabaseReader::new(&conn);
+    let limit = limits.resolve_page_size(query.limit);
+
+    match reader.list_redirects(limit, query.offset.unwrap_or(0)) {
+        Ok(redirects) => {
+            let response: Vec<RedirectResponse> = redirects
+                .into_iter()
+                .map(|(from, to)| RedirectResponse { from, to })
+                .collect();
+            Ok(warp::reply::json(&response))
+        }
+        Err(_) => Err(warp::reject::not_found()),
+    }
+}
+
+async fn handle_stats(db: Arc<Mutex<Connection>>) -> Result<impl Reply, Rejection> {
+    let conn = db.lock().await;
+    let reader = DatabaseReader::new(&conn);
+
+    let stats = (|| -> WikiResult<StatsResponse> {
+        Ok(StatsResponse {
+            articles: reader.count_articles()?,
+            categories: reader.count_categories()?,
+            images: reader.count_images()?,
+        })
+    })();
+    match stats {
+        Ok(stats) => Ok(warp::reply::json(&stats)),
+        Err(_) => Err(warp::reject::not_found()),
+    }
 }
 
 async fn handle_status() -> Result<impl Reply, Rejection> {
@@ -360,4 +1781,910 @@ async fn handle_status() -> Result<impl Reply, Rejection> {
         "status": "ok",
         "version": env!("CARGO_PKG_VERSION"),
     })))
-} 
\ No newline at end of file
+}
+
+async fn handle_version(db: Arc<Mutex<Connection>>) -> Result<impl Reply, Rejection> {
+    let conn = db.lock().await;
+    let reader = DatabaseReader::new(&conn);
+
+    let version = (|| -> WikiResult<VersionResponse> {
+        Ok(VersionResponse {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_sha: option_env!("DAVINCI3_GIT_SHA").map(str::to_string),
+            build_date: option_env!("DAVINCI3_BUILD_TIMESTAMP")
+                .and_then(|secs| secs.parse::<i64>().ok())
+                .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+                .map(|date| date.to_rfc3339()),
+            schema_version: reader.schema_version()?,
+            dump_date: reader.get_metadata(crate::db::DUMP_DATE_KEY)?,
+        })
+    })();
+    match version {
+        Ok(version) => Ok(warp::reply::json(&version)),
+        Err(e) => {
+            warn!("Failed to read version information: {}", e);
+            Err(warp::reject::custom(DatabaseFailure))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::schema::init_database;
+    use crate::db::writer::DatabaseWriter;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_semantic_results_keep_similarity_order() -> WikiResult<()> {
+        let temp_file = NamedTempFile::new()?;
+        let conn = Connection::open(temp_file.path())?;
+        init_database(&conn)?;
+
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        for (title, content) in [
+            ("Alpha", "Alpha is the first letter."),
+            ("Beta", "Beta is the second letter."),
+            ("Gamma", "Gamma is the third letter."),
+        ] {
+            let mut article = WikiArticle::new(title.to_string(), content.to_string());
+            article.add_category("Letters".to_string());
+            article.update_size();
+            writer.write_article(&article, &tx)?;
+        }
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let reader = DatabaseReader::new(&conn);
+        let similar = vec![
+            ("Gamma".to_string(), 0.9),
+            ("Missing".to_string(), 0.8),
+            ("Alpha".to_string(), 0.5),
+            ("Beta".to_string(), 0.1),
+        ];
+        let (results, dangling) = build_semantic_results(&reader, similar)?;
+        assert_eq!(dangling, vec!["Missing".to_string()]);
+
+        let titles: Vec<&str> = results.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["Gamma", "Alpha", "Beta"]);
+        assert!(results.windows(2).all(|w| w[0].score >= w[1].score));
+        assert_eq!(results[0].excerpt, "Gamma is the third letter.");
+        assert_eq!(results[0].categories, vec!["Letters".to_string()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_limit_is_clamped_to_max() {
+        let limits = ApiLimits::default();
+        assert_eq!(limits.resolve_page_size(None), limits.default_page_size);
+        assert_eq!(limits.resolve_page_size(Some(25)), 25);
+        assert_eq!(limits.resolve_page_size(Some(10000)), limits.max_page_size);
+
+        let limits = ApiLimits { default_page_size: 5, max_page_size: 50, ..Default::default() };
+        assert_eq!(limits.resolve_page_size(None), 5);
+        assert_eq!(limits.resolve_page_size(Some(10000)), 50);
+
+        // Semantic overfetch stays within the batch size, but never below the page
+        let limits = ApiLimits { max_batch_size: 30, ..Default::default() };
+        let config = SearchConfig::default();
+        assert_eq!(config.semantic_candidates(10, &limits), 20);
+        assert_eq!(config.semantic_candidates(20, &limits), 30);
+        assert_eq!(config.semantic_candidates(50, &limits), 50);
+    }
+
+    #[test]
+    fn test_select_section() {
+        let mut article = WikiArticle::new(
+            "Sectioned".to_string(),
+            "Lead.\n== History ==\nPast events.\n== Modern era ==\nRecent events.".to_string(),
+        );
+        article.update_size();
+
+        let (index, section) = select_section(article.sections(), "1").unwrap().unwrap();
+        assert_eq!(index, 1);
+        assert_eq!(section.heading, "History");
+        assert_eq!(section.body, "Past events.");
+
+        let (index, section) = select_section(article.sections(), "modern-era").unwrap().unwrap();
+        assert_eq!(index, 2);
+        assert_eq!(section.body, "Recent events.");
+
+        assert!(select_section(article.sections(), "7").unwrap().is_none());
+        assert!(select_section(article.sections(), "no-such-heading").unwrap().is_none());
+        assert!(select_section(article.sections(), "bad slug!").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_semantic_search_skips_dangling_vectors() -> WikiResult<()> {
+        let temp_file = NamedTempFile::new()?;
+        let conn = Connection::open(temp_file.path())?;
+        init_database(&conn)?;
+
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        for title in ["One", "Two", "Three", "Four"] {
+            let mut article = WikiArticle::new(title.to_string(), format!("{} content.", title));
+            article.update_size();
+            writer.write_article(&article, &tx)?;
+        }
+        writer.delete_article("Two", &tx)?;
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let vector_dir = tempfile::TempDir::new()?;
+        let store = VectorStore::new(vector_dir.path(), "http://localhost:11434").await?;
+        store.store_embedding("One", &[1.0, 0.0])?;
+        store.store_embedding("Two", &[0.9, 0.1])?;
+        store.store_embedding("Three", &[0.8, 0.2])?;
+        store.store_embedding("Four", &[0.0, 1.0])?;
+
+        let db = Mutex::new(conn);
+        let config = SearchConfig::default();
+        let results = find_semantic_results(&store, &db, &[1.0, 0.0], 3, config, ApiLimits::default()).await?;
+        let titles: Vec<&str> = results.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["One", "Three", "Four"]);
+        assert!(store.has_embedding("Two")?);
+
+        let config = SearchConfig { prune_dangling_vectors: true, ..Default::default() };
+        find_semantic_results(&store, &db, &[1.0, 0.0], 3, config, ApiLimits::default()).await?;
+        assert!(!store.has_embedding("Two")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_oversized_article_is_truncated() {
+        let mut article = WikiArticle::new("Huge".to_string(), "é".repeat(1000));
+        article.update_size();
+
+        let response = ArticleResponse::from_article(article.clone(), 101, false);
+        assert!(response.truncated);
+        assert_eq!(response.content.len(), 100);
+        assert!(response.content.chars().all(|c| c == 'é'));
+        assert_eq!(response.size, article.size);
+
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["truncated"], true);
+
+        let response = ArticleResponse::from_article(article, DEFAULT_MAX_ARTICLE_BYTES, false);
+        assert!(!response.truncated);
+        assert_eq!(response.content.len(), 2000);
+
+        // The limit applies to the escaped content, which is never cut inside an entity
+        let article = WikiArticle::new("Angles".to_string(), "a<b<c".to_string());
+        let response = ArticleResponse::from_article(article, 7, true);
+        assert!(response.truncated);
+        assert_eq!(response.content, "a&lt;b");
+    }
+
+    /// A server over an empty database in `dir`
+    async fn test_server(dir: &std::path::Path) -> WikiResult<ApiServer> {
+        let db_path = dir.join("wiki.db");
+        init_database(&Connection::open(&db_path)?)?;
+        let vector_store = Arc::new(VectorStore::new(dir.join("vectors"), crate::llm::DEFAULT_OLLAMA_URL).await?);
+        let llm_service = Arc::new(LlmService::new(crate::llm::DEFAULT_OLLAMA_URL, None)?);
+        Ok(ApiServer::new(&db_path.to_string_lossy(), vector_store, llm_service))
+    }
+
+    #[tokio::test]
+    async fn test_post_body_over_limit_is_rejected() -> WikiResult<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let routes = test_server(temp_dir.path()).await?
+            .with_max_body_bytes(64)
+            .routes()
+            .await?;
+
+        let oversized = serde_json::json!({ "query": "a".repeat(100) }).to_string();
+        let response = warp::test::request()
+            .method("POST")
+            .path("/api/search")
+            .header("content-type", "application/json")
+            .body(oversized)
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), warp::http::StatusCode::PAYLOAD_TOO_LARGE);
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/api/search")
+            .header("content-type", "application/json")
+            .body(r#"{"query":"letter"}"#)
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), warp::http::StatusCode::OK);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reloading_config_updates_rate_limits_and_origins() -> WikiResult<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let mut config = ApiConfig::default();
+        config.rate_limits.standard = RateLimit { max_requests: 1, window_secs: 60 };
+        let server = test_server(temp_dir.path()).await?.with_rate_limits(&config.rate_limits);
+        let routes = server.routes().await?;
+
+        let articles = || warp::test::request().path("/api/articles");
+        assert_eq!(articles().reply(&routes).await.status(), warp::http::StatusCode::OK);
+        assert_eq!(articles().reply(&routes).await.status(), warp::http::StatusCode::TOO_MANY_REQUESTS);
+        let cross_origin = articles().header("origin", "http://wiki.example").reply(&routes).await;
+        assert_eq!(cross_origin.status(), warp::http::StatusCode::FORBIDDEN);
+
+        config.rate_limits.standard = RateLimit { max_requests: 3, window_secs: 60 };
+        config.allowed_origins.push("http://wiki.example".to_string());
+        server.reload_config(&config);
+
+        let response = articles().reply(&routes).await;
+        assert_eq!(response.status(), warp::http::StatusCode::OK);
+        assert_eq!(response.headers()["x-ratelimit-remaining"], "2");
+        let cross_origin = articles().header("origin", "http://wiki.example").reply(&routes).await;
+        assert_eq!(cross_origin.status(), warp::http::StatusCode::OK);
+        assert_eq!(cross_origin.headers()[warp::http::header::ACCESS_CONTROL_ALLOW_ORIGIN], "http://wiki.example");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_wrong_method_returns_405_with_allow() -> WikiResult<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let routes = test_server(temp_dir.path()).await?.routes().await?;
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/api/articles/Alpha")
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), warp::http::StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(response.headers()[warp::http::header::ALLOW], "GET");
+
+        let response = warp::test::request()
+            .method("DELETE")
+            .path("/api/search")
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), warp::http::StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(response.headers()[warp::http::header::ALLOW], "GET, POST");
+
+        // Unknown paths are still 404
+        let response = warp::test::request()
+            .method("POST")
+            .path("/api/nowhere")
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), warp::http::StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_article_exists_check() -> WikiResult<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let server = test_server(temp_dir.path()).await?;
+        let conn = Connection::open(temp_dir.path().join("wiki.db"))?;
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        writer.write_article(&WikiArticle::new("Alpha".to_string(), "First.".to_string()), &tx)?;
+        let mut redirect = WikiArticle::new("Alias".to_string(), "#REDIRECT [[Alpha]]".to_string());
+        redirect.redirect_to = Some("Alpha".to_string());
+        writer.write_article(&redirect, &tx)?;
+        DatabaseWriter::commit_transaction(tx)?;
+        let routes = server.routes().await?;
+
+        for (title, status) in [
+            ("Alpha", warp::http::StatusCode::OK),
+            ("Alias", warp::http::StatusCode::OK),
+            ("Missing", warp::http::StatusCode::NOT_FOUND),
+        ] {
+            let response = warp::test::request()
+                .path(&format!("/api/articles/{}/exists", title))
+                .reply(&routes)
+                .await;
+            assert_eq!(response.status(), status, "{}", title);
+            assert!(response.body().is_empty(), "{}", title);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_version_reports_crate_and_schema_versions() -> WikiResult<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let server = test_server(temp_dir.path()).await?;
+        let conn = Connection::open(temp_dir.path().join("wiki.db"))?;
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        writer.set_metadata(crate::db::DUMP_DATE_KEY, "20240301", &tx)?;
+        DatabaseWriter::commit_transaction(tx)?;
+        let routes = server.routes().await?;
+
+        let response = warp::test::request().path("/api/version").reply(&routes).await;
+        assert_eq!(response.status(), warp::http::StatusCode::OK);
+        let version: VersionResponse = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(version.schema_version, Some(crate::db::SCHEMA_VERSION));
+        assert_eq!(version.dump_date.as_deref(), Some("20240301"));
+        assert!(version.build_date.is_some());
+
+        // A database that can't be read is a server error, not a missing page
+        conn.execute("DROP TABLE metadata", [])?;
+        let response = warp::test::request().path("/api/version").reply(&routes).await;
+        assert_eq!(response.status(), warp::http::StatusCode::INTERNAL_SERVER_ERROR);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_article_views_are_counted_after_flush() -> WikiResult<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let server = test_server(temp_dir.path()).await?;
+        let mut conn = Connection::open(temp_dir.path().join("wiki.db"))?;
+        {
+            let writer = DatabaseWriter::new(&conn);
+            let tx = writer.begin_transaction()?;
+            writer.write_article(&WikiArticle::new("Alpha".to_string(), "First.".to_string()), &tx)?;
+            writer.write_article(&WikiArticle::new("Beta".to_string(), "Second.".to_string()), &tx)?;
+            DatabaseWriter::commit_transaction(tx)?;
+        }
+        let routes = server.routes().await?;
+
+        for title in ["Alpha", "Alpha", "Beta", "Missing"] {
+            warp::test::request()
+                .path(&format!("/api/articles/{}", title))
+                .reply(&routes)
+                .await;
+        }
+
+        // Nothing is written until the buffer is flushed
+        assert!(DatabaseReader::new(&conn).most_viewed(10)?.is_empty());
+        assert_eq!(server.view_counter().flush(&mut conn)?, 2);
+
+        assert_eq!(
+            DatabaseReader::new(&conn).most_viewed(10)?,
+            vec![("Alpha".to_string(), 2), ("Beta".to_string(), 1)]
+        );
+
+        let response = warp::test::request().path("/api/trending?limit=1").reply(&routes).await;
+        let trending: Vec<TrendingResponse> = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(trending.len(), 1);
+        assert_eq!((trending[0].title.as_str(), trending[0].views), ("Alpha", 2));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_repeat_summary_with_etag_is_not_modified() -> WikiResult<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let mut ollama = mockito::Server::new_async().await;
+        let generate = ollama.mock("POST", "/api/generate")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"model":"llama2","response":"A short summary."}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let db_path = temp_dir.path().join("wiki.db");
+        let conn = Connection::open(&db_path)?;
+        init_database(&conn)?;
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        writer.write_article(&WikiArticle::new("Alpha".to_string(), "First.".to_string()), &tx)?;
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let vector_store = Arc::new(VectorStore::new(temp_dir.path().join("vectors"), &ollama.url()).await?);
+        let llm_service = Arc::new(LlmService::new(&ollama.url(), None)?);
+        let routes = ApiServer::new(&db_path.to_string_lossy(), vector_store, llm_service)
+            .routes()
+            .await?;
+
+        let first = warp::test::request().path("/api/articles/Alpha/summary").reply(&routes).await;
+        assert_eq!(first.status(), warp::http::StatusCode::OK);
+        let etag = first.headers()[warp::http::header::ETAG].to_str().unwrap().to_string();
+
+        let repeat = warp::test::request()
+            .path("/api/articles/Alpha/summary")
+            .header("if-none-match", &etag)
+            .reply(&routes)
+            .await;
+        assert_eq!(repeat.status(), warp::http::StatusCode::NOT_MODIFIED);
+        assert_eq!(repeat.headers()[warp::http::header::ETAG], etag.as_str());
+        assert!(repeat.body().is_empty());
+
+        // A stale tag gets the cached summary without another LLM call
+        let stale = warp::test::request()
+            .path("/api/articles/Alpha/summary")
+            .header("if-none-match", "\"stale\"")
+            .reply(&routes)
+            .await;
+        let summary: SummaryResponse = serde_json::from_slice(stale.body()).unwrap();
+        assert_eq!(summary.summary, "A short summary.");
+
+        generate.assert_async().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_repeat_semantic_query_reuses_cached_results() -> WikiResult<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let mut ollama = mockito::Server::new_async().await;
+        let embeddings = ollama.mock("POST", "/api/embeddings")
+            .with_status(200)
+            .with_body(r#"{"data":[{"embedding":[1.0,0.0]}]}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let db_path = temp_dir.path().join("wiki.db");
+        let conn = Connection::open(&db_path)?;
+        init_database(&conn)?;
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        writer.write_article(&WikiArticle::new("Alpha".to_string(), "First.".to_string()), &tx)?;
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let vector_store = Arc::new(VectorStore::new(temp_dir.path().join("vectors"), &ollama.url()).await?);
+        vector_store.store_embedding("Alpha", &[1.0, 0.0])?;
+        let llm_service = Arc::new(LlmService::new(&ollama.url(), None)?);
+        let server = ApiServer::new(&db_path.to_string_lossy(), vector_store, llm_service);
+        let routes = server.routes().await?;
+
+        for path in ["/api/semantic-search?query=first%20article", "/api/semantic-search?query=First%20%20Article"] {
+            let response = warp::test::request().path(path).reply(&routes).await;
+            assert_eq!(response.status(), warp::http::StatusCode::OK);
+            let results: Vec<SemanticSearchResult> = serde_json::from_slice(response.body()).unwrap();
+            assert_eq!(results[0].title, "Alpha");
+        }
+
+        embeddings.assert_async().await;
+        assert_eq!(server.semantic_cache().len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_article_word_count_and_reading_time() -> WikiResult<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let server = test_server(temp_dir.path()).await?;
+        let conn = Connection::open(temp_dir.path().join("wiki.db"))?;
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        // 450 words, counted by the writer rather than taken from the article
+        let content = "The quick brown fox jumps over the lazy dog again.\n".repeat(45);
+        writer.write_article(&WikiArticle::new("Fox".to_string(), content), &tx)?;
+        writer.write_article(&WikiArticle::new("Short".to_string(), "Just three words".to_string()), &tx)?;
+        DatabaseWriter::commit_transaction(tx)?;
+        let routes = server.routes().await?;
+
+        let response = warp::test::request().path("/api/articles/Fox").reply(&routes).await;
+        let article: ArticleResponse = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(article.word_count, 450);
+        // 450 words at 200 wpm rounds up to 3 minutes
+        assert_eq!(article.reading_time_minutes, 3);
+
+        let response = warp::test::request().path("/api/articles/Short").reply(&routes).await;
+        let article: ArticleResponse = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!((article.word_count, article.reading_time_minutes), (3, 1));
+
+        // The reading time is the one stored at insert
+        conn.execute(
+            "UPDATE article_stats SET reading_minutes = 7
+             WHERE article_id = (SELECT article_id FROM article_titles WHERE title = 'Short')",
+            [],
+        )?;
+        let response = warp::test::request().path("/api/articles/Short").reply(&routes).await;
+        let article: ArticleResponse = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(article.reading_time_minutes, 7);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_script_in_content_is_escaped_when_sanitizing() -> WikiResult<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let server = test_server(temp_dir.path()).await?.with_html_sanitization(true);
+        let conn = Connection::open(temp_dir.path().join("wiki.db"))?;
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        let content = "Leftover <script>alert('x')</script> markup".to_string();
+        writer.write_article(&WikiArticle::new("Leftover".to_string(), content.clone()), &tx)?;
+        DatabaseWriter::commit_transaction(tx)?;
+        let routes = server.routes().await?;
+
+        let response = warp::test::request().path("/api/articles/Leftover").reply(&routes).await;
+        let article: ArticleResponse = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(article.content, "Leftover &lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt; markup");
+        assert!(!String::from_utf8_lossy(response.body()).contains("<script>"));
+
+        let response = warp::test::request().path("/api/search?query=leftover").reply(&routes).await;
+        let results: Vec<ArticleResponse> = serde_json::from_slice(response.body()).unwrap();
+        assert!(results[0].content.contains("&lt;script&gt;"));
+
+        let response = warp::test::request().path("/api/articles/Leftover?raw=true").reply(&routes).await;
+        let article: ArticleResponse = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(article.content, content);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_every_text_route_is_escaped_when_sanitizing() -> WikiResult<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let mut ollama = mockito::Server::new_async().await;
+        ollama.mock("POST", "/api/generate")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"model":"llama2","response":"It orbits the <b>Sun</b> [1]."}"#)
+            .create_async()
+            .await;
+
+        let db_path = temp_dir.path().join("wiki.db");
+        let conn = Connection::open(&db_path)?;
+        init_database(&conn)?;
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        let mut article = WikiArticle::new("Earth".to_string(), "Earth orbits the <i>Sun</i>.".to_string());
+        article.references = vec![WikiReference { name: Some("<n>".to_string()), content: "<script>x</script>".to_string() }];
+        article.langlinks = vec![WikiLangLink { lang: "fr".to_string(), target: "<i>Terre</i>".to_string() }];
+        writer.write_article(&article, &tx)?;
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let vector_store = Arc::new(VectorStore::new(temp_dir.path().join("vectors"), &ollama.url()).await?);
+        let llm_service = Arc::new(LlmService::new(&ollama.url(), None)?);
+        let routes = ApiServer::new(&db_path.to_string_lossy(), vector_store, llm_service)
+            .with_html_sanitization(true)
+            .routes()
+            .await?;
+        let body = |path: &'static str| {
+            let routes = routes.clone();
+            async move {
+                let response = warp::test::request().path(path).reply(&routes).await;
+                assert_eq!(response.status(), warp::http::StatusCode::OK, "{}", path);
+                String::from_utf8_lossy(response.body()).into_owned()
+            }
+        };
+
+        let references = body("/api/articles/Earth/references").await;
+        assert!(references.contains("&lt;script&gt;x&lt;/script&gt;") && references.contains("&lt;n&gt;"), "{}", references);
+        let langlinks = body("/api/articles/Earth/langlinks").await;
+        assert!(langlinks.contains("&lt;i&gt;Terre&lt;/i&gt;"), "{}", langlinks);
+        let summary = body("/api/articles/Earth/summary").await;
+        assert!(summary.contains("&lt;b&gt;Sun&lt;/b&gt;"), "{}", summary);
+        let answer = body("/api/articles/Earth/ask?q=What%20does%20Earth%20orbit%3F").await;
+        assert!(answer.contains("&lt;b&gt;Sun&lt;/b&gt;") && answer.contains("&lt;i&gt;Sun&lt;/i&gt;"), "{}", answer);
+        for text in [references, langlinks, summary, answer] {
+            assert!(!text.contains('<'), "{}", text);
+        }
+
+        // Cached summaries are stored unescaped and served raw on request
+        let raw = body("/api/articles/Earth/summary?raw=true").await;
+        assert!(raw.contains("<b>Sun</b>"), "{}", raw);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_article_listing_is_sorted() -> WikiResult<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let server = test_server(temp_dir.path()).await?;
+        let conn = Connection::open(temp_dir.path().join("wiki.db"))?;
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        // Inserted out of order, with sizes that disagree with the title order
+        for (title, content) in [("Mango", "Medium text"), ("Apple", "The longest text of all"), ("Zucchini", "Tiny")] {
+            let mut article = WikiArticle::new(title.to_string(), content.to_string());
+            article.update_size();
+            writer.write_article(&article, &tx)?;
+        }
+        DatabaseWriter::commit_transaction(tx)?;
+        let routes = server.routes().await?;
+
+        let titles = |body: &[u8]| -> Vec<String> {
+            let articles: Vec<ArticleResponse> = serde_json::from_slice(body).unwrap();
+            articles.into_iter().map(|a| a.title).collect()
+        };
+
+        let response = warp::test::request().path("/api/articles").reply(&routes).await;
+        assert_eq!(titles(response.body()), vec!["Apple", "Mango", "Zucchini"]);
+
+        let response = warp::test::request().path("/api/articles?sort=size&order=desc").reply(&routes).await;
+        assert_eq!(titles(response.body()), vec!["Apple", "Mango", "Zucchini"]);
+
+        let response = warp::test::request().path("/api/articles?order=desc").reply(&routes).await;
+        assert_eq!(titles(response.body()), vec!["Zucchini", "Mango", "Apple"]);
+
+        let response = warp::test::request().path("/api/articles?sort=popularity").reply(&routes).await;
+        assert_eq!(response.status(), warp::http::StatusCode::BAD_REQUEST);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_redirects_are_paged() -> WikiResult<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let server = test_server(temp_dir.path()).await?;
+        let conn = Connection::open(temp_dir.path().join("wiki.db"))?;
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        writer.write_article(&WikiArticle::new("Earth".to_string(), "Third planet.".to_string()), &tx)?;
+        for from in ["Terra", "Planet Earth", "Gaia", "The World", "Blue Planet"] {
+            let mut redirect = WikiArticle::new(from.to_string(), String::new());
+            redirect.redirect_to = Some("Earth".to_string());
+            writer.write_article(&redirect, &tx)?;
+        }
+        DatabaseWriter::commit_transaction(tx)?;
+        let routes = server.routes().await?;
+
+        let mut seen = Vec::new();
+        for offset in [0, 2, 4, 6] {
+            let response = warp::test::request()
+                .path(&format!("/api/redirects?limit=2&offset={}", offset))
+                .reply(&routes)
+                .await;
+            let page: Vec<RedirectResponse> = serde_json::from_slice(response.body()).unwrap();
+            assert!(page.len() <= 2);
+            assert!(page.iter().all(|r| r.to == "Earth"));
+            seen.extend(page.into_iter().map(|r| r.from));
+        }
+        assert_eq!(seen, vec!["Blue Planet", "Gaia", "Planet Earth", "Terra", "The World"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_every_listing_respects_max_page_size() -> WikiResult<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let server = test_server(temp_dir.path()).await?
+            .with_limits(ApiLimits { max_page_size: 2, ..Default::default() });
+        let conn = Connection::open(temp_dir.path().join("wiki.db"))?;
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        for (i, title) in ["Mercury", "Venus", "Earth", "Mars"].into_iter().enumerate() {
+            let mut article = WikiArticle::new(title.to_string(), format!("{} is a planet.", title));
+            article.categories.insert(format!("Group {}", i));
+            writer.write_article(&article, &tx)?;
+            let mut redirect = WikiArticle::new(format!("Planet {}", title), String::new());
+            redirect.redirect_to = Some(title.to_string());
+            writer.write_article(&redirect, &tx)?;
+        }
+        DatabaseWriter::commit_transaction(tx)?;
+        conn.execute(
+            "INSERT INTO view_counts (title, views) VALUES ('Mercury', 1), ('Venus', 2), ('Earth', 3), ('Mars', 4)",
+            [],
+        )?;
+        let routes = server.routes().await?;
+
+        for path in [
+            "/api/articles",
+            "/api/articles?limit=50",
+            "/api/search?query=planet",
+            "/api/search?query=planet&limit=50",
+            "/api/categories?limit=50",
+            "/api/trending?limit=50",
+            "/api/redirects?limit=50",
+        ] {
+            let response = warp::test::request().path(path).reply(&routes).await;
+            assert_eq!(response.status(), warp::http::StatusCode::OK, "{}", path);
+            let items: Vec<serde_json::Value> = serde_json::from_slice(response.body()).unwrap();
+            assert_eq!(items.len(), 2, "{}", path);
+        }
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/api/search")
+            .header("content-type", "application/json")
+            .body(r#"{"query":"planet","limit":50}"#)
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), warp::http::StatusCode::OK);
+        let items: Vec<serde_json::Value> = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(items.len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_category_intersect_all_and_any() -> WikiResult<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let server = test_server(temp_dir.path()).await?;
+        let conn = Connection::open(temp_dir.path().join("wiki.db"))?;
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        for (title, categories) in [
+            ("Athens", vec!["Cities", "Greece"]),
+            ("Sparta", vec!["Cities", "Greece", "Ruins"]),
+            ("Rome", vec!["Cities"]),
+            ("Olympus", vec!["Greece"]),
+        ] {
+            let mut article = WikiArticle::new(title.to_string(), format!("{}.", title));
+            for category in categories {
+                article.add_category(category.to_string());
+            }
+            writer.write_article(&article, &tx)?;
+        }
+        DatabaseWriter::commit_transaction(tx)?;
+        let routes = server.routes().await?;
+
+        let titles = |path: &'static str| {
+            let routes = routes.clone();
+            async move {
+                let response = warp::test::request().path(path).reply(&routes).await;
+                assert_eq!(response.status(), warp::http::StatusCode::OK, "{}", path);
+                serde_json::from_slice::<Vec<String>>(response.body()).unwrap()
+            }
+        };
+
+        assert_eq!(titles("/api/categories/intersect?names=Cities,Greece&mode=all").await, vec!["Athens", "Sparta"]);
+        // `all` is the default
+        assert_eq!(titles("/api/categories/intersect?names=Cities,%20Greece").await, vec!["Athens", "Sparta"]);
+        assert_eq!(
+            titles("/api/categories/intersect?names=Ruins,Cities&mode=any").await,
+            vec!["Athens", "Rome", "Sparta"]
+        );
+        assert_eq!(titles("/api/categories/intersect?names=Cities&mode=any&limit=1&offset=1").await, vec!["Rome"]);
+
+        let response = warp::test::request().path("/api/categories/intersect?names=,").reply(&routes).await;
+        assert_eq!(response.status(), warp::http::StatusCode::BAD_REQUEST);
+        let response = warp::test::request().path("/api/categories/intersect?names=Cities&mode=some").reply(&routes).await;
+        assert_eq!(response.status(), warp::http::StatusCode::BAD_REQUEST);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_related_articles_respect_min_score() -> WikiResult<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let server = test_server(temp_dir.path()).await?;
+        let conn = Connection::open(temp_dir.path().join("wiki.db"))?;
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        for (title, embedding) in [
+            ("Lion", [1.0, 0.0]),
+            ("Tiger", [0.9, 0.1]),
+            ("Leopard", [0.7, 0.3]),
+            ("Oak", [0.0, 1.0]),
+        ] {
+            writer.write_article(&WikiArticle::new(title.to_string(), format!("{}.", title)), &tx)?;
+            server.vector_store.store_embedding(title, &embedding)?;
+        }
+        DatabaseWriter::commit_transaction(tx)?;
+        let routes = server.routes().await?;
+
+        let related = |path: &'static str| {
+            let routes = routes.clone();
+            async move {
+                let response = warp::test::request().path(path).reply(&routes).await;
+                assert_eq!(response.status(), warp::http::StatusCode::OK, "{}", path);
+                serde_json::from_slice::<Vec<SemanticSearchResult>>(response.body())
+                    .unwrap()
+                    .into_iter()
+                    .map(|result| result.title)
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(related("/api/articles/Lion/related").await, vec!["Tiger", "Leopard", "Oak"]);
+        assert_eq!(related("/api/articles/Lion/related?min_score=0.5").await, vec!["Tiger", "Leopard"]);
+        assert_eq!(related("/api/articles/Lion/related?min_score=0.95").await, vec!["Tiger"]);
+        assert_eq!(related("/api/articles/Lion/related?max_candidates=1").await, vec!["Tiger"]);
+
+        let response = warp::test::request().path("/api/articles/Lion/related?max_candidates=0").reply(&routes).await;
+        assert_eq!(response.status(), warp::http::StatusCode::BAD_REQUEST);
+        let response = warp::test::request().path("/api/articles/Missing/related").reply(&routes).await;
+        assert_eq!(response.status(), warp::http::StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_semantic_search_with_empty_vector_store_is_unavailable() -> WikiResult<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let routes = test_server(temp_dir.path()).await?.routes().await?;
+
+        let response = warp::test::request()
+            .path("/api/semantic-search?query=stars")
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), warp::http::StatusCode::SERVICE_UNAVAILABLE);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert!(body["message"].as_str().unwrap().contains("run reembed"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_summary_model_override() -> WikiResult<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let mut ollama = mockito::Server::new_async().await;
+        ollama.mock("GET", "/api/tags")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"models":[{"name":"llama2:latest"},{"name":"mistral:latest"}]}"#)
+            .create_async()
+            .await;
+        let generate = ollama.mock("POST", "/api/generate")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({ "model": "mistral" })))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"model":"mistral","response":"Mistral summary."}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let db_path = temp_dir.path().join("wiki.db");
+        let conn = Connection::open(&db_path)?;
+        init_database(&conn)?;
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        writer.write_article(&WikiArticle::new("Alpha".to_string(), "First.".to_string()), &tx)?;
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let vector_store = Arc::new(VectorStore::new(temp_dir.path().join("vectors"), &ollama.url()).await?);
+        let llm_service = Arc::new(LlmService::new(&ollama.url(), None)?);
+        let routes = ApiServer::new(&db_path.to_string_lossy(), vector_store, llm_service)
+            .routes()
+            .await?;
+
+        let unknown = warp::test::request()
+            .path("/api/articles/Alpha/summary?model=gpt-none")
+            .reply(&routes)
+            .await;
+        assert_eq!(unknown.status(), warp::http::StatusCode::BAD_REQUEST);
+
+        let known = warp::test::request()
+            .path("/api/articles/Alpha/summary?model=mistral")
+            .reply(&routes)
+            .await;
+        assert_eq!(known.status(), warp::http::StatusCode::OK);
+        let summary: SummaryResponse = serde_json::from_slice(known.body()).unwrap();
+        assert_eq!(summary.summary, "Mistral summary.");
+
+        generate.assert_async().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ask_article() -> WikiResult<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let mut ollama = mockito::Server::new_async().await;
+        ollama.mock("POST", "/api/generate")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"model":"llama2","response":"It orbits the Sun [1]."}"#)
+            .create_async()
+            .await;
+
+        let db_path = temp_dir.path().join("wiki.db");
+        let conn = Connection::open(&db_path)?;
+        init_database(&conn)?;
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        writer.write_article(&WikiArticle::new("Earth".to_string(), "Earth orbits the Sun.".to_string()), &tx)?;
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let vector_store = Arc::new(VectorStore::new(temp_dir.path().join("vectors"), &ollama.url()).await?);
+        let llm_service = Arc::new(LlmService::new(&ollama.url(), None)?);
+        let routes = ApiServer::new(&db_path.to_string_lossy(), vector_store, llm_service)
+            .routes()
+            .await?;
+
+        let response = warp::test::request()
+            .path("/api/articles/Earth/ask?q=What%20does%20Earth%20orbit%3F")
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), warp::http::StatusCode::OK);
+        let answer: AskResponse = serde_json::from_slice(response.body()).unwrap();
+        assert!(!answer.answer.is_empty());
+        assert_eq!(answer.citations.len(), 1);
+
+        let missing = warp::test::request()
+            .path("/api/articles/Mars/ask?q=What%20does%20Mars%20orbit%3F")
+            .reply(&routes)
+            .await;
+        assert_eq!(missing.status(), warp::http::StatusCode::NOT_FOUND);
+
+        let empty = warp::test::request().path("/api/articles/Earth/ask?q=").reply(&routes).await;
+        assert_eq!(empty.status(), warp::http::StatusCode::BAD_REQUEST);
+
+        Ok(())
+    }
+}
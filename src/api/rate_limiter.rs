@@ -5,7 +5,7 @@ use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 use warp::Filter;
 use warp::filters::BoxedFilter;
-use warp::http::StatusCode;
+use warp::http::{Method, StatusCode};
 use warp::reject::Rejection;
 use warp::reply::Reply;
 use serde::Serialize;
@@ -37,6 +37,16 @@ impl RateLimiter {
         }
     }
 
+    /// Maximum number of requests allowed in the window
+    pub fn max_requests(&self) -> usize {
+        self.max_requests
+    }
+
+    /// Window duration in seconds
+    pub fn window_secs(&self) -> u64 {
+        self.window_secs
+    }
+
     /// Check if a request from IP is allowed, and update request history
     pub async fn is_allowed(&self, ip: IpAddr) -> bool {
         let mut history = self.request_history.lock().await;
@@ -83,22 +93,8 @@ impl RateLimiter {
         0 // No waiting needed
     }
     
-    /// Periodically clean up expired entries
-    pub async fn start_cleanup(self, interval_secs: u64) {
-        let cleanup_interval = Duration::from_secs(interval_secs);
-        
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(cleanup_interval);
-            
-            loop {
-                interval.tick().await;
-                self.cleanup().await;
-            }
-        });
-    }
-    
     /// Clean up expired entries
-    async fn cleanup(&self) {
+    pub(crate) async fn cleanup(&self) {
         let mut history = self.request_history.lock().await;
         let now = Instant::now();
         let window_duration = Duration::from_secs(self.window_secs);
@@ -121,64 +117,249 @@ impl RateLimiter {
         }
     }
     
-    /// Create a warp filter that applies rate limiting
-    pub fn with_rate_limiting<T: Reply + Send>(
+    /// Create a warp filter that applies rate limiting with this limiter alone.
+    ///
+    /// `OPTIONS` requests (CORS preflights) pass through without using up the quota.
+    #[cfg(test)]
+    pub fn with_rate_limiting<T: Reply + Send + 'static>(
         &self,
         route: BoxedFilter<(T,)>,
     ) -> BoxedFilter<(impl Reply,)> {
         let rate_limiter = self.clone();
-        
-        warp::any()
-            .and(route)
-            .and(warp::addr::remote())
-            .and_then(move |reply: T, addr: Option<std::net::SocketAddr>| {
-                let rate_limiter = rate_limiter.clone();
-                async move {
-                    // Get IP from request or use a default
-                    let ip = addr
-                        .map(|socket_addr| socket_addr.ip())
-                        .unwrap_or_else(|| IpAddr::from([127, 0, 0, 1]));
-                    
-                    // Check if request is allowed
-                    if rate_limiter.is_allowed(ip).await {
-                        // Request allowed, return original reply
-                        Ok(warp::reply::with_header(
-                            reply,
-                            "X-RateLimit-Remaining",
-                            (rate_limiter.max_requests - 1).to_string(),
-                        ))
-                    } else {
-                        // Request denied, return 429 Too Many Requests
-                        let retry_after = rate_limiter.retry_after(ip).await;
-                        let response = RateLimitExceededResponse {
-                            status: "error".to_string(),
-                            message: "Rate limit exceeded. Please try again later.".to_string(),
-                            retry_after,
-                        };
-                        
-                        let json = warp::reply::json(&response);
-                        let reply = warp::reply::with_status(json, StatusCode::TOO_MANY_REQUESTS);
-                        let reply = warp::reply::with_header(
-                            reply,
-                            "Retry-After",
-                            retry_after.to_string(),
-                        );
-                        
-                        Ok(reply)
-                    }
-                }
-            })
-            .boxed()
+        with_current_rate_limiter(route, move || rate_limiter.clone())
     }
 }
 
-/// Warp filter that applies rate limiting to a route
-pub fn with_rate_limiting<T: Reply + Send>(
-    rate_limiter: &RateLimiter,
-    route: BoxedFilter<(T,)>,
-) -> BoxedFilter<(impl Reply,)> {
-    rate_limiter.with_rate_limiting(route)
+/// Warp filter that applies rate limiting with whichever limiter `current`
+/// returns for each request, so limiters can be swapped while serving.
+///
+/// `OPTIONS` requests (CORS preflights) pass through without using up the quota.
+pub fn with_current_rate_limiter<T, F>(route: BoxedFilter<(T,)>, current: F) -> BoxedFilter<(impl Reply,)>
+where
+    T: Reply + Send + 'static,
+    F: Fn() -> RateLimiter + Clone + Send + Sync + 'static,
+{
+    warp::any()
+        .and(route)
+        .and(warp::addr::remote())
+        .and(warp::method())
+        .and_then(move |reply: T, addr: Option<std::net::SocketAddr>, method: Method| {
+            let rate_limiter = current();
+            async move {
+                if method == Method::OPTIONS {
+                    return Ok::<_, Rejection>(reply.into_response());
+                }
+
+                // Get IP from request or use a default
+                let ip = addr
+                    .map(|socket_addr| socket_addr.ip())
+                    .unwrap_or_else(|| IpAddr::from([127, 0, 0, 1]));
+                
+                // Check if request is allowed
+                if rate_limiter.is_allowed(ip).await {
+                    // Request allowed, return original reply
+                    Ok(warp::reply::with_header(
+                        reply,
+                        "X-RateLimit-Remaining",
+                        (rate_limiter.max_requests - 1).to_string(),
+                    ).into_response())
+                } else {
+                    // Request denied, return 429 Too Many Requests
+                    let retry_after = rate_limiter.retry_after(ip).await;
+                    let response = RateLimitExceededResponse {
+                        status: "error".to_string(),
+                        message: "Rate limit exceeded. Please try again later.".to_string(),
+                        retry_after,
+                    };
+                    
+                    let json = warp::reply::json(&response);
+                    let reply = warp::reply::with_status(json, StatusCode::TOO_MANY_REQUESTS);
+                    let reply = warp::reply::with_header(
+                        reply,
+                        "Retry-After",
+                        retry_after.to_string(),
+                    );
+                    
+                    Ok(reply.into_response())
+                }
+            }
+        })
+        .boxed()
 }
 
 #[cfg(test)]
-mod tests; 
\ No newline at end of file
+mod tests {
+    use super::*;
+    use std::net::IpAddr;
+    use std::str::FromStr;
+    use tokio::time::{sleep, Duration};
+    use warp::test::request;
+    use warp::http::StatusCode;
+
+    #[tokio::test]
+    async fn test_rate_limiter_allows_within_limit() {
+        let rate_limiter = RateLimiter::new(5, 60);
+        let ip = IpAddr::from_str("127.0.0.1").unwrap();
+        
+        // Should allow 5 requests in a row
+        for _ in 0..5 {
+            assert!(rate_limiter.is_allowed(ip).await);
+        }
+        
+        // Should deny the 6th request
+        assert!(!rate_limiter.is_allowed(ip).await);
+    }
+    
+    #[tokio::test]
+    async fn test_rate_limiter_reset_after_window() {
+        let rate_limiter = RateLimiter::new(2, 1); // 2 requests per second
+        let ip = IpAddr::from_str("127.0.0.1").unwrap();
+        
+        // Use up the quota
+        assert!(rate_limiter.is_allowed(ip).await);
+        assert!(rate_limiter.is_allowed(ip).await);
+        assert!(!rate_limiter.is_allowed(ip).await);
+        
+        // Wait for the window to pass
+        sleep(Duration::from_secs(1)).await;
+        
+        // Should be allowed again
+        assert!(rate_limiter.is_allowed(ip).await);
+    }
+    
+    #[tokio::test]
+    async fn test_rate_limiter_retry_after() {
+        let rate_limiter = RateLimiter::new(3, 10); // 3 requests per 10 seconds
+        let ip = IpAddr::from_str("127.0.0.1").unwrap();
+        
+        // Use up the quota
+        assert!(rate_limiter.is_allowed(ip).await);
+        assert!(rate_limiter.is_allowed(ip).await);
+        assert!(rate_limiter.is_allowed(ip).await);
+        assert!(!rate_limiter.is_allowed(ip).await);
+        
+        // Should recommend waiting close to 10 seconds
+        let retry_after = rate_limiter.retry_after(ip).await;
+        assert!(retry_after > 0);
+        assert!(retry_after <= 11); // Allow some flexibility in timing
+    }
+    
+    #[tokio::test]
+    async fn test_rate_limiter_different_ips() {
+        let rate_limiter = RateLimiter::new(2, 60);
+        let ip1 = IpAddr::from_str("127.0.0.1").unwrap();
+        let ip2 = IpAddr::from_str("192.168.1.1").unwrap();
+        
+        // Both IPs should be allowed their quota independently
+        assert!(rate_limiter.is_allowed(ip1).await);
+        assert!(rate_limiter.is_allowed(ip1).await);
+        assert!(!rate_limiter.is_allowed(ip1).await);
+        
+        assert!(rate_limiter.is_allowed(ip2).await);
+        assert!(rate_limiter.is_allowed(ip2).await);
+        assert!(!rate_limiter.is_allowed(ip2).await);
+    }
+    
+    #[tokio::test]
+    async fn test_rate_limiter_cleanup() {
+        let rate_limiter = RateLimiter::new(2, 1); // 2 requests per second
+        let ip1 = IpAddr::from_str("127.0.0.1").unwrap();
+        let ip2 = IpAddr::from_str("192.168.1.1").unwrap();
+        
+        // Add some requests
+        rate_limiter.is_allowed(ip1).await;
+        rate_limiter.is_allowed(ip2).await;
+        
+        // Both IPs should have entries
+        {
+            let history = rate_limiter.request_history.lock().await;
+            assert_eq!(history.len(), 2);
+        }
+        
+        // Wait for window to pass
+        sleep(Duration::from_secs(1)).await;
+        
+        // Run cleanup
+        rate_limiter.cleanup().await;
+        
+        // Both IPs should be removed since their timestamps expired
+        {
+            let history = rate_limiter.request_history.lock().await;
+            assert_eq!(history.len(), 0);
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_with_rate_limiting_filter() {
+        // Create a rate limiter
+        let rate_limiter = RateLimiter::new(2, 60);
+        
+        // Create a simple test route
+        let route = warp::any()
+            .map(|| "Hello, World!")
+            .boxed();
+        
+        // Apply rate limiting
+        let limited_route = rate_limiter.with_rate_limiting(route);
+        
+        // First request should pass with 200 OK
+        let resp1 = request()
+            .method("GET")
+            .path("/")
+            .remote_addr(([127, 0, 0, 1], 8080).into())
+            .reply(&limited_route)
+            .await;
+        assert_eq!(resp1.status(), StatusCode::OK);
+        assert_eq!(resp1.body(), "Hello, World!");
+        
+        // Second request should pass with 200 OK
+        let resp2 = request()
+            .method("GET")
+            .path("/")
+            .remote_addr(([127, 0, 0, 1], 8080).into())
+            .reply(&limited_route)
+            .await;
+        assert_eq!(resp2.status(), StatusCode::OK);
+        assert_eq!(resp2.body(), "Hello, World!");
+        
+        // Third request should be rate limited with 429 Too Many Requests
+        let resp3 = request()
+            .method("GET")
+            .path("/")
+            .remote_addr(([127, 0, 0, 1], 8080).into())
+            .reply(&limited_route)
+            .await;
+        assert_eq!(resp3.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(resp3.headers().contains_key("retry-after"));
+    }
+    
+    #[tokio::test]
+    async fn test_options_requests_are_not_counted() {
+        let rate_limiter = RateLimiter::new(2, 60);
+        let route = warp::any()
+            .map(|| "Hello, World!")
+            .boxed();
+        let limited_route = rate_limiter.with_rate_limiting(route);
+        
+        // Preflights well beyond the quota
+        for _ in 0..5 {
+            let resp = request()
+                .method("OPTIONS")
+                .path("/")
+                .remote_addr(([127, 0, 0, 1], 8080).into())
+                .reply(&limited_route)
+                .await;
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+        
+        // The real request still has its full quota
+        let resp = request()
+            .method("GET")
+            .path("/")
+            .remote_addr(([127, 0, 0, 1], 8080).into())
+            .reply(&limited_route)
+            .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.body(), "Hello, World!");
+    }
+}
@@ -0,0 +1,157 @@
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use arc_swap::ArcSwap;
+use tracing::{info, warn};
+
+use crate::error_handling::{WikiError, WikiResult};
+use super::cors::AllowedOrigins;
+use super::{ApiConfig, ApiRateLimiters, ApiServer};
+
+impl ApiConfig {
+    /// Read settings from a JSON file; fields it leaves out keep their defaults
+    pub fn load<P: AsRef<Path>>(path: P) -> WikiResult<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+        serde_json::from_str(&contents)
+            .map_err(|e| WikiError::Configuration(format!("Invalid config file {}: {}", path.display(), e)))
+    }
+
+    /// Address to listen on, from `host` and `port`
+    pub fn socket_addr(&self) -> WikiResult<SocketAddr> {
+        let ip: IpAddr = self.host.parse()
+            .map_err(|_| WikiError::Configuration(format!("Invalid host address: {}", self.host)))?;
+        Ok(SocketAddr::new(ip, self.port))
+    }
+
+    /// Settings that differ in `other` but only take effect when the server restarts
+    pub fn restart_required_changes(&self, other: &ApiConfig) -> Vec<&'static str> {
+        let mut changed = Vec::new();
+        if self.port != other.port {
+            changed.push("port");
+        }
+        if self.host != other.host {
+            changed.push("host");
+        }
+        if self.db_path != other.db_path {
+            changed.push("db_path");
+        }
+        if self.vector_path != other.vector_path {
+            changed.push("vector_path");
+        }
+        if self.ollama_url != other.ollama_url {
+            changed.push("ollama_url");
+        }
+        changed
+    }
+}
+
+/// Settings of a running server that a config reload swaps in place
+#[derive(Clone)]
+pub struct ReloadHandle {
+    rate_limiters: Arc<ArcSwap<ApiRateLimiters>>,
+    allowed_origins: AllowedOrigins,
+}
+
+impl ReloadHandle {
+    /// Swap in the rate limits and allowed origins of `config`.
+    ///
+    /// Requests already in flight finish under the old settings; request
+    /// history starts afresh under the new limits.
+    pub fn apply(&self, config: &ApiConfig) {
+        self.rate_limiters.store(Arc::new(ApiRateLimiters::from_config(&config.rate_limits)));
+        self.allowed_origins.store(Arc::new(config.allowed_origins.clone()));
+    }
+
+    /// Re-read `path` and apply it on top of `active`, warning about changed
+    /// settings that need a restart. Returns the config now in effect.
+    pub fn reload(&self, path: &Path, active: &ApiConfig) -> WikiResult<ApiConfig> {
+        let loaded = ApiConfig::load(path)?;
+        for setting in active.restart_required_changes(&loaded) {
+            warn!("Ignoring change to {} in {}; restart the server to apply it", setting, path.display());
+        }
+        self.apply(&loaded);
+
+        Ok(ApiConfig {
+            allowed_origins: loaded.allowed_origins,
+            rate_limits: loaded.rate_limits,
+            ..active.clone()
+        })
+    }
+
+    /// Reload `path` on every `SIGHUP` for as long as the runtime runs
+    #[cfg(unix)]
+    pub fn watch_sighup(self, path: PathBuf, active: ApiConfig) -> WikiResult<tokio::task::JoinHandle<()>> {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut hangups = signal(SignalKind::hangup())?;
+        Ok(tokio::spawn(async move {
+            let mut active = active;
+            while hangups.recv().await.is_some() {
+                match self.reload(&path, &active) {
+                    Ok(config) => {
+                        info!("Reloaded config from {}", path.display());
+                        active = config;
+                    }
+                    Err(e) => warn!("Failed to reload config from {}, keeping current settings: {}", path.display(), e),
+                }
+            }
+        }))
+    }
+}
+
+impl ApiServer {
+    /// Handle for swapping the rate limits and allowed origins of this server while it runs
+    pub fn reload_handle(&self) -> ReloadHandle {
+        ReloadHandle {
+            rate_limiters: self.rate_limiters.clone(),
+            allowed_origins: self.allowed_origins.clone(),
+        }
+    }
+
+    /// Apply the rate limits and allowed origins of `config` to this server, including running routes
+    pub fn reload_config(&self, config: &ApiConfig) {
+        self.reload_handle().apply(config);
+    }
+
+    /// Serve on `config.host` and `config.port`, reloading rate limits and allowed
+    /// origins from `path` whenever the process receives `SIGHUP`
+    pub async fn run_with_config_file(&self, path: PathBuf, config: ApiConfig) -> WikiResult<()> {
+        let addr = config.socket_addr()?;
+        #[cfg(unix)]
+        self.reload_handle().watch_sighup(path, config)?;
+        #[cfg(not(unix))]
+        {
+            let _ = (path, config);
+            warn!("Config reload on SIGHUP is not supported on this platform");
+        }
+        self.serve(addr).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_config_file_keeps_defaults() -> WikiResult<()> {
+        let file = tempfile::NamedTempFile::new()?;
+        std::fs::write(file.path(), r#"{"port": 9090, "rate_limits": {"llm": {"max_requests": 1, "window_secs": 10}}}"#)?;
+
+        let config = ApiConfig::load(file.path())?;
+        let defaults = ApiConfig::default();
+        assert_eq!(config.port, 9090);
+        assert_eq!(config.rate_limits.llm.max_requests, 1);
+        assert_eq!(config.rate_limits.standard, defaults.rate_limits.standard);
+        assert_eq!(config.allowed_origins, defaults.allowed_origins);
+        assert_eq!(defaults.restart_required_changes(&config), vec!["port"]);
+        assert_eq!(config.socket_addr()?, SocketAddr::from(([127, 0, 0, 1], 9090)));
+
+        std::fs::write(file.path(), "{not json")?;
+        assert!(matches!(ApiConfig::load(file.path()), Err(WikiError::Configuration(_))));
+
+        let config = ApiConfig { host: "localhost:80".to_string(), ..ApiConfig::default() };
+        assert!(matches!(config.socket_addr(), Err(WikiError::Configuration(_))));
+        Ok(())
+    }
+}
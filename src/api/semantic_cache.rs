@@ -0,0 +1,126 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::lru::LruMap;
+use super::SemanticSearchResult;
+
+/// Semantic queries whose results are kept by default
+pub const DEFAULT_SEMANTIC_CACHE_SIZE: usize = 256;
+
+/// How long cached semantic results are served before the query is recomputed
+pub const DEFAULT_SEMANTIC_CACHE_TTL: Duration = Duration::from_secs(300);
+
+#[derive(Debug)]
+struct CacheEntry {
+    results: Vec<SemanticSearchResult>,
+    generation: usize,
+    inserted: Instant,
+}
+
+/// Semantic search results kept in memory, keyed by the normalized query and
+/// page size and tagged with the vector store generation they were computed
+/// against. Least recently used entries are evicted once `capacity` is reached.
+#[derive(Debug, Clone)]
+pub struct SemanticCache {
+    state: Arc<std::sync::Mutex<LruMap<(String, usize), CacheEntry>>>,
+    ttl: Duration,
+}
+
+impl Default for SemanticCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_SEMANTIC_CACHE_SIZE, DEFAULT_SEMANTIC_CACHE_TTL)
+    }
+}
+
+impl SemanticCache {
+    /// Cache holding up to `capacity` queries for `ttl` each; a capacity of 0 disables caching
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            state: Arc::new(std::sync::Mutex::new(LruMap::new(capacity))),
+            ttl,
+        }
+    }
+
+    /// Cached results for `query`, if they were computed against `generation` and haven't expired
+    pub fn get(&self, query: &str, limit: usize, generation: usize) -> Option<Vec<SemanticSearchResult>> {
+        let key = (normalize_query(query), limit);
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        let entry = state.peek(&key)?;
+        if entry.generation != generation || entry.inserted.elapsed() >= self.ttl {
+            state.remove(&key);
+            return None;
+        }
+        state.get(&key).map(|entry| entry.results.clone())
+    }
+
+    /// Remember `results` for `query`, evicting the least recently used query when full
+    pub fn insert(&self, query: &str, limit: usize, generation: usize, results: Vec<SemanticSearchResult>) {
+        let key = (normalize_query(query), limit);
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).insert(key, CacheEntry {
+            results,
+            generation,
+            inserted: Instant::now(),
+        });
+    }
+
+    /// Drop every cached query
+    pub fn clear(&self) {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).is_empty()
+    }
+}
+
+/// Case- and whitespace-insensitive form of a query used as the cache key
+fn normalize_query(query: &str) -> String {
+    query.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(title: &str) -> Vec<SemanticSearchResult> {
+        vec![SemanticSearchResult {
+            title: title.to_string(),
+            excerpt: String::new(),
+            categories: Vec::new(),
+            score: 1.0,
+        }]
+    }
+
+    #[test]
+    fn test_semantic_cache_evicts_and_invalidates() {
+        let cache = SemanticCache::new(2, DEFAULT_SEMANTIC_CACHE_TTL);
+        cache.insert("Alpha  Query", 10, 1, result("Alpha"));
+        assert_eq!(cache.get("alpha query", 10, 1).unwrap()[0].title, "Alpha");
+        assert!(cache.get("alpha query", 5, 1).is_none());
+
+        // A write to the vector store makes older results stale
+        assert!(cache.get("alpha query", 10, 2).is_none());
+        assert!(cache.is_empty());
+
+        cache.insert("alpha", 10, 2, result("Alpha"));
+        cache.insert("beta", 10, 2, result("Beta"));
+        assert!(cache.get("alpha", 10, 2).is_some());
+        cache.insert("gamma", 10, 2, result("Gamma"));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("beta", 10, 2).is_none());
+        assert!(cache.get("alpha", 10, 2).is_some());
+
+        let expired = SemanticCache::new(2, Duration::ZERO);
+        expired.insert("alpha", 10, 2, result("Alpha"));
+        assert!(expired.get("alpha", 10, 2).is_none());
+
+        let disabled = SemanticCache::new(0, DEFAULT_SEMANTIC_CACHE_TTL);
+        disabled.insert("alpha", 10, 2, result("Alpha"));
+        assert!(disabled.is_empty());
+    }
+}
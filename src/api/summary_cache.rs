@@ -0,0 +1,150 @@
+use std::sync::Arc;
+use rusqlite::Connection;
+
+use crate::db::{DatabaseReader, DatabaseWriter};
+use crate::error_handling::WikiResult;
+use super::lru::LruMap;
+
+/// Summaries kept in memory by default; older ones are still read from the `summaries` table
+pub const DEFAULT_SUMMARY_CACHE_SIZE: usize = 1024;
+
+#[derive(Debug)]
+struct CacheEntry {
+    etag: String,
+    summary: String,
+}
+
+/// Generated article summaries kept in memory, keyed by article title and
+/// tagged with the hash of the content and LLM options they were made from.
+/// Least recently used entries are evicted once `capacity` is reached.
+///
+/// The `summaries` table backs the cache, so summaries outlive the server and
+/// can be generated ahead of time by `warmup --summaries`.
+#[derive(Debug, Clone)]
+pub struct SummaryCache {
+    state: Arc<std::sync::Mutex<LruMap<String, CacheEntry>>>,
+}
+
+impl Default for SummaryCache {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_SUMMARY_CACHE_SIZE)
+    }
+}
+
+impl SummaryCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cache holding up to `capacity` summaries; a capacity of 0 leaves every lookup to the database
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            state: Arc::new(std::sync::Mutex::new(LruMap::new(capacity))),
+        }
+    }
+
+    /// Cached summary for `title`, if it was generated from the content and options behind `etag`
+    pub fn get(&self, title: &str, etag: &str) -> Option<String> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.peek(title)?.etag != etag {
+            return None;
+        }
+        state.get(title).map(|entry| entry.summary.clone())
+    }
+
+    /// Remember `summary` for `title`, replacing any summary of an older revision
+    /// and evicting the least recently used title when full
+    pub fn insert(&self, title: &str, etag: &str, summary: String) {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).insert(title.to_string(), CacheEntry {
+            etag: etag.to_string(),
+            summary,
+        });
+    }
+
+    /// Cached summary for `title`, falling back to the `summaries` table and keeping what it finds
+    pub fn load(&self, conn: &Connection, title: &str, etag: &str) -> WikiResult<Option<String>> {
+        if let Some(summary) = self.get(title, etag) {
+            return Ok(Some(summary));
+        }
+        let stored = DatabaseReader::new(conn).get_summary(title, etag)?;
+        if let Some(ref summary) = stored {
+            self.insert(title, etag, summary.clone());
+        }
+        Ok(stored)
+    }
+
+    /// Remember `summary` for `title` and store it in the `summaries` table
+    pub fn save(&self, conn: &Connection, title: &str, etag: &str, summary: String) -> WikiResult<()> {
+        let writer = DatabaseWriter::new(conn);
+        let tx = writer.begin_transaction()?;
+        writer.save_summary(title, etag, &summary, &tx)?;
+        DatabaseWriter::commit_transaction(tx)?;
+        self.insert(title, etag, summary);
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).is_empty()
+    }
+}
+
+/// Whether an `If-None-Match` header value matches `etag`
+pub fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match.split(',').any(|candidate| {
+        let candidate = candidate.trim();
+        candidate == "*" || candidate.trim_start_matches("W/") == etag
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{summary_etag, LlmService};
+
+    #[test]
+    fn test_etag_changes_with_content_and_options() {
+        let llm = LlmService::new("http://localhost:11434", None).unwrap();
+        let etag = summary_etag("Some content.", &llm);
+        assert_eq!(etag, summary_etag("Some content.", &llm));
+        assert_ne!(etag, summary_etag("Edited content.", &llm));
+
+        let other_model = LlmService::new("http://localhost:11434", Some("mistral")).unwrap();
+        assert_ne!(etag, summary_etag("Some content.", &other_model));
+
+        assert!(etag_matches(&etag, &etag));
+        assert!(etag_matches(&format!("\"stale\", W/{}", etag), &etag));
+        assert!(!etag_matches("\"stale\"", &etag));
+
+        let cache = SummaryCache::new();
+        cache.insert("Title", &etag, "Summary.".to_string());
+        assert_eq!(cache.get("Title", &etag).as_deref(), Some("Summary."));
+        assert_eq!(cache.get("Title", "\"stale\""), None);
+    }
+
+    #[test]
+    fn test_summary_cache_evicts_least_recently_used() {
+        let cache = SummaryCache::with_capacity(2);
+        cache.insert("Alpha", "\"a\"", "Alpha summary.".to_string());
+        cache.insert("Beta", "\"b\"", "Beta summary.".to_string());
+        assert!(cache.get("Alpha", "\"a\"").is_some());
+
+        cache.insert("Gamma", "\"c\"", "Gamma summary.".to_string());
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("Beta", "\"b\""), None);
+        assert!(cache.get("Alpha", "\"a\"").is_some());
+        assert!(cache.get("Gamma", "\"c\"").is_some());
+
+        // A newer revision replaces the entry instead of taking another slot
+        cache.insert("Alpha", "\"a2\"", "New alpha summary.".to_string());
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("Alpha", "\"a2\"").as_deref(), Some("New alpha summary."));
+
+        let disabled = SummaryCache::with_capacity(0);
+        disabled.insert("Alpha", "\"a\"", "Alpha summary.".to_string());
+        assert!(disabled.is_empty());
+    }
+}
@@ -0,0 +1,103 @@
+use std::net::SocketAddr;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+use crate::error_handling::{WikiError, WikiResult};
+use super::ApiServer;
+
+/// An API server running in-process on an OS-assigned port, for tests
+pub struct TestServer {
+    addr: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+    handle: JoinHandle<()>,
+}
+
+impl TestServer {
+    /// Address the server is listening on
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Base URL of the server, e.g. `http://127.0.0.1:54321`
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Stop accepting connections and wait for the server task to finish
+    pub async fn shutdown(mut self) -> WikiResult<()> {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        (&mut self.handle)
+            .await
+            .map_err(|e| WikiError::OperationFailed(format!("Test server task failed: {}", e)))
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        // Servers that were not shut down explicitly are stopped with the test
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+impl ApiServer {
+    /// Start the server on `127.0.0.1:0` in a background task.
+    ///
+    /// The returned handle reports the chosen address and stops the server
+    /// on `shutdown()` or when dropped.
+    pub async fn spawn_test_server(&self) -> WikiResult<TestServer> {
+        let routes = self.routes().await?;
+        let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+
+        let (addr, server) = warp::serve(routes)
+            .try_bind_with_graceful_shutdown(([127, 0, 0, 1], 0), async {
+                let _ = shutdown_rx.await;
+            })
+            .map_err(|e| WikiError::OperationFailed(format!("Failed to bind test server: {}", e)))?;
+
+        Ok(TestServer {
+            addr,
+            shutdown: Some(shutdown),
+            handle: tokio::spawn(server),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::db::schema::init_database;
+    use crate::llm::{LlmService, DEFAULT_OLLAMA_URL};
+    use crate::vector::VectorStore;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_spawn_query_and_stop() -> WikiResult<()> {
+        let temp_dir = TempDir::new()?;
+        let db_path = temp_dir.path().join("wiki.db");
+        init_database(&rusqlite::Connection::open(&db_path)?)?;
+        let vector_store = Arc::new(VectorStore::new(temp_dir.path().join("vectors"), DEFAULT_OLLAMA_URL).await?);
+        let llm_service = Arc::new(LlmService::new(DEFAULT_OLLAMA_URL, None)?);
+
+        let server = ApiServer::new(&db_path.to_string_lossy(), vector_store, llm_service)
+            .spawn_test_server()
+            .await?;
+        assert_ne!(server.addr().port(), 0);
+
+        let status: serde_json::Value = reqwest::get(format!("{}/api/status", server.url()))
+            .await?
+            .json()
+            .await?;
+        assert_eq!(status["status"], "ok");
+
+        let url = server.url();
+        server.shutdown().await?;
+        assert!(reqwest::get(format!("{}/api/status", url)).await.is_err());
+
+        Ok(())
+    }
+}
@@ -2,6 +2,7 @@ use regex::Regex;
 use serde::{Deserialize, Serialize};
 use warp::{Filter, Rejection, reject, Reply};
 use std::sync::Arc;
+use percent_encoding::percent_decode_str;
 
 /// Error response for validation failures
 #[derive(Debug, Serialize)]
@@ -28,6 +29,9 @@ pub fn validation_error(message: &str, field: Option<&str>) -> ValidationError {
     }
 }
 
+/// Longest question accepted by the article QA endpoint
+const MAX_QUESTION_LENGTH: usize = 500;
+
 /// Validation rules for search queries
 pub struct SearchQueryValidator {
     /// Maximum length allowed for search query
@@ -175,48 +179,107 @@ impl TitleValidator {
     }
 }
 
+/// Validate every field of a search request
+fn check_search_query(
+    validator: &SearchQueryValidator,
+    limits: super::ApiLimits,
+    query: &super::SearchQuery,
+) -> Result<(), ValidationError> {
+    // Validate query string
+    validator.validate_query(&query.query)?;
+    
+    // Validate limit, letting oversized limits through when they will be clamped
+    let limit = if limits.clamp_page_size {
+        query.limit.map(|limit| limit.min(limits.max_page_size))
+    } else {
+        query.limit
+    };
+    validator.validate_limit(limit)?;
+    
+    // Validate offset
+    validator.validate_offset(query.offset)
+}
+
 /// Create a warp filter for validating search queries
-pub fn validate_search_query() -> impl Filter<Extract = (), Error = Rejection> + Clone {
-    let validator = Arc::new(SearchQueryValidator::default());
+pub fn validate_search_query(limits: super::ApiLimits) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    let validator = Arc::new(SearchQueryValidator {
+        max_limit: limits.max_page_size,
+        ..SearchQueryValidator::default()
+    });
     
     warp::query::<super::SearchQuery>()
         .and_then(move |query: super::SearchQuery| {
             let validator = validator.clone();
             async move {
-                // Validate query string
-                if let Err(e) = validator.validate_query(&query.query) {
-                    return Err(warp::reject::custom(e));
-                }
-                
-                // Validate limit
-                if let Err(e) = validator.validate_limit(query.limit) {
-                    return Err(warp::reject::custom(e));
-                }
-                
-                // Validate offset
-                if let Err(e) = validator.validate_offset(query.offset) {
-                    return Err(warp::reject::custom(e));
-                }
-                
-                Ok(())
+                check_search_query(&validator, limits, &query).map_err(warp::reject::custom)
             }
         })
 }
 
-/// Create a warp filter for validating article titles
-pub fn validate_article_title() -> impl Filter<Extract = (), Error = Rejection> + Clone {
+/// Create a warp filter that reads a search request from a size-limited JSON body and validates it
+pub fn validate_search_body(
+    limits: super::ApiLimits,
+) -> impl Filter<Extract = (super::SearchQuery,), Error = Rejection> + Clone {
+    let validator = Arc::new(SearchQueryValidator {
+        max_limit: limits.max_page_size,
+        ..SearchQueryValidator::default()
+    });
+    
+    super::json_body::<super::SearchQuery>(limits.max_body_bytes)
+        .and_then(move |query: super::SearchQuery| {
+            let validator = validator.clone();
+            async move {
+                check_search_query(&validator, limits, &query)
+                    .map(|_| query)
+                    .map_err(warp::reject::custom)
+            }
+        })
+}
+
+/// Validate a question asked about an article
+fn check_question(question: &str) -> Result<(), ValidationError> {
+    if question.trim().is_empty() {
+        return Err(validation_error("Question cannot be empty", Some("q")));
+    }
+
+    if question.chars().count() > MAX_QUESTION_LENGTH {
+        return Err(validation_error(
+            &format!("Question exceeds maximum length of {} characters", MAX_QUESTION_LENGTH),
+            Some("q")
+        ));
+    }
+
+    Ok(())
+}
+
+/// Create a warp filter that reads and validates the `q` parameter of an article question
+pub fn validate_question() -> impl Filter<Extract = (super::AskQuery,), Error = Rejection> + Clone {
+    warp::query::<super::AskQuery>()
+        .and_then(|query: super::AskQuery| async move {
+            check_question(&query.q)
+                .map(|_| query)
+                .map_err(warp::reject::custom)
+        })
+}
+
+/// Create a warp filter that reads the article title path segment, percent-decodes it and validates it
+pub fn validate_article_title() -> impl Filter<Extract = (String,), Error = Rejection> + Clone {
     let validator = Arc::new(TitleValidator::default());
     
     warp::path::param::<String>()
-        .and_then(move |title: String| {
+        .and_then(move |segment: String| {
             let validator = validator.clone();
             async move {
+                // warp hands over the raw segment, so `Alpha%20Centauri` still needs decoding
+                let title = percent_decode_str(&segment)
+                    .decode_utf8()
+                    .map_err(|_| warp::reject::custom(validation_error("Article title is not valid UTF-8", Some("title"))))?
+                    .into_owned();
                 if let Err(e) = validator.validate(&title) {
                     return Err(warp::reject::custom(e));
                 }
                 
-                Ok(())
+                Ok(title)
             }
         })
-        .untuple_one()
 } 
\ No newline at end of file
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use rusqlite::{Connection, params};
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+use crate::error_handling::WikiResult;
+
+/// Buffers article views in memory and writes them to `view_counts` in batches,
+/// so serving an article never waits on a database write
+#[derive(Debug, Clone, Default)]
+pub struct ViewCounter {
+    pending: Arc<std::sync::Mutex<HashMap<String, u64>>>,
+}
+
+impl ViewCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count one view of `title`
+    pub fn record(&self, title: &str) {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        *pending.entry(title.to_string()).or_insert(0) += 1;
+    }
+
+    /// Add the buffered views to the database in one transaction, returning how many articles were updated
+    pub fn flush(&self, conn: &mut Connection) -> WikiResult<usize> {
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap_or_else(|e| e.into_inner()));
+        if pending.is_empty() {
+            return Ok(0);
+        }
+
+        let result = (|| -> WikiResult<()> {
+            let tx = conn.transaction()?;
+            {
+                let mut stmt = tx.prepare(
+                    "INSERT INTO view_counts (title, views) VALUES (?1, ?2)
+                     ON CONFLICT(title) DO UPDATE SET views = views + excluded.views"
+                )?;
+                for (title, views) in &pending {
+                    stmt.execute(params![title, *views as i64])?;
+                }
+            }
+            tx.commit()?;
+            Ok(())
+        })();
+
+        if let Err(e) = result {
+            // Put the views back so they are retried on the next flush
+            let mut buffered = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+            for (title, views) in pending {
+                *buffered.entry(title).or_insert(0) += views;
+            }
+            return Err(e);
+        }
+
+        debug!("Flushed view counts for {} articles", pending.len());
+        Ok(pending.len())
+    }
+
+    /// Periodically flush buffered views to the database
+    pub async fn start_flush(self, db: Arc<Mutex<Connection>>, interval_secs: u64) {
+        let flush_interval = Duration::from_secs(interval_secs);
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(flush_interval);
+
+            loop {
+                interval.tick().await;
+                let mut conn = db.lock().await;
+                if let Err(e) = self.flush(&mut conn) {
+                    warn!("Failed to flush view counts: {}", e);
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,163 @@
+use reqwest::{Client, Response, Url};
+use serde::de::DeserializeOwned;
+
+use crate::api::{ArticleResponse, SemanticSearchResult, StatsResponse, SummaryResponse};
+use crate::error_handling::{WikiError, WikiResult};
+
+/// Typed client for the HTTP API served by [`crate::api::ApiServer`]
+#[derive(Clone)]
+pub struct WikiApiClient {
+    base_url: Url,
+    client: Client,
+}
+
+impl WikiApiClient {
+    /// Create a client for a server at `base_url`, e.g. `http://127.0.0.1:8080`
+    pub fn new(base_url: &str) -> WikiResult<Self> {
+        Self::with_client(base_url, crate::http::default_client())
+    }
+
+    /// Create a client that sends requests through an existing `reqwest::Client`
+    pub fn with_client(base_url: &str, client: Client) -> WikiResult<Self> {
+        let base_url = Url::parse(base_url)
+            .map_err(|e| WikiError::Configuration(format!("Invalid API URL '{}': {}", base_url, e)))?;
+        if base_url.cannot_be_a_base() {
+            return Err(WikiError::Configuration(format!("Invalid API URL '{}'", base_url)));
+        }
+        Ok(Self { base_url, client })
+    }
+
+    pub async fn get_article(&self, title: &str) -> WikiResult<ArticleResponse> {
+        self.get(&["articles", title], &[]).await
+    }
+
+    /// Keyword search, returning at most `limit` results (the server default when `None`)
+    pub async fn search(&self, query: &str, limit: Option<usize>) -> WikiResult<Vec<ArticleResponse>> {
+        self.get(&["search"], &search_params(query, limit)).await
+    }
+
+    /// Semantic search, ranked by embedding similarity
+    pub async fn semantic_search(&self, query: &str, limit: Option<usize>) -> WikiResult<Vec<SemanticSearchResult>> {
+        self.get(&["semantic-search"], &search_params(query, limit)).await
+    }
+
+    /// LLM-generated summary of an article
+    pub async fn summary(&self, title: &str) -> WikiResult<SummaryResponse> {
+        self.get(&["articles", title, "summary"], &[]).await
+    }
+
+    /// Article, category and image counts
+    pub async fn stats(&self) -> WikiResult<StatsResponse> {
+        self.get(&["stats"], &[]).await
+    }
+
+    /// GET `/api/<segments>`, percent-encoding each segment
+    async fn get<T: DeserializeOwned>(&self, segments: &[&str], query: &[(&str, String)]) -> WikiResult<T> {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .expect("base URL checked in constructor")
+            .pop_if_empty()
+            .push("api")
+            .extend(segments);
+
+        let response = self.client.get(url).query(query).send().await?;
+        Ok(check_status(response).await?.json::<T>().await?)
+    }
+}
+
+fn search_params(query: &str, limit: Option<usize>) -> Vec<(&'static str, String)> {
+    let mut params = vec![("query", query.to_string())];
+    if let Some(limit) = limit {
+        params.push(("limit", limit.to_string()));
+    }
+    params
+}
+
+/// Map non-2xx responses to `WikiError::Api`, using the server's error message when present
+async fn check_status(response: Response) -> WikiResult<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    let message = serde_json::from_str::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|json| json.get("message").and_then(|m| m.as_str()).map(|m| m.to_string()))
+        .unwrap_or(body);
+    Err(WikiError::Api(status.as_u16(), message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::api::ApiServer;
+    use crate::db::{init_database, DatabaseWriter};
+    use crate::llm::LlmService;
+    use crate::parser::models::WikiArticle;
+    use crate::vector::VectorStore;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_client_against_test_server() -> WikiResult<()> {
+        let temp_dir = TempDir::new()?;
+        let mut ollama = mockito::Server::new_async().await;
+        ollama.mock("POST", "/api/embeddings")
+            .with_status(200)
+            .with_body(r#"{"data":[{"embedding":[1.0,0.0]}]}"#)
+            .create_async()
+            .await;
+        ollama.mock("POST", "/api/generate")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"model":"llama2","response":"A short summary."}"#)
+            .create_async()
+            .await;
+
+        let db_path = temp_dir.path().join("wiki.db");
+        let conn = rusqlite::Connection::open(&db_path)?;
+        init_database(&conn)?;
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        for (title, content) in [("Alpha Centauri", "A star system."), ("Beta", "Another star.")] {
+            let mut article = WikiArticle::new(title.to_string(), content.to_string());
+            article.add_category("Stars".to_string());
+            article.update_size();
+            writer.write_article(&article, &tx)?;
+        }
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let vector_store = Arc::new(VectorStore::new(temp_dir.path().join("vectors"), &ollama.url()).await?);
+        vector_store.store_embedding("Alpha Centauri", &[1.0, 0.0])?;
+        vector_store.store_embedding("Beta", &[0.0, 1.0])?;
+        let llm_service = Arc::new(LlmService::new(&ollama.url(), None)?);
+        let server = ApiServer::new(&db_path.to_string_lossy(), vector_store, llm_service)
+            .spawn_test_server()
+            .await?;
+        let client = WikiApiClient::new(&server.url())?;
+
+        let article = client.get_article("Alpha Centauri").await?;
+        assert_eq!(article.content, "A star system.");
+
+        let results = client.search("star", Some(5)).await?;
+        assert_eq!(results.len(), 2);
+
+        let similar = client.semantic_search("nearest star", Some(1)).await?;
+        assert_eq!(similar[0].title, "Alpha Centauri");
+
+        let summary = client.summary("Beta").await?;
+        assert_eq!(summary.summary, "A short summary.");
+
+        let stats = client.stats().await?;
+        assert_eq!((stats.articles, stats.categories), (2, 1));
+
+        match client.get_article("Missing").await {
+            Err(WikiError::Api(404, _)) => {}
+            other => panic!("expected a 404 API error, got {:?}", other.map(|a| a.title)),
+        }
+
+        server.shutdown().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use tracing::info;
+
+use crate::error_handling::{WikiError, WikiResult};
+
+/// One line of a corpus archive (gzip-compressed JSON lines)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ArchiveRecord {
+    Article {
+        title: String,
+        content: String,
+        last_modified: String,
+        first_seen: Option<String>,
+        categories: Vec<String>,
+    },
+    Category {
+        name: String,
+    },
+    Redirect {
+        from: String,
+        to: String,
+    },
+}
+
+/// Number of records of each kind written to an archive
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ExportReport {
+    pub articles: usize,
+    pub categories: usize,
+    pub redirects: usize,
+}
+
+/// Stream every article, category and redirect to `output` as gzip-compressed JSON lines.
+///
+/// Rows are written as they are read, so memory use does not grow with the corpus.
+pub fn export_archive<W: Write>(conn: &Connection, output: W) -> WikiResult<ExportReport> {
+    let mut out = BufWriter::new(GzEncoder::new(output, Compression::default()));
+    let mut report = ExportReport::default();
+
+    let mut stmt = conn.prepare("SELECT name FROM categories ORDER BY name")?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        write_record(&mut out, &ArchiveRecord::Category { name: row.get(0)? })?;
+        report.categories += 1;
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT a.title, a.content, a.last_modified, a.first_seen,
+                (SELECT group_concat(c.name, char(31))
+                 FROM article_categories ac
+                 JOIN categories c ON c.id = ac.category_id
+                 WHERE ac.article_id = a.rowid)
+         FROM articles a
+         ORDER BY a.rowid",
+    )?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let categories: Option<String> = row.get(4)?;
+        write_record(&mut out, &ArchiveRecord::Article {
+            title: row.get(0)?,
+            content: row.get(1)?,
+            last_modified: row.get(2)?,
+            first_seen: row.get(3)?,
+            categories: categories
+                .map(|c| c.split('\u{1f}').map(|s| s.to_string()).collect())
+                .unwrap_or_default(),
+        })?;
+        report.articles += 1;
+    }
+
+    let mut stmt = conn.prepare("SELECT from_title, to_title FROM redirects ORDER BY from_title")?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        write_record(&mut out, &ArchiveRecord::Redirect { from: row.get(0)?, to: row.get(1)? })?;
+        report.redirects += 1;
+    }
+
+    out.into_inner()
+        .map_err(|e| WikiError::Io(e.into_error()))?
+        .finish()?;
+
+    info!(
+        "Exported {} articles, {} categories and {} redirects",
+        report.articles, report.categories, report.redirects
+    );
+    Ok(report)
+}
+
+/// Export the corpus to an archive file at `path`
+pub fn export_archive_to_path<P: AsRef<Path>>(conn: &Connection, path: P) -> WikiResult<ExportReport> {
+    info!("Exporting corpus to {}", path.as_ref().display());
+    export_archive(conn, File::create(path)?)
+}
+
+/// Read records back from an archive written by [`export_archive`], one at a time
+pub fn read_archive<R: Read>(input: R) -> impl Iterator<Item = WikiResult<ArchiveRecord>> {
+    BufReader::new(MultiGzDecoder::new(input))
+        .lines()
+        .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|line| {
+            let line = line?;
+            serde_json::from_str(&line).map_err(|e| WikiError::Parse(e.to_string()))
+        })
+}
+
+fn write_record<W: Write>(out: &mut W, record: &ArchiveRecord) -> WikiResult<()> {
+    serde_json::to_writer(&mut *out, record).map_err(io::Error::from)?;
+    out.write_all(b"\n")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{init_database, DatabaseWriter};
+    use crate::parser::models::WikiArticle;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_export_archive_round_trip() -> WikiResult<()> {
+        let temp_file = NamedTempFile::new()?;
+        let conn = Connection::open(temp_file.path())?;
+        init_database(&conn)?;
+
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        for i in 0..3 {
+            let mut article = WikiArticle::new(format!("Article {}", i), format!("Content {}", i));
+            article.add_category("Shared".to_string());
+            writer.write_article(&article, &tx)?;
+        }
+        let mut redirect = WikiArticle::new("Old Name".to_string(), String::new());
+        redirect.redirect_to = Some("Article 0".to_string());
+        writer.write_article(&redirect, &tx)?;
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let archive = NamedTempFile::new()?;
+        let report = export_archive_to_path(&conn, archive.path())?;
+        assert_eq!(report, ExportReport { articles: 4, categories: 1, redirects: 1 });
+
+        let records = read_archive(File::open(archive.path())?).collect::<WikiResult<Vec<_>>>()?;
+        assert_eq!(records.len(), 6);
+        assert!(records.contains(&ArchiveRecord::Category { name: "Shared".to_string() }));
+        assert!(records.contains(&ArchiveRecord::Redirect {
+            from: "Old Name".to_string(),
+            to: "Article 0".to_string(),
+        }));
+
+        let articles: Vec<_> = records
+            .iter()
+            .filter_map(|record| match record {
+                ArchiveRecord::Article { title, content, categories, .. } => Some((title, content, categories)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(articles.len(), 4);
+        for i in 0..3 {
+            let (_, content, categories) = articles
+                .iter()
+                .find(|(title, _, _)| **title == format!("Article {}", i))
+                .expect("article missing from archive");
+            assert_eq!(**content, format!("Content {}", i));
+            assert_eq!(**categories, vec!["Shared".to_string()]);
+        }
+
+        Ok(())
+    }
+}
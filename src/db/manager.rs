@@ -3,8 +3,9 @@ use tracing::{debug, info, warn};
 use chrono::{DateTime, Utc};
 
 use crate::error_handling::{WikiError, WikiResult};
-use crate::parser::models::{WikiArticle, WikiCategory, WikiImage};
+use crate::parser::models::{count_words, reading_time_minutes, WikiArticle, WikiCategory, WikiImage};
 use crate::db::schema;
+use crate::db::writer::normalize_title;
 
 pub struct DatabaseManager {
     conn: Connection,
@@ -19,6 +20,7 @@ impl DatabaseManager {
     }
 
     fn init(&self) -> WikiResult<()> {
+        schema::ensure_fts5(&self.conn)?;
         schema::init_database(&self.conn)?;
         Ok(())
     }
@@ -32,22 +34,62 @@ impl DatabaseManager {
         Ok(version)
     }
 
-    pub fn begin_transaction(&mut self) -> WikiResult<Transaction> {
-        Ok(self.conn.transaction()?)
+    /// Rebuild the full-text index from the stored articles, repairing an index that has
+    /// drifted from the content. Returns the number of articles indexed.
+    pub fn rebuild_fts(&self) -> WikiResult<usize> {
+        let fts_table = self.fts_table()?;
+        info!("Rebuilding full-text index {}", fts_table);
+        self.conn.execute(&format!("INSERT INTO {0}({0}) VALUES('rebuild')", fts_table), [])?;
+        let count: usize = self.conn.query_row("SELECT COUNT(*) FROM articles", [], |row| row.get(0))?;
+        info!("Rebuilt full-text index over {} articles", count);
+        Ok(count)
+    }
+
+    /// Merge the full-text index into a single segment and rewrite the database file
+    /// without the free pages left behind by updates and deletes
+    pub fn optimize(&self) -> WikiResult<()> {
+        let fts_table = self.fts_table()?;
+        info!("Merging full-text index {}", fts_table);
+        self.conn.execute(&format!("INSERT INTO {0}({0}) VALUES('optimize')", fts_table), [])?;
+        info!("Vacuuming database");
+        self.conn.execute_batch("VACUUM")?;
+        Ok(())
+    }
+
+    /// Full-text table of this database, see [`schema::fts_table`]
+    fn fts_table(&self) -> WikiResult<&'static str> {
+        Ok(schema::fts_table(&self.conn)?)
+    }
+
+    /// Start a transaction; the manager stays borrowed shared so its insert helpers can join it
+    pub fn begin_transaction(&self) -> WikiResult<Transaction> {
+        Ok(self.conn.unchecked_transaction()?)
     }
 
     pub fn insert_article(&self, article: &WikiArticle, tx: &Transaction) -> WikiResult<i64> {
         // Insert into articles table
         tx.execute(
-            "INSERT INTO articles (title, content, size, last_modified) VALUES (?1, ?2, ?3, ?4)",
+            "INSERT INTO articles (title, content, size, last_modified, first_seen, page_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
             params![
                 article.title,
                 article.content,
                 article.size,
                 article.last_modified.to_rfc3339(),
+                Utc::now().to_rfc3339(),
+                article.id,
             ],
         )?;
         let article_id = tx.last_insert_rowid();
+        tx.execute(
+            "INSERT OR REPLACE INTO article_titles (title, article_id) VALUES (?1, ?2)",
+            params![normalize_title(&article.title), article_id],
+        )?;
+
+        let word_count = count_words(&article.content);
+        tx.execute(
+            "INSERT OR REPLACE INTO article_stats (article_id, word_count, reading_minutes) VALUES (?1, ?2, ?3)",
+            params![article_id, word_count, reading_time_minutes(word_count)],
+        )?;
 
         // Handle redirect if present
         if let Some(ref redirect_to) = article.redirect_to {
@@ -109,7 +151,7 @@ impl DatabaseManager {
 
         // Insert new image if it doesn't exist
         tx.execute(
-            "INSERT INTO images (filename, path, size, mime_type, hash, caption) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            "INSERT INTO images (filename, path, size, mime_type, hash, caption, width, height) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
             params![
                 image.filename,
                 image.path,
@@ -117,6 +159,8 @@ impl DatabaseManager {
                 image.mime_type,
                 image.hash,
                 image.caption,
+                image.width,
+                image.height,
             ],
         )?;
         Ok(tx.last_insert_rowid())
@@ -124,13 +168,17 @@ impl DatabaseManager {
 
     pub fn search_articles(&self, query: &str) -> WikiResult<Vec<WikiArticle>> {
         let mut stmt = self.conn.prepare(
-            "SELECT title, content, last_modified, size FROM articles 
+            "SELECT title, content, last_modified, size, COALESCE(page_id, rowid),
+                    (SELECT s.word_count FROM article_stats s WHERE s.article_id = articles.rowid),
+                    (SELECT s.reading_minutes FROM article_stats s WHERE s.article_id = articles.rowid)
+             FROM articles 
              WHERE articles MATCH ?1 
              ORDER BY rank"
         )?;
 
         let articles = stmt.query_map(params![query], |row| {
             Ok(WikiArticle {
+                id: row.get(4)?,
                 title: row.get(0)?,
                 content: row.get(1)?,
                 categories: Default::default(),
@@ -144,6 +192,11 @@ impl DatabaseManager {
                 size: row.get(3)?,
                 redirect_to: None,
                 images: Vec::new(),
+                raw_wikitext: None,
+                references: Vec::new(),
+                langlinks: Vec::new(),
+                word_count: row.get::<_, Option<usize>>(5)?.unwrap_or_default(),
+                reading_minutes: row.get::<_, Option<usize>>(6)?.unwrap_or_default(),
             })
         })?
         .collect::<Result<Vec<_>, _>>()?;
@@ -155,6 +208,7 @@ impl DatabaseManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::db::writer::DatabaseWriter;
     use tempfile::tempdir;
 
     #[test]
@@ -165,7 +219,71 @@ mod tests {
         let db = DatabaseManager::new(&db_path.to_string_lossy())?;
         let version = db.get_schema_version()?;
         
-        assert_eq!(version, 1);
+        assert_eq!(version, schema::SCHEMA_VERSION);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rebuild_fts_repairs_drifted_index() -> WikiResult<()> {
+        let temp_dir = tempdir().map_err(|e| WikiError::Io(e))?;
+        let db_path = temp_dir.path().join("test.db");
+        let db = DatabaseManager::new(&db_path.to_string_lossy())?;
+
+        let mut article = WikiArticle::new("Comet".to_string(), "An icy body with a glowing tail.".to_string());
+        article.update_size();
+        let tx = db.conn.unchecked_transaction()?;
+        let id = db.insert_article(&article, &tx)?;
+        tx.commit()?;
+
+        // Change the stored text behind the index's back, as a manual edit would
+        db.conn.execute(
+            "UPDATE articles_content SET c1 = 'A rocky asteroid.' WHERE id = ?1",
+            params![id],
+        )?;
+        assert_eq!(db.search_articles("icy")?.len(), 1);
+        assert!(db.search_articles("asteroid")?.is_empty());
+
+        assert_eq!(db.rebuild_fts()?, 1);
+        assert!(db.search_articles("icy")?.is_empty());
+        let found = db.search_articles("asteroid")?;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].title, "Comet");
+        assert_eq!(found[0].content, "A rocky asteroid.");
+        Ok(())
+    }
+
+    #[test]
+    fn test_rebuild_fts_repairs_external_content_index() -> WikiResult<()> {
+        let temp_dir = tempdir().map_err(|e| WikiError::Io(e))?;
+        let db_path = temp_dir.path().join("test.db");
+
+        // A plain articles table indexed through articles_fts, which the writer leaves stale
+        {
+            let conn = Connection::open(&db_path)?;
+            let writer = DatabaseWriter::new(&conn);
+            writer.create_tables()?;
+            let tx = writer.begin_transaction()?;
+            writer.write_article(&WikiArticle::new("Comet".to_string(), "An icy body with a glowing tail.".to_string()), &tx)?;
+            DatabaseWriter::commit_transaction(tx)?;
+        }
+
+        let db = DatabaseManager::new(&db_path.to_string_lossy())?;
+        let matches = |term: &str| -> WikiResult<Vec<String>> {
+            let mut stmt = db.conn.prepare("SELECT title FROM articles_fts WHERE articles_fts MATCH ?1 ORDER BY rank")?;
+            let titles = stmt.query_map(params![term], |row| row.get(0))?.collect::<Result<Vec<_>, _>>()?;
+            Ok(titles)
+        };
+        assert!(matches("icy")?.is_empty());
+
+        assert_eq!(db.rebuild_fts()?, 1);
+        assert_eq!(matches("icy")?, vec!["Comet".to_string()]);
+        assert!(matches("asteroid")?.is_empty());
+
+        // A later edit to the content table is picked up by the next rebuild
+        db.conn.execute("UPDATE articles SET content = 'A rocky asteroid.' WHERE title = 'Comet'", [])?;
+        assert_eq!(db.rebuild_fts()?, 1);
+        assert_eq!(matches("asteroid")?, vec!["Comet".to_string()]);
+        assert!(matches("icy")?.is_empty());
         Ok(())
     }
 } 
\ No newline at end of file
@@ -3,6 +3,7 @@ pub mod writer;
 pub mod reader;
 pub mod parallel;
 pub mod manager;
+pub mod export;
 
 use rusqlite::{Connection, Transaction, params};
 use tracing::{debug, info, warn};
@@ -12,7 +13,13 @@ use crate::error_handling::{WikiError, WikiResult};
 use crate::parser::models::{WikiArticle, WikiCategory, WikiImage};
 
 pub use manager::DatabaseManager;
+/// Shorthand for [`DatabaseManager`]
+pub type DbManager = DatabaseManager;
 pub use schema::*;
-pub use writer::DatabaseWriter;
-pub use reader::DatabaseReader;
-pub use parallel::*; 
\ No newline at end of file
+pub use writer::{DatabaseWriter, ImportReport};
+pub use reader::{ArticleSort, CategoryMatch, DatabaseReader, SearchFields, SortOrder};
+pub use export::{export_archive, export_archive_to_path, read_archive, ArchiveRecord, ExportReport};
+pub use parallel::*;
+
+#[cfg(test)]
+mod tests; 
\ No newline at end of file
@@ -2,10 +2,16 @@ use rusqlite::Connection;
 use std::sync::mpsc::{channel, Receiver};
 use std::thread;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
-use crate::error_handling::WikiResult;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::info;
+
+use crate::error_handling::{WikiError, WikiResult};
 use crate::parser::models::WikiArticle;
-use crate::db::writer::DatabaseWriter;
+use crate::db::writer::{DatabaseWriter, ImportReport};
 
 pub struct ParallelDatabaseWriter {
     thread_count: usize,
@@ -95,6 +101,165 @@ impl ParallelDatabaseWriter {
     }
 }
 
+/// Cap on the articles an [`ArticleWriterTask`] inserts, shared with the producer so it
+/// can stop reading once the writer has inserted enough.
+///
+/// Only articles actually inserted count: redirects and pages that lose a title
+/// collision do not.
+#[derive(Debug, Clone, Default)]
+pub struct InsertLimit {
+    max: Option<usize>,
+    reached: Arc<AtomicBool>,
+}
+
+impl InsertLimit {
+    pub fn new(max: Option<usize>) -> Self {
+        Self {
+            max,
+            reached: Arc::new(AtomicBool::new(max == Some(0))),
+        }
+    }
+
+    /// Whether the writer has inserted `max` articles
+    pub fn is_reached(&self) -> bool {
+        self.reached.load(Ordering::Acquire)
+    }
+
+    /// Inserts still allowed after `inserted`, or `None` without a limit
+    fn remaining(&self, inserted: usize) -> Option<usize> {
+        self.max.map(|max| max.saturating_sub(inserted))
+    }
+
+    /// Record `inserted` articles, returning whether the limit is now reached
+    fn record(&self, inserted: usize) -> bool {
+        let reached = self.remaining(inserted) == Some(0);
+        if reached {
+            self.reached.store(true, Ordering::Release);
+        }
+        reached
+    }
+}
+
+/// Dedicated writer that commits articles received over a bounded channel in batches.
+///
+/// The bounded channel applies backpressure so a fast producer (the parser) can run
+/// ahead of the writer by at most `capacity` articles.
+pub struct ArticleWriterTask {
+    sender: mpsc::Sender<WikiArticle>,
+    handle: JoinHandle<WikiResult<ImportReport>>,
+}
+
+impl ArticleWriterTask {
+    pub fn spawn<P: AsRef<Path>>(
+        db_path: P,
+        batch_size: usize,
+        capacity: usize,
+        store_raw_wikitext: bool,
+    ) -> Self {
+        Self::spawn_with_progress(db_path, batch_size, capacity, store_raw_wikitext, |_| {})
+    }
+
+    /// Like [`spawn`](Self::spawn), calling `on_commit` with the articles written so far after each batch
+    pub fn spawn_with_progress<P, F>(
+        db_path: P,
+        batch_size: usize,
+        capacity: usize,
+        store_raw_wikitext: bool,
+        on_commit: F,
+    ) -> Self
+    where
+        P: AsRef<Path>,
+        F: FnMut(usize) + Send + 'static,
+    {
+        Self::spawn_with_limit(db_path, batch_size, capacity, store_raw_wikitext, InsertLimit::default(), on_commit)
+    }
+
+    /// Like [`spawn_with_progress`](Self::spawn_with_progress), stopping once `limit` articles
+    /// are inserted. The channel is closed then, so later sends fail; check
+    /// [`InsertLimit::is_reached`] before sending
+    pub fn spawn_with_limit<P, F>(
+        db_path: P,
+        batch_size: usize,
+        capacity: usize,
+        store_raw_wikitext: bool,
+        limit: InsertLimit,
+        mut on_commit: F,
+    ) -> Self
+    where
+        P: AsRef<Path>,
+        F: FnMut(usize) + Send + 'static,
+    {
+        let db_path = db_path.as_ref().to_path_buf();
+        let batch_size = batch_size.max(1);
+        let (sender, mut receiver) = mpsc::channel::<WikiArticle>(capacity.max(1));
+
+        let handle = tokio::task::spawn_blocking(move || -> WikiResult<ImportReport> {
+            let conn = Connection::open(&db_path)?;
+            let writer = DatabaseWriter::new(&conn).with_raw_wikitext(store_raw_wikitext);
+            let mut batch = Vec::with_capacity(batch_size);
+            let mut inserted = 0;
+
+            while !limit.record(inserted) {
+                let Some(article) = receiver.blocking_recv() else { break };
+                batch.push(article);
+                // Commit early near the limit, so the producer learns promptly that it was reached
+                if batch.len() >= batch_size || limit.remaining(inserted).is_some_and(|left| batch.len() >= left) {
+                    inserted += Self::commit_batch(&writer, &batch, limit.remaining(inserted))?;
+                    batch.clear();
+                    on_commit(writer.report().articles_written);
+                }
+            }
+
+            if !batch.is_empty() {
+                Self::commit_batch(&writer, &batch, limit.remaining(inserted))?;
+                on_commit(writer.report().articles_written);
+            }
+
+            Ok(writer.report())
+        });
+
+        Self { sender, handle }
+    }
+
+    /// Get a sender for feeding articles to the writer
+    pub fn sender(&self) -> mpsc::Sender<WikiArticle> {
+        self.sender.clone()
+    }
+
+    /// Queue an article, waiting while the channel is full
+    pub async fn send(&self, article: WikiArticle) -> WikiResult<()> {
+        self.sender.send(article).await
+            .map_err(|_| WikiError::OperationFailed("Article writer has stopped".to_string()))
+    }
+
+    /// Close the channel and wait for all queued articles to be committed
+    pub async fn finish(self) -> WikiResult<ImportReport> {
+        drop(self.sender);
+        self.handle.await
+            .map_err(|e| WikiError::OperationFailed(format!("Article writer task failed: {}", e)))?
+    }
+
+    /// Import `batch`, stopping after `remaining` inserts; returns the articles inserted
+    /// (redirects and collision losers are written or skipped without counting)
+    fn commit_batch(writer: &DatabaseWriter, batch: &[WikiArticle], remaining: Option<usize>) -> WikiResult<usize> {
+        let tx = writer.begin_transaction()?;
+        let mut inserted = 0;
+        for article in batch {
+            if remaining.is_some_and(|left| inserted >= left) {
+                break;
+            }
+            let written = writer.report().articles_written;
+            writer.import_article(article, &tx)?;
+            if !article.is_redirect() && writer.report().articles_written > written {
+                inserted += 1;
+            }
+        }
+        DatabaseWriter::commit_transaction(tx)?;
+        info!("Committed batch of {} articles", batch.len());
+        Ok(inserted)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -138,4 +303,34 @@ mod tests {
         
         Ok(())
     }
-} 
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_article_writer_task() -> WikiResult<()> {
+        let temp_file = NamedTempFile::new().unwrap();
+        let conn = Connection::open(temp_file.path()).unwrap();
+        init_database(&conn)?;
+
+        // A channel much smaller than the input forces producer and writer to interleave
+        let writer = ArticleWriterTask::spawn(temp_file.path(), 10, 4, false);
+        let sender = writer.sender();
+        let producer = tokio::spawn(async move {
+            for i in 0..50 {
+                let mut article = WikiArticle::new(
+                    format!("Article {}", i),
+                    format!("Content of article {}", i),
+                );
+                article.update_size();
+                sender.send(article).await.unwrap();
+            }
+        });
+
+        producer.await.unwrap();
+        let report = writer.finish().await?;
+        assert_eq!(report.articles_written, 50);
+
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM articles", [], |row| row.get(0))?;
+        assert_eq!(count, 50);
+
+        Ok(())
+    }
+}
@@ -1,9 +1,132 @@
-use rusqlite::{Connection, params};
+use rusqlite::{Connection, params, params_from_iter};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use tracing::{debug, info};
 use chrono::{DateTime, Utc};
 
 use crate::error_handling::{WikiError, WikiResult};
-use crate::parser::models::{WikiArticle, WikiImage};
+use crate::parser::models::{WikiArticle, WikiImage, WikiLangLink, WikiReference};
+use crate::vector::VectorStore;
+use super::writer::normalize_title;
+
+/// Titles bound per `IN (...)` query, well under SQLite's default variable limit
+/// (`SQLITE_MAX_VARIABLE_NUMBER`, 999 before SQLite 3.32); longer lists are split across queries
+const TITLES_PER_QUERY: usize = 500;
+
+/// Views at which an article gets half of the popularity boost
+const POPULARITY_HALF_VIEWS: f64 = 10.0;
+
+/// With a popularity boost, the best `limit * POPULARITY_CANDIDATES` matches by BM25 are
+/// fetched and reranked, so a popular article can overtake ones ranked a little above it
+const POPULARITY_CANDIDATES: usize = 5;
+
+/// Every article with its categories and redirect target, in insertion order
+const ALL_ARTICLES_SQL: &str =
+    "SELECT a.title, a.content, a.last_modified, a.size, COALESCE(a.page_id, a.rowid),
+            (SELECT group_concat(c.name, char(31))
+             FROM article_categories ac
+             JOIN categories c ON c.id = ac.category_id
+             WHERE ac.article_id = a.rowid),
+            (SELECT r.to_title FROM redirects r WHERE r.from_title = a.title),
+            (SELECT s.word_count FROM article_stats s WHERE s.article_id = a.rowid),
+            (SELECT s.reading_minutes FROM article_stats s WHERE s.article_id = a.rowid)
+     FROM articles a
+     ORDER BY a.rowid";
+
+/// Articles in the category `?1` with all of their categories, by title, `?2` rows from offset `?3`
+const CATEGORY_ARTICLES_SQL: &str =
+    "SELECT a.title, a.content, a.last_modified, a.size, COALESCE(a.page_id, a.rowid),
+            (SELECT group_concat(c.name, char(31))
+             FROM article_categories ac
+             JOIN categories c ON c.id = ac.category_id
+             WHERE ac.article_id = a.rowid),
+            (SELECT r.to_title FROM redirects r WHERE r.from_title = a.title),
+            (SELECT s.word_count FROM article_stats s WHERE s.article_id = a.rowid),
+            (SELECT s.reading_minutes FROM article_stats s WHERE s.article_id = a.rowid)
+     FROM articles a
+     JOIN article_categories ac ON ac.article_id = a.rowid
+     JOIN categories c ON c.id = ac.category_id
+     WHERE c.name = ?1
+     ORDER BY a.title
+     LIMIT ?2 OFFSET ?3";
+
+/// Which full-text columns a keyword search matches against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchFields {
+    Title,
+    Content,
+    #[default]
+    Both,
+}
+
+/// Column an article listing is sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArticleSort {
+    #[default]
+    Title,
+    Modified,
+    Size,
+}
+
+impl ArticleSort {
+    fn column(&self) -> &'static str {
+        match self {
+            ArticleSort::Title => "title",
+            ArticleSort::Modified => "last_modified",
+            ArticleSort::Size => "size",
+        }
+    }
+}
+
+/// Direction of a sorted listing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    fn keyword(&self) -> &'static str {
+        match self {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        }
+    }
+}
+
+/// How a set of categories is matched when listing their articles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CategoryMatch {
+    /// Articles in every one of the categories
+    #[default]
+    All,
+    /// Articles in at least one of the categories
+    Any,
+}
+
+impl SearchFields {
+    /// Restrict an FTS5 query to these columns with a column filter
+    fn scope(&self, query: &str) -> String {
+        match self {
+            SearchFields::Title => format!("title : ({})", query),
+            SearchFields::Content => format!("content : ({})", query),
+            SearchFields::Both => query.to_string(),
+        }
+    }
+}
+
+/// Lightweight view of an article used for search result listings
+#[derive(Debug, Clone)]
+pub struct ArticleExcerpt {
+    pub title: String,
+    pub excerpt: String,
+    pub categories: Vec<String>,
+}
 
 pub struct DatabaseReader<'a> {
     conn: &'a Connection,
@@ -16,10 +139,14 @@ impl<'a> DatabaseReader<'a> {
 
     pub fn get_article(&self, title: &str) -> WikiResult<Option<WikiArticle>> {
         let result = self.conn.query_row(
-            "SELECT title, content, last_modified, size FROM articles WHERE title = ?1",
+            "SELECT title, content, last_modified, size, COALESCE(page_id, rowid),
+                    (SELECT s.word_count FROM article_stats s WHERE s.article_id = articles.rowid),
+                    (SELECT s.reading_minutes FROM article_stats s WHERE s.article_id = articles.rowid)
+             FROM articles WHERE title = ?1",
             params![title],
             |row| {
                 Ok(WikiArticle {
+                    id: row.get(4)?,
                     title: row.get(0)?,
                     content: row.get(1)?,
                     categories: Default::default(),
@@ -33,6 +160,11 @@ impl<'a> DatabaseReader<'a> {
                     size: row.get(3)?,
                     redirect_to: None,
                     images: Vec::new(),
+                    raw_wikitext: None,
+                    references: Vec::new(),
+                    langlinks: Vec::new(),
+                    word_count: row.get::<_, Option<usize>>(5)?.unwrap_or_default(),
+                    reading_minutes: row.get::<_, Option<usize>>(6)?.unwrap_or_default(),
                 })
             },
         );
@@ -54,7 +186,7 @@ impl<'a> DatabaseReader<'a> {
                 // Load images
                 let mut images = Vec::new();
                 let mut stmt = self.conn.prepare(
-                    "SELECT i.filename, i.path, i.size, i.mime_type, i.hash, i.caption
+                    "SELECT i.filename, i.path, i.size, i.mime_type, i.hash, i.caption, i.width, i.height
                      FROM images i
                      JOIN article_images ai ON i.id = ai.image_id
                      WHERE ai.article_id = (SELECT rowid FROM articles WHERE title = ?1)"
@@ -68,6 +200,8 @@ impl<'a> DatabaseReader<'a> {
                         mime_type: row.get(3)?,
                         hash: row.get(4)?,
                         caption: row.get(5)?,
+                        width: row.get(6)?,
+                        height: row.get(7)?,
                     });
                 }
 
@@ -82,13 +216,92 @@ impl<'a> DatabaseReader<'a> {
         }
     }
 
+    /// Load every article into memory; use [`Self::for_each_article`] for large databases
+    pub fn get_all_articles(&self) -> WikiResult<Vec<WikiArticle>> {
+        let mut articles = Vec::new();
+        self.for_each_article(|article| {
+            articles.push(article);
+            Ok(())
+        })?;
+        Ok(articles)
+    }
+
+    /// Stream every article to `callback` one row at a time, returning how many were read.
+    ///
+    /// Memory use does not grow with the number of articles.
+    pub fn for_each_article<F>(&self, mut callback: F) -> WikiResult<usize>
+    where
+        F: FnMut(WikiArticle) -> WikiResult<()>,
+    {
+        let mut stmt = self.conn.prepare_cached(ALL_ARTICLES_SQL)?;
+        let mut rows = stmt.query([])?;
+        let mut count = 0;
+        while let Some(row) = rows.next()? {
+            callback(article_from_row(row)?)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Full articles in `category`, by title; see [`Self::get_articles_in_category`] for titles only
+    pub fn get_articles_by_category(&self, category: &str) -> WikiResult<Vec<WikiArticle>> {
+        self.query_category_articles(category, -1, 0)
+    }
+
+    /// One page of [`Self::get_articles_by_category`]
+    pub fn get_articles_by_category_page(
+        &self,
+        category: &str,
+        limit: usize,
+        offset: usize,
+    ) -> WikiResult<Vec<WikiArticle>> {
+        self.query_category_articles(category, limit as i64, offset as i64)
+    }
+
+    fn query_category_articles(&self, category: &str, limit: i64, offset: i64) -> WikiResult<Vec<WikiArticle>> {
+        let mut stmt = self.conn.prepare_cached(CATEGORY_ARTICLES_SQL)?;
+        let articles = stmt
+            .query_map(params![category, limit, offset], article_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(articles)
+    }
+
+    /// Up to `limit` articles in title order
     pub fn get_articles(&self, limit: usize) -> WikiResult<Vec<WikiArticle>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT title, content, last_modified, size FROM articles LIMIT ?1"
-        )?;
+        self.get_articles_sorted(limit, ArticleSort::default(), SortOrder::default())
+    }
+
+    /// Up to `limit` articles ordered by `sort`; ties are broken by title so the order is stable.
+    ///
+    /// Title order walks the `article_titles` index and fetches only the listed articles by rowid;
+    /// the other columns are not indexed, so those orders scan the articles table
+    pub fn get_articles_sorted(&self, limit: usize, sort: ArticleSort, order: SortOrder) -> WikiResult<Vec<WikiArticle>> {
+        let from = match sort {
+            ArticleSort::Title => format!(
+                "FROM article_titles t CROSS JOIN articles a ON a.rowid = t.article_id
+                 ORDER BY t.title {order}",
+                order = order.keyword(),
+            ),
+            _ => format!(
+                "FROM articles a
+                 ORDER BY a.{column} {order}, a.title {order}",
+                column = sort.column(),
+                order = order.keyword(),
+            ),
+        };
+        let sql = format!(
+            "SELECT a.title, a.content, a.last_modified, a.size, COALESCE(a.page_id, a.rowid),
+                    (SELECT s.word_count FROM article_stats s WHERE s.article_id = a.rowid),
+                    (SELECT s.reading_minutes FROM article_stats s WHERE s.article_id = a.rowid)
+             {}
+             LIMIT ?1",
+            from
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
 
         let articles = stmt.query_map(params![limit as i64], |row| {
             Ok(WikiArticle {
+                id: row.get(4)?,
                 title: row.get(0)?,
                 content: row.get(1)?,
                 categories: Default::default(),
@@ -102,6 +315,11 @@ impl<'a> DatabaseReader<'a> {
                 size: row.get(3)?,
                 redirect_to: None,
                 images: Vec::new(),
+                raw_wikitext: None,
+                references: Vec::new(),
+                langlinks: Vec::new(),
+                word_count: row.get::<_, Option<usize>>(5)?.unwrap_or_default(),
+                reading_minutes: row.get::<_, Option<usize>>(6)?.unwrap_or_default(),
             })
         })?
         .collect::<Result<Vec<_>, _>>()?;
@@ -110,15 +328,69 @@ impl<'a> DatabaseReader<'a> {
     }
 
     pub fn search_articles(&self, query: &str, limit: usize) -> WikiResult<Vec<WikiArticle>> {
+        self.search_articles_filtered(query, limit, false)
+    }
+
+    /// Full-text search; redirect stubs are left out unless `include_redirects` is set
+    pub fn search_articles_filtered(
+        &self,
+        query: &str,
+        limit: usize,
+        include_redirects: bool,
+    ) -> WikiResult<Vec<WikiArticle>> {
+        self.search_articles_in(query, limit, include_redirects, SearchFields::Both)
+    }
+
+    /// Keyword search over only the title, only the content, or both
+    pub fn search_articles_in(
+        &self,
+        query: &str,
+        limit: usize,
+        include_redirects: bool,
+        fields: SearchFields,
+    ) -> WikiResult<Vec<WikiArticle>> {
+        self.search_articles_boosted(query, limit, include_redirects, fields, 0.0)
+    }
+
+    /// Keyword search ranked by BM25 blended with popularity.
+    ///
+    /// Matches come back in the table's configured `rank` order. With a boost, each of the
+    /// best candidates has its BM25 score improved by up to `popularity_boost` as its view
+    /// count grows, `popularity_boost * views / (views + 10)`, and the candidates are
+    /// reranked, so frequently read articles win close calls. A boost of 0 ranks by BM25 alone.
+    pub fn search_articles_boosted(
+        &self,
+        query: &str,
+        limit: usize,
+        include_redirects: bool,
+        fields: SearchFields,
+        popularity_boost: f64,
+    ) -> WikiResult<Vec<WikiArticle>> {
+        let query = fields.scope(query);
+        let candidates = if popularity_boost > 0.0 {
+            limit.saturating_mul(POPULARITY_CANDIDATES)
+        } else {
+            limit
+        };
         let mut stmt = self.conn.prepare(
-            "SELECT title, content, last_modified, size FROM articles 
+            "SELECT articles.title, articles.content, articles.last_modified, articles.size, r.to_title, COALESCE(articles.page_id, articles.rowid),
+                    (SELECT s.word_count FROM article_stats s WHERE s.article_id = articles.rowid),
+                    (SELECT s.reading_minutes FROM article_stats s WHERE s.article_id = articles.rowid),
+                    rank, COALESCE((SELECT v.views FROM view_counts v WHERE v.title = articles.title), 0)
+             FROM articles
+             LEFT JOIN redirects r ON r.from_title = articles.title
              WHERE articles MATCH ?1 
+               AND (?3 OR r.from_title IS NULL)
              ORDER BY rank
              LIMIT ?2"
         )?;
 
-        let articles = stmt.query_map(params![query, limit as i64], |row| {
-            Ok(WikiArticle {
+        let params = params![query, candidates as i64, include_redirects];
+        let mut ranked = stmt.query_map(params, |row| {
+            let views: f64 = row.get(9)?;
+            let score = row.get::<_, f64>(8)? - popularity_boost * (views / (views + POPULARITY_HALF_VIEWS));
+            let article = WikiArticle {
+                id: row.get(5)?,
                 title: row.get(0)?,
                 content: row.get(1)?,
                 categories: Default::default(),
@@ -130,15 +402,269 @@ impl<'a> DatabaseReader<'a> {
                     ))?
                     .with_timezone(&Utc),
                 size: row.get(3)?,
-                redirect_to: None,
+                redirect_to: row.get(4)?,
                 images: Vec::new(),
-            })
+                raw_wikitext: None,
+                references: Vec::new(),
+                langlinks: Vec::new(),
+                word_count: row.get::<_, Option<usize>>(6)?.unwrap_or_default(),
+                reading_minutes: row.get::<_, Option<usize>>(7)?.unwrap_or_default(),
+            };
+            Ok((score, article))
         })?
         .collect::<Result<Vec<_>, _>>()?;
 
+        // BM25 scores are lower for better matches; the sort is stable, so ties keep rank order
+        if popularity_boost > 0.0 {
+            ranked.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        }
+        ranked.truncate(limit);
+
+        Ok(ranked.into_iter().map(|(_, article)| article).collect())
+    }
+
+    /// Fetch excerpts and categories for several titles, `TITLES_PER_QUERY` at a time.
+    ///
+    /// Results follow the order of `titles`; titles that are not found are skipped.
+    pub fn get_article_excerpts(&self, titles: &[String], excerpt_len: usize) -> WikiResult<Vec<ArticleExcerpt>> {
+        let normalized: Vec<String> = titles.iter().map(|t| normalize_title(t)).collect();
+        let mut by_title: HashMap<String, ArticleExcerpt> = HashMap::with_capacity(titles.len());
+
+        for chunk in normalized.chunks(TITLES_PER_QUERY) {
+            let placeholders = (0..chunk.len())
+                .map(|i| format!("?{}", i + 2))
+                .collect::<Vec<_>>()
+                .join(", ");
+            // Look the titles up in the article_titles index, then fetch each article by rowid
+            let sql = format!(
+                "SELECT t.title, a.title, substr(a.content, 1, ?1), group_concat(c.name, char(31))
+                 FROM article_titles t
+                 CROSS JOIN articles a ON a.rowid = t.article_id
+                 LEFT JOIN article_categories ac ON ac.article_id = a.rowid
+                 LEFT JOIN categories c ON c.id = ac.category_id
+                 WHERE t.title IN ({})
+                 GROUP BY a.rowid",
+                placeholders
+            );
+
+            let mut values: Vec<rusqlite::types::Value> = Vec::with_capacity(chunk.len() + 1);
+            values.push((excerpt_len as i64).into());
+            values.extend(chunk.iter().map(|t| t.clone().into()));
+
+            let mut stmt = self.conn.prepare(&sql)?;
+            let rows = stmt.query_map(params_from_iter(values), |row| {
+                let categories: Option<String> = row.get(3)?;
+                Ok((row.get::<_, String>(0)?, ArticleExcerpt {
+                    title: row.get(1)?,
+                    excerpt: row.get(2)?,
+                    categories: categories
+                        .map(|c| c.split('\u{1f}').map(|s| s.to_string()).collect())
+                        .unwrap_or_default(),
+                }))
+            })?;
+
+            for row in rows {
+                let (key, excerpt) = row?;
+                by_title.insert(key, excerpt);
+            }
+        }
+
+        Ok(normalized.iter().filter_map(|t| by_title.remove(t)).collect())
+    }
+
+    /// Load several articles with their categories, keyed by title.
+    ///
+    /// Titles are matched by their `normalize_title` form and fetched in chunks of `TITLES_PER_QUERY`;
+    /// missing titles are simply absent from the map, which is keyed by the stored title.
+    /// Images are not loaded.
+    pub fn get_articles_by_titles(&self, titles: &[String]) -> WikiResult<HashMap<String, WikiArticle>> {
+        let normalized: Vec<String> = titles.iter().map(|t| normalize_title(t)).collect();
+        let mut articles = HashMap::with_capacity(titles.len());
+
+        for chunk in normalized.chunks(TITLES_PER_QUERY) {
+            let placeholders = (0..chunk.len())
+                .map(|i| format!("?{}", i + 1))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let sql = format!(
+                "SELECT a.title, a.content, a.last_modified, a.size, group_concat(c.name, char(31)), COALESCE(a.page_id, a.rowid),
+                        (SELECT s.word_count FROM article_stats s WHERE s.article_id = a.rowid),
+                        (SELECT s.reading_minutes FROM article_stats s WHERE s.article_id = a.rowid)
+                 FROM article_titles t
+                 CROSS JOIN articles a ON a.rowid = t.article_id
+                 LEFT JOIN article_categories ac ON ac.article_id = a.rowid
+                 LEFT JOIN categories c ON c.id = ac.category_id
+                 WHERE t.title IN ({})
+                 GROUP BY a.rowid",
+                placeholders
+            );
+
+            let mut stmt = self.conn.prepare(&sql)?;
+            let rows = stmt.query_map(params_from_iter(chunk.iter()), |row| {
+                let categories: Option<String> = row.get(4)?;
+                Ok(WikiArticle {
+                    id: row.get(5)?,
+                    title: row.get(0)?,
+                    content: row.get(1)?,
+                    categories: categories
+                        .map(|c| c.split('\u{1f}').map(|s| s.to_string()).collect())
+                        .unwrap_or_default(),
+                    last_modified: DateTime::parse_from_rfc3339(&row.get::<_, String>(2)?)
+                        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(
+                            2,
+                            rusqlite::types::Type::Text,
+                            Box::new(e),
+                        ))?
+                        .with_timezone(&Utc),
+                    size: row.get(3)?,
+                    redirect_to: None,
+                    images: Vec::new(),
+                    raw_wikitext: None,
+                    references: Vec::new(),
+                    langlinks: Vec::new(),
+                    word_count: row.get::<_, Option<usize>>(6)?.unwrap_or_default(),
+                    reading_minutes: row.get::<_, Option<usize>>(7)?.unwrap_or_default(),
+                })
+            })?;
+
+            for article in rows {
+                let article = article?;
+                articles.insert(article.title.clone(), article);
+            }
+        }
+
         Ok(articles)
     }
 
+    /// Get the original wikitext of an article, if it was stored at import
+    pub fn get_raw_wikitext(&self, title: &str) -> WikiResult<Option<String>> {
+        match self.conn.query_row(
+            "SELECT raw_wikitext FROM articles WHERE title = ?1",
+            params![title],
+            |row| row.get::<_, Option<String>>(0),
+        ) {
+            Ok(raw) => Ok(raw),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(WikiError::from(e)),
+        }
+    }
+
+    /// Get an article's citations in order, or `None` if the article does not exist
+    pub fn get_references(&self, title: &str) -> WikiResult<Option<Vec<WikiReference>>> {
+        let rowid: i64 = match self.conn.query_row(
+            "SELECT rowid FROM articles WHERE title = ?1",
+            params![title],
+            |row| row.get(0),
+        ) {
+            Ok(rowid) => rowid,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+            Err(e) => return Err(WikiError::from(e)),
+        };
+
+        let mut stmt = self.conn.prepare(
+            "SELECT name, content FROM \"references\" WHERE article_id = ?1 ORDER BY position"
+        )?;
+        let references = stmt
+            .query_map(params![rowid], |row| {
+                Ok(WikiReference {
+                    name: row.get(0)?,
+                    content: row.get(1)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Some(references))
+    }
+
+    /// Get an article's links to other language editions by language code,
+    /// or `None` if the article does not exist
+    pub fn get_langlinks(&self, title: &str) -> WikiResult<Option<Vec<WikiLangLink>>> {
+        // Resolve the stored title through the article_titles index, then fetch by rowid
+        let stored = match self.conn.query_row(
+            "SELECT a.title FROM article_titles t CROSS JOIN articles a ON a.rowid = t.article_id
+             WHERE t.title = ?1",
+            params![normalize_title(title)],
+            |row| row.get::<_, String>(0),
+        ) {
+            Ok(stored) => stored,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+            Err(e) => return Err(WikiError::from(e)),
+        };
+
+        let mut stmt = self.conn.prepare(
+            "SELECT lang, target FROM langlinks WHERE title = ?1 ORDER BY lang"
+        )?;
+        let langlinks = stmt
+            .query_map(params![stored], |row| {
+                Ok(WikiLangLink {
+                    lang: row.get(0)?,
+                    target: row.get(1)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Some(langlinks))
+    }
+
+    /// Get the time an article was first imported into the corpus
+    pub fn get_first_seen(&self, title: &str) -> WikiResult<Option<DateTime<Utc>>> {
+        match self.conn.query_row(
+            "SELECT first_seen FROM articles WHERE title = ?1",
+            params![title],
+            |row| row.get::<_, Option<String>>(0),
+        ) {
+            Ok(Some(ts)) => Ok(DateTime::parse_from_rfc3339(&ts)
+                .ok()
+                .map(|dt| dt.with_timezone(&Utc))),
+            Ok(None) | Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(WikiError::from(e)),
+        }
+    }
+
+    /// List titles of articles first imported at or after `since`, oldest first
+    pub fn articles_added_since(&self, since: DateTime<Utc>) -> WikiResult<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT title FROM articles
+             WHERE first_seen >= ?1
+             ORDER BY first_seen, title"
+        )?;
+
+        let titles = stmt.query_map(params![since.to_rfc3339()], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+
+        Ok(titles)
+    }
+
+    /// List titles of articles that have no embedding in the vector store yet
+    pub fn titles_without_embeddings(&self, vector_store: &VectorStore) -> WikiResult<Vec<String>> {
+        let mut missing = Vec::new();
+        for title in self.list_titles()? {
+            if !vector_store.has_embedding(&title)? {
+                missing.push(title);
+            }
+        }
+
+        Ok(missing)
+    }
+
+    /// All article titles in alphabetical order
+    pub fn list_titles(&self) -> WikiResult<Vec<String>> {
+        let mut stmt = self.conn.prepare("SELECT title FROM articles ORDER BY title")?;
+        let titles = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        Ok(titles.collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Whether `title` names an article, or a redirect to one, without loading any content
+    pub fn article_exists(&self, title: &str) -> WikiResult<bool> {
+        let target = self.get_redirect(title)?.unwrap_or_else(|| title.to_string());
+        let exists = self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM article_titles WHERE title = ?1)",
+            params![normalize_title(&target)],
+            |row| row.get(0),
+        )?;
+        Ok(exists)
+    }
+
     pub fn get_redirect(&self, title: &str) -> WikiResult<Option<String>> {
         match self.conn.query_row(
             "SELECT to_title FROM redirects WHERE from_title = ?1",
@@ -151,6 +677,38 @@ impl<'a> DatabaseReader<'a> {
         }
     }
 
+    /// Value recorded under `key` in the install metadata
+    pub fn get_metadata(&self, key: &str) -> WikiResult<Option<String>> {
+        match self.conn.query_row(
+            "SELECT value FROM metadata WHERE key = ?1",
+            params![key],
+            |row| row.get(0),
+        ) {
+            Ok(value) => Ok(Some(value)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(WikiError::from(e)),
+        }
+    }
+
+    /// Stored summary of `title`, if it was generated for `etag`
+    pub fn get_summary(&self, title: &str, etag: &str) -> WikiResult<Option<String>> {
+        match self.conn.query_row(
+            "SELECT summary FROM summaries WHERE title = ?1 AND etag = ?2",
+            params![title, etag],
+            |row| row.get(0),
+        ) {
+            Ok(summary) => Ok(Some(summary)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(WikiError::from(e)),
+        }
+    }
+
+    /// Schema version the database was last initialized with, if any
+    pub fn schema_version(&self) -> WikiResult<Option<i32>> {
+        let version = self.conn.query_row("SELECT MAX(version) FROM schema_version", [], |row| row.get(0))?;
+        Ok(version)
+    }
+
     pub fn list_categories(&self) -> WikiResult<Vec<String>> {
         let mut stmt = self.conn.prepare("SELECT name FROM categories ORDER BY name")?;
         let categories = stmt.query_map([], |row| row.get(0))?;
@@ -163,6 +721,66 @@ impl<'a> DatabaseReader<'a> {
         Ok(results)
     }
 
+    /// Categories with their article counts, busiest first (ties by name)
+    pub fn category_counts(&self, limit: usize) -> WikiResult<Vec<(String, usize)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT c.name, COUNT(*) AS article_count
+             FROM article_categories ac
+             JOIN categories c ON c.id = ac.category_id
+             GROUP BY ac.category_id
+             ORDER BY article_count DESC, c.name
+             LIMIT ?1"
+        )?;
+        let counts = stmt
+            .query_map(params![limit as i64], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(counts)
+    }
+
+    /// A page of redirects as (from, to), ordered by the redirecting title
+    pub fn list_redirects(&self, limit: usize, offset: usize) -> WikiResult<Vec<(String, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT from_title, to_title FROM redirects ORDER BY from_title LIMIT ?1 OFFSET ?2"
+        )?;
+        let redirects = stmt
+            .query_map(params![limit as i64, offset as i64], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(redirects)
+    }
+
+    /// Most viewed articles with their view counts, most viewed first (ties by title)
+    pub fn most_viewed(&self, limit: usize) -> WikiResult<Vec<(String, u64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT title, views FROM view_counts ORDER BY views DESC, title LIMIT ?1"
+        )?;
+        let views = stmt
+            .query_map(params![limit as i64], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as u64))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(views)
+    }
+
+    pub fn count_articles(&self) -> WikiResult<usize> {
+        let count: i64 = self.conn.query_row("SELECT COUNT(*) FROM articles", [], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
+    pub fn count_categories(&self) -> WikiResult<usize> {
+        let count: i64 = self.conn.query_row("SELECT COUNT(*) FROM categories", [], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
+    pub fn count_images(&self) -> WikiResult<usize> {
+        let count: i64 = self.conn.query_row("SELECT COUNT(*) FROM images", [], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
     pub fn get_articles_in_category(&self, category: &str) -> WikiResult<Vec<String>> {
         let mut stmt = self.conn.prepare(
             "SELECT a.title 
@@ -182,6 +800,79 @@ impl<'a> DatabaseReader<'a> {
         
         Ok(results)
     }
+
+    /// Titles of articles in all or any of `names`, by title, `limit` rows from `offset`
+    pub fn get_articles_in_categories(
+        &self,
+        names: &[String],
+        mode: CategoryMatch,
+        limit: usize,
+        offset: usize,
+    ) -> WikiResult<Vec<String>> {
+        let mut names: Vec<&str> = names.iter().map(|name| name.as_str()).collect();
+        names.sort_unstable();
+        names.dedup();
+        if names.is_empty() {
+            return Ok(Vec::new());
+        }
+        if names.len() > TITLES_PER_QUERY {
+            return Err(WikiError::OperationFailed(format!(
+                "At most {} categories can be matched at once",
+                TITLES_PER_QUERY
+            )));
+        }
+
+        let required = match mode {
+            CategoryMatch::All => names.len(),
+            CategoryMatch::Any => 1,
+        };
+        let placeholders = vec!["?"; names.len()].join(", ");
+        let sql = format!(
+            "SELECT a.title
+             FROM articles a
+             JOIN article_categories ac ON ac.article_id = a.rowid
+             JOIN categories c ON c.id = ac.category_id
+             WHERE c.name IN ({})
+             GROUP BY a.rowid
+             HAVING COUNT(DISTINCT c.id) >= {}
+             ORDER BY a.title
+             LIMIT {} OFFSET {}",
+            placeholders, required, limit, offset
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let titles = stmt
+            .query_map(params_from_iter(names), |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(titles)
+    }
+}
+
+/// Build an article from a row laid out as in [`ALL_ARTICLES_SQL`]
+fn article_from_row(row: &rusqlite::Row) -> rusqlite::Result<WikiArticle> {
+    let categories: Option<String> = row.get(5)?;
+    Ok(WikiArticle {
+        id: row.get(4)?,
+        title: row.get(0)?,
+        content: row.get(1)?,
+        categories: categories
+            .map(|c| c.split('\u{1f}').map(|s| s.to_string()).collect())
+            .unwrap_or_default(),
+        last_modified: DateTime::parse_from_rfc3339(&row.get::<_, String>(2)?)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(
+                2,
+                rusqlite::types::Type::Text,
+                Box::new(e),
+            ))?
+            .with_timezone(&Utc),
+        size: row.get(3)?,
+        redirect_to: row.get(6)?,
+        images: Vec::new(),
+        raw_wikitext: None,
+        references: Vec::new(),
+        langlinks: Vec::new(),
+        word_count: row.get::<_, Option<usize>>(7)?.unwrap_or_default(),
+        reading_minutes: row.get::<_, Option<usize>>(8)?.unwrap_or_default(),
+    })
 }
 
 #[cfg(test)]
@@ -198,6 +889,129 @@ mod tests {
         (conn, temp_file)
     }
 
+    #[test]
+    fn test_get_articles_by_titles() -> WikiResult<()> {
+        let (conn, _temp_file) = create_test_db();
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        for title in ["One", "Two", "Three", "Four"] {
+            let mut article = WikiArticle::new(title.to_string(), format!("{} content", title));
+            article.add_category("Numbers".to_string());
+            article.update_size();
+            writer.write_article(&article, &tx)?;
+        }
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let titles: Vec<String> = ["One", "Two", "Three", "Four", "Missing"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        let reader = DatabaseReader::new(&conn);
+        let articles = reader.get_articles_by_titles(&titles)?;
+
+        assert_eq!(articles.len(), 4);
+        assert!(!articles.contains_key("Missing"));
+        assert_eq!(articles["Three"].content, "Three content");
+        assert!(articles["Two"].categories.contains("Numbers"));
+
+        // Titles resolve through the article_titles index like single lookups
+        let articles = reader.get_articles_by_titles(&["three".to_string()])?;
+        assert_eq!(articles["Three"].content, "Three content");
+        let excerpts = reader.get_article_excerpts(&["four".to_string(), "One".to_string()], 4)?;
+        assert_eq!(excerpts.iter().map(|e| e.title.as_str()).collect::<Vec<_>>(), ["Four", "One"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_batch_fetches_chunk_past_the_variable_limit() -> WikiResult<()> {
+        let (conn, _temp_file) = create_test_db();
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        for i in (0..2000).step_by(100) {
+            writer.write_article(&WikiArticle::new(format!("Article {}", i), format!("Body {}", i)), &tx)?;
+        }
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let titles: Vec<String> = (0..2000).rev().map(|i| format!("Article {}", i)).collect();
+        let reader = DatabaseReader::new(&conn);
+
+        let articles = reader.get_articles_by_titles(&titles)?;
+        assert_eq!(articles.len(), 20);
+        assert_eq!(articles["Article 1900"].content, "Body 1900");
+
+        let excerpts = reader.get_article_excerpts(&titles, 4)?;
+        assert_eq!(excerpts.len(), 20);
+        assert_eq!(excerpts[0].title, "Article 1900");
+        assert_eq!(excerpts[19].title, "Article 0");
+        assert_eq!(excerpts[0].excerpt, "Body");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_category_counts_busiest_first() -> WikiResult<()> {
+        let (conn, _temp_file) = create_test_db();
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        for (title, categories) in [
+            ("Mercury", vec!["Planets", "Elements"]),
+            ("Venus", vec!["Planets"]),
+            ("Earth", vec!["Planets"]),
+            ("Iron", vec!["Elements"]),
+            ("Pluto", vec!["Dwarf planets"]),
+        ] {
+            let mut article = WikiArticle::new(title.to_string(), format!("{} content", title));
+            for category in categories {
+                article.add_category(category.to_string());
+            }
+            writer.write_article(&article, &tx)?;
+        }
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let reader = DatabaseReader::new(&conn);
+        assert_eq!(
+            reader.category_counts(10)?,
+            vec![
+                ("Planets".to_string(), 3),
+                ("Elements".to_string(), 2),
+                ("Dwarf planets".to_string(), 1),
+            ]
+        );
+        assert_eq!(reader.category_counts(1)?, vec![("Planets".to_string(), 3)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_excludes_redirects_by_default() -> WikiResult<()> {
+        let (conn, _temp_file) = create_test_db();
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+
+        let mut article = WikiArticle::new("Widget".to_string(), "A widget is a small gadget.".to_string());
+        article.update_size();
+        writer.write_article(&article, &tx)?;
+
+        let mut redirect = WikiArticle::new("Widgets".to_string(), "Redirect to widget".to_string());
+        redirect.redirect_to = Some("Widget".to_string());
+        redirect.update_size();
+        writer.write_article(&redirect, &tx)?;
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let reader = DatabaseReader::new(&conn);
+        let results = reader.search_articles("widget", 10)?;
+        let titles: Vec<&str> = results.iter().map(|a| a.title.as_str()).collect();
+        assert_eq!(titles, vec!["Widget"]);
+
+        let results = reader.search_articles_filtered("widget", 10, true)?;
+        assert_eq!(results.len(), 2);
+        let redirect = results.iter().find(|a| a.title == "Widgets").unwrap();
+        assert_eq!(redirect.redirect_to.as_deref(), Some("Widget"));
+
+        Ok(())
+    }
+
     #[test]
     fn test_get_article() -> WikiResult<()> {
         let (mut conn, _temp_file) = create_test_db();
@@ -260,4 +1074,250 @@ mod tests {
 
         Ok(())
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_article_exists_follows_redirects() -> WikiResult<()> {
+        let (conn, _temp_file) = create_test_db();
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        writer.write_article(&WikiArticle::new("Target".to_string(), "The target.".to_string()), &tx)?;
+        for (from, to) in [("Alias", "Target"), ("Broken", "Nowhere")] {
+            let mut redirect = WikiArticle::new(from.to_string(), format!("#REDIRECT [[{}]]", to));
+            redirect.redirect_to = Some(to.to_string());
+            writer.write_article(&redirect, &tx)?;
+        }
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let reader = DatabaseReader::new(&conn);
+        assert!(reader.article_exists("Target")?);
+        assert!(reader.article_exists("Alias")?);
+        assert!(!reader.article_exists("Broken")?);
+        assert!(!reader.article_exists("Missing")?);
+        // Looked up by normalized title, as stored in article_titles
+        assert!(reader.article_exists("target")?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_titles_without_embeddings() -> WikiResult<()> {
+        let (conn, _temp_file) = create_test_db();
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        for title in ["First", "Second", "Third"] {
+            let article = WikiArticle::new(title.to_string(), format!("{} article.", title));
+            writer.write_article(&article, &tx)?;
+        }
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let vector_dir = tempfile::TempDir::new()?;
+        let vector_store = VectorStore::new(vector_dir.path(), "http://localhost:11434").await?;
+        vector_store.store_embedding("First", &[0.1, 0.2])?;
+        vector_store.store_embedding("Third", &[0.3, 0.4])?;
+
+        let reader = DatabaseReader::new(&conn);
+        assert_eq!(reader.titles_without_embeddings(&vector_store)?, vec!["Second".to_string()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_article_has_rowid() -> WikiResult<()> {
+        let (conn, _temp_file) = create_test_db();
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        writer.write_article(&WikiArticle::new("First".to_string(), "One.".to_string()), &tx)?;
+        writer.write_article(&WikiArticle::new("Second".to_string(), "Two.".to_string()), &tx)?;
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let reader = DatabaseReader::new(&conn);
+        let first = reader.get_article("First")?.unwrap();
+        let second = reader.get_article("Second")?.unwrap();
+        let first_id = first.id.expect("article read back without an id");
+        assert!(first_id > 0);
+        assert_ne!(second.id, Some(first_id));
+
+        let listed = reader.get_articles(10)?;
+        assert!(listed.iter().any(|a| a.title == "First" && a.id == Some(first_id)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_all_articles_returns_inserted_count() -> WikiResult<()> {
+        let (conn, _temp_file) = create_test_db();
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        for i in 0..25 {
+            let mut article = WikiArticle::new(format!("Article {}", i), format!("Content {}", i));
+            article.add_category("Numbers".to_string());
+            writer.write_article(&article, &tx)?;
+        }
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let reader = DatabaseReader::new(&conn);
+        let all = reader.get_all_articles()?;
+        assert_eq!(all.len(), 25);
+        assert_eq!(all[0].title, "Article 0");
+        assert!(all[0].categories.contains("Numbers"));
+
+        let mut streamed = 0;
+        assert_eq!(reader.for_each_article(|_| { streamed += 1; Ok(()) })?, 25);
+        assert_eq!(streamed, 25);
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_articles_by_category() -> WikiResult<()> {
+        let (conn, _temp_file) = create_test_db();
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        let mut both = WikiArticle::new("Both".to_string(), "In two categories.".to_string());
+        both.add_category("Red".to_string());
+        both.add_category("Blue".to_string());
+        writer.write_article(&both, &tx)?;
+        let mut red = WikiArticle::new("Only Red".to_string(), "In one category.".to_string());
+        red.add_category("Red".to_string());
+        writer.write_article(&red, &tx)?;
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let reader = DatabaseReader::new(&conn);
+        let in_red = reader.get_articles_by_category("Red")?;
+        assert_eq!(in_red.iter().map(|a| a.title.as_str()).collect::<Vec<_>>(), vec!["Both", "Only Red"]);
+        assert_eq!(in_red[0].content, "In two categories.");
+        assert_eq!(in_red[0].categories.len(), 2);
+
+        let in_blue = reader.get_articles_by_category("Blue")?;
+        assert_eq!(in_blue.len(), 1);
+        assert_eq!(in_blue[0].title, "Both");
+
+        let page = reader.get_articles_by_category_page("Red", 1, 1)?;
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].title, "Only Red");
+
+        assert_eq!(reader.get_articles_in_category("Red")?, vec!["Both".to_string(), "Only Red".to_string()]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_articles_in_categories() -> WikiResult<()> {
+        let (conn, _temp_file) = create_test_db();
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        for (title, categories) in [
+            ("Purple", vec!["Red", "Blue"]),
+            ("White", vec!["Red", "Blue", "Green"]),
+            ("Cherry", vec!["Red"]),
+            ("Sky", vec!["Blue"]),
+            ("Grass", vec!["Green"]),
+        ] {
+            let mut article = WikiArticle::new(title.to_string(), format!("{} content", title));
+            for category in categories {
+                article.add_category(category.to_string());
+            }
+            writer.write_article(&article, &tx)?;
+        }
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let reader = DatabaseReader::new(&conn);
+        let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            reader.get_articles_in_categories(&names(&["Red", "Blue"]), CategoryMatch::All, 10, 0)?,
+            vec!["Purple", "White"]
+        );
+        assert_eq!(
+            reader.get_articles_in_categories(&names(&["Red", "Blue", "Green"]), CategoryMatch::All, 10, 0)?,
+            vec!["White"]
+        );
+        // A repeated name must not make an article in it alone count as in "all"
+        assert_eq!(
+            reader.get_articles_in_categories(&names(&["Red", "Red"]), CategoryMatch::All, 10, 0)?,
+            vec!["Cherry", "Purple", "White"]
+        );
+        assert!(reader.get_articles_in_categories(&names(&["Red", "Missing"]), CategoryMatch::All, 10, 0)?.is_empty());
+
+        assert_eq!(
+            reader.get_articles_in_categories(&names(&["Blue", "Green"]), CategoryMatch::Any, 10, 0)?,
+            vec!["Grass", "Purple", "Sky", "White"]
+        );
+        assert_eq!(
+            reader.get_articles_in_categories(&names(&["Blue", "Green"]), CategoryMatch::Any, 2, 1)?,
+            vec!["Purple", "Sky"]
+        );
+        assert!(reader.get_articles_in_categories(&[], CategoryMatch::Any, 10, 0)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_scoped_to_title() -> WikiResult<()> {
+        let (conn, _temp_file) = create_test_db();
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        writer.write_article(&WikiArticle::new("Volcano".to_string(), "A mountain that erupts.".to_string()), &tx)?;
+        writer.write_article(&WikiArticle::new("Iceland".to_string(), "An island with many a volcano.".to_string()), &tx)?;
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let reader = DatabaseReader::new(&conn);
+        let titles = |fields| -> WikiResult<Vec<String>> {
+            let mut titles: Vec<String> = reader
+                .search_articles_in("volcano", 10, false, fields)?
+                .into_iter()
+                .map(|a| a.title)
+                .collect();
+            titles.sort();
+            Ok(titles)
+        };
+
+        assert_eq!(titles(SearchFields::Title)?, vec!["Volcano".to_string()]);
+        assert_eq!(titles(SearchFields::Content)?, vec!["Iceland".to_string()]);
+        assert_eq!(titles(SearchFields::Both)?, vec!["Iceland".to_string(), "Volcano".to_string()]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_popularity_boost_breaks_bm25_tie() -> WikiResult<()> {
+        let (conn, _temp_file) = create_test_db();
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        // Same content and title length, so BM25 scores them identically
+        for title in ["Ares", "Zeus"] {
+            writer.write_article(&WikiArticle::new(title.to_string(), "A god of the Greek pantheon.".to_string()), &tx)?;
+        }
+        DatabaseWriter::commit_transaction(tx)?;
+        conn.execute("INSERT INTO view_counts (title, views) VALUES ('Zeus', 40), ('Ares', 2)", [])?;
+
+        let reader = DatabaseReader::new(&conn);
+        let ranked = |boost| -> WikiResult<Vec<String>> {
+            Ok(reader
+                .search_articles_boosted("pantheon", 10, false, SearchFields::Both, boost)?
+                .into_iter()
+                .map(|a| a.title)
+                .collect())
+        };
+
+        assert_eq!(ranked(1.0)?, vec!["Zeus".to_string(), "Ares".to_string()]);
+        assert_eq!(ranked(0.0)?.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rank_weights_title_matches() -> WikiResult<()> {
+        let (conn, _temp_file) = create_test_db();
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        writer.write_article(&WikiArticle::new("Volcano".to_string(), "A rupture in the crust.".to_string()), &tx)?;
+        writer.write_article(
+            &WikiArticle::new("Iceland".to_string(), "An island with a volcano, another volcano and a third volcano.".to_string()),
+            &tx,
+        )?;
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let titles: Vec<String> = DatabaseReader::new(&conn)
+            .search_articles_boosted("volcano", 10, false, SearchFields::Both, 0.0)?
+            .into_iter()
+            .map(|a| a.title)
+            .collect();
+        assert_eq!(titles, vec!["Volcano".to_string(), "Iceland".to_string()]);
+        Ok(())
+    }
+}
@@ -1,7 +1,35 @@
 use rusqlite::{Connection, Result as SqlResult};
 use tracing::info;
 
-pub const SCHEMA_VERSION: i32 = 1;
+use crate::error_handling::{WikiError, WikiResult};
+use super::writer::normalize_title;
+
+pub const SCHEMA_VERSION: i32 = 10;
+
+/// Metadata key holding the date (YYYYMMDD) of the dump the database was installed from
+pub const DUMP_DATE_KEY: &str = "dump_date";
+
+/// Fail with a clear configuration error when the linked SQLite was built without
+/// FTS5, which the `articles` table needs; call before [`init_database`]
+pub fn ensure_fts5(conn: &Connection) -> WikiResult<()> {
+    fts5_probe_result(conn.execute_batch(
+        "CREATE VIRTUAL TABLE temp.fts5_probe USING fts5(content);
+         DROP TABLE temp.fts5_probe;",
+    ))
+}
+
+/// Turn a failed FTS5 probe caused by the missing module into a [`WikiError::Configuration`]
+fn fts5_probe_result(probe: SqlResult<()>) -> WikiResult<()> {
+    match probe {
+        Ok(()) => Ok(()),
+        Err(e) if e.to_string().contains("no such module") => Err(WikiError::Configuration(format!(
+            "SQLite was built without FTS5 full-text search ({}). Build with rusqlite's \"bundled\" \
+             feature, which compiles FTS5 in, or link a system SQLite compiled with SQLITE_ENABLE_FTS5",
+            e
+        ))),
+        Err(e) => Err(WikiError::from(e)),
+    }
+}
 
 pub fn init_database(conn: &Connection) -> SqlResult<()> {
     info!("Initializing database schema v{}", SCHEMA_VERSION);
@@ -15,12 +43,13 @@ pub fn init_database(conn: &Connection) -> SqlResult<()> {
     )?;
 
     // Create articles table with FTS5
+    conn.execute(&articles_table_sql("articles"), [])?;
+
+    // Create article_titles table (indexed lookup by normalized title, which the FTS5 table lacks)
     conn.execute(
-        "CREATE VIRTUAL TABLE IF NOT EXISTS articles USING fts5(
-            title,
-            content,
-            size UNINDEXED,
-            last_modified UNINDEXED
+        "CREATE TABLE IF NOT EXISTS article_titles (
+            title TEXT PRIMARY KEY,
+            article_id INTEGER NOT NULL
         )",
         [],
     )?;
@@ -54,7 +83,9 @@ pub fn init_database(conn: &Connection) -> SqlResult<()> {
             size INTEGER NOT NULL DEFAULT 0,
             mime_type TEXT NOT NULL,
             hash TEXT NOT NULL,
-            caption TEXT
+            caption TEXT,
+            width INTEGER,
+            height INTEGER
         )",
         [],
     )?;
@@ -79,11 +110,80 @@ pub fn init_database(conn: &Connection) -> SqlResult<()> {
         [],
     )?;
 
+    // Create references table ("references" is a keyword, so it is always quoted)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS \"references\" (
+            id INTEGER PRIMARY KEY,
+            article_id INTEGER NOT NULL,
+            position INTEGER NOT NULL,
+            name TEXT,
+            content TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // Create langlinks table (the article in other language editions)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS langlinks (
+            title TEXT NOT NULL,
+            lang TEXT NOT NULL,
+            target TEXT NOT NULL,
+            PRIMARY KEY (title, lang)
+        )",
+        [],
+    )?;
+
+    // Create metadata table (facts about the install, such as the dump date)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS metadata (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // Create summaries table (generated summaries, tagged by the content and options they came from)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS summaries (
+            title TEXT PRIMARY KEY,
+            etag TEXT NOT NULL,
+            summary TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // Create view_counts table
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS view_counts (
+            title TEXT PRIMARY KEY,
+            views INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+
+    // Create article_stats table (display metadata computed at insert)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS article_stats (
+            article_id INTEGER PRIMARY KEY,
+            word_count INTEGER NOT NULL,
+            reading_minutes INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
     // Create indexes
     conn.execute("CREATE INDEX IF NOT EXISTS idx_categories_name ON categories(name)", [])?;
     conn.execute("CREATE INDEX IF NOT EXISTS idx_images_filename ON images(filename)", [])?;
     conn.execute("CREATE INDEX IF NOT EXISTS idx_images_hash ON images(hash)", [])?;
     conn.execute("CREATE INDEX IF NOT EXISTS idx_redirects_to ON redirects(to_title)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_references_article ON \"references\"(article_id)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_view_counts_views ON view_counts(views)", [])?;
+
+    // Add columns introduced after a database was first created
+    migrate_columns(conn)?;
+    migrate_articles_table(conn)?;
+    backfill_article_titles(conn)?;
+    configure_article_rank(conn)?;
 
     // Set schema version
     conn.execute("INSERT OR REPLACE INTO schema_version (version) VALUES (?1)", [SCHEMA_VERSION])?;
@@ -92,6 +192,135 @@ pub fn init_database(conn: &Connection) -> SqlResult<()> {
     Ok(())
 }
 
+/// Ranking function FTS5 uses for `rank` on the `articles` table: BM25 with a title
+/// match weighted ten times a content match. The unindexed columns hold no terms, so
+/// they need no weight
+const ARTICLE_RANK: &str = "bm25(10.0, 1.0)";
+
+/// Store [`ARTICLE_RANK`] in the full-text table's configuration so `ORDER BY rank`
+/// uses it. The setting lives with the table, so it is applied again after a migration
+/// rebuilds the table
+fn configure_article_rank(conn: &Connection) -> SqlResult<()> {
+    let table = fts_table(conn)?;
+    conn.execute(&format!("INSERT INTO {0} ({0}, rank) VALUES ('rank', ?1)", table), [ARTICLE_RANK])?;
+    Ok(())
+}
+
+/// Full-text table of a database. Databases made by `DatabaseWriter::create_tables`
+/// index a plain `articles` table through `articles_fts`; otherwise `articles` is the
+/// FTS5 table itself
+pub fn fts_table(conn: &Connection) -> SqlResult<&'static str> {
+    let has_fts_table: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE name = 'articles_fts')",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(if has_fts_table { "articles_fts" } else { "articles" })
+}
+
+/// Columns added to existing tables since v1, as (table, column, declaration)
+const ADDED_COLUMNS: &[(&str, &str, &str)] = &[
+    ("images", "width", "INTEGER"),
+    ("images", "height", "INTEGER"),
+];
+
+/// Columns of the `articles` FTS5 table, as (column, declaration, value given to rows
+/// copied from an older table that lacks the column)
+const ARTICLE_COLUMNS: &[(&str, &str, &str)] = &[
+    ("title", "title", "''"),
+    ("content", "content", "''"),
+    ("size", "size UNINDEXED", "0"),
+    ("last_modified", "last_modified UNINDEXED", "NULL"),
+    // The first import we know of is the one that set last_modified
+    ("first_seen", "first_seen UNINDEXED", "last_modified"),
+    ("raw_wikitext", "raw_wikitext UNINDEXED", "NULL"),
+    // The `<id>` of the page in the dump, when it had one
+    ("page_id", "page_id UNINDEXED", "NULL"),
+];
+
+/// `CREATE VIRTUAL TABLE` statement for an articles table named `name`
+fn articles_table_sql(name: &str) -> String {
+    let columns: Vec<&str> = ARTICLE_COLUMNS.iter().map(|(_, declaration, _)| *declaration).collect();
+    format!("CREATE VIRTUAL TABLE IF NOT EXISTS {} USING fts5({})", name, columns.join(", "))
+}
+
+/// Names of the columns of `table`
+fn table_columns(conn: &Connection, table: &str) -> SqlResult<Vec<String>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<SqlResult<Vec<_>>>()?;
+    Ok(columns)
+}
+
+/// Add any of [`ADDED_COLUMNS`] missing from databases created by an older version
+fn migrate_columns(conn: &Connection) -> SqlResult<()> {
+    for (table, column, declaration) in ADDED_COLUMNS {
+        let exists = table_columns(conn, table)?.iter().any(|name| name == column);
+        if !exists {
+            info!("Adding column {}.{}", table, column);
+            conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, declaration), [])?;
+        }
+    }
+    Ok(())
+}
+
+/// Rebuild an `articles` table created by an older version that lacks some of
+/// [`ARTICLE_COLUMNS`]. FTS5 tables cannot be altered, so the rows are copied into a
+/// new table, keeping their rowids so the tables that reference articles stay valid
+fn migrate_articles_table(conn: &Connection) -> SqlResult<()> {
+    let existing = table_columns(conn, "articles")?;
+    if ARTICLE_COLUMNS.iter().all(|(column, _, _)| existing.iter().any(|name| name == column)) {
+        return Ok(());
+    }
+
+    info!("Rebuilding articles table to add new columns");
+    let columns: Vec<&str> = ARTICLE_COLUMNS.iter().map(|(column, _, _)| *column).collect();
+    let values: Vec<String> = ARTICLE_COLUMNS
+        .iter()
+        .map(|(column, _, fill)| {
+            if existing.iter().any(|name| name == column) {
+                column.to_string()
+            } else {
+                fill.to_string()
+            }
+        })
+        .collect();
+
+    conn.execute_batch("SAVEPOINT migrate_articles")?;
+    let migrated = conn.execute_batch(&format!(
+        "ALTER TABLE articles RENAME TO articles_old;
+         {};
+         INSERT INTO articles (rowid, {}) SELECT rowid, {} FROM articles_old;
+         DROP TABLE articles_old;",
+        articles_table_sql("articles"),
+        columns.join(", "),
+        values.join(", "),
+    ));
+    match migrated {
+        Ok(()) => conn.execute_batch("RELEASE migrate_articles"),
+        Err(e) => {
+            conn.execute_batch("ROLLBACK TO migrate_articles; RELEASE migrate_articles")?;
+            Err(e)
+        }
+    }
+}
+
+/// Index the titles of articles stored before `article_titles` existed
+fn backfill_article_titles(conn: &Connection) -> SqlResult<()> {
+    let indexed: bool = conn.query_row("SELECT EXISTS(SELECT 1 FROM article_titles)", [], |row| row.get(0))?;
+    if !indexed {
+        let mut select = conn.prepare("SELECT title, rowid FROM articles")?;
+        let mut insert = conn.prepare("INSERT OR IGNORE INTO article_titles (title, article_id) VALUES (?1, ?2)")?;
+        let mut rows = select.query([])?;
+        while let Some(row) = rows.next()? {
+            let title: String = row.get(0)?;
+            insert.execute(rusqlite::params![normalize_title(&title), row.get::<_, i64>(1)?])?;
+        }
+    }
+    Ok(())
+}
+
 pub fn check_schema_version(conn: &Connection) -> SqlResult<bool> {
     let version: i32 = conn.query_row(
         "SELECT version FROM schema_version LIMIT 1",
@@ -114,12 +343,13 @@ pub fn init_schema(conn: &Connection) -> SqlResult<()> {
     )?;
 
     // Create articles table with FTS5
+    conn.execute(&articles_table_sql("articles"), [])?;
+
+    // Create article_titles table (indexed lookup by normalized title, which the FTS5 table lacks)
     conn.execute(
-        "CREATE VIRTUAL TABLE IF NOT EXISTS articles USING fts5(
-            title,
-            content,
-            size UNINDEXED,
-            last_modified UNINDEXED
+        "CREATE TABLE IF NOT EXISTS article_titles (
+            title TEXT PRIMARY KEY,
+            article_id INTEGER NOT NULL
         )",
         [],
     )?;
@@ -153,7 +383,9 @@ pub fn init_schema(conn: &Connection) -> SqlResult<()> {
             size INTEGER NOT NULL DEFAULT 0,
             mime_type TEXT NOT NULL,
             hash TEXT NOT NULL,
-            caption TEXT
+            caption TEXT,
+            width INTEGER,
+            height INTEGER
         )",
         [],
     )?;
@@ -178,15 +410,174 @@ pub fn init_schema(conn: &Connection) -> SqlResult<()> {
         [],
     )?;
 
+    // Create references table ("references" is a keyword, so it is always quoted)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS \"references\" (
+            id INTEGER PRIMARY KEY,
+            article_id INTEGER NOT NULL,
+            position INTEGER NOT NULL,
+            name TEXT,
+            content TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // Create langlinks table (the article in other language editions)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS langlinks (
+            title TEXT NOT NULL,
+            lang TEXT NOT NULL,
+            target TEXT NOT NULL,
+            PRIMARY KEY (title, lang)
+        )",
+        [],
+    )?;
+
+    // Create metadata table (facts about the install, such as the dump date)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS metadata (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // Create summaries table (generated summaries, tagged by the content and options they came from)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS summaries (
+            title TEXT PRIMARY KEY,
+            etag TEXT NOT NULL,
+            summary TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // Create view_counts table
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS view_counts (
+            title TEXT PRIMARY KEY,
+            views INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+
+    // Create article_stats table (display metadata computed at insert)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS article_stats (
+            article_id INTEGER PRIMARY KEY,
+            word_count INTEGER NOT NULL,
+            reading_minutes INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
     // Create indexes
     conn.execute("CREATE INDEX IF NOT EXISTS idx_categories_name ON categories(name)", [])?;
     conn.execute("CREATE INDEX IF NOT EXISTS idx_images_filename ON images(filename)", [])?;
     conn.execute("CREATE INDEX IF NOT EXISTS idx_images_hash ON images(hash)", [])?;
     conn.execute("CREATE INDEX IF NOT EXISTS idx_redirects_to ON redirects(to_title)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_references_article ON \"references\"(article_id)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_view_counts_views ON view_counts(views)", [])?;
+
+    // Add columns introduced after a database was first created
+    migrate_columns(conn)?;
+    migrate_articles_table(conn)?;
+    backfill_article_titles(conn)?;
+    configure_article_rank(conn)?;
 
     // Set schema version
     conn.execute("INSERT OR REPLACE INTO schema_version (version) VALUES (?1)", [SCHEMA_VERSION])?;
 
     info!("Database schema initialized successfully");
     Ok(())
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_fts5_is_a_configuration_error() {
+        let conn = Connection::open_in_memory().unwrap();
+        assert!(ensure_fts5(&conn).is_ok());
+
+        let missing = rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_ERROR),
+            Some("no such module: fts5".to_string()),
+        );
+        match fts5_probe_result(Err(missing)) {
+            Err(WikiError::Configuration(message)) => {
+                assert!(message.contains("without FTS5"), "{}", message);
+                assert!(message.contains("SQLITE_ENABLE_FTS5"), "{}", message);
+            }
+            other => panic!("expected a configuration error, got {:?}", other),
+        }
+
+        let locked = rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY),
+            Some("database is locked".to_string()),
+        );
+        assert!(matches!(fts5_probe_result(Err(locked)), Err(WikiError::Database(_))));
+    }
+
+    #[test]
+    fn test_articles_table_from_older_version_is_migrated() -> WikiResult<()> {
+        let conn = Connection::open_in_memory()?;
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE articles USING fts5(title, content, size UNINDEXED, last_modified UNINDEXED);
+             INSERT INTO articles (rowid, title, content, size, last_modified)
+                VALUES (7, 'Old Article', 'Stored before first_seen.', 25, '2020-01-01T00:00:00+00:00');",
+        )?;
+
+        init_database(&conn)?;
+
+        let (rowid, first_seen, raw): (i64, String, Option<String>) = conn.query_row(
+            "SELECT rowid, first_seen, raw_wikitext FROM articles WHERE articles MATCH 'stored'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        assert_eq!((rowid, first_seen.as_str(), raw), (7, "2020-01-01T00:00:00+00:00", None));
+
+        let article_id: i64 = conn.query_row(
+            "SELECT article_id FROM article_titles WHERE title = 'Old Article'",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(article_id, 7);
+
+        let writer = crate::db::DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        writer.write_article(&crate::parser::models::WikiArticle::new("New Article".to_string(), "Fresh.".to_string()), &tx)?;
+        crate::db::DatabaseWriter::commit_transaction(tx)?;
+
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM articles", [], |row| row.get(0))?;
+        assert_eq!(count, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_articles_table_without_raw_wikitext_is_migrated() -> WikiResult<()> {
+        let conn = Connection::open_in_memory()?;
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE articles USING fts5(title, content, size UNINDEXED, last_modified UNINDEXED, first_seen UNINDEXED);
+             INSERT INTO articles (title, content, size, last_modified, first_seen)
+                VALUES ('Old Article', 'Cleaned.', 8, '2020-01-01T00:00:00+00:00', '2019-06-01T00:00:00+00:00');",
+        )?;
+
+        init_database(&conn)?;
+
+        let first_seen: String = conn.query_row("SELECT first_seen FROM articles", [], |row| row.get(0))?;
+        assert_eq!(first_seen, "2019-06-01T00:00:00+00:00");
+
+        let mut article = crate::parser::models::WikiArticle::new("Raw Article".to_string(), "Cleaned.".to_string());
+        article.raw_wikitext = Some("[[Cleaned]].".to_string());
+        let writer = crate::db::DatabaseWriter::new(&conn).with_raw_wikitext(true);
+        let tx = writer.begin_transaction()?;
+        writer.write_article(&article, &tx)?;
+        crate::db::DatabaseWriter::commit_transaction(tx)?;
+
+        let reader = crate::db::DatabaseReader::new(&conn);
+        assert_eq!(reader.get_raw_wikitext("Raw Article")?.as_deref(), Some("[[Cleaned]]."));
+        assert_eq!(reader.get_raw_wikitext("Old Article")?, None);
+        Ok(())
+    }
+}
@@ -17,6 +17,7 @@ fn test_article_insertion() -> WikiResult<()> {
     let tx = db.begin_transaction()?;
 
     let article = WikiArticle {
+        id: None,
         title: "Test Article".to_string(),
         content: "This is a test article.".to_string(),
         categories: vec!["Test Category".to_string()].into_iter().collect(),
@@ -24,6 +25,11 @@ fn test_article_insertion() -> WikiResult<()> {
         size: 0,
         redirect_to: None,
         images: vec![],
+        raw_wikitext: None,
+        references: Vec::new(),
+        langlinks: Vec::new(),
+        word_count: 0,
+        reading_minutes: 0,
     };
 
     let article_id = db.insert_article(&article, &tx)?;
@@ -68,6 +74,8 @@ fn test_image_insertion() -> WikiResult<()> {
         mime_type: "image/jpeg".to_string(),
         hash: "abcdef123456".to_string(),
         caption: Some("Test Caption".to_string()),
+        width: None,
+        height: None,
     };
 
     let image_id = db.insert_image(&image, &tx)?;
@@ -94,9 +102,12 @@ fn test_full_article_with_relations() -> WikiResult<()> {
         mime_type: "image/jpeg".to_string(),
         hash: "abcdef123456".to_string(),
         caption: Some("Test Caption".to_string()),
+        width: None,
+        height: None,
     };
 
     let mut article = WikiArticle {
+        id: None,
         title: "Test Article".to_string(),
         content: "This is a test article with an image.".to_string(),
         categories: vec!["Test Category".to_string(), "Another Category".to_string()]
@@ -106,6 +117,11 @@ fn test_full_article_with_relations() -> WikiResult<()> {
         size: 0,
         redirect_to: None,
         images: vec![image],
+        raw_wikitext: None,
+        references: Vec::new(),
+        langlinks: Vec::new(),
+        word_count: 0,
+        reading_minutes: 0,
     };
 
     article.update_size();
@@ -119,4 +135,30 @@ fn test_full_article_with_relations() -> WikiResult<()> {
     assert_eq!(results.len(), 1);
     assert_eq!(results[0].title, "Test Article");
     Ok(())
-} 
\ No newline at end of file
+}
+
+#[test]
+fn test_read_back_keeps_the_page_id() -> WikiResult<()> {
+    let temp_file = NamedTempFile::new()?;
+    let conn = rusqlite::Connection::open(temp_file.path())?;
+    init_database(&conn)?;
+
+    // The parsed page id is kept; an article without one gets the rowid it was stored under
+    let mut parsed = WikiArticle::new("Parsed".to_string(), "Parsed content.".to_string());
+    parsed.id = Some(4242);
+    let writer = DatabaseWriter::new(&conn);
+    let tx = writer.begin_transaction()?;
+    writer.write_article(&parsed, &tx)?;
+    writer.write_article(&WikiArticle::new("Unnumbered".to_string(), "Other content.".to_string()), &tx)?;
+    DatabaseWriter::commit_transaction(tx)?;
+
+    let reader = DatabaseReader::new(&conn);
+    assert_eq!(reader.get_article("Parsed")?.expect("Parsed missing").id, Some(4242));
+    let unnumbered = reader.get_article("Unnumbered")?.expect("Unnumbered missing");
+    let rowid: i64 = conn.query_row("SELECT rowid FROM articles WHERE title = 'Unnumbered'", [], |row| row.get(0))?;
+    assert_eq!(unnumbered.id, Some(rowid));
+
+    let listed = reader.get_articles(10)?;
+    assert!(listed.iter().any(|a| a.title == "Parsed" && a.id == Some(4242)));
+    Ok(())
+}
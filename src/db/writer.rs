@@ -1,13 +1,44 @@
-use rusqlite::{Connection, Transaction, params};
+use rusqlite::{Connection, OptionalExtension, Transaction, params};
+use chrono::{DateTime, Utc};
+use std::cell::RefCell;
 use std::collections::HashMap;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 use crate::error_handling::{WikiError, WikiResult};
-use crate::parser::models::{WikiArticle, WikiImage};
+use crate::parser::models::{count_words, reading_time_minutes, WikiArticle, WikiImage};
+
+/// Redirect hops followed when flattening a chain before giving up
+const MAX_REDIRECT_HOPS: usize = 8;
+
+/// Summary of an import run
+#[derive(Debug, Default, Clone)]
+pub struct ImportReport {
+    /// Number of articles written, not counting pages that replaced or lost to a colliding title
+    pub articles_written: usize,
+    /// Number of pages whose title normalized to an already imported title
+    pub title_collisions: usize,
+}
+
+/// Normalize a title the way MediaWiki does: underscores and runs of whitespace become
+/// single spaces and only the first letter is case-folded, so "Gold" and "GOLD" stay distinct
+pub fn normalize_title(title: &str) -> String {
+    let joined = title
+        .split(|c: char| c.is_whitespace() || c == '_')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut chars = joined.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => joined,
+    }
+}
 
 pub struct DatabaseWriter<'a> {
     conn: &'a Connection,
     category_cache: HashMap<String, i64>,
+    report: RefCell<ImportReport>,
+    store_raw_wikitext: bool,
 }
 
 impl<'a> DatabaseWriter<'a> {
@@ -15,24 +46,47 @@ impl<'a> DatabaseWriter<'a> {
         Self {
             conn,
             category_cache: HashMap::new(),
+            report: RefCell::new(ImportReport::default()),
+            store_raw_wikitext: false,
         }
     }
 
+    /// Also store the original wikitext of each article (off by default to save space)
+    pub fn with_raw_wikitext(mut self, enabled: bool) -> Self {
+        self.store_raw_wikitext = enabled;
+        self
+    }
+
+    /// Report for the articles imported through this writer so far
+    pub fn report(&self) -> ImportReport {
+        self.report.borrow().clone()
+    }
+
     pub fn create_tables(&self) -> WikiResult<()> {
         self.conn.execute_batch(
-            "CREATE TABLE IF NOT EXISTS articles (title TEXT PRIMARY KEY, content TEXT, size INTEGER, last_modified TEXT);
+            "CREATE TABLE IF NOT EXISTS articles (title TEXT PRIMARY KEY, content TEXT, size INTEGER, last_modified TEXT, first_seen TEXT, raw_wikitext TEXT, page_id INTEGER);
+             CREATE TABLE IF NOT EXISTS article_titles (title TEXT PRIMARY KEY, article_id INTEGER NOT NULL);
              CREATE TABLE IF NOT EXISTS categories (id INTEGER PRIMARY KEY, name TEXT UNIQUE);
              CREATE TABLE IF NOT EXISTS article_categories (article_id INTEGER, category_id INTEGER, 
                 PRIMARY KEY (article_id, category_id),
                 FOREIGN KEY (article_id) REFERENCES articles(rowid),
                 FOREIGN KEY (category_id) REFERENCES categories(id));
              CREATE TABLE IF NOT EXISTS images (id INTEGER PRIMARY KEY, filename TEXT, path TEXT, 
-                size INTEGER, mime_type TEXT, hash TEXT UNIQUE, caption TEXT);
+                size INTEGER, mime_type TEXT, hash TEXT UNIQUE, caption TEXT, width INTEGER, height INTEGER);
              CREATE TABLE IF NOT EXISTS article_images (article_id INTEGER, image_id INTEGER,
                 PRIMARY KEY (article_id, image_id),
                 FOREIGN KEY (article_id) REFERENCES articles(rowid),
                 FOREIGN KEY (image_id) REFERENCES images(id));
              CREATE TABLE IF NOT EXISTS redirects (from_title TEXT PRIMARY KEY, to_title TEXT);
+             CREATE TABLE IF NOT EXISTS view_counts (title TEXT PRIMARY KEY, views INTEGER NOT NULL DEFAULT 0);
+             CREATE TABLE IF NOT EXISTS article_stats (article_id INTEGER PRIMARY KEY, word_count INTEGER NOT NULL,
+                reading_minutes INTEGER NOT NULL);
+             CREATE TABLE IF NOT EXISTS \"references\" (id INTEGER PRIMARY KEY, article_id INTEGER, position INTEGER,
+                name TEXT, content TEXT,
+                FOREIGN KEY (article_id) REFERENCES articles(rowid));
+             CREATE TABLE IF NOT EXISTS langlinks (title TEXT, lang TEXT, target TEXT, PRIMARY KEY (title, lang));
+             CREATE TABLE IF NOT EXISTS metadata (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+             CREATE TABLE IF NOT EXISTS summaries (title TEXT PRIMARY KEY, etag TEXT NOT NULL, summary TEXT NOT NULL);
              CREATE VIRTUAL TABLE IF NOT EXISTS articles_fts USING fts5(title, content, content='articles', content_rowid='rowid');"
         ).map_err(WikiError::from)
     }
@@ -41,22 +95,99 @@ impl<'a> DatabaseWriter<'a> {
         self.conn.transaction().map_err(WikiError::from)
     }
 
+    /// Rowid of a stored article, looked up by normalized title through the indexed `article_titles` table
+    fn article_id(&self, title: &str, tx: &Transaction) -> WikiResult<Option<i64>> {
+        tx.query_row(
+            "SELECT article_id FROM article_titles WHERE title = ?1",
+            params![normalize_title(title)],
+            |row| row.get(0),
+        ).optional().map_err(WikiError::from)
+    }
+
+    /// Title, size and last modified time of the stored article `title` normalizes to
+    fn stored_article(&self, title: &str, tx: &Transaction) -> WikiResult<Option<(String, usize, DateTime<Utc>)>> {
+        let stored = tx.query_row(
+            "SELECT a.title, a.size, a.last_modified FROM article_titles t
+             JOIN articles a ON a.rowid = t.article_id
+             WHERE t.title = ?1",
+            params![normalize_title(title)],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(2)?)),
+        ).optional()?;
+
+        Ok(stored.map(|(title, size, last_modified)| {
+            let last_modified = DateTime::parse_from_rfc3339(&last_modified)
+                .map(|date| date.with_timezone(&Utc))
+                .unwrap_or_default();
+            (title, size as usize, last_modified)
+        }))
+    }
+
     pub fn write_article(&self, article: &WikiArticle, tx: &Transaction) -> WikiResult<()> {
-        // Insert into articles FTS table
+        let existing = self.article_id(&article.title, tx)?;
+
+        let raw_wikitext = if self.store_raw_wikitext {
+            article.raw_wikitext.as_deref()
+        } else {
+            None
+        };
+
+        let rowid = match existing {
+            Some(rowid) => {
+                // Update in place so first_seen survives re-imports
+                tx.execute(
+                    "UPDATE articles SET title = ?1, content = ?2, size = ?3, last_modified = ?4, raw_wikitext = ?5, page_id = ?6
+                     WHERE rowid = ?7",
+                    params![
+                        article.title,
+                        article.content,
+                        article.size,
+                        article.last_modified.to_rfc3339(),
+                        raw_wikitext,
+                        article.id,
+                        rowid,
+                    ],
+                )?;
+                tx.execute("DELETE FROM article_categories WHERE article_id = ?1", params![rowid])?;
+                tx.execute("DELETE FROM article_images WHERE article_id = ?1", params![rowid])?;
+                tx.execute("DELETE FROM \"references\" WHERE article_id = ?1", params![rowid])?;
+                tx.execute("DELETE FROM langlinks WHERE title = ?1", params![article.title])?;
+                rowid
+            }
+            None => {
+                // Insert into articles FTS table
+                tx.execute(
+                    "INSERT INTO articles (title, content, size, last_modified, first_seen, raw_wikitext, page_id)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![
+                        article.title,
+                        article.content,
+                        article.size,
+                        article.last_modified.to_rfc3339(),
+                        Utc::now().to_rfc3339(),
+                        raw_wikitext,
+                        article.id,
+                    ],
+                )?;
+                let rowid = tx.last_insert_rowid();
+                tx.execute(
+                    "INSERT OR REPLACE INTO article_titles (title, article_id) VALUES (?1, ?2)",
+                    params![normalize_title(&article.title), rowid],
+                )?;
+                rowid
+            }
+        };
+
+        // Display metadata is derived from the stored content
+        let word_count = count_words(&article.content);
         tx.execute(
-            "INSERT INTO articles (title, content, size, last_modified) VALUES (?1, ?2, ?3, ?4)",
-            params![
-                article.title,
-                article.content,
-                article.size,
-                article.last_modified.to_rfc3339(),
-            ],
+            "INSERT OR REPLACE INTO article_stats (article_id, word_count, reading_minutes) VALUES (?1, ?2, ?3)",
+            params![rowid, word_count, reading_time_minutes(word_count)],
         )?;
 
         // Handle redirect if present
         if let Some(ref redirect_to) = article.redirect_to {
             tx.execute(
-                "INSERT INTO redirects (from_title, to_title) VALUES (?1, ?2)",
+                "INSERT OR REPLACE INTO redirects (from_title, to_title) VALUES (?1, ?2)",
                 params![article.title, redirect_to],
             )?;
             return Ok(());
@@ -66,63 +197,166 @@ impl<'a> DatabaseWriter<'a> {
         for category in &article.categories {
             let category_id = self.get_or_create_category(category, tx)?;
             tx.execute(
-                "INSERT INTO article_categories (article_id, category_id) VALUES (
-                    (SELECT rowid FROM articles WHERE title = ?1),
-                    ?2
-                )",
-                params![article.title, category_id],
+                "INSERT INTO article_categories (article_id, category_id) VALUES (?1, ?2)",
+                params![rowid, category_id],
             )?;
         }
 
-        // Process images
+        // Process images; an article may show the same image twice
         for image in &article.images {
             let image_id = self.write_image(image, tx)?;
             tx.execute(
-                "INSERT INTO article_images (article_id, image_id) VALUES (
-                    (SELECT rowid FROM articles WHERE title = ?1),
-                    ?2
-                )",
-                params![article.title, image_id],
+                "INSERT OR IGNORE INTO article_images (article_id, image_id) VALUES (?1, ?2)",
+                params![rowid, image_id],
+            )?;
+        }
+
+        // Process references
+        for (position, reference) in article.references.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO \"references\" (article_id, position, name, content) VALUES (?1, ?2, ?3, ?4)",
+                params![rowid, position as i64, reference.name, reference.content],
+            )?;
+        }
+
+        // Process language links
+        for langlink in &article.langlinks {
+            tx.execute(
+                "INSERT OR REPLACE INTO langlinks (title, lang, target) VALUES (?1, ?2, ?3)",
+                params![article.title, langlink.lang, langlink.target],
             )?;
         }
 
         Ok(())
     }
 
+    /// Write an article as part of an import, resolving title collisions.
+    ///
+    /// When a page normalizes to the title of a stored article, imported earlier
+    /// in this run or before it, the longer one is kept (the newer one on equal
+    /// size) and the collision is counted in the report.
+    pub fn import_article(&self, article: &WikiArticle, tx: &Transaction) -> WikiResult<()> {
+        if let Some((existing_title, size, last_modified)) = self.stored_article(&article.title, tx)? {
+            if existing_title != article.title {
+                self.report.borrow_mut().title_collisions += 1;
+
+                let replace = article.size > size
+                    || (article.size == size && article.last_modified > last_modified);
+                warn!(
+                    "Title collision between '{}' and '{}', keeping '{}'",
+                    existing_title,
+                    article.title,
+                    if replace { &article.title } else { &existing_title }
+                );
+
+                if !replace {
+                    return Ok(());
+                }
+                self.delete_article(&existing_title, tx)?;
+                return self.write_article(article, tx);
+            }
+        }
+
+        self.report.borrow_mut().articles_written += 1;
+        self.write_article(article, tx)
+    }
+
+    /// Remove an article and its relations
+    pub fn delete_article(&self, title: &str, tx: &Transaction) -> WikiResult<()> {
+        if let Some(rowid) = self.article_id(title, tx)? {
+            tx.execute("DELETE FROM article_categories WHERE article_id = ?1", params![rowid])?;
+            tx.execute("DELETE FROM article_images WHERE article_id = ?1", params![rowid])?;
+            tx.execute("DELETE FROM \"references\" WHERE article_id = ?1", params![rowid])?;
+            tx.execute("DELETE FROM article_stats WHERE article_id = ?1", params![rowid])?;
+            tx.execute("DELETE FROM articles WHERE rowid = ?1", params![rowid])?;
+            tx.execute("DELETE FROM article_titles WHERE title = ?1", params![normalize_title(title)])?;
+        }
+        tx.execute("DELETE FROM redirects WHERE from_title = ?1", params![title])?;
+        tx.execute("DELETE FROM langlinks WHERE title = ?1", params![title])?;
+
+        Ok(())
+    }
+
+    /// Point every redirect whose target is itself a redirect at the final target.
+    ///
+    /// Chains longer than `MAX_REDIRECT_HOPS` and redirect loops are left as they are.
+    /// Returns how many redirects were changed.
+    pub fn flatten_redirects(&self, tx: &Transaction) -> WikiResult<usize> {
+        let chained: usize = tx.query_row(
+            "SELECT COUNT(*) FROM redirects WHERE to_title IN (SELECT from_title FROM redirects)",
+            [],
+            |row| row.get(0),
+        )?;
+
+        // Chains are followed in SQL, one hop per step through the from_title key, and the
+        // resolved targets staged in a temp table so nothing is held in memory
+        tx.execute_batch(
+            "DROP TABLE IF EXISTS temp.flattened_redirects;
+             CREATE TEMP TABLE flattened_redirects (from_title TEXT PRIMARY KEY, to_title TEXT NOT NULL);",
+        )?;
+        tx.execute(
+            "INSERT INTO temp.flattened_redirects (from_title, to_title)
+             WITH RECURSIVE chain(origin, target, hops) AS (
+                 SELECT from_title, to_title, 0 FROM redirects
+                 WHERE to_title IN (SELECT from_title FROM redirects)
+                 UNION ALL
+                 SELECT c.origin, r.to_title, c.hops + 1
+                 FROM chain c JOIN redirects r ON r.from_title = c.target
+                 WHERE c.hops < ?1 AND c.target != c.origin
+             )
+             -- A chain is resolved once it reaches a title that is not a redirect
+             SELECT origin, target FROM chain
+             WHERE NOT EXISTS (SELECT 1 FROM redirects r WHERE r.from_title = chain.target)",
+            params![MAX_REDIRECT_HOPS as i64],
+        )?;
+        let flattened = tx.execute(
+            "UPDATE redirects
+             SET to_title = (SELECT f.to_title FROM temp.flattened_redirects f WHERE f.from_title = redirects.from_title)
+             WHERE from_title IN (SELECT from_title FROM temp.flattened_redirects)",
+            [],
+        )?;
+        tx.execute_batch("DROP TABLE temp.flattened_redirects")?;
+
+        if flattened < chained {
+            warn!("Leaving {} redirect chains unresolved (loops or longer than {} hops)", chained - flattened, MAX_REDIRECT_HOPS);
+        }
+        Ok(flattened)
+    }
+
     fn get_or_create_category(&self, category: &str, tx: &Transaction) -> WikiResult<i64> {
-        // Try to get existing category
-        match tx.query_row(
-            "SELECT id FROM categories WHERE name = ?1",
+        // Insert first so a category created by another connection in the meantime
+        // is not a UNIQUE violation; on conflict nothing is returned and we look it up
+        let inserted: Option<i64> = tx.query_row(
+            "INSERT INTO categories (name) VALUES (?1) ON CONFLICT(name) DO NOTHING RETURNING id",
             params![category],
             |row| row.get(0),
-        ) {
-            Ok(id) => Ok(id),
-            Err(rusqlite::Error::QueryReturnedNoRows) => {
-                // Create new category
-                tx.execute(
-                    "INSERT INTO categories (name) VALUES (?1)",
-                    params![category],
-                )?;
-                Ok(tx.last_insert_rowid())
-            }
-            Err(e) => Err(WikiError::from(e)),
+        ).optional()?;
+
+        match inserted {
+            Some(id) => Ok(id),
+            None => tx.query_row(
+                "SELECT id FROM categories WHERE name = ?1",
+                params![category],
+                |row| row.get(0),
+            ).map_err(WikiError::from),
         }
     }
 
     fn write_image(&self, image: &WikiImage, tx: &Transaction) -> WikiResult<i64> {
-        // Check if image already exists by hash
-        if let Ok(id) = tx.query_row(
-            "SELECT id FROM images WHERE hash = ?1",
-            params![image.hash],
-            |row| row.get::<_, i64>(0),
-        ) {
+        // Check if image already exists by hash; parsed images have none yet, so go by filename
+        let existing = if image.hash.is_empty() {
+            tx.query_row("SELECT id FROM images WHERE filename = ?1", params![image.filename], |row| row.get::<_, i64>(0))
+        } else {
+            tx.query_row("SELECT id FROM images WHERE hash = ?1", params![image.hash], |row| row.get::<_, i64>(0))
+        };
+        if let Ok(id) = existing {
             return Ok(id);
         }
 
         // Insert new image
         tx.execute(
-            "INSERT INTO images (filename, path, size, mime_type, hash, caption) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            "INSERT INTO images (filename, path, size, mime_type, hash, caption, width, height) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
             params![
                 image.filename,
                 image.path,
@@ -130,12 +364,41 @@ impl<'a> DatabaseWriter<'a> {
                 image.mime_type,
                 image.hash,
                 image.caption,
+                image.width,
+                image.height,
             ],
         )?;
 
         Ok(tx.last_insert_rowid())
     }
 
+    /// Record the pixel dimensions of a downloaded image, returning whether the image is known
+    pub fn set_image_dimensions(&self, filename: &str, width: u32, height: u32, tx: &Transaction) -> WikiResult<bool> {
+        let updated = tx.execute(
+            "UPDATE images SET width = ?1, height = ?2 WHERE filename = ?3",
+            params![width, height, filename],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Record a fact about the install under `key`, replacing any earlier value
+    pub fn set_metadata(&self, key: &str, value: &str, tx: &Transaction) -> WikiResult<()> {
+        tx.execute(
+            "INSERT OR REPLACE INTO metadata (key, value) VALUES (?1, ?2)",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    /// Store the summary of `title` generated for `etag`, replacing any older one
+    pub fn save_summary(&self, title: &str, etag: &str, summary: &str, tx: &Transaction) -> WikiResult<()> {
+        tx.execute(
+            "INSERT OR REPLACE INTO summaries (title, etag, summary) VALUES (?1, ?2, ?3)",
+            params![title, etag, summary],
+        )?;
+        Ok(())
+    }
+
     pub fn commit_transaction(tx: Transaction) -> WikiResult<()> {
         tx.commit().map_err(WikiError::from)
     }
@@ -144,11 +407,12 @@ impl<'a> DatabaseWriter<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::parser::models::WikiArticle;
+    use crate::parser::models::{WikiArticle, WikiImage, WikiReference};
     use chrono::Utc;
     use std::collections::HashSet;
     use tempfile::NamedTempFile;
     use crate::db::schema::init_database;
+    use crate::db::reader::DatabaseReader;
 
     fn create_test_db() -> (Connection, NamedTempFile) {
         let temp_file = NamedTempFile::new().unwrap();
@@ -218,4 +482,313 @@ mod tests {
 
         Ok(())
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_image_dimensions_round_trip() -> WikiResult<()> {
+        let (conn, _temp_file) = create_test_db();
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        let mut article = WikiArticle::new("Pictured".to_string(), "Has images.".to_string());
+        article.add_image(
+            WikiImage::new("photo.png".to_string(), "/images/photo.png".to_string(), "image/png".to_string(), "a1".to_string())
+                .with_dimensions(640, 480),
+        );
+        article.add_image(WikiImage::new(
+            "later.jpg".to_string(),
+            "/images/later.jpg".to_string(),
+            "image/jpeg".to_string(),
+            "b2".to_string(),
+        ));
+        writer.write_article(&article, &tx)?;
+
+        // Dimensions of an image downloaded after the import
+        assert!(writer.set_image_dimensions("later.jpg", 1024, 768, &tx)?);
+        assert!(!writer.set_image_dimensions("missing.gif", 1, 1, &tx)?);
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let article = DatabaseReader::new(&conn).get_article("Pictured")?.unwrap();
+        let dimensions = |filename: &str| {
+            let image = article.images.iter().find(|i| i.filename == filename).unwrap();
+            (image.width, image.height)
+        };
+        assert_eq!(dimensions("photo.png"), (Some(640), Some(480)));
+        assert_eq!(dimensions("later.jpg"), (Some(1024), Some(768)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_flatten_double_redirects() -> WikiResult<()> {
+        let (conn, _temp_file) = create_test_db();
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        for (from, to) in [("A", "B"), ("B", "C"), ("Loop 1", "Loop 2"), ("Loop 2", "Loop 1")] {
+            let mut article = WikiArticle::new(from.to_string(), String::new());
+            article.redirect_to = Some(to.to_string());
+            writer.write_article(&article, &tx)?;
+        }
+        writer.write_article(&WikiArticle::new("C".to_string(), "Final.".to_string()), &tx)?;
+
+        assert_eq!(writer.flatten_redirects(&tx)?, 1);
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let reader = DatabaseReader::new(&conn);
+        assert_eq!(reader.get_redirect("A")?.as_deref(), Some("C"));
+        assert_eq!(reader.get_redirect("B")?.as_deref(), Some("C"));
+        assert_eq!(reader.get_redirect("Loop 1")?.as_deref(), Some("Loop 2"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_flatten_leaves_overlong_chains() -> WikiResult<()> {
+        let (conn, _temp_file) = create_test_db();
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        // Page 0 -> Page 1 -> ... -> Page N, where Page N is an article
+        let longest = MAX_REDIRECT_HOPS + 2;
+        for i in 0..longest {
+            let mut article = WikiArticle::new(format!("Page {}", i), String::new());
+            article.redirect_to = Some(format!("Page {}", i + 1));
+            writer.write_article(&article, &tx)?;
+        }
+        writer.write_article(&WikiArticle::new(format!("Page {}", longest), "Final.".to_string()), &tx)?;
+
+        // Every redirect within MAX_REDIRECT_HOPS hops of the article is flattened
+        let flattened = writer.flatten_redirects(&tx)?;
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let reader = DatabaseReader::new(&conn);
+        let target = format!("Page {}", longest);
+        assert_eq!(flattened, longest - 2);
+        assert_eq!(reader.get_redirect("Page 0")?, Some("Page 1".to_string()));
+        assert_eq!(reader.get_redirect("Page 1")?, Some(target.clone()));
+        assert_eq!(reader.get_redirect(&format!("Page {}", longest - 1))?, Some(target));
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_preserves_first_seen() -> WikiResult<()> {
+        let (conn, _temp_file) = create_test_db();
+        let writer = DatabaseWriter::new(&conn);
+
+        let mut article = WikiArticle::new(
+            "Test Article".to_string(),
+            "Original content.".to_string(),
+        );
+        article.last_modified = Utc::now() - chrono::Duration::days(1);
+        let tx = writer.begin_transaction()?;
+        writer.write_article(&article, &tx)?;
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let reader = DatabaseReader::new(&conn);
+        let first_seen = reader.get_first_seen("Test Article")?.unwrap();
+
+        article.content = "Updated content.".to_string();
+        article.last_modified = Utc::now();
+        let tx = writer.begin_transaction()?;
+        writer.write_article(&article, &tx)?;
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM articles WHERE title = ?1",
+            params!["Test Article"],
+            |row| row.get(0),
+        )?;
+        assert_eq!(count, 1);
+
+        assert_eq!(reader.get_first_seen("Test Article")?, Some(first_seen));
+        let updated = reader.get_article("Test Article")?.unwrap();
+        assert_eq!(updated.content, "Updated content.");
+        assert_eq!(updated.last_modified.timestamp(), article.last_modified.timestamp());
+
+        assert_eq!(reader.articles_added_since(first_seen)?, vec!["Test Article".to_string()]);
+        assert!(reader.articles_added_since(Utc::now() + chrono::Duration::days(1))?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_title_collision() -> WikiResult<()> {
+        let (conn, _temp_file) = create_test_db();
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+
+        let mut short = WikiArticle::new("Foo Bar".to_string(), "Short.".to_string());
+        short.update_size();
+        let mut long = WikiArticle::new("foo_Bar".to_string(), "A much longer article body.".to_string());
+        long.update_size();
+
+        writer.import_article(&short, &tx)?;
+        writer.import_article(&long, &tx)?;
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM articles", [], |row| row.get(0))?;
+        assert_eq!(count, 1);
+
+        let title: String = conn.query_row("SELECT title FROM articles", [], |row| row.get(0))?;
+        assert_eq!(title, "foo_Bar");
+
+        assert_eq!(writer.report().articles_written, 1);
+        assert_eq!(writer.report().title_collisions, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_collisions_fold_only_the_first_letter() -> WikiResult<()> {
+        assert_eq!(normalize_title("gold_medal  winners"), "Gold medal winners");
+        assert_eq!(normalize_title("GOLD"), "GOLD");
+
+        let (conn, _temp_file) = create_test_db();
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        for title in ["Gold", "GOLD", "Apple", "APPLE"] {
+            writer.import_article(&WikiArticle::new(title.to_string(), format!("{} text.", title)), &tx)?;
+        }
+        DatabaseWriter::commit_transaction(tx)?;
+        assert_eq!(writer.report().articles_written, 4);
+        assert_eq!(writer.report().title_collisions, 0);
+
+        // Titles stored by an earlier import collide too
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        writer.import_article(&WikiArticle::new("gold".to_string(), "Short.".to_string()), &tx)?;
+        DatabaseWriter::commit_transaction(tx)?;
+        assert_eq!(writer.report().title_collisions, 1);
+
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM articles", [], |row| row.get(0))?;
+        assert_eq!(count, 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_raw_wikitext_storage() -> WikiResult<()> {
+        let (conn, _temp_file) = create_test_db();
+
+        let mut article = WikiArticle::new("Raw Article".to_string(), "Cleaned text".to_string());
+        article.raw_wikitext = Some("{{Infobox}} Cleaned [[text]]".to_string());
+        article.update_size();
+
+        let writer = DatabaseWriter::new(&conn).with_raw_wikitext(true);
+        let tx = writer.begin_transaction()?;
+        writer.write_article(&article, &tx)?;
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let reader = DatabaseReader::new(&conn);
+        assert_eq!(
+            reader.get_raw_wikitext("Raw Article")?.as_deref(),
+            Some("{{Infobox}} Cleaned [[text]]")
+        );
+
+        article.title = "Cleaned Only".to_string();
+        let writer = DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        writer.write_article(&article, &tx)?;
+        DatabaseWriter::commit_transaction(tx)?;
+
+        assert_eq!(reader.get_raw_wikitext("Cleaned Only")?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parsed_langlinks_are_written() -> WikiResult<()> {
+        let (conn, _temp_file) = create_test_db();
+        let writer = DatabaseWriter::new(&conn);
+
+        let mut parser = crate::parser::WikiXmlParser::from_string(
+            "<mediawiki><page><title>Cat</title><revision>\
+             <text>The cat is a small mammal.\n[[Category:Felines]]\n[[fr:Chat]]\n[[de:Hauskatze]]</text>\
+             </revision></page></mediawiki>",
+        );
+        let tx = writer.begin_transaction()?;
+        parser.parse_articles(|article| writer.write_article(&article, &tx))?;
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let target: String = conn.query_row(
+            "SELECT target FROM langlinks WHERE title = 'Cat' AND lang = 'fr'",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(target, "Chat");
+
+        let reader = DatabaseReader::new(&conn);
+        let langs: Vec<String> = reader.get_langlinks("Cat")?.unwrap().into_iter().map(|link| link.lang).collect();
+        assert_eq!(langs, vec!["de", "fr"]);
+        assert_eq!(reader.get_langlinks("cat")?.map(|links| links.len()), Some(2));
+        assert_eq!(reader.get_langlinks("Dog")?, None);
+        assert!(!reader.get_article("Cat")?.unwrap().content.contains("fr:Chat"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_references() -> WikiResult<()> {
+        let (conn, _temp_file) = create_test_db();
+        let writer = DatabaseWriter::new(&conn);
+
+        let mut article = WikiArticle::new("Cited".to_string(), "Claim.".to_string());
+        article.references = vec![
+            WikiReference { name: Some("a".to_string()), content: "First source".to_string() },
+            WikiReference { name: None, content: "Second source".to_string() },
+        ];
+        let tx = writer.begin_transaction()?;
+        writer.write_article(&article, &tx)?;
+        DatabaseWriter::commit_transaction(tx)?;
+
+        let reader = DatabaseReader::new(&conn);
+        assert_eq!(reader.get_references("Cited")?, Some(article.references.clone()));
+        assert_eq!(reader.get_references("Missing")?, None);
+
+        // Rewriting replaces rather than appends
+        article.references.truncate(1);
+        let tx = writer.begin_transaction()?;
+        writer.write_article(&article, &tx)?;
+        DatabaseWriter::commit_transaction(tx)?;
+        assert_eq!(reader.get_references("Cited")?.unwrap().len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_same_category_from_two_transactions() -> WikiResult<()> {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        static OTHER_BLOCKED: AtomicBool = AtomicBool::new(false);
+        fn on_busy(_attempts: i32) -> bool {
+            OTHER_BLOCKED.store(true, Ordering::SeqCst);
+            std::thread::sleep(std::time::Duration::from_millis(1));
+            true
+        }
+
+        let (conn, temp_file) = create_test_db();
+        let writer = DatabaseWriter::new(&conn);
+
+        // The first transaction inserts the category and stays open
+        let tx = writer.begin_transaction()?;
+        let first = writer.get_or_create_category("Shared", &tx)?;
+
+        // The second inserts it on another connection before the first commits, waiting on its lock
+        let path = temp_file.path().to_path_buf();
+        let other = std::thread::spawn(move || -> WikiResult<i64> {
+            let other_conn = Connection::open(path)?;
+            other_conn.busy_handler(Some(on_busy))?;
+            let other_writer = DatabaseWriter::new(&other_conn);
+            let tx = other_writer.begin_transaction()?;
+            let second = other_writer.get_or_create_category("Shared", &tx)?;
+            assert_eq!(other_writer.get_or_create_category("Shared", &tx)?, second);
+            DatabaseWriter::commit_transaction(tx)?;
+            Ok(second)
+        });
+        while !OTHER_BLOCKED.load(Ordering::SeqCst) {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        DatabaseWriter::commit_transaction(tx)?;
+        let second = other.join().unwrap()?;
+
+        assert_eq!(first, second);
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM categories WHERE name = 'Shared'", [], |row| row.get(0))?;
+        assert_eq!(count, 1);
+        Ok(())
+    }
+}
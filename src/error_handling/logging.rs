@@ -1,7 +1,48 @@
-use tracing::{Level, subscriber::set_global_default};
-use tracing_subscriber::{EnvFilter, FmtSubscriber, fmt::format::FmtSpan};
+use std::str::FromStr;
+use tracing::{Level, Subscriber, subscriber::set_global_default};
+use tracing_subscriber::{EnvFilter, FmtSubscriber, fmt::{format::FmtSpan, MakeWriter}};
 use crate::error_handling::{WikiError, WikiResult};
 
+/// Environment variable selecting the production log format
+pub const LOG_FORMAT_ENV: &str = "DAVINCI3_LOG_FORMAT";
+
+/// Output format for production logs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Multi-line, human-readable output
+    Pretty,
+    /// Single-line, human-readable output
+    #[default]
+    Compact,
+    /// One JSON object per line, for log aggregators
+    Json,
+}
+
+impl LogFormat {
+    /// Read the format from `DAVINCI3_LOG_FORMAT`, falling back to `Compact`
+    pub fn from_env() -> Self {
+        std::env::var(LOG_FORMAT_ENV)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_default()
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = WikiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "pretty" => Ok(LogFormat::Pretty),
+            "compact" => Ok(LogFormat::Compact),
+            "json" => Ok(LogFormat::Json),
+            other => Err(WikiError::Configuration(format!(
+                "Unknown log format '{}', expected pretty, compact or json", other
+            ))),
+        }
+    }
+}
+
 /// Initialize logging with a custom level
 pub fn init_logging(level: Level) -> WikiResult<()> {
     let subscriber = FmtSubscriber::builder()
@@ -49,33 +90,79 @@ pub fn init_debug_logging() -> WikiResult<()> {
     }
 }
 
-/// Initialize logging for production with minimal output
+/// Initialize logging for production with minimal output, in the format from `DAVINCI3_LOG_FORMAT`
 pub fn init_production_logging() -> WikiResult<()> {
-    let subscriber = FmtSubscriber::builder()
+    init_production_logging_with_format(LogFormat::from_env())
+}
+
+/// Initialize logging for production with minimal output in the given format
+pub fn init_production_logging_with_format(format: LogFormat) -> WikiResult<()> {
+    let subscriber = production_subscriber(format, std::io::stdout);
+
+    match set_global_default(subscriber) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(WikiError::OperationFailed(format!(
+            "Failed to initialize production logging: {}", e
+        ))),
+    }
+}
+
+/// Initialize production logging on stderr, leaving stdout to machine-readable output
+pub fn init_stderr_logging_with_format(format: LogFormat) -> WikiResult<()> {
+    let subscriber = production_subscriber(format, std::io::stderr);
+
+    match set_global_default(subscriber) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(WikiError::OperationFailed(format!(
+            "Failed to initialize production logging: {}", e
+        ))),
+    }
+}
+
+fn production_subscriber<W>(format: LogFormat, writer: W) -> Box<dyn Subscriber + Send + Sync>
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let builder = FmtSubscriber::builder()
         .with_env_filter(
             EnvFilter::from_default_env()
                 .add_directive(Level::INFO.into())
                 .add_directive("tokio=warn".parse().unwrap())
                 .add_directive("runtime=warn".parse().unwrap())
         )
+        .with_writer(writer)
         .with_target(false)
         .with_thread_ids(false)
         .with_file(false)
         .with_line_number(false)
-        .with_span_events(FmtSpan::NONE)
-        .try_init();
+        .with_span_events(FmtSpan::NONE);
 
-    match subscriber {
-        Ok(_) => Ok(()),
-        Err(e) => Err(WikiError::OperationFailed(format!(
-            "Failed to initialize production logging: {}", e
-        ))),
+    match format {
+        LogFormat::Pretty => Box::new(builder.pretty().finish()),
+        LogFormat::Compact => Box::new(builder.compact().finish()),
+        // Flatten so `message` sits next to `level` instead of under `fields`
+        LogFormat::Json => Box::new(builder.json().flatten_event(true).finish()),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
 
     #[test]
     fn test_init_logging() {
@@ -91,4 +178,37 @@ mod tests {
     fn test_production_logging() {
         assert!(init_production_logging().is_ok());
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_log_format_parsing() {
+        assert_eq!("json".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert_eq!(" Pretty ".parse::<LogFormat>().unwrap(), LogFormat::Pretty);
+        assert_eq!("COMPACT".parse::<LogFormat>().unwrap(), LogFormat::Compact);
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+
+    #[test]
+    fn test_json_logging_emits_parseable_lines() {
+        let buffer = SharedBuffer::default();
+        let writer = buffer.clone();
+        let subscriber = production_subscriber(LogFormat::Json, move || writer.clone());
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("first message");
+            tracing::warn!(articles = 3, "second message");
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("log line is not valid JSON"))
+            .collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["level"], "INFO");
+        assert_eq!(lines[0]["message"], "first message");
+        assert_eq!(lines[1]["level"], "WARN");
+        assert_eq!(lines[1]["message"], "second message");
+        assert_eq!(lines[1]["articles"], 3);
+    }
+}
@@ -59,6 +59,9 @@ pub enum WikiError {
 
     #[error("Configuration error: {0}")]
     Configuration(String),
+
+    #[error("API error ({0}): {1}")]
+    Api(u16, String),
 }
 
 impl From<heed::Error> for WikiError {
@@ -69,4 +72,7 @@ impl From<heed::Error> for WikiError {
 
 pub type WikiResult<T> = Result<T, WikiError>;
 
-pub use logging::{init_debug_logging, init_logging, init_production_logging};
\ No newline at end of file
+pub use logging::{
+    init_debug_logging, init_logging, init_production_logging, init_production_logging_with_format,
+    init_stderr_logging_with_format, LogFormat,
+};
\ No newline at end of file
@@ -0,0 +1,86 @@
+use reqwest::Client;
+
+use crate::error_handling::{WikiError, WikiResult};
+
+/// User-Agent sent on every outbound request; Wikimedia blocks generic agents without contact details
+pub const DEFAULT_USER_AGENT: &str = concat!(
+    "davinci3-wiki/",
+    env!("CARGO_PKG_VERSION"),
+    " (https://github.com/rayman546/davinci3-wiki)"
+);
+
+/// Build an HTTP client that identifies itself as `user_agent`
+pub fn client_with_user_agent(user_agent: &str) -> WikiResult<Client> {
+    Client::builder()
+        .user_agent(user_agent)
+        .build()
+        .map_err(|e| WikiError::Configuration(format!("Invalid User-Agent '{}': {}", user_agent, e)))
+}
+
+/// Check that `url` is an absolute http(s) URL and drop trailing slashes, so API paths
+/// such as `/api/generate` can be appended to it
+pub fn normalize_ollama_url(url: &str) -> WikiResult<String> {
+    let trimmed = url.trim().trim_end_matches('/');
+    let invalid = |reason: &str| {
+        WikiError::Configuration(format!(
+            "Invalid Ollama URL '{}': {}; expected something like http://localhost:11434",
+            url, reason
+        ))
+    };
+    // Without a scheme, `host:11434` parses as scheme `host`, so check the scheme explicitly
+    let parsed = url::Url::parse(trimmed).map_err(|e| invalid(&e.to_string()))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        return Err(invalid("the URL must start with http:// or https://"));
+    }
+    Ok(trimmed.to_string())
+}
+
+/// Build an HTTP client with [`DEFAULT_USER_AGENT`]
+pub fn default_client() -> Client {
+    client_with_user_agent(DEFAULT_USER_AGENT).expect("default User-Agent is a valid header")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ollama_url_is_normalized() {
+        assert_eq!(normalize_ollama_url("http://host:11434/").unwrap(), "http://host:11434");
+        assert_eq!(normalize_ollama_url(" https://host/ollama// ").unwrap(), "https://host/ollama");
+        assert_eq!(normalize_ollama_url("http://localhost:11434").unwrap(), "http://localhost:11434");
+
+        for url in ["host:11434", "localhost", "ftp://host:11434", ""] {
+            match normalize_ollama_url(url) {
+                Err(WikiError::Configuration(message)) => assert!(message.contains("http://"), "{}", message),
+                other => panic!("expected a configuration error for '{}', got {:?}", url, other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_user_agent_is_sent() -> WikiResult<()> {
+        let mut server = mockito::Server::new_async().await;
+        let default_mock = server
+            .mock("GET", "/default")
+            .match_header("user-agent", DEFAULT_USER_AGENT)
+            .create_async()
+            .await;
+        let custom_mock = server
+            .mock("GET", "/custom")
+            .match_header("user-agent", "my-mirror/1.0 (admin@example.org)")
+            .create_async()
+            .await;
+
+        default_client().get(format!("{}/default", server.url())).send().await?;
+        client_with_user_agent("my-mirror/1.0 (admin@example.org)")?
+            .get(format!("{}/custom", server.url()))
+            .send()
+            .await?;
+
+        default_mock.assert_async().await;
+        custom_mock.assert_async().await;
+        assert!(client_with_user_agent("bad\nagent").is_err());
+        Ok(())
+    }
+}
@@ -7,6 +7,7 @@ use sha2::{Sha256, Digest};
 use tracing::{debug, info, warn};
 
 use crate::error_handling::{WikiError, WikiResult};
+use crate::parser::models::WikiImage;
 
 #[derive(Debug, Clone)]
 pub struct ImageMetadata {
@@ -18,10 +19,31 @@ pub struct ImageMetadata {
     pub height: u32,
 }
 
+impl ImageMetadata {
+    /// Convert to the stored image record, keeping the measured dimensions
+    pub fn to_wiki_image(&self, path: &Path) -> WikiImage {
+        WikiImage::new(
+            self.filename.clone(),
+            path.to_string_lossy().into_owned(),
+            self.content_type.clone(),
+            self.hash.clone(),
+        )
+        .with_size(self.size)
+        .with_dimensions(self.width, self.height)
+    }
+}
+
 pub struct ImageProcessor {
     cache_dir: PathBuf,
     max_size: usize,
     client: Client,
+    sniff_content_type: bool,
+}
+
+/// Whether a `Content-Type` header says nothing useful about the image format
+fn is_generic_content_type(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+    !mime.starts_with("image/") || mime == "image/*"
 }
 
 impl ImageProcessor {
@@ -32,12 +54,25 @@ impl ImageProcessor {
         Ok(Self {
             cache_dir,
             max_size,
-            client: Client::new(),
+            client: crate::http::default_client(),
+            sniff_content_type: true,
         })
     }
 
+    /// Identify image downloads with this User-Agent
+    pub fn with_user_agent(mut self, user_agent: &str) -> WikiResult<Self> {
+        self.client = crate::http::client_with_user_agent(user_agent)?;
+        Ok(self)
+    }
+
+    /// Detect the format from the image bytes when the server sends a generic `Content-Type`
+    pub fn with_content_sniffing(mut self, enabled: bool) -> Self {
+        self.sniff_content_type = enabled;
+        self
+    }
+
     pub async fn download_image(&self, url: &str) -> WikiResult<ImageMetadata> {
-        let response = self.client.get(url).send().await?;
+        let mut response = self.client.get(url).send().await?;
         let content_type = response
             .headers()
             .get("content-type")
@@ -45,12 +80,25 @@ impl ImageProcessor {
             .unwrap_or("application/octet-stream")
             .to_string();
 
-        let bytes = response.bytes().await?;
-        let size = bytes.len();
+        // Reject up front when the server admits the body is too large
+        if let Some(length) = response.content_length() {
+            if length as usize > self.max_size {
+                return Err(WikiError::ImageTooLarge(length as usize, self.max_size));
+            }
+        }
 
-        if size > self.max_size {
-            return Err(WikiError::ImageTooLarge(size, self.max_size));
+        // Stream the body so an oversized image is abandoned as soon as it crosses the limit
+        let mut bytes = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if bytes.len() + chunk.len() > self.max_size {
+                warn!("Aborting download of {} after {} bytes", url, bytes.len() + chunk.len());
+                return Err(WikiError::ImageTooLarge(bytes.len() + chunk.len(), self.max_size));
+            }
+            bytes.extend_from_slice(&chunk);
         }
+        let size = bytes.len();
+
+        let content_type = self.detect_content_type(content_type, &bytes);
 
         let mut hasher = Sha256::new();
         hasher.update(&bytes);
@@ -74,6 +122,19 @@ impl ImageProcessor {
         })
     }
 
+    fn detect_content_type(&self, header: String, bytes: &[u8]) -> String {
+        if !self.sniff_content_type || !is_generic_content_type(&header) {
+            return header;
+        }
+        match image::guess_format(bytes) {
+            Ok(format) => {
+                debug!("Sniffed {:?} behind Content-Type {}", format, header);
+                format.to_mime_type().to_string()
+            }
+            Err(_) => header,
+        }
+    }
+
     fn get_extension(&self, content_type: &str) -> &str {
         match content_type {
             "image/jpeg" | "image/jpg" => "jpg",
@@ -130,6 +191,74 @@ impl ImageProcessor {
 mod tests {
     use super::*;
     use tempfile::TempDir;
+    use std::io::{Cursor, Write};
+
+    fn png_bytes() -> Vec<u8> {
+        let mut buffer = Cursor::new(Vec::new());
+        DynamicImage::new_rgb8(4, 3).write_to(&mut buffer, ImageFormat::Png).unwrap();
+        buffer.into_inner()
+    }
+
+    #[tokio::test]
+    async fn test_oversized_download_aborts_early() -> WikiResult<()> {
+        const BODY_BYTES: usize = 8 * 1024 * 1024;
+        const LIMIT: usize = 64 * 1024;
+
+        // A chunked body carries no Content-Length, so only streaming can catch it
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/huge")
+            .with_header("content-type", "image/png")
+            .with_chunked_body(|w| {
+                let chunk = vec![0u8; 16 * 1024];
+                for _ in 0..BODY_BYTES / chunk.len() {
+                    w.write_all(&chunk)?;
+                }
+                Ok(())
+            })
+            .create_async()
+            .await;
+
+        let temp_dir = TempDir::new()?;
+        let processor = ImageProcessor::new(temp_dir.path(), LIMIT).await?;
+        match processor.download_image(&format!("{}/huge", server.url())).await {
+            Err(WikiError::ImageTooLarge(received, max)) => {
+                assert_eq!(max, LIMIT);
+                assert!(received > LIMIT);
+                assert!(received < BODY_BYTES / 4, "read {} bytes before aborting", received);
+            }
+            other => panic!("expected ImageTooLarge, got {:?}", other.map(|m| m.size)),
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sniffs_generic_content_type() -> WikiResult<()> {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/image")
+            .with_header("content-type", "application/octet-stream")
+            .with_body(png_bytes())
+            .create_async()
+            .await;
+
+        let temp_dir = TempDir::new()?;
+        let processor = ImageProcessor::new(temp_dir.path(), 1024 * 1024).await?;
+        let metadata = processor.download_image(&format!("{}/image", server.url())).await?;
+
+        assert_eq!(metadata.content_type, "image/png");
+        assert!(metadata.filename.ends_with(".png"));
+        assert!(temp_dir.path().join(&metadata.filename).exists());
+        assert_eq!((metadata.width, metadata.height), (4, 3));
+
+        // With sniffing disabled the header is trusted as before
+        let processor = ImageProcessor::new(temp_dir.path(), 1024 * 1024).await?.with_content_sniffing(false);
+        let metadata = processor.download_image(&format!("{}/image", server.url())).await?;
+        assert_eq!(metadata.content_type, "application/octet-stream");
+        assert!(metadata.filename.ends_with(".bin"));
+
+        Ok(())
+    }
 
     #[tokio::test]
     async fn test_image_processor() -> WikiResult<()> {
@@ -5,22 +5,58 @@ use serde::{Deserialize, Serialize};
 use tracing::{debug, info, warn, error};
 use std::process::Command;
 use tokio::io::AsyncWriteExt;
-use bzip2::bufread::BzDecoder;
+use sha2::{Digest, Sha256};
 use std::io::Read;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::fmt;
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
 use tokio::process::Command as TokioCommand;
+use tokio::sync::mpsc;
 
 use crate::error_handling::{WikiError, WikiResult};
-use crate::parser::{WikiXmlParser, models::WikiArticle};
+use crate::parser::{DecompressedStream, DumpFormat, TemplateReport, WikiXmlParser, models::WikiArticle};
 use crate::db::schema;
-use crate::db::writer::DatabaseWriter;
-use crate::vector::VectorStore;
+use crate::db::{ArticleWriterTask, InsertLimit};
+use crate::vector::{estimate_map_size, VectorStore};
+use crate::llm::LlmService;
 
-const OLLAMA_VERSION: &str = "0.1.27";
+const OLLAMA_VERSION: &str = "0.5.7";
+/// SHA-256 of the `OLLAMA_VERSION` release assets, as published in the release's `sha256sum.txt`.
+/// An asset without an entry here can only be installed with `ollama_sha256` set
+const OLLAMA_SHA256: &[(&str, &str)] = &[];
+const OLLAMA_RELEASES_URL: &str = "https://github.com/ollama/ollama/releases/download";
 const OLLAMA_MODEL: &str = "llama2";
-const WIKIDUMP_URL: &str = "https://dumps.wikimedia.org/simplewiki/latest/simplewiki-latest-pages-articles1.xml.bz2";
+const WIKIDUMPS_URL: &str = "https://dumps.wikimedia.org";
+const DEFAULT_DUMP_LANGUAGE: &str = "simple";
 const BATCH_SIZE: usize = 100; // Number of articles to process at once
+const WRITER_CHANNEL_CAPACITY: usize = 1000; // Articles the parser may run ahead of the writer
+const OLLAMA_READY_TIMEOUT_SECS: u64 = 30;
+const OLLAMA_READY_POLL_MILLIS: u64 = 500;
+const ESTIMATED_EMBEDDING_DIMENSION: usize = 4096; // llama2 embedding size
+const DRY_RUN_SAMPLE_BYTES: usize = 4 * 1024 * 1024; // Compressed dump bytes sampled by a dry run
+const TEMPLATE_REPORT_SIZE: usize = 20; // Stripped templates listed in the install report
+const EMBEDDING_CHECKPOINT_FILE: &str = "embedding.checkpoint"; // Kept in the vector store directory
+const EMBEDDING_RETRIES: u32 = 3; // Attempts per article before giving up
+const EMBEDDING_RETRY_DELAY_MILLIS: u64 = 200; // Multiplied by the attempt number
+const DEFAULT_EMBEDDING_CONCURRENCY: usize = 1; // Embedding requests in flight to Ollama
+const DEFAULT_SUMMARY_CONCURRENCY: usize = 1; // Summary requests in flight to Ollama during a warmup
+/// Boilerplate sections left out of the text sent to the embedding model
+const DEFAULT_EMBEDDING_EXCLUDED_SECTIONS: &[&str] = &[
+    "References",
+    "Notes",
+    "Citations",
+    "Sources",
+    "Bibliography",
+    "Further reading",
+    "External links",
+    "See also",
+];
+const DOWNLOAD_PROGRESS_BYTES: u64 = 1024 * 1024; // Downloaded bytes between progress events
+const PARSE_PROGRESS_ARTICLES: usize = 100; // Parsed pages between progress events
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InstallConfig {
     pub data_dir: PathBuf,
     pub cache_dir: PathBuf,
@@ -28,6 +64,39 @@ pub struct InstallConfig {
     pub ollama_url: String,
     pub max_image_size: usize,
     pub max_batch_size: usize,
+    /// Ollama release to install when it is missing
+    pub ollama_version: String,
+    /// Expected SHA-256 of the downloaded Ollama release asset; required when `ollama_version`
+    /// is not the pinned release, which is verified against its built-in checksums
+    pub ollama_sha256: Option<String>,
+    /// Keep the original wikitext alongside the cleaned content
+    pub store_raw_wikitext: bool,
+    /// Stop importing after this many articles (useful for demos and tests)
+    pub max_articles: Option<usize>,
+    /// Ollama model used to generate embeddings
+    pub embedding_model: String,
+    /// LMDB map size for the vector store; estimated from the article count when unset
+    pub vector_map_size: Option<usize>,
+    /// Count the templates stripped during parsing and include the most common in the report
+    pub template_report: bool,
+    /// Characters of article text sent to the embedding model
+    pub max_embedding_chars: usize,
+    /// Most embedding requests in flight to Ollama at once while embedding the corpus
+    pub embedding_concurrency: usize,
+    /// Headings of sections left out of the embedded text, with their subsections; matched ignoring case
+    pub embedding_excluded_sections: Vec<String>,
+    /// Most summary requests in flight to Ollama at once while warming up summaries
+    pub summary_concurrency: usize,
+    /// Wikipedia language code of the dump, e.g. `simple` or `en`
+    pub dump_language: String,
+    /// Dump date as `YYYYMMDD`; the moving `latest` dump when unset
+    pub dump_date: Option<String>,
+    /// User-Agent sent with every download and Ollama request
+    pub user_agent: String,
+    /// Only import articles in at least one of these categories (all articles when empty)
+    pub include_categories: Vec<String>,
+    /// Never import articles in any of these categories, even if included
+    pub exclude_categories: Vec<String>,
 }
 
 impl Default for InstallConfig {
@@ -36,50 +105,682 @@ impl Default for InstallConfig {
             data_dir: PathBuf::from("data"),
             cache_dir: PathBuf::from("cache"),
             vector_store_dir: PathBuf::from("vectors"),
-            ollama_url: "http://localhost:11434".to_string(),
+            ollama_url: crate::llm::DEFAULT_OLLAMA_URL.to_string(),
             max_image_size: 10 * 1024 * 1024, // 10MB
             max_batch_size: 32,
+            ollama_version: OLLAMA_VERSION.to_string(),
+            ollama_sha256: None,
+            store_raw_wikitext: false,
+            max_articles: None,
+            embedding_model: crate::vector::DEFAULT_EMBEDDING_MODEL.to_string(),
+            vector_map_size: None,
+            template_report: false,
+            max_embedding_chars: crate::vector::DEFAULT_MAX_EMBEDDING_CHARS,
+            embedding_concurrency: DEFAULT_EMBEDDING_CONCURRENCY,
+            embedding_excluded_sections: DEFAULT_EMBEDDING_EXCLUDED_SECTIONS.iter().map(|s| s.to_string()).collect(),
+            summary_concurrency: DEFAULT_SUMMARY_CONCURRENCY,
+            dump_language: DEFAULT_DUMP_LANGUAGE.to_string(),
+            dump_date: None,
+            user_agent: crate::http::DEFAULT_USER_AGENT.to_string(),
+            include_categories: Vec::new(),
+            exclude_categories: Vec::new(),
         }
     }
 }
 
+/// Selects the articles an import keeps by their category membership
+#[derive(Debug, Clone, Default)]
+struct CategoryFilter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl CategoryFilter {
+    fn new(include: &[String], exclude: &[String]) -> Self {
+        Self {
+            include: include.iter().map(|c| Self::normalize(c)).collect(),
+            exclude: exclude.iter().map(|c| Self::normalize(c)).collect(),
+        }
+    }
+
+    /// Compare names the way MediaWiki does loosely: underscores are spaces, case and sort keys ignored
+    fn normalize(category: &str) -> String {
+        let name = category.split('|').next().unwrap_or("");
+        let name = name.trim();
+        let name = name.strip_prefix("Category:").unwrap_or(name);
+        name.replace('_', " ").trim().to_lowercase()
+    }
+
+    fn is_active(&self) -> bool {
+        !self.include.is_empty() || !self.exclude.is_empty()
+    }
+
+    /// Whether an article belongs in the import; exclusions win over inclusions
+    fn accepts(&self, article: &WikiArticle) -> bool {
+        let categories: Vec<String> = article.categories.iter().map(|c| Self::normalize(c)).collect();
+        if categories.iter().any(|c| self.exclude.contains(c)) {
+            return false;
+        }
+        self.include.is_empty() || categories.iter().any(|c| self.include.contains(c))
+    }
+}
+
+/// Progress of a full re-embed, saved after every article so an interrupted run can resume
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct EmbeddingCheckpoint {
+    /// Model the run was embedding with; a checkpoint for another model is ignored
+    model: String,
+    /// Last title embedded; titles are processed in alphabetical order
+    last_title: String,
+    /// Articles embedded so far across all attempts of the run
+    embedded: usize,
+}
+
+impl EmbeddingCheckpoint {
+    fn load(path: &Path) -> Option<Self> {
+        let data = std::fs::read(path).ok()?;
+        match serde_json::from_slice(&data) {
+            Ok(checkpoint) => Some(checkpoint),
+            Err(e) => {
+                warn!("Ignoring unreadable embedding checkpoint {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    fn save(&self, path: &Path) -> WikiResult<()> {
+        let data = serde_json::to_vec(self)
+            .map_err(|e| WikiError::OperationFailed(format!("Failed to encode embedding checkpoint: {}", e)))?;
+        std::fs::write(path, data)?;
+        Ok(())
+    }
+}
+
+/// What an installation produced and how long each phase took
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct InstallReport {
+    /// Distinct articles written to the database
+    pub articles: usize,
+    /// Categories in the database after the import
+    pub categories: usize,
+    /// Images in the database after the import
+    pub images: usize,
+    /// Embeddings generated during this run
+    pub embeddings: usize,
+    /// Parsed pages that were not written (e.g. title collisions or filtered out)
+    pub skipped: usize,
+    /// Parsed pages left out by the category include/exclude lists, also counted in `skipped`
+    pub filtered: usize,
+    /// Most common templates stripped from article text, when the template report is enabled
+    pub templates: Vec<(String, usize)>,
+    /// Wall-clock time of each phase, in the order they ran
+    pub durations: Vec<(String, Duration)>,
+}
+
+impl InstallReport {
+    fn record_phase(&mut self, phase: &str, started: Instant) {
+        self.durations.push((phase.to_string(), started.elapsed()));
+    }
+}
+
+impl fmt::Display for InstallReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Install report:")?;
+        writeln!(f, " - Articles: {}", self.articles)?;
+        writeln!(f, " - Categories: {}", self.categories)?;
+        writeln!(f, " - Images: {}", self.images)?;
+        writeln!(f, " - Embeddings: {}", self.embeddings)?;
+        write!(f, " - Skipped: {}", self.skipped)?;
+        if self.filtered > 0 {
+            write!(f, " ({} outside the selected categories)", self.filtered)?;
+        }
+        if !self.templates.is_empty() {
+            write!(f, "\n - Most common stripped templates:")?;
+            for (name, count) in &self.templates {
+                write!(f, "\n   - {}: {}", name, count)?;
+            }
+        }
+        for (phase, duration) in &self.durations {
+            write!(f, "\n - {}: {:.2?}", phase, duration)?;
+        }
+        Ok(())
+    }
+}
+
+/// State of an installation reported by [`InstallManager::status`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InstallStatus {
+    pub data_dir: PathBuf,
+    pub cache_dir: PathBuf,
+    pub vector_store_dir: PathBuf,
+    pub ollama_url: String,
+    pub data_dir_exists: bool,
+    pub cache_dir_exists: bool,
+    pub vector_store_dir_exists: bool,
+    pub database_exists: bool,
+    /// Whether the `ollama` binary runs
+    pub ollama_installed: bool,
+    /// Whether the Ollama API answers at `ollama_url`
+    pub ollama_serving: bool,
+    pub article_count: Option<usize>,
+    pub schema_version: Option<i32>,
+    /// Date (YYYYMMDD) of the dump the database was installed from
+    pub dump_date: Option<String>,
+    /// Why the database could not be read, when it exists
+    pub database_error: Option<String>,
+}
+
+impl fmt::Display for InstallStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Configuration:")?;
+        writeln!(f, " - Data directory: {}", self.data_dir.display())?;
+        writeln!(f, " - Cache directory: {}", self.cache_dir.display())?;
+        writeln!(f, " - Vector store directory: {}", self.vector_store_dir.display())?;
+        writeln!(f, " - Ollama URL: {}", self.ollama_url)?;
+        writeln!(f, "\nInstallation Status:")?;
+        writeln!(f, " - Data directory exists: {}", self.data_dir_exists)?;
+        writeln!(f, " - Cache directory exists: {}", self.cache_dir_exists)?;
+        writeln!(f, " - Vector store directory exists: {}", self.vector_store_dir_exists)?;
+        writeln!(f, " - Database exists: {}", self.database_exists)?;
+        writeln!(f, " - Ollama installed: {}", self.ollama_installed)?;
+        write!(f, " - Ollama serving: {}", self.ollama_serving)?;
+        if let Some(count) = self.article_count {
+            write!(f, "\n - Articles count: {}", count)?;
+        }
+        if let Some(version) = self.schema_version {
+            write!(f, "\n - Schema version: {}", version)?;
+        }
+        if let Some(dump_date) = &self.dump_date {
+            write!(f, "\n - Dump date: {}", dump_date)?;
+        }
+        if let Some(error) = &self.database_error {
+            write!(f, "\n - Error reading database: {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Space reclaimed by [`InstallManager::optimize`], in bytes on disk
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct OptimizeReport {
+    pub database_before: u64,
+    pub database_after: u64,
+    /// Vector store size before and after compaction; `None` when there is no vector store
+    pub vector_store: Option<(u64, u64)>,
+}
+
+impl fmt::Display for OptimizeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Optimize report:")?;
+        write!(f, " - Database: {} -> {} bytes", self.database_before, self.database_after)?;
+        if let Some((before, after)) = self.vector_store {
+            write!(f, "\n - Vector store: {} -> {} bytes", before, after)?;
+        }
+        Ok(())
+    }
+}
+
+/// Summaries produced by [`InstallManager::warmup_summaries`]
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct WarmupReport {
+    /// Summaries generated and stored by this run
+    pub summarized: usize,
+    /// Articles whose stored summary was already current
+    pub already_stored: usize,
+    /// Articles the LLM failed to summarize; a later warmup retries them
+    pub failed: usize,
+}
+
+impl fmt::Display for WarmupReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Warmup report:")?;
+        writeln!(f, " - Summaries generated: {}", self.summarized)?;
+        writeln!(f, " - Already stored: {}", self.already_stored)?;
+        write!(f, " - Failed: {}", self.failed)
+    }
+}
+
+/// What warming up one article's summary came to
+enum SummaryWarmup {
+    Missing,
+    Stored,
+    Generated { title: String, etag: String, summary: String },
+    Failed,
+}
+
+/// Progress of an installation, sent to the channel given to [`InstallManager::with_events`].
+///
+/// Progress events are dropped rather than stalling the install when the channel is full;
+/// `PhaseComplete` is always delivered while the receiver is alive.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum InstallEvent {
+    /// Bytes of the dump downloaded so far, out of `total` when the server reports it
+    DownloadProgress { downloaded: u64, total: Option<u64> },
+    /// Pages parsed from the dump so far
+    ParseProgress { parsed: usize },
+    /// Articles committed to the database so far
+    InsertProgress { written: usize },
+    /// Articles embedded so far, out of those that needed an embedding
+    EmbedProgress { embedded: usize, total: usize },
+    /// Articles whose summary is warm so far, out of those selected for a warmup
+    SummaryProgress { summarized: usize, total: usize },
+    /// A phase finished, named as in [`InstallReport::durations`]
+    PhaseComplete { phase: String, duration: Duration },
+}
+
+impl fmt::Display for InstallEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DownloadProgress { downloaded, total: Some(total) } if *total > 0 => write!(
+                f, "Downloaded {} / {} bytes ({:.1}%)",
+                downloaded, total, *downloaded as f64 / *total as f64 * 100.0
+            ),
+            Self::DownloadProgress { downloaded, .. } => write!(f, "Downloaded {} bytes", downloaded),
+            Self::ParseProgress { parsed } => write!(f, "Parsed {} pages", parsed),
+            Self::InsertProgress { written } => write!(f, "Inserted {} articles", written),
+            Self::EmbedProgress { embedded, total } => write!(f, "Embedded {}/{} articles", embedded, total),
+            Self::SummaryProgress { summarized, total } => write!(f, "Summarized {}/{} articles", summarized, total),
+            Self::PhaseComplete { phase, duration } => write!(f, "Finished {} in {:.2?}", phase, duration),
+        }
+    }
+}
+
+/// Text embedded for an article: its title and content, leaving out sections headed by one of
+/// `excluded_sections` along with their subsections
+fn embedding_text(article: &WikiArticle, excluded_sections: &[String]) -> String {
+    if excluded_sections.is_empty() {
+        return format!("Title: {}\n\nContent: {}", article.title, article.content);
+    }
+
+    let excluded: Vec<String> = excluded_sections.iter().map(|heading| heading.trim().to_lowercase()).collect();
+    let mut parts = Vec::new();
+    // Level of the excluded heading whose subsections are being skipped
+    let mut skipping: Option<usize> = None;
+    for section in article.sections() {
+        if skipping.is_some_and(|level| section.level > level) {
+            continue;
+        }
+        skipping = None;
+        if section.level > 0 && excluded.contains(&section.heading.trim().to_lowercase()) {
+            skipping = Some(section.level);
+            continue;
+        }
+        if section.level > 0 {
+            let marker = "=".repeat(section.level);
+            parts.push(format!("{} {} {}", marker, section.heading, marker));
+        }
+        if !section.body.is_empty() {
+            parts.push(section.body);
+        }
+    }
+    format!("Title: {}\n\nContent: {}", article.title, parts.join("\n"))
+}
+
+/// Send a progress event without waiting, dropping it if the channel is full or closed
+fn emit_progress(events: &Option<mpsc::Sender<InstallEvent>>, event: InstallEvent) {
+    if let Some(events) = events {
+        let _ = events.try_send(event);
+    }
+}
+
+/// What an install would do, produced by a dry run without writing anything
+#[derive(Debug, Clone, Serialize)]
+pub struct InstallPlan {
+    /// Local path or URL the dump would be read from
+    pub dump_source: String,
+    /// Compressed size of the dump, if known
+    pub dump_size: Option<u64>,
+    /// Articles expected to be imported, extrapolated from a sample of the dump
+    pub estimated_articles: Option<usize>,
+    /// Disk space the database and vector store are expected to need
+    pub estimated_disk_bytes: Option<u64>,
+    pub ollama_installed: bool,
+    pub data_dir: PathBuf,
+    pub vector_store_dir: PathBuf,
+}
+
+impl fmt::Display for InstallPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn or_unknown<T: fmt::Display>(value: &Option<T>) -> String {
+            value.as_ref().map_or_else(|| "unknown".to_string(), |v| v.to_string())
+        }
+
+        writeln!(f, "Install plan (dry run, nothing was written):")?;
+        writeln!(f, " - Dump source: {}", self.dump_source)?;
+        writeln!(f, " - Dump size: {} bytes", or_unknown(&self.dump_size))?;
+        writeln!(f, " - Estimated articles: {}", or_unknown(&self.estimated_articles))?;
+        writeln!(f, " - Estimated disk needed: {} bytes", or_unknown(&self.estimated_disk_bytes))?;
+        writeln!(f, " - Ollama installed: {}", self.ollama_installed)?;
+        writeln!(f, " - Data directory: {}", self.data_dir.display())?;
+        write!(f, " - Vector store directory: {}", self.vector_store_dir.display())
+    }
+}
+
+/// Page count and sizes observed in the first part of a compressed dump
+struct DumpSample {
+    pages: usize,
+    compressed_bytes: u64,
+    decompressed_bytes: u64,
+}
+
+/// Counts the bytes pulled through it, so the sample knows how much compressed input it used
+struct CountingReader<R> {
+    inner: R,
+    count: Arc<AtomicU64>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.fetch_add(n as u64, Ordering::Relaxed);
+        Ok(n)
+    }
+}
+
+/// Decompress up to `DRY_RUN_SAMPLE_BYTES` of a dump and count its pages
+fn sample_dump<R: Read + Send + 'static>(compressed: R, format: DumpFormat) -> WikiResult<DumpSample> {
+    let count = Arc::new(AtomicU64::new(0));
+    let counting = CountingReader { inner: compressed.take(DRY_RUN_SAMPLE_BYTES as u64), count: count.clone() };
+    let mut decoder = format.decoder(counting);
+
+    let mut pages = 0;
+    let mut decompressed_bytes = 0u64;
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut carry = Vec::new();
+    loop {
+        let n = match decoder.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            // The sample usually ends mid-stream; whatever decoded so far still counts
+            Err(_) => break,
+        };
+        decompressed_bytes += n as u64;
+        carry.extend_from_slice(&buf[..n]);
+        pages += carry.windows(6).filter(|w| *w == b"<page>").count();
+        // Keep a short tail so a tag split across reads is still seen once
+        let keep = carry.len().min(5);
+        carry.drain(..carry.len() - keep);
+    }
+
+    Ok(DumpSample {
+        pages,
+        compressed_bytes: count.load(Ordering::Relaxed),
+        decompressed_bytes,
+    })
+}
+
 pub struct InstallManager {
     config: InstallConfig,
     client: Client,
+    events: Option<mpsc::Sender<InstallEvent>>,
+}
+
+/// Build the release download URL of the Ollama asset for an OS/architecture pair.
+///
+/// `os` and `arch` use the values of `std::env::consts::{OS, ARCH}`.
+pub fn ollama_asset_url(version: &str, os: &str, arch: &str) -> WikiResult<String> {
+    Ok(format!(
+        "{}/v{}/{}",
+        OLLAMA_RELEASES_URL,
+        version.trim_start_matches('v'),
+        ollama_asset(os, arch)?
+    ))
+}
+
+/// Name of the Ollama release asset for an OS/architecture pair
+fn ollama_asset(os: &str, arch: &str) -> WikiResult<&'static str> {
+    Ok(match (os, arch) {
+        ("windows", "x86_64") | ("windows", "aarch64") => "OllamaSetup.exe",
+        ("linux", "x86_64") => "ollama-linux-amd64.tgz",
+        ("linux", "aarch64") => "ollama-linux-arm64.tgz",
+        ("macos", "x86_64") | ("macos", "aarch64") => "Ollama-darwin.zip",
+        _ => {
+            return Err(WikiError::Installation(format!(
+                "No Ollama release available for {}/{}", os, arch
+            )))
+        }
+    })
+}
+
+/// Expected SHA-256 of an Ollama release asset: the configured one, else the pinned
+/// checksum of `OLLAMA_VERSION`. Fails when neither is known, so nothing unverified is installed
+fn expected_ollama_sha256(version: &str, asset: &str, configured: Option<&str>) -> WikiResult<String> {
+    if let Some(expected) = configured {
+        return Ok(expected.to_ascii_lowercase());
+    }
+    let pinned = (version.trim_start_matches('v') == OLLAMA_VERSION)
+        .then(|| OLLAMA_SHA256.iter().find(|(name, _)| *name == asset))
+        .flatten();
+    match pinned {
+        Some((_, digest)) => Ok(digest.to_string()),
+        None => Err(WikiError::Installation(format!(
+            "No known checksum for Ollama {} ({}); set ollama_sha256 to the value from the release's sha256sum.txt",
+            version, asset
+        ))),
+    }
+}
+
+/// Build the download URL of a Wikipedia articles dump.
+///
+/// `date` is a dump date as `YYYYMMDD`; `None` selects the `latest` dump, which
+/// changes as new dumps are published.
+pub fn wikidump_url(language: &str, date: Option<&str>) -> WikiResult<String> {
+    if language.is_empty() || !language.chars().all(|c| c.is_ascii_lowercase() || c == '-' || c == '_') {
+        return Err(WikiError::Configuration(format!("Invalid dump language '{}'", language)));
+    }
+
+    let date = match date {
+        Some(date) => {
+            if date.len() != 8 || chrono::NaiveDate::parse_from_str(date, "%Y%m%d").is_err() {
+                return Err(WikiError::Configuration(format!(
+                    "Invalid dump date '{}', expected YYYYMMDD", date
+                )));
+            }
+            date
+        }
+        None => "latest",
+    };
+
+    let wiki = format!("{}wiki", language.replace('-', "_"));
+    Ok(format!(
+        "{}/{}/{}/{}-{}-pages-articles1.xml.bz2",
+        WIKIDUMPS_URL, wiki, date, wiki, date
+    ))
 }
 
+/// Alternative names for the installer types used across the codebase
+pub type Installer = InstallManager;
+pub type InstallerConfig = InstallConfig;
+
 impl InstallManager {
-    pub fn new(config: InstallConfig) -> Self {
-        Self {
-            config,
-            client: Client::new(),
+    /// Fails with [`WikiError::Configuration`] when `config.user_agent` is not a valid header
+    /// value or `config.ollama_url` is not an http(s) URL. The URL is normalized here, so
+    /// every request and report uses the same form of it
+    pub fn new(mut config: InstallConfig) -> WikiResult<Self> {
+        let client = crate::http::client_with_user_agent(&config.user_agent)?;
+        config.ollama_url = crate::http::normalize_ollama_url(&config.ollama_url)?;
+        Ok(Self { config, client, events: None })
+    }
+
+    /// Report install progress as [`InstallEvent`]s on this channel
+    pub fn with_events(mut self, events: mpsc::Sender<InstallEvent>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Record a finished phase and announce it to the event channel, if any
+    async fn finish_phase(&self, report: &mut InstallReport, phase: &str, started: Instant) {
+        report.record_phase(phase, started);
+        if let (Some(events), Some((_, duration))) = (&self.events, report.durations.last()) {
+            let duration = *duration;
+            // A closed receiver only means nobody is listening any more
+            let _ = events.send(InstallEvent::PhaseComplete { phase: phase.to_string(), duration }).await;
         }
     }
 
-    pub async fn install(&self) -> WikiResult<()> {
+    pub async fn install(&self) -> WikiResult<InstallReport> {
         info!("Starting installation...");
+        let mut report = InstallReport::default();
 
         // Create directories
+        let started = Instant::now();
         self.create_directories().await?;
+        self.finish_phase(&mut report, "directories", started).await;
 
         // Check and install Ollama
+        let started = Instant::now();
         self.install_ollama().await?;
+        self.finish_phase(&mut report, "ollama", started).await;
 
         // Pull required models
+        let started = Instant::now();
         self.pull_models().await?;
+        self.finish_phase(&mut report, "models", started).await;
 
         // Download Wikipedia dump
+        let started = Instant::now();
         let dump_path = self.download_wikidump().await?;
+        self.finish_phase(&mut report, "download", started).await;
 
         // Process Wikipedia dump and store in database
+        let started = Instant::now();
         let db_path = self.config.data_dir.join("wiki.db");
-        self.process_wikidump(&dump_path, &db_path).await?;
+        self.process_wikidump(&dump_path, &db_path, &mut report).await?;
+        self.finish_phase(&mut report, "import", started).await;
 
         // Generate embeddings for articles
-        self.generate_embeddings(&db_path).await?;
+        let started = Instant::now();
+        report.embeddings = self.generate_embeddings(&db_path).await?;
+        self.finish_phase(&mut report, "embeddings", started).await;
 
         info!("Installation completed successfully!");
-        Ok(())
+        Ok(report)
+    }
+
+    /// Run preflight checks and estimate the install without writing anything.
+    ///
+    /// Uses the dump already in the cache when present, otherwise samples the start
+    /// of the remote dump.
+    pub async fn plan(&self) -> WikiResult<InstallPlan> {
+        let ollama_installed = self.check_ollama_installed().await.unwrap_or(false);
+        let local_dump = self.dump_path();
+
+        let (dump_source, dump_size, sample) = if local_dump.exists() {
+            let size = fs::metadata(&local_dump).await?.len();
+            let path = local_dump.clone();
+            let sample = tokio::task::spawn_blocking(move || {
+                let format = DumpFormat::detect(&path)?;
+                sample_dump(std::io::BufReader::new(std::fs::File::open(path)?), format)
+            })
+            .await
+            .map_err(|e| WikiError::OperationFailed(format!("Failed to sample dump: {}", e)))??;
+            (local_dump.display().to_string(), Some(size), Some(sample))
+        } else {
+            let (size, bytes) = self.fetch_dump_sample().await?;
+            let sample = sample_dump(std::io::Cursor::new(bytes), DumpFormat::Bzip2)?;
+            (self.dump_url()?, size, Some(sample))
+        };
+
+        let estimated_articles = match (dump_size, &sample) {
+            (Some(size), Some(sample)) if sample.compressed_bytes > 0 => {
+                let estimate = (sample.pages as f64 * size as f64 / sample.compressed_bytes as f64).round() as usize;
+                Some(self.config.max_articles.map_or(estimate, |max| estimate.min(max)))
+            }
+            _ => None,
+        };
+
+        let estimated_disk_bytes = match (estimated_articles, &sample) {
+            (Some(articles), Some(sample)) if sample.pages > 0 => {
+                let bytes_per_page = sample.decompressed_bytes / sample.pages as u64;
+                let vectors = estimate_map_size(articles, ESTIMATED_EMBEDDING_DIMENSION, self.config.vector_map_size);
+                Some(bytes_per_page * articles as u64 + vectors as u64)
+            }
+            _ => None,
+        };
+
+        Ok(InstallPlan {
+            dump_source,
+            dump_size,
+            estimated_articles,
+            estimated_disk_bytes,
+            ollama_installed,
+            data_dir: self.config.data_dir.clone(),
+            vector_store_dir: self.config.vector_store_dir.clone(),
+        })
+    }
+
+    /// Fetch the dump size and its first `DRY_RUN_SAMPLE_BYTES` without downloading the rest
+    async fn fetch_dump_sample(&self) -> WikiResult<(Option<u64>, Vec<u8>)> {
+        use futures_util::StreamExt;
+
+        let response = self.client.get(self.dump_url()?)
+            .header(reqwest::header::RANGE, format!("bytes=0-{}", DRY_RUN_SAMPLE_BYTES - 1))
+            .send()
+            .await
+            .map_err(|e| WikiError::Installation(format!("Failed to reach Wikipedia dump: {}", e)))?;
+
+        // A ranged response reports the full size after the slash in Content-Range
+        let total_size = response.headers()
+            .get(reqwest::header::CONTENT_RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit('/').next())
+            .and_then(|value| value.parse().ok())
+            .or_else(|| response.content_length());
+
+        let mut bytes = Vec::with_capacity(DRY_RUN_SAMPLE_BYTES);
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| WikiError::Installation(format!("Error while sampling dump: {}", e)))?;
+            bytes.extend_from_slice(&chunk);
+            if bytes.len() >= DRY_RUN_SAMPLE_BYTES {
+                break;
+            }
+        }
+
+        Ok((total_size, bytes))
+    }
+
+    /// Install from a local dump file (plain, gzip or bz2 XML) instead of downloading one.
+    ///
+    /// Skips the Ollama setup; embeddings are generated against the configured Ollama URL.
+    pub async fn install_from_file<P: AsRef<Path>>(&self, dump_path: P) -> WikiResult<InstallReport> {
+        let dump_path = dump_path.as_ref();
+        info!("Installing from {}", dump_path.display());
+        if !dump_path.exists() {
+            return Err(WikiError::Installation(format!(
+                "Dump file not found: {}", dump_path.display()
+            )));
+        }
+        self.install_from_stream(DecompressedStream::open(dump_path)?).await
+    }
+
+    /// Install from a dump read from `reader`, e.g. stdin piped from a downloader.
+    ///
+    /// The compression (plain, gzip or bz2) is detected from the first bytes. Like
+    /// [`Self::install_from_file`], this skips the Ollama setup.
+    pub async fn install_from_reader<R: Read + Send + 'static>(&self, reader: R) -> WikiResult<InstallReport> {
+        info!("Installing from a dump stream");
+        self.install_from_stream(DecompressedStream::from_reader(reader)?).await
+    }
+
+    async fn install_from_stream(&self, stream: DecompressedStream) -> WikiResult<InstallReport> {
+        let mut report = InstallReport::default();
+
+        let started = Instant::now();
+        self.create_directories().await?;
+        self.finish_phase(&mut report, "directories", started).await;
+
+        let started = Instant::now();
+        let db_path = self.config.data_dir.join("wiki.db");
+        self.process_dump_stream(stream, &db_path, &mut report).await?;
+        self.finish_phase(&mut report, "import", started).await;
+
+        let started = Instant::now();
+        report.embeddings = self.generate_embeddings(&db_path).await?;
+        self.finish_phase(&mut report, "embeddings", started).await;
+
+        Ok(report)
     }
 
     async fn create_directories(&self) -> WikiResult<()> {
@@ -102,15 +803,7 @@ impl InstallManager {
         
         #[cfg(target_os = "windows")]
         {
-            let installer_url = format!(
-                "https://github.com/jmorganca/ollama/releases/download/v{}/ollama-windows.exe",
-                OLLAMA_VERSION
-            );
-            let response = self.client.get(&installer_url).send().await?;
-            let bytes = response.bytes().await?;
-            
-            let installer_path = self.config.cache_dir.join("ollama-installer.exe");
-            fs::write(&installer_path, &bytes).await?;
+            let installer_path = self.download_ollama_installer("ollama-installer.exe").await?;
 
             let status = Command::new(installer_path)
                 .arg("/VERYSILENT")
@@ -122,39 +815,96 @@ impl InstallManager {
             }
         }
 
+        // Elsewhere the checksummed release is unpacked under data_dir, which needs no root
         #[cfg(target_os = "linux")]
         {
-            let status = Command::new("curl")
-                .args(["-fsSL", "https://ollama.ai/install.sh"])
-                .stdout(std::process::Stdio::piped())
-                .spawn()
-                .map_err(|e| WikiError::Installation(format!("Failed to download Ollama installer: {}", e)))?
-                .wait()
-                .map_err(|e| WikiError::Installation(format!("Failed to run Ollama installer: {}", e)))?;
-
-            if !status.success() {
-                return Err(WikiError::Installation("Ollama installation failed".to_string()));
-            }
+            let archive_path = self.download_ollama_installer("ollama-linux.tgz").await?;
+            self.unpack_ollama(Command::new("tar").arg("-xzf").arg(&archive_path).arg("-C")).await?;
         }
 
         #[cfg(target_os = "macos")]
         {
-            let status = Command::new("brew")
-                .args(["install", "ollama"])
-                .status()
-                .map_err(|e| WikiError::Installation(format!("Failed to install Ollama: {}", e)))?;
-
-            if !status.success() {
-                return Err(WikiError::Installation("Ollama installation failed".to_string()));
-            }
+            let archive_path = self.download_ollama_installer("ollama-darwin.zip").await?;
+            self.unpack_ollama(Command::new("ditto").args(["-x", "-k"]).arg(&archive_path)).await?;
         }
 
         info!("Ollama installed successfully");
         Ok(())
     }
 
+    /// Run `unpack`, which is given the destination as its last argument, into the Ollama directory
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    async fn unpack_ollama(&self, unpack: &mut Command) -> WikiResult<()> {
+        let dir = self.ollama_dir();
+        fs::create_dir_all(&dir).await?;
+        let status = unpack
+            .arg(&dir)
+            .status()
+            .map_err(|e| WikiError::Installation(format!("Failed to unpack Ollama: {}", e)))?;
+
+        if !status.success() {
+            return Err(WikiError::Installation(format!("Failed to unpack Ollama into {}", dir.display())));
+        }
+        Ok(())
+    }
+
+    /// Directory the Ollama release is unpacked into on Linux and macOS
+    fn ollama_dir(&self) -> PathBuf {
+        self.config.data_dir.join("ollama")
+    }
+
+    /// The Ollama binary unpacked under `data_dir` when present, otherwise `ollama` on the PATH
+    fn ollama_binary(&self) -> PathBuf {
+        let local = if cfg!(target_os = "macos") {
+            self.ollama_dir().join("Ollama.app/Contents/Resources/ollama")
+        } else {
+            self.ollama_dir().join("bin").join("ollama")
+        };
+        if local.is_file() {
+            local
+        } else {
+            PathBuf::from("ollama")
+        }
+    }
+
+    /// Download the Ollama release asset for this platform and verify its checksum
+    #[cfg_attr(not(any(target_os = "windows", target_os = "linux", target_os = "macos")), allow(dead_code))]
+    async fn download_ollama_installer(&self, file_name: &str) -> WikiResult<PathBuf> {
+        let asset = ollama_asset(std::env::consts::OS, std::env::consts::ARCH)?;
+        // Fail before downloading when the download could not be verified
+        let expected = expected_ollama_sha256(&self.config.ollama_version, asset, self.config.ollama_sha256.as_deref())?;
+        let url = ollama_asset_url(
+            &self.config.ollama_version,
+            std::env::consts::OS,
+            std::env::consts::ARCH,
+        )?;
+        info!("Downloading Ollama {} from {}", self.config.ollama_version, url);
+
+        let response = self.client.get(&url).send().await?;
+        if !response.status().is_success() {
+            return Err(WikiError::Installation(format!(
+                "Failed to download Ollama installer, status code: {}",
+                response.status()
+            )));
+        }
+        let bytes = response.bytes().await?;
+
+        let digest = format!("{:x}", Sha256::digest(&bytes));
+        if digest != expected {
+            return Err(WikiError::Installation(format!(
+                "Ollama installer checksum mismatch: expected {}, got {}",
+                expected, digest
+            )));
+        }
+        debug!("Ollama installer checksum verified");
+
+        let installer_path = self.config.cache_dir.join(file_name);
+        fs::write(&installer_path, &bytes).await?;
+        Ok(installer_path)
+    }
+
     async fn check_ollama_installed(&self) -> WikiResult<bool> {
-        let status = Command::new("ollama")
+        let status = Command::new(self.ollama_binary())
             .arg("--version")
             .status()
             .map_err(|_| WikiError::Installation("Failed to check Ollama version".to_string()))?;
@@ -162,10 +912,57 @@ impl InstallManager {
         Ok(status.success())
     }
 
+    /// Check whether the Ollama server answers on its API
+    async fn is_ollama_serving(&self) -> bool {
+        match self.client.get(format!("{}/api/tags", self.config.ollama_url)).send().await {
+            Ok(response) => response.status().is_success(),
+            Err(_) => false,
+        }
+    }
+
+    /// Poll the Ollama API until it responds or the timeout elapses
+    async fn wait_for_ollama(&self, timeout: Duration) -> WikiResult<()> {
+        let deadline = tokio::time::Instant::now() + timeout;
+
+        loop {
+            if self.is_ollama_serving().await {
+                debug!("Ollama is serving at {}", self.config.ollama_url);
+                return Ok(());
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Err(WikiError::Installation(format!(
+                    "Ollama did not respond at {} within {} seconds",
+                    self.config.ollama_url,
+                    timeout.as_secs()
+                )));
+            }
+            tokio::time::sleep(Duration::from_millis(OLLAMA_READY_POLL_MILLIS)).await;
+        }
+    }
+
+    /// Start the Ollama server if it is installed but not running, then wait for it
+    async fn ensure_ollama_running(&self) -> WikiResult<()> {
+        if self.is_ollama_serving().await {
+            return Ok(());
+        }
+
+        info!("Ollama is not running, starting server...");
+        TokioCommand::new(self.ollama_binary())
+            .arg("serve")
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .map_err(|e| WikiError::Installation(format!("Failed to start Ollama server: {}", e)))?;
+
+        self.wait_for_ollama(Duration::from_secs(OLLAMA_READY_TIMEOUT_SECS)).await
+    }
+
     async fn pull_models(&self) -> WikiResult<()> {
+        self.ensure_ollama_running().await?;
+
         info!("Pulling required models...");
 
-        let status = Command::new("ollama")
+        let status = Command::new(self.ollama_binary())
             .args(["pull", OLLAMA_MODEL])
             .status()
             .map_err(|e| WikiError::Installation(format!("Failed to pull model {}: {}", OLLAMA_MODEL, e)))?;
@@ -178,13 +975,27 @@ impl InstallManager {
         Ok(())
     }
 
+    /// Download URL of the configured dump
+    fn dump_url(&self) -> WikiResult<String> {
+        wikidump_url(&self.config.dump_language, self.config.dump_date.as_deref())
+    }
+
+    /// Location of the downloaded dump; transient, so it lives under the cache directory
+    fn dump_path(&self) -> PathBuf {
+        self.config.cache_dir.join("downloads").join("wiki-dump.xml.bz2")
+    }
+
     async fn download_wikidump(&self) -> WikiResult<PathBuf> {
-        let dump_path = self.config.data_dir.join("wiki-dump.xml.bz2");
+        let dump_path = self.dump_path();
+        if let Some(parent) = dump_path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
         
-        info!("Downloading Wikipedia dump from {}", WIKIDUMP_URL);
+        let dump_url = self.dump_url()?;
+        info!("Downloading Wikipedia dump from {}", dump_url);
         
         // Create a progress indicator
-        let response = self.client.get(WIKIDUMP_URL)
+        let response = self.client.get(&dump_url)
             .send()
             .await
             .map_err(|e| WikiError::Installation(format!("Failed to download Wikipedia dump: {}", e)))?;
@@ -201,6 +1012,8 @@ impl InstallManager {
         
         let mut file = fs::File::create(&dump_path).await?;
         let mut downloaded: u64 = 0;
+        let mut reported: u64 = 0;
+        let total = (total_size > 0).then_some(total_size);
         let mut stream = response.bytes_stream();
         
         use futures_util::StreamExt;
@@ -209,6 +1022,10 @@ impl InstallManager {
             file.write_all(&chunk).await?;
             
             downloaded += chunk.len() as u64;
+            if downloaded - reported >= DOWNLOAD_PROGRESS_BYTES {
+                reported = downloaded;
+                emit_progress(&self.events, InstallEvent::DownloadProgress { downloaded, total });
+            }
             
             // Log progress every 5MB
             if downloaded % (5 * 1024 * 1024) == 0 {
@@ -221,71 +1038,133 @@ impl InstallManager {
             }
         }
         
+        emit_progress(&self.events, InstallEvent::DownloadProgress { downloaded, total });
         info!("Download completed: {} bytes", downloaded);
         Ok(dump_path)
     }
     
-    async fn process_wikidump(&self, dump_path: &Path, db_path: &Path) -> WikiResult<()> {
+    async fn process_wikidump(&self, dump_path: &Path, db_path: &Path, report: &mut InstallReport) -> WikiResult<()> {
+        self.process_dump_stream(DecompressedStream::open(dump_path)?, db_path, report).await
+    }
+
+    /// Parse the decompressed dump XML from `stream` into the database at `db_path`
+    async fn process_dump_stream(&self, stream: DecompressedStream, db_path: &Path, report: &mut InstallReport) -> WikiResult<()> {
         info!("Processing Wikipedia dump...");
         
         // Initialize database
         info!("Initializing database at {}", db_path.display());
-        let mut db_conn = rusqlite::Connection::open(db_path)?;
+        let db_conn = rusqlite::Connection::open(db_path)?;
+        schema::ensure_fts5(&db_conn)?;
         schema::init_database(&db_conn)?;
-        let mut db_writer = DatabaseWriter::new(&mut db_conn);
+        drop(db_conn);
         
-        // Create parser
-        let parser = WikiXmlParser::new();
+        // Start the writer so inserts run alongside parsing
+        // `max_articles` caps the articles inserted, so filtered pages, redirects and collisions don't count
+        let insert_events = self.events.clone();
+        let limit = InsertLimit::new(self.config.max_articles);
+        let writer = ArticleWriterTask::spawn_with_limit(
+            db_path,
+            BATCH_SIZE,
+            WRITER_CHANNEL_CAPACITY,
+            self.config.store_raw_wikitext,
+            limit.clone(),
+            move |written| emit_progress(&insert_events, InstallEvent::InsertProgress { written }),
+        );
+        let sender = writer.sender();
         
         // Process the dump file
         info!("Decompressing and parsing dump file...");
         
-        // Read the bz2 file in a blocking task to avoid blocking the async runtime
-        let dump_path_clone = dump_path.to_path_buf();
-        let articles = tokio::task::spawn_blocking(move || -> WikiResult<Vec<WikiArticle>> {
-            // Open the BZ2 file
-            let file = std::fs::File::open(dump_path_clone)?;
-            let buf_reader = std::io::BufReader::new(file);
-            let mut decompressor = BzDecoder::new(buf_reader);
-            
-            // Read the decompressed content
-            let mut xml_content = String::new();
-            decompressor.read_to_string(&mut xml_content)?;
-            
-            // Parse the XML content
-            let mut parser = WikiXmlParser::new();
-            parser.parse(&xml_content)
-        }).await.map_err(|e| WikiError::OperationFailed(format!("Failed to process dump file: {}", e)))??;
+        // Read the dump in a blocking task to avoid blocking the async runtime
+        let template_report = self.config.template_report;
+        let store_raw_wikitext = self.config.store_raw_wikitext;
+        let category_filter = CategoryFilter::new(&self.config.include_categories, &self.config.exclude_categories);
+        if category_filter.is_active() {
+            info!(
+                "Filtering articles by category (include: {:?}, exclude: {:?})",
+                self.config.include_categories, self.config.exclude_categories
+            );
+        }
+        let parse_events = self.events.clone();
+        let started = Instant::now();
+        let parsed = tokio::task::spawn_blocking(move || -> WikiResult<(usize, usize, Option<TemplateReport>, Option<DateTime<Utc>>)> {
+            // Parse as the bytes arrive (decompression runs on its own thread), handing each article to the writer
+            let mut parser = WikiXmlParser::from_reader(std::io::BufReader::new(stream))
+                .with_raw_wikitext(store_raw_wikitext)
+                .with_template_report(template_report);
+            let mut filtered = 0;
+            let mut seen = 0;
+            let parsed = parser.parse_articles_while(|article| {
+                if limit.is_reached() {
+                    return Ok(false);
+                }
+                seen += 1;
+                if seen % PARSE_PROGRESS_ARTICLES == 0 {
+                    emit_progress(&parse_events, InstallEvent::ParseProgress { parsed: seen });
+                }
+                if !category_filter.accepts(&article) {
+                    filtered += 1;
+                    return Ok(true);
+                }
+                match sender.blocking_send(article) {
+                    Ok(()) => Ok(true),
+                    // The writer closes the channel once the limit is reached
+                    Err(_) if limit.is_reached() => Ok(false),
+                    Err(_) => Err(WikiError::OperationFailed("Article writer has stopped".to_string())),
+                }
+            })?;
+            emit_progress(&parse_events, InstallEvent::ParseProgress { parsed });
+            let dump_date = parser.parse_metadata()?.dump_date;
+            Ok((parsed, filtered, parser.template_report().cloned(), dump_date))
+        }).await.map_err(|e| WikiError::OperationFailed(format!("Failed to process dump file: {}", e)))?;
         
-        info!("Parsed {} articles", articles.len());
+        // Always wait for the writer so a writer error is not masked by the parse result
+        let import = writer.finish().await?;
+        let (parsed, filtered, templates, parsed_dump_date) = parsed?;
         
-        // Insert articles into database
-        info!("Inserting articles into database...");
+        let elapsed = started.elapsed().as_secs_f64().max(f64::EPSILON);
+        info!("Parsed {} articles in {:.1}s ({:.0} articles/s)", parsed, elapsed, parsed as f64 / elapsed);
+        info!(
+            "All articles inserted successfully ({} written, {} title collisions)",
+            import.articles_written, import.title_collisions
+        );
         
-        // Process articles in batches to avoid holding the transaction too long
-        for (batch_idx, batch) in articles.chunks(100).enumerate() {
-            info!("Processing batch {} with {} articles", batch_idx, batch.len());
-            
-            // Create a new transaction for each batch
-            let tx = db_writer.begin_transaction()?;
-            
-            for article in batch {
-                // Clone the article for the transaction
-                db_writer.write_article(article, &tx)?;
-            }
-            
-            // Commit the transaction
-            tx.commit()?;
-            
-            info!("Inserted {} articles in batch {}", batch.len(), batch_idx);
+        // Dumps contain double redirects; point them at their final target
+        let mut db_conn = rusqlite::Connection::open(db_path)?;
+        let tx = db_conn.transaction()?;
+        let writer = crate::db::DatabaseWriter::new(&tx);
+        let flattened = writer.flatten_redirects(&tx)?;
+        // The requested dump date wins over the dump's own timestamp, which many dumps lack;
+        // with neither, no date is recorded rather than a made-up one
+        let dump_date = self.config.dump_date.clone()
+            .or_else(|| parsed_dump_date.map(|date| date.format("%Y%m%d").to_string()));
+        if let Some(dump_date) = dump_date {
+            writer.set_metadata(schema::DUMP_DATE_KEY, &dump_date, &tx)?;
         }
+        tx.commit()?;
+        info!("Flattened {} double redirects", flattened);
         
-        info!("All articles inserted successfully");
+        let db_reader = crate::db::DatabaseReader::new(&db_conn);
+        report.articles = import.articles_written;
+        report.skipped = parsed.saturating_sub(import.articles_written);
+        report.filtered = filtered;
+        if filtered > 0 {
+            info!("Skipped {} articles outside the selected categories", filtered);
+        }
+        report.categories = db_reader.count_categories()?;
+        report.images = db_reader.count_images()?;
+        if let Some(templates) = templates {
+            report.templates = templates.most_common(TEMPLATE_REPORT_SIZE);
+            for (name, count) in &report.templates {
+                warn!("Stripped {} uses of template {{{{{}}}}}", count, name);
+            }
+        }
         
         Ok(())
     }
     
-    async fn generate_embeddings(&self, db_path: &Path) -> WikiResult<()> {
+    /// Embed articles missing from the vector store, returning how many were generated
+    async fn generate_embeddings(&self, db_path: &Path) -> WikiResult<usize> {
         info!("Generating embeddings for articles...");
         
         // Open database connection
@@ -293,38 +1172,315 @@ impl InstallManager {
         let db_reader = crate::db::DatabaseReader::new(&db_conn);
         
         // Initialize vector store
-        let vector_store = VectorStore::new(
-            &self.config.vector_store_dir,
-            &self.config.ollama_url
-        )?;
+        let vector_store = self.open_vector_store(db_reader.count_articles()?).await?;
         
-        // Get all articles
-        let articles = db_reader.get_articles(1000)?;
-        info!("Found {} articles for embedding generation", articles.len());
-        
-        // Generate embeddings in batches
-        for (i, article) in articles.iter().enumerate() {
-            // Generate embedding for article title and content
-            let text = format!("Title: {}\n\nContent: {}", article.title, article.content);
-            let embedding = vector_store.generate_embedding(&text).await?;
-            
-            // Store embedding with article title as key
-            vector_store.store_embedding(&article.title, &embedding)?;
-            
-            if (i + 1) % 10 == 0 || i + 1 == articles.len() {
-                info!("Generated embeddings for {}/{} articles", i + 1, articles.len());
-            }
-        }
+        // Only embed articles that are missing from the vector store, so reruns resume
+        let titles = db_reader.titles_without_embeddings(&vector_store)?;
+        info!("Found {} articles without embeddings", titles.len());
+        let excluded = &self.config.embedding_excluded_sections;
+        let generated = Self::embed_titles(&db_reader, &vector_store, &titles, excluded, None, &self.events).await?;
         
         info!("All embeddings generated successfully");
-        Ok(())
+        Ok(generated)
     }
 
-    pub async fn uninstall(&self) -> WikiResult<()> {
-        info!("Starting uninstallation...");
+    /// Regenerate embeddings for every article, or only for vectors made by another model.
+    ///
+    /// A stale-only run resumes naturally since re-embedded vectors are no longer stale. A full
+    /// run checkpoints its progress and, if interrupted, continues after the last embedded title.
+    pub async fn reembed(&self, only_stale: bool) -> WikiResult<usize> {
+        let db_conn = rusqlite::Connection::open(self.config.data_dir.join("wiki.db"))?;
+        let db_reader = crate::db::DatabaseReader::new(&db_conn);
+        let vector_store = self.open_vector_store(db_reader.count_articles()?).await?;
+
+        if only_stale {
+            let titles = vector_store.stale_keys()?;
+            info!("Re-embedding {} stale articles with {}", titles.len(), vector_store.embedding_model());
+            let excluded = &self.config.embedding_excluded_sections;
+            return Self::embed_titles(&db_reader, &vector_store, &titles, excluded, None, &self.events).await;
+        }
+
+        let checkpoint_path = self.embedding_checkpoint_path();
+        let mut titles = db_reader.list_titles()?;
+        let mut checkpoint = EmbeddingCheckpoint {
+            model: vector_store.embedding_model().to_string(),
+            last_title: String::new(),
+            embedded: 0,
+        };
+        if let Some(saved) = EmbeddingCheckpoint::load(&checkpoint_path) {
+            if saved.model == checkpoint.model {
+                info!("Resuming re-embed after '{}' ({} already embedded)", saved.last_title, saved.embedded);
+                titles.retain(|title| title.as_str() > saved.last_title.as_str());
+                checkpoint = saved;
+            }
+        }
+        info!("Re-embedding {} articles with {}", titles.len(), checkpoint.model);
+
+        let progress = Some((checkpoint_path.as_path(), &mut checkpoint));
+        let excluded = &self.config.embedding_excluded_sections;
+        let generated = Self::embed_titles(&db_reader, &vector_store, &titles, excluded, progress, &self.events).await?;
+
+        // The run is complete, so the next full re-embed starts over
+        if checkpoint_path.exists() {
+            std::fs::remove_file(&checkpoint_path)?;
+        }
+        Ok(generated)
+    }
+
+    fn embedding_checkpoint_path(&self) -> PathBuf {
+        self.config.vector_store_dir.join(EMBEDDING_CHECKPOINT_FILE)
+    }
+
+    /// Open the vector store sized for `article_count` embeddings
+    async fn open_vector_store(&self, article_count: usize) -> WikiResult<VectorStore> {
+        let map_size = estimate_map_size(
+            article_count,
+            ESTIMATED_EMBEDDING_DIMENSION,
+            self.config.vector_map_size,
+        );
+        Ok(VectorStore::with_map_size(&self.config.vector_store_dir, &self.config.ollama_url, map_size)
+            .await?
+            .with_user_agent(&self.config.user_agent)?
+            .with_embedding_model(&self.config.embedding_model)
+            .with_max_input_chars(self.config.max_embedding_chars)
+            .with_max_concurrent_embeddings(self.config.embedding_concurrency))
+    }
+
+    /// Paths, what exists on disk, Ollama availability and what the database holds
+    pub async fn status(&self) -> InstallStatus {
+        let db_path = self.config.data_dir.join("wiki.db");
+        let mut status = InstallStatus {
+            data_dir: self.config.data_dir.clone(),
+            cache_dir: self.config.cache_dir.clone(),
+            vector_store_dir: self.config.vector_store_dir.clone(),
+            ollama_url: self.config.ollama_url.clone(),
+            data_dir_exists: self.config.data_dir.exists(),
+            cache_dir_exists: self.config.cache_dir.exists(),
+            vector_store_dir_exists: self.config.vector_store_dir.exists(),
+            database_exists: db_path.exists(),
+            // A missing binary fails to spawn, which just means it isn't installed
+            ollama_installed: self.check_ollama_installed().await.unwrap_or(false),
+            ollama_serving: self.is_ollama_serving().await,
+            article_count: None,
+            schema_version: None,
+            dump_date: None,
+            database_error: None,
+        };
+
+        if status.database_exists {
+            let read = (|| -> WikiResult<()> {
+                let conn = rusqlite::Connection::open(&db_path)?;
+                let reader = crate::db::DatabaseReader::new(&conn);
+                status.article_count = Some(reader.count_articles()?);
+                status.schema_version = reader.schema_version()?;
+                status.dump_date = reader.get_metadata(schema::DUMP_DATE_KEY)?;
+                Ok(())
+            })();
+            if let Err(e) = read {
+                status.database_error = Some(e.to_string());
+            }
+        }
+
+        status
+    }
+
+    /// Merge the search index, vacuum the database and compact the vector store.
+    ///
+    /// Nothing else, such as a running server, should have the data open meanwhile.
+    pub async fn optimize(&self) -> WikiResult<OptimizeReport> {
+        let db_path = self.config.data_dir.join("wiki.db");
+        if !db_path.exists() {
+            return Err(WikiError::Installation(format!("No database at {}", db_path.display())));
+        }
+        let mut report = OptimizeReport {
+            database_before: fs::metadata(&db_path).await?.len(),
+            ..Default::default()
+        };
+
+        let vacuum_path = db_path.clone();
+        let article_count = tokio::task::spawn_blocking(move || -> WikiResult<usize> {
+            crate::db::DatabaseManager::new(&vacuum_path.to_string_lossy())?.optimize()?;
+            let conn = rusqlite::Connection::open(&vacuum_path)?;
+            crate::db::DatabaseReader::new(&conn).count_articles()
+        })
+        .await
+        .map_err(|e| WikiError::OperationFailed(format!("Failed to optimize database: {}", e)))??;
+        report.database_after = fs::metadata(&db_path).await?.len();
+
+        if self.config.vector_store_dir.exists() {
+            let vector_store = self.open_vector_store(article_count).await?;
+            let before = vector_store.disk_size()?;
+            let vector_store = vector_store.compact().await?;
+            report.vector_store = Some((before, vector_store.disk_size()?));
+        }
+
+        Ok(report)
+    }
+
+    /// Generate and store summaries for every article, or for the `top` most viewed, so the
+    /// API serves them without waiting on the LLM.
+    ///
+    /// Up to `summary_concurrency` requests run at once. Articles that already have a summary
+    /// for their current content are skipped, so an interrupted warmup picks up where it stopped.
+    pub async fn warmup_summaries(&self, top: Option<usize>) -> WikiResult<WarmupReport> {
+        use futures_util::StreamExt;
+
+        let db_path = self.config.data_dir.join("wiki.db");
+        if !db_path.exists() {
+            return Err(WikiError::Installation(format!("No database at {}", db_path.display())));
+        }
+        let conn = rusqlite::Connection::open(&db_path)?;
+        let db_reader = crate::db::DatabaseReader::new(&conn);
+        let llm = LlmService::new(&self.config.ollama_url, None)?.with_user_agent(&self.config.user_agent)?;
+        let titles = Self::warmup_titles(&db_reader, top)?;
+        info!("Warming up summaries of {} articles with {}", titles.len(), llm.model());
+
+        let concurrency = self.config.summary_concurrency.max(1);
+        let mut outcomes = futures_util::stream::iter(&titles)
+            .map(|title| {
+                let article = db_reader.get_article(title);
+                let (db_reader, llm) = (&db_reader, &llm);
+                async move {
+                    let article = match article? {
+                        Some(article) => article,
+                        None => return Ok(SummaryWarmup::Missing),
+                    };
+                    let etag = crate::llm::summary_etag(&article.content, llm);
+                    if db_reader.get_summary(&article.title, &etag)?.is_some() {
+                        return Ok(SummaryWarmup::Stored);
+                    }
+                    match llm.summarize_article(&article.title, &article.content).await {
+                        Ok(summary) => Ok(SummaryWarmup::Generated { title: article.title, etag, summary }),
+                        Err(e) => {
+                            warn!("Failed to summarize '{}': {}", article.title, e);
+                            Ok::<_, WikiError>(SummaryWarmup::Failed)
+                        }
+                    }
+                }
+            })
+            .buffer_unordered(concurrency);
+
+        let writer = crate::db::DatabaseWriter::new(&conn);
+        let mut report = WarmupReport::default();
+        let mut processed = 0;
+        while let Some(outcome) = outcomes.next().await {
+            processed += 1;
+            match outcome? {
+                SummaryWarmup::Missing => (),
+                SummaryWarmup::Stored => report.already_stored += 1,
+                SummaryWarmup::Failed => report.failed += 1,
+                SummaryWarmup::Generated { title, etag, summary } => {
+                    let tx = writer.begin_transaction()?;
+                    writer.save_summary(&title, &etag, &summary, &tx)?;
+                    crate::db::DatabaseWriter::commit_transaction(tx)?;
+                    report.summarized += 1;
+                }
+            }
+            emit_progress(&self.events, InstallEvent::SummaryProgress { summarized: processed, total: titles.len() });
+            if processed % 10 == 0 || processed == titles.len() {
+                info!("Warmed up summaries of {}/{} articles", processed, titles.len());
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Every title, or the `top` most viewed topped up in title order when fewer have been viewed
+    fn warmup_titles(db_reader: &crate::db::DatabaseReader<'_>, top: Option<usize>) -> WikiResult<Vec<String>> {
+        let top = match top {
+            Some(top) => top,
+            None => return db_reader.list_titles(),
+        };
+        let mut titles: Vec<String> = db_reader.most_viewed(top)?.into_iter().map(|(title, _)| title).collect();
+        if titles.len() < top {
+            let viewed: std::collections::HashSet<String> = titles.iter().cloned().collect();
+            let unviewed = db_reader.list_titles()?.into_iter().filter(|title| !viewed.contains(title));
+            titles.extend(unviewed.take(top - viewed.len()));
+        }
+        Ok(titles)
+    }
+
+    /// Embed and store the given articles, returning how many were embedded.
+    ///
+    /// Up to the store's embedding concurrency requests run at once, but embeddings are stored
+    /// in title order; one waiting to be stored still holds its slot, so a slow writer holds back
+    /// new requests. With a checkpoint, progress is saved after each article so the caller can resume.
+    async fn embed_titles(
+        db_reader: &crate::db::DatabaseReader<'_>,
+        vector_store: &VectorStore,
+        titles: &[String],
+        excluded_sections: &[String],
+        mut checkpoint: Option<(&Path, &mut EmbeddingCheckpoint)>,
+        events: &Option<mpsc::Sender<InstallEvent>>,
+    ) -> WikiResult<usize> {
+        use futures_util::StreamExt;
+
+        let concurrency = vector_store.max_concurrent_embeddings().unwrap_or(1);
+        let mut embeddings = futures_util::stream::iter(titles)
+            .map(|title| {
+                let article = db_reader.get_article(title);
+                async move {
+                    let article = match article? {
+                        Some(article) => article,
+                        None => return Ok((title, None)),
+                    };
+
+                    // Generate embedding for article title and content
+                    let text = embedding_text(&article, excluded_sections);
+                    let embedding = Self::generate_embedding_with_retry(vector_store, &article.title, &text).await?;
+                    Ok::<_, WikiError>((title, Some((article.title, embedding))))
+                }
+            })
+            .buffered(concurrency);
+
+        let mut generated = 0;
+        let mut processed = 0;
+        while let Some(result) = embeddings.next().await {
+            processed += 1;
+            let (title, (article_title, embedding)) = match result? {
+                (title, Some(embedded)) => (title, embedded),
+                (_, None) => continue,
+            };
+
+            // Store embedding with article title as key
+            vector_store.store_embedding_async(&article_title, &embedding).await?;
+            generated += 1;
+            emit_progress(events, InstallEvent::EmbedProgress { embedded: generated, total: titles.len() });
 
-        // Remove data files
-        let wiki_dump_path = self.config.data_dir.join("wiki-dump.xml.bz2");
+            if let Some((path, checkpoint)) = checkpoint.as_mut() {
+                checkpoint.last_title = title.clone();
+                checkpoint.embedded += 1;
+                checkpoint.save(path)?;
+            }
+
+            if processed % 10 == 0 || processed == titles.len() {
+                info!("Generated embeddings for {}/{} articles", processed, titles.len());
+            }
+        }
+
+        Ok(generated)
+    }
+
+    /// Generate an embedding, retrying transient failures with a growing delay
+    async fn generate_embedding_with_retry(vector_store: &VectorStore, title: &str, text: &str) -> WikiResult<Vec<f32>> {
+        let mut attempt = 1;
+        loop {
+            match vector_store.generate_embedding(text).await {
+                Ok(embedding) => return Ok(embedding),
+                Err(e) if attempt < EMBEDDING_RETRIES => {
+                    warn!("Embedding '{}' failed (attempt {}/{}): {}", title, attempt, EMBEDDING_RETRIES, e);
+                    tokio::time::sleep(Duration::from_millis(EMBEDDING_RETRY_DELAY_MILLIS * attempt as u64)).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Remove downloaded files, the database and all install directories
+    async fn remove_files(&self) -> WikiResult<()> {
+        // Remove downloaded dump
+        let wiki_dump_path = self.dump_path();
         if wiki_dump_path.exists() {
             info!("Removing Wikipedia dump file...");
             fs::remove_file(&wiki_dump_path).await?;
@@ -350,6 +1506,17 @@ impl InstallManager {
             fs::remove_dir_all(&self.config.vector_store_dir).await?;
         }
 
+        Ok(())
+    }
+
+    pub async fn uninstall(&self) -> WikiResult<()> {
+        info!("Starting uninstallation...");
+
+        // An Ollama unpacked under data_dir goes with it
+        #[cfg_attr(target_os = "windows", allow(unused_variables))]
+        let local_ollama = self.ollama_dir().exists();
+        self.remove_files().await?;
+
         // Uninstall Ollama
         info!("Uninstalling Ollama...");
         #[cfg(target_os = "windows")]
@@ -366,7 +1533,9 @@ impl InstallManager {
         }
 
         #[cfg(any(target_os = "linux", target_os = "macos"))]
-        {
+        if local_ollama {
+            info!("Removed Ollama with the data directory");
+        } else {
             let status = Command::new("which")
                 .arg("ollama")
                 .output()
@@ -401,7 +1570,7 @@ mod tests {
             ..Default::default()
         };
 
-        let installer = InstallManager::new(config);
+        let installer = InstallManager::new(config)?;
 
         // Test installation
         installer.install().await?;
@@ -424,4 +1593,770 @@ mod tests {
 
         Ok(())
     }
-} 
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_dump_downloads_to_cache_dir() -> WikiResult<()> {
+        let temp_dir = TempDir::new()?;
+        let config = InstallConfig {
+            data_dir: temp_dir.path().join("data"),
+            cache_dir: temp_dir.path().join("cache"),
+            vector_store_dir: temp_dir.path().join("vectors"),
+            ..Default::default()
+        };
+
+        let installer = InstallManager::new(config)?;
+        installer.create_directories().await?;
+
+        let dump_path = installer.dump_path();
+        assert!(dump_path.starts_with(&installer.config.cache_dir));
+        assert!(!dump_path.starts_with(&installer.config.data_dir));
+
+        fs::create_dir_all(dump_path.parent().unwrap()).await?;
+        fs::write(&dump_path, b"dump").await?;
+
+        installer.remove_files().await?;
+        assert!(!dump_path.exists());
+        assert!(!installer.config.cache_dir.exists());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_ollama_succeeds_once_serving() -> WikiResult<()> {
+        let mut server = mockito::Server::new_async().await;
+        let config = InstallConfig {
+            ollama_url: server.url(),
+            ..Default::default()
+        };
+        let installer = InstallManager::new(config)?;
+
+        // Unmatched requests get a 501 until the endpoint is mocked
+        assert!(!installer.is_ollama_serving().await);
+
+        let (ready, mock) = tokio::join!(
+            installer.wait_for_ollama(Duration::from_secs(5)),
+            async {
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                server.mock("GET", "/api/tags")
+                    .with_status(200)
+                    .with_body(r#"{"models":[]}"#)
+                    .create_async()
+                    .await
+            }
+        );
+
+        assert!(ready.is_ok());
+        mock.assert_async().await;
+        Ok(())
+    }
+
+    #[test]
+    fn test_wikidump_url() -> WikiResult<()> {
+        assert_eq!(
+            wikidump_url("en", Some("20240101"))?,
+            "https://dumps.wikimedia.org/enwiki/20240101/enwiki-20240101-pages-articles1.xml.bz2"
+        );
+        assert_eq!(
+            wikidump_url("simple", None)?,
+            "https://dumps.wikimedia.org/simplewiki/latest/simplewiki-latest-pages-articles1.xml.bz2"
+        );
+        assert!(wikidump_url("en", Some("2024-01-01")).is_err());
+        assert!(wikidump_url("en", Some("20241301")).is_err());
+        assert!(wikidump_url("en/../x", None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_ollama_asset_url() -> WikiResult<()> {
+        assert_eq!(
+            ollama_asset_url("0.5.7", "linux", "x86_64")?,
+            "https://github.com/ollama/ollama/releases/download/v0.5.7/ollama-linux-amd64.tgz"
+        );
+        assert_eq!(
+            ollama_asset_url("v0.5.7", "linux", "aarch64")?,
+            "https://github.com/ollama/ollama/releases/download/v0.5.7/ollama-linux-arm64.tgz"
+        );
+        assert!(ollama_asset_url("0.5.7", "windows", "x86_64")?.ends_with("/v0.5.7/OllamaSetup.exe"));
+        assert!(ollama_asset_url("0.5.7", "macos", "aarch64")?.ends_with("/v0.5.7/Ollama-darwin.zip"));
+        assert!(ollama_asset_url("0.5.7", "freebsd", "x86_64").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_user_agent_is_a_configuration_error() {
+        let config = InstallConfig { user_agent: "bad\nagent".to_string(), ..Default::default() };
+        assert!(matches!(InstallManager::new(config), Err(WikiError::Configuration(_))));
+    }
+
+    #[test]
+    fn test_ollama_url_is_normalized_once() -> WikiResult<()> {
+        let config = InstallConfig { ollama_url: "http://localhost:11434/".to_string(), ..Default::default() };
+        assert_eq!(InstallManager::new(config)?.config.ollama_url, "http://localhost:11434");
+
+        let config = InstallConfig { ollama_url: "localhost:11434".to_string(), ..Default::default() };
+        assert!(matches!(InstallManager::new(config), Err(WikiError::Configuration(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_ollama_download_needs_a_known_checksum() {
+        // Another release is only installed against a configured checksum
+        assert!(expected_ollama_sha256("0.6.0", "ollama-linux-amd64.tgz", None).is_err());
+        assert_eq!(
+            expected_ollama_sha256("0.6.0", "ollama-linux-amd64.tgz", Some("ABC123")).unwrap(),
+            "abc123"
+        );
+        for (asset, digest) in OLLAMA_SHA256 {
+            assert_eq!(expected_ollama_sha256(OLLAMA_VERSION, asset, None).unwrap(), *digest);
+        }
+    }
+
+    #[test]
+    fn test_ollama_binary_prefers_the_unpacked_release() -> WikiResult<()> {
+        let temp_dir = TempDir::new()?;
+        let installer = InstallManager::new(InstallConfig {
+            data_dir: temp_dir.path().join("data"),
+            cache_dir: temp_dir.path().join("cache"),
+            vector_store_dir: temp_dir.path().join("vectors"),
+            ..Default::default()
+        })?;
+        assert_eq!(installer.ollama_binary(), PathBuf::from("ollama"));
+
+        let local = if cfg!(target_os = "macos") {
+            installer.ollama_dir().join("Ollama.app/Contents/Resources/ollama")
+        } else {
+            installer.ollama_dir().join("bin").join("ollama")
+        };
+        std::fs::create_dir_all(local.parent().unwrap())?;
+        std::fs::write(&local, b"")?;
+        assert_eq!(installer.ollama_binary(), local);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_install_report_matches_dump() -> WikiResult<()> {
+        use bzip2::write::BzEncoder;
+        use bzip2::Compression;
+        use std::io::Write;
+
+        let temp_dir = TempDir::new()?;
+        let installer = InstallManager::new(InstallConfig {
+            data_dir: temp_dir.path().join("data"),
+            cache_dir: temp_dir.path().join("cache"),
+            vector_store_dir: temp_dir.path().join("vectors"),
+            ..Default::default()
+        })?;
+        installer.create_directories().await?;
+
+        // Three pages, two of which normalize to the same title
+        let xml = r#"<mediawiki>
+            <siteinfo><sitename>Wikipedia</sitename><lang>en</lang></siteinfo>
+            <page><title>Alpha</title><revision><text>Alpha article text.
+[[File:Alpha.png|thumb|The letter]] [[Category:Letters]]</text></revision></page>
+            <page><title>Beta</title><revision><text>Beta article text.
+[[Image:Beta.svg]] [[File:Beta2.jpg]] [[Category:Letters]] [[Category:Greek]]</text></revision></page>
+            <page><title>alpha</title><revision><text>Short.</text></revision></page>
+        </mediawiki>"#;
+        let dump_path = temp_dir.path().join("dump.xml.bz2");
+        let mut encoder = BzEncoder::new(std::fs::File::create(&dump_path)?, Compression::default());
+        encoder.write_all(xml.as_bytes())?;
+        encoder.finish()?;
+
+        let db_path = temp_dir.path().join("data").join("wiki.db");
+        let mut report = InstallReport::default();
+        installer.process_wikidump(&dump_path, &db_path, &mut report).await?;
+
+        assert_eq!(report.articles, 2);
+        assert_eq!(report.skipped, 1);
+        assert_eq!(report.embeddings, 0);
+        assert_eq!(report.categories, 2);
+        assert_eq!(report.images, 3);
+
+        let conn = rusqlite::Connection::open(&db_path)?;
+        let reader = crate::db::DatabaseReader::new(&conn);
+        assert_eq!(reader.list_categories()?.len(), 2);
+        assert_eq!(reader.count_images()?, 3);
+        // The dump has no timestamp and none was requested
+        assert_eq!(reader.get_metadata(schema::DUMP_DATE_KEY)?, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_import_filters_by_category() -> WikiResult<()> {
+        let temp_dir = TempDir::new()?;
+        let installer = InstallManager::new(InstallConfig {
+            data_dir: temp_dir.path().join("data"),
+            cache_dir: temp_dir.path().join("cache"),
+            vector_store_dir: temp_dir.path().join("vectors"),
+            include_categories: vec!["Physics".to_string(), "Chemistry".to_string()],
+            exclude_categories: vec!["Stubs".to_string()],
+            ..Default::default()
+        })?;
+        installer.create_directories().await?;
+
+        let xml = r#"<mediawiki>
+            <siteinfo><sitename>Wikipedia</sitename><lang>en</lang></siteinfo>
+            <page><title>Gravity</title><revision><text>Gravity attracts.
+[[Category:Physics]]</text></revision></page>
+            <page><title>Water</title><revision><text>Water is wet.
+[[Category:chemistry]]</text></revision></page>
+            <page><title>Poetry</title><revision><text>Poetry rhymes.
+[[Category:Literature]]</text></revision></page>
+            <page><title>Muon</title><revision><text>A particle.
+[[Category:Physics]]
+[[Category:Stubs]]</text></revision></page>
+            <page><title>Uncategorized</title><revision><text>No categories here.</text></revision></page>
+        </mediawiki>"#;
+        let dump_path = temp_dir.path().join("dump.xml");
+        std::fs::write(&dump_path, xml)?;
+
+        let db_path = temp_dir.path().join("data").join("wiki.db");
+        let mut report = InstallReport::default();
+        installer.process_wikidump(&dump_path, &db_path, &mut report).await?;
+
+        assert_eq!(report.articles, 2);
+        assert_eq!(report.filtered, 3);
+        assert_eq!(report.skipped, 3);
+
+        let conn = rusqlite::Connection::open(&db_path)?;
+        let titles = crate::db::DatabaseReader::new(&conn).list_titles()?;
+        assert_eq!(titles, vec!["Gravity".to_string(), "Water".to_string()]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_max_articles_counts_inserted_articles() -> WikiResult<()> {
+        let temp_dir = TempDir::new()?;
+        let installer = InstallManager::new(InstallConfig {
+            data_dir: temp_dir.path().join("data"),
+            cache_dir: temp_dir.path().join("cache"),
+            vector_store_dir: temp_dir.path().join("vectors"),
+            exclude_categories: vec!["Stubs".to_string()],
+            max_articles: Some(2),
+            ..Default::default()
+        })?;
+        installer.create_directories().await?;
+
+        // A redirect, a filtered page and a collision loser come first; none count towards the limit
+        let mut xml = String::from(r#"<mediawiki><siteinfo><sitename>Wikipedia</sitename><lang>en</lang></siteinfo>
+            <page><title>Alpha</title><revision><text>Alpha is the first letter.</text></revision></page>
+            <page><title>AlphaLetter</title><redirect title="Alpha" /><revision><text>#REDIRECT [[Alpha]]</text></revision></page>
+            <page><title>Muon</title><revision><text>A particle.
+[[Category:Stubs]]</text></revision></page>
+            <page><title>alpha</title><revision><text>Short.</text></revision></page>"#);
+        // Enough pages to fill the writer channel, so the parser has to stop early
+        let total = 4 + WRITER_CHANNEL_CAPACITY + 2 * BATCH_SIZE;
+        for i in 0..total - 4 {
+            xml.push_str(&format!("<page><title>Page {}</title><revision><text>Page {} text.</text></revision></page>", i, i));
+        }
+        xml.push_str("</mediawiki>");
+        let dump_path = temp_dir.path().join("dump.xml");
+        std::fs::write(&dump_path, xml)?;
+
+        let db_path = temp_dir.path().join("data").join("wiki.db");
+        let mut report = InstallReport::default();
+        installer.process_wikidump(&dump_path, &db_path, &mut report).await?;
+
+        let conn = rusqlite::Connection::open(&db_path)?;
+        let titles = crate::db::DatabaseReader::new(&conn).list_titles()?;
+        // The redirect is still written, it just isn't counted
+        assert_eq!(titles, vec!["Alpha".to_string(), "AlphaLetter".to_string(), "Page 0".to_string()]);
+        assert_eq!(report.filtered, 1);
+        assert!(report.articles + report.skipped < total);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_embedding_text_leaves_out_excluded_sections() {
+        let content = "Lead text.\n== History ==\nHistory text.\n== References ==\nA cited source.\n\
+                       === Primary ===\nA primary source.\n== Legacy ==\nLegacy text.";
+        let article = WikiArticle::new("Topic".to_string(), content.to_string());
+
+        let text = embedding_text(&article, &InstallConfig::default().embedding_excluded_sections);
+        assert!(text.starts_with("Title: Topic\n\nContent: Lead text."));
+        assert!(text.contains("History text.") && text.contains("Legacy text."));
+        assert!(!text.contains("References") && !text.contains("cited source"));
+        // Subsections of an excluded section go with it
+        assert!(!text.contains("primary source"));
+
+        assert_eq!(embedding_text(&article, &[]), format!("Title: Topic\n\nContent: {}", content));
+    }
+
+    #[tokio::test]
+    async fn test_reembed_only_stale() -> WikiResult<()> {
+        let temp_dir = TempDir::new()?;
+        let mut server = mockito::Server::new_async().await;
+        let mock = server.mock("POST", "/api/embeddings")
+            .with_status(200)
+            .with_body(r#"{"data":[{"embedding":[0.5,0.5]}]}"#)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let installer = InstallManager::new(InstallConfig {
+            data_dir: temp_dir.path().join("data"),
+            cache_dir: temp_dir.path().join("cache"),
+            vector_store_dir: temp_dir.path().join("vectors"),
+            ollama_url: server.url(),
+            embedding_model: "new-model".to_string(),
+            ..Default::default()
+        })?;
+        installer.create_directories().await?;
+
+        let conn = rusqlite::Connection::open(temp_dir.path().join("data").join("wiki.db"))?;
+        schema::init_database(&conn)?;
+        let writer = crate::db::DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        for title in ["Alpha", "Beta", "Gamma"] {
+            let mut article = WikiArticle::new(title.to_string(), format!("{} text", title));
+            article.update_size();
+            writer.write_article(&article, &tx)?;
+        }
+        crate::db::DatabaseWriter::commit_transaction(tx)?;
+
+        let store = installer.open_vector_store(3).await?;
+        store.store_embedding_with_model("Alpha", &[1.0, 0.0], "old-model")?;
+        store.store_embedding_with_model("Beta", &[1.0, 0.0], "old-model")?;
+        store.store_embedding("Gamma", &[0.0, 1.0])?;
+
+        assert_eq!(installer.reembed(true).await?, 2);
+        mock.assert_async().await;
+
+        for title in ["Alpha", "Beta", "Gamma"] {
+            assert_eq!(store.embedding_model_of(title)?.as_deref(), Some("new-model"));
+        }
+        assert_eq!(store.get_embedding("Alpha")?, Some(vec![0.5, 0.5]));
+        assert_eq!(store.get_embedding("Gamma")?, Some(vec![0.0, 1.0]));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reembed_limits_embedding_requests_in_flight() -> WikiResult<()> {
+        use std::sync::atomic::AtomicUsize;
+        use warp::Filter;
+
+        let temp_dir = TempDir::new()?;
+
+        // Ollama stand-in that records the most requests it was serving at once
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let embeddings = warp::post().and(warp::path!("api" / "embeddings")).then({
+            let (in_flight, peak) = (in_flight.clone(), peak.clone());
+            move || {
+                let (in_flight, peak) = (in_flight.clone(), peak.clone());
+                async move {
+                    peak.fetch_max(in_flight.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    warp::reply::json(&serde_json::json!({ "data": [{ "embedding": [0.5, 0.5] }] }))
+                }
+            }
+        });
+        let (addr, server) = warp::serve(embeddings).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let installer = InstallManager::new(InstallConfig {
+            data_dir: temp_dir.path().join("data"),
+            cache_dir: temp_dir.path().join("cache"),
+            vector_store_dir: temp_dir.path().join("vectors"),
+            ollama_url: format!("http://{}", addr),
+            embedding_concurrency: 3,
+            ..Default::default()
+        })?;
+        installer.create_directories().await?;
+
+        let conn = rusqlite::Connection::open(temp_dir.path().join("data").join("wiki.db"))?;
+        schema::init_database(&conn)?;
+        let writer = crate::db::DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        for i in 0..12 {
+            let mut article = WikiArticle::new(format!("Article {:02}", i), "Some text".to_string());
+            article.update_size();
+            writer.write_article(&article, &tx)?;
+        }
+        crate::db::DatabaseWriter::commit_transaction(tx)?;
+
+        assert_eq!(installer.reembed(false).await?, 12);
+        let peak = peak.load(Ordering::SeqCst);
+        assert!(peak <= 3, "{} embedding requests were in flight", peak);
+        assert!(peak > 1, "embedding requests never overlapped");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reembed_resumes_from_checkpoint() -> WikiResult<()> {
+        let temp_dir = TempDir::new()?;
+        let mut server = mockito::Server::new_async().await;
+
+        // Ollama serves two embeddings and then fails until restarted
+        let first_run = server.mock("POST", "/api/embeddings")
+            .with_body(r#"{"data":[{"embedding":[1.0,0.0]}]}"#)
+            .expect(2)
+            .create_async()
+            .await;
+        let outage = server.mock("POST", "/api/embeddings")
+            .with_status(500)
+            .create_async()
+            .await;
+
+        let installer = InstallManager::new(InstallConfig {
+            data_dir: temp_dir.path().join("data"),
+            cache_dir: temp_dir.path().join("cache"),
+            vector_store_dir: temp_dir.path().join("vectors"),
+            ollama_url: server.url(),
+            ..Default::default()
+        })?;
+        installer.create_directories().await?;
+
+        let titles = ["Alpha", "Beta", "Delta", "Epsilon", "Gamma"];
+        let conn = rusqlite::Connection::open(temp_dir.path().join("data").join("wiki.db"))?;
+        schema::init_database(&conn)?;
+        let writer = crate::db::DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        for title in titles {
+            let mut article = WikiArticle::new(title.to_string(), format!("{} text", title));
+            article.update_size();
+            writer.write_article(&article, &tx)?;
+        }
+        crate::db::DatabaseWriter::commit_transaction(tx)?;
+
+        assert!(installer.reembed(false).await.is_err());
+        first_run.assert_async().await;
+        let checkpoint = EmbeddingCheckpoint::load(&installer.embedding_checkpoint_path()).unwrap();
+        assert_eq!(checkpoint.last_title, "Beta");
+        assert_eq!(checkpoint.embedded, 2);
+
+        // Once Ollama is back only the remaining three articles are embedded
+        outage.remove_async().await;
+        let second_run = server.mock("POST", "/api/embeddings")
+            .with_body(r#"{"data":[{"embedding":[0.0,1.0]}]}"#)
+            .expect(3)
+            .create_async()
+            .await;
+
+        assert_eq!(installer.reembed(false).await?, 3);
+        second_run.assert_async().await;
+        assert!(!installer.embedding_checkpoint_path().exists());
+
+        let store = installer.open_vector_store(titles.len()).await?;
+        assert_eq!(store.get_embedding("Beta")?, Some(vec![1.0, 0.0]));
+        for title in ["Delta", "Epsilon", "Gamma"] {
+            assert_eq!(store.get_embedding(title)?, Some(vec![0.0, 1.0]));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_plans_without_writing() -> WikiResult<()> {
+        use bzip2::write::BzEncoder;
+        use bzip2::Compression;
+        use std::io::Write;
+
+        let temp_dir = TempDir::new()?;
+        let installer = InstallManager::new(InstallConfig {
+            data_dir: temp_dir.path().join("data"),
+            cache_dir: temp_dir.path().join("cache"),
+            vector_store_dir: temp_dir.path().join("vectors"),
+            ..Default::default()
+        })?;
+
+        let mut xml = String::from("<mediawiki><siteinfo><sitename>Wikipedia</sitename></siteinfo>");
+        for i in 0..25 {
+            xml.push_str(&format!("<page><title>Page {}</title><text>Text {}</text></page>", i, i));
+        }
+        xml.push_str("</mediawiki>");
+
+        let dump_path = installer.dump_path();
+        std::fs::create_dir_all(dump_path.parent().unwrap())?;
+        let mut encoder = BzEncoder::new(std::fs::File::create(&dump_path)?, Compression::default());
+        encoder.write_all(xml.as_bytes())?;
+        encoder.finish()?;
+
+        let plan = installer.plan().await?;
+        assert_eq!(plan.dump_source, dump_path.display().to_string());
+        assert_eq!(plan.dump_size, Some(std::fs::metadata(&dump_path)?.len()));
+        assert_eq!(plan.estimated_articles, Some(25));
+        assert!(plan.estimated_disk_bytes.unwrap() > 0);
+        assert!(plan.to_string().contains("Estimated articles: 25"));
+
+        assert!(!temp_dir.path().join("data").exists());
+        assert!(!temp_dir.path().join("vectors").exists());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_import_plain_gzip_and_bz2_dumps() -> WikiResult<()> {
+        use std::io::Write;
+
+        let temp_dir = TempDir::new()?;
+        let xml = r#"<mediawiki>
+            <siteinfo><sitename>Wikipedia</sitename><lang>en</lang></siteinfo>
+            <page><title>Alpha</title><revision><text>Alpha text.</text></revision></page>
+            <page><title>Beta</title><revision><text>Beta text.</text></revision></page>
+        </mediawiki>"#;
+
+        let plain = temp_dir.path().join("dump.xml");
+        std::fs::write(&plain, xml)?;
+
+        let gzip = temp_dir.path().join("dump.xml.gz");
+        let mut encoder = flate2::write::GzEncoder::new(std::fs::File::create(&gzip)?, flate2::Compression::default());
+        encoder.write_all(xml.as_bytes())?;
+        encoder.finish()?;
+
+        let bzip2 = temp_dir.path().join("dump.xml.bz2");
+        let mut encoder = bzip2::write::BzEncoder::new(std::fs::File::create(&bzip2)?, bzip2::Compression::default());
+        encoder.write_all(xml.as_bytes())?;
+        encoder.finish()?;
+
+        for (name, dump) in [("plain", &plain), ("gzip", &gzip), ("bzip2", &bzip2)] {
+            let installer = InstallManager::new(InstallConfig {
+                data_dir: temp_dir.path().join(name),
+                cache_dir: temp_dir.path().join(name).join("cache"),
+                vector_store_dir: temp_dir.path().join(name).join("vectors"),
+                ..Default::default()
+            })?;
+            installer.create_directories().await?;
+            let db_path = temp_dir.path().join(name).join("wiki.db");
+            let mut report = InstallReport::default();
+            installer.process_wikidump(dump, &db_path, &mut report).await?;
+            assert_eq!(report.articles, 2, "{} dump", name);
+
+            let conn = rusqlite::Connection::open(&db_path)?;
+            let reader = crate::db::DatabaseReader::new(&conn);
+            assert_eq!(reader.list_titles()?, vec!["Alpha".to_string(), "Beta".to_string()]);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_install_from_reader_detects_compression() -> WikiResult<()> {
+        use std::io::Write;
+
+        let temp_dir = TempDir::new()?;
+        let mut server = mockito::Server::new_async().await;
+        let mock = server.mock("POST", "/api/embeddings")
+            .with_body(r#"{"data":[{"embedding":[1.0,0.0]}]}"#)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let xml = r#"<mediawiki>
+            <siteinfo><sitename>Wikipedia</sitename><lang>en</lang></siteinfo>
+            <page><title>Alpha</title><revision><text>Alpha text.</text></revision></page>
+            <page><title>Beta</title><revision><text>Beta text.</text></revision></page>
+        </mediawiki>"#;
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(xml.as_bytes())?;
+        let piped = encoder.finish()?;
+
+        let installer = InstallManager::new(InstallConfig {
+            data_dir: temp_dir.path().join("data"),
+            cache_dir: temp_dir.path().join("cache"),
+            vector_store_dir: temp_dir.path().join("vectors"),
+            ollama_url: server.url(),
+            ..Default::default()
+        })?;
+        let report = installer.install_from_reader(std::io::Cursor::new(piped)).await?;
+        assert_eq!(report.articles, 2);
+        assert_eq!(report.embeddings, 2);
+        mock.assert_async().await;
+
+        let conn = rusqlite::Connection::open(temp_dir.path().join("data").join("wiki.db"))?;
+        let reader = crate::db::DatabaseReader::new(&conn);
+        assert_eq!(reader.list_titles()?, vec!["Alpha".to_string(), "Beta".to_string()]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_status_serializes_to_json() -> WikiResult<()> {
+        let temp_dir = TempDir::new()?;
+        let mut server = mockito::Server::new_async().await;
+        server.mock("POST", "/api/embeddings")
+            .with_body(r#"{"data":[{"embedding":[1.0,0.0]}]}"#)
+            .create_async()
+            .await;
+        server.mock("GET", "/api/tags")
+            .with_body(r#"{"models":[]}"#)
+            .create_async()
+            .await;
+
+        let installer = InstallManager::new(InstallConfig {
+            data_dir: temp_dir.path().join("data"),
+            cache_dir: temp_dir.path().join("cache"),
+            vector_store_dir: temp_dir.path().join("vectors"),
+            ollama_url: server.url(),
+            dump_date: Some("20240301".to_string()),
+            ..Default::default()
+        })?;
+        let xml = r#"<mediawiki>
+            <siteinfo><sitename>Wikipedia</sitename><lang>en</lang></siteinfo>
+            <page><title>Alpha</title><revision><text>Alpha text.</text></revision></page>
+        </mediawiki>"#;
+        installer.install_from_reader(std::io::Cursor::new(xml.as_bytes().to_vec())).await?;
+
+        let json = serde_json::to_string(&installer.status().await).unwrap();
+        let status: serde_json::Value = serde_json::from_str(&json).unwrap();
+        for key in [
+            "data_dir", "cache_dir", "vector_store_dir", "ollama_url", "data_dir_exists",
+            "vector_store_dir_exists", "database_exists", "ollama_installed", "ollama_serving",
+            "article_count", "schema_version", "dump_date",
+        ] {
+            assert!(status.get(key).is_some(), "missing {}", key);
+        }
+        assert_eq!(status["database_exists"], true);
+        assert_eq!(status["ollama_serving"], true);
+        assert_eq!(status["article_count"], 1);
+        assert_eq!(status["schema_version"], schema::SCHEMA_VERSION);
+        assert_eq!(status["dump_date"], "20240301");
+        assert_eq!(status["database_error"], serde_json::Value::Null);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_warmup_stores_summaries_of_top_articles() -> WikiResult<()> {
+        let temp_dir = TempDir::new()?;
+        let mut server = mockito::Server::new_async().await;
+        let generate = server.mock("POST", "/api/generate")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"model":"llama2","response":"Warm summary."}"#)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let installer = InstallManager::new(InstallConfig {
+            data_dir: temp_dir.path().join("data"),
+            cache_dir: temp_dir.path().join("cache"),
+            vector_store_dir: temp_dir.path().join("vectors"),
+            ollama_url: server.url(),
+            summary_concurrency: 2,
+            ..Default::default()
+        })?;
+        installer.create_directories().await?;
+
+        let conn = rusqlite::Connection::open(temp_dir.path().join("data").join("wiki.db"))?;
+        schema::init_database(&conn)?;
+        let writer = crate::db::DatabaseWriter::new(&conn);
+        let tx = writer.begin_transaction()?;
+        for title in ["Alpha", "Beta", "Gamma"] {
+            writer.write_article(&WikiArticle::new(title.to_string(), format!("{} text.", title)), &tx)?;
+        }
+        crate::db::DatabaseWriter::commit_transaction(tx)?;
+        conn.execute("INSERT INTO view_counts (title, views) VALUES ('Gamma', 9)", [])?;
+
+        // Gamma is the most viewed; Alpha tops up the unviewed rest
+        let report = installer.warmup_summaries(Some(2)).await?;
+        assert_eq!(report, WarmupReport { summarized: 2, already_stored: 0, failed: 0 });
+
+        let llm = LlmService::new(&server.url(), None)?;
+        let reader = crate::db::DatabaseReader::new(&conn);
+        for (title, stored) in [("Alpha", true), ("Beta", false), ("Gamma", true)] {
+            let etag = crate::llm::summary_etag(&format!("{} text.", title), &llm);
+            assert_eq!(reader.get_summary(title, &etag)?.is_some(), stored, "{}", title);
+        }
+
+        // Stored summaries are reused without asking the LLM again
+        let report = installer.warmup_summaries(Some(2)).await?;
+        assert_eq!(report, WarmupReport { summarized: 0, already_stored: 2, failed: 0 });
+        generate.assert_async().await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_install_report_lists_stripped_templates() -> WikiResult<()> {
+        let temp_dir = TempDir::new()?;
+        let mut xml = String::from("<mediawiki><siteinfo><sitename>Wikipedia</sitename></siteinfo>");
+        for i in 0..3 {
+            xml.push_str(&format!(
+                "<page><title>Town {}</title><revision><text>{{{{Infobox settlement|name=Town {}}}}} A town.</text></revision></page>",
+                i, i
+            ));
+        }
+        xml.push_str("</mediawiki>");
+        let dump_path = temp_dir.path().join("dump.xml");
+        std::fs::write(&dump_path, xml)?;
+
+        let installer = InstallManager::new(InstallConfig {
+            data_dir: temp_dir.path().join("data"),
+            cache_dir: temp_dir.path().join("cache"),
+            vector_store_dir: temp_dir.path().join("vectors"),
+            template_report: true,
+            ..Default::default()
+        })?;
+        installer.create_directories().await?;
+        let mut report = InstallReport::default();
+        installer.process_wikidump(&dump_path, &temp_dir.path().join("data").join("wiki.db"), &mut report).await?;
+
+        assert_eq!(report.templates, vec![("Infobox settlement".to_string(), 3)]);
+        assert!(report.to_string().contains("Infobox settlement: 3"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_install_emits_progress_events() -> WikiResult<()> {
+        let temp_dir = TempDir::new()?;
+        let mut server = mockito::Server::new_async().await;
+        server.mock("POST", "/api/embeddings")
+            .with_status(200)
+            .with_body(r#"{"data":[{"embedding":[1.0,0.0]}]}"#)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let xml = r#"<mediawiki>
+            <siteinfo><sitename>Wikipedia</sitename><lang>en</lang></siteinfo>
+            <page><title>Alpha</title><revision><text>Alpha article text.</text></revision></page>
+            <page><title>Beta</title><revision><text>Beta article text.</text></revision></page>
+        </mediawiki>"#;
+        let dump_path = temp_dir.path().join("dump.xml");
+        std::fs::write(&dump_path, xml)?;
+
+        let (sender, mut receiver) = mpsc::channel(64);
+        let installer = InstallManager::new(InstallConfig {
+            data_dir: temp_dir.path().join("data"),
+            cache_dir: temp_dir.path().join("cache"),
+            vector_store_dir: temp_dir.path().join("vectors"),
+            ollama_url: server.url(),
+            ..Default::default()
+        })?
+        .with_events(sender);
+        let report = installer.install_from_file(&dump_path).await?;
+        drop(installer);
+
+        let mut events = Vec::new();
+        while let Some(event) = receiver.recv().await {
+            events.push(event);
+        }
+
+        let phases: Vec<&str> = events.iter()
+            .filter_map(|event| match event {
+                InstallEvent::PhaseComplete { phase, .. } => Some(phase.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(phases, vec!["directories", "import", "embeddings"]);
+        assert_eq!(report.durations.len(), 3);
+
+        let position = |wanted: &InstallEvent| events.iter().position(|event| event == wanted);
+        let parsed = position(&InstallEvent::ParseProgress { parsed: 2 }).expect("final parse progress");
+        let written = position(&InstallEvent::InsertProgress { written: 2 }).expect("insert progress");
+        let embedded = position(&InstallEvent::EmbedProgress { embedded: 2, total: 2 }).expect("embed progress");
+        let import_done = events.iter()
+            .position(|event| matches!(event, InstallEvent::PhaseComplete { phase, .. } if phase == "import"))
+            .unwrap();
+        assert!(parsed < import_done && written < import_done && import_done < embedded);
+
+        Ok(())
+    }
+}
@@ -4,4 +4,12 @@ pub mod parser;
 pub mod db;
 pub mod vector;
 pub mod llm;
-pub mod api; 
\ No newline at end of file
+pub mod api;
+pub mod client;
+pub mod http;
+
+pub use api::{ApiConfig, ApiServer};
+pub use db::{DatabaseManager, DbManager};
+pub use installer::{InstallConfig, InstallManager, Installer, InstallerConfig};
+pub use llm::{LlmClient, LlmService, LLMClient};
+pub use parser::{Article, WikiArticle, WikiDumpParser, WikiXmlParser, XmlParser};
@@ -1,12 +1,41 @@
+use lazy_static::lazy_static;
+use regex::Regex;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use tracing::{debug, info, warn};
+use unicode_segmentation::UnicodeSegmentation;
 
 use crate::error_handling::{WikiError, WikiResult};
 
+pub const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
 const DEFAULT_MODEL: &str = "llama2";
 const DEFAULT_TEMPERATURE: f32 = 0.7;
 const DEFAULT_MAX_TOKENS: usize = 1024;
+/// Default token budget for each chunk of a long article during summarization
+const DEFAULT_SUMMARY_CHUNK_TOKENS: usize = 2048;
+/// Rough number of characters per token used to estimate prompt sizes
+const CHARS_PER_TOKEN: usize = 4;
+/// Stop sequences that keep summaries and answers from running on past the response
+const DEFAULT_STOP_SEQUENCES: &[&str] = &["\nNote:", "\nQuestion:", "\nTitle:", "\nSources:"];
+
+lazy_static! {
+    static ref CITATION_RE: Regex = Regex::new(r"\[(\d+)\]").unwrap();
+}
+
+/// A passage of source text that an answer can cite
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Citation {
+    pub source_title: String,
+    pub snippet: String,
+}
+
+/// An answer along with the source passages it cites
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CitedAnswer {
+    pub answer: String,
+    pub citations: Vec<Citation>,
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 struct GenerationRequest {
@@ -15,6 +44,8 @@ struct GenerationRequest {
     stream: bool,
     max_tokens: Option<usize>,
     temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop: Option<Vec<String>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -23,22 +54,118 @@ struct GenerationResponse {
     response: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct ModelList {
+    models: Vec<ModelInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelInfo {
+    name: String,
+}
+
+#[derive(Clone)]
 pub struct LlmService {
     client: Client,
     ollama_url: String,
     model: String,
+    summary_chunk_tokens: usize,
+    stop_sequences: Vec<String>,
 }
 
+/// Alternative names for the LLM service used across the codebase
+pub type LlmClient = LlmService;
+pub type LLMClient = LlmService;
+
 impl LlmService {
-    pub fn new(ollama_url: &str, model: Option<&str>) -> Self {
-        Self {
-            client: Client::new(),
-            ollama_url: ollama_url.to_string(),
+    /// Service talking to Ollama at `ollama_url`; fails on a URL without an http(s) scheme
+    pub fn new(ollama_url: &str, model: Option<&str>) -> WikiResult<Self> {
+        Ok(Self {
+            client: crate::http::default_client(),
+            ollama_url: crate::http::normalize_ollama_url(ollama_url)?,
             model: model.unwrap_or(DEFAULT_MODEL).to_string(),
+            summary_chunk_tokens: DEFAULT_SUMMARY_CHUNK_TOKENS,
+            stop_sequences: DEFAULT_STOP_SEQUENCES.iter().map(|s| s.to_string()).collect(),
+        })
+    }
+
+    /// Identify requests to Ollama with this User-Agent
+    pub fn with_user_agent(mut self, user_agent: &str) -> WikiResult<Self> {
+        self.client = crate::http::client_with_user_agent(user_agent)?;
+        Ok(self)
+    }
+
+    /// Override the stop sequences used for summaries and answers
+    pub fn with_stop_sequences(mut self, stop_sequences: Vec<String>) -> Self {
+        self.stop_sequences = stop_sequences;
+        self
+    }
+
+    /// Set the token budget for each chunk when summarizing long articles
+    pub fn with_summary_chunk_tokens(mut self, tokens: usize) -> Self {
+        self.summary_chunk_tokens = tokens.max(1);
+        self
+    }
+
+    /// Model used for generation
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// A copy of this service that generates with `model` instead
+    pub fn for_model(&self, model: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            ..self.clone()
         }
     }
 
+    /// Names of the models available on the Ollama server, e.g. `llama2:latest`
+    pub async fn list_models(&self) -> WikiResult<Vec<String>> {
+        let response = self.client
+            .get(format!("{}/api/tags", self.ollama_url))
+            .send()
+            .await
+            .map_err(|e| WikiError::OperationFailed(format!("Failed to list LLM models: {}", e)))?
+            .json::<ModelList>()
+            .await
+            .map_err(|e| WikiError::OperationFailed(format!("Failed to parse LLM model list: {}", e)))?;
+
+        Ok(response.models.into_iter().map(|model| model.name).collect())
+    }
+
+    /// Whether `model` is available, treating an untagged name as `:latest`
+    pub async fn has_model(&self, model: &str) -> WikiResult<bool> {
+        Ok(self.list_models().await?.iter().any(|name| {
+            name == model || name.strip_suffix(":latest") == Some(model)
+        }))
+    }
+
+    /// Describes every setting that changes a generated summary, for use in cache keys
+    pub fn summary_options_key(&self) -> String {
+        format!(
+            "model={};chunk_tokens={};stop={}",
+            self.model,
+            self.summary_chunk_tokens,
+            self.stop_sequences.join("\u{1f}")
+        )
+    }
+
     pub async fn generate_text(&self, prompt: &str) -> WikiResult<String> {
+        self.generate(prompt, None).await
+    }
+
+    /// Generate text that ends at the first of the configured stop sequences
+    async fn generate_with_stops(&self, prompt: &str) -> WikiResult<String> {
+        let stop = if self.stop_sequences.is_empty() {
+            None
+        } else {
+            Some(self.stop_sequences.clone())
+        };
+        self.generate(prompt, stop).await
+    }
+
+    async fn generate(&self, prompt: &str, stop: Option<Vec<String>>) -> WikiResult<String> {
         info!("Generating text with model: {}", self.model);
         debug!("Prompt: {}", prompt);
 
@@ -48,6 +175,7 @@ impl LlmService {
             stream: false,
             max_tokens: Some(1024),
             temperature: Some(0.7),
+            stop,
         };
 
         let response = self.client
@@ -64,23 +192,126 @@ impl LlmService {
         Ok(response.response)
     }
 
+    /// Summarize an article, using map-reduce over chunks when it exceeds the chunk budget
     pub async fn summarize_article(&self, title: &str, content: &str) -> WikiResult<String> {
+        let chunks = split_into_chunks(content, self.summary_chunk_tokens);
+        if chunks.len() <= 1 {
+            let prompt = format!(
+                "Please provide a concise summary of the following Wikipedia article:\n\nTitle: {}\n\n{}",
+                title, content
+            );
+            return self.generate_with_stops(&prompt).await;
+        }
+
+        info!("Summarizing '{}' in {} chunks", title, chunks.len());
+
+        // Map: summarize each chunk independently
+        let mut partial_summaries = Vec::with_capacity(chunks.len());
+        for (i, chunk) in chunks.iter().enumerate() {
+            let prompt = format!(
+                "Please provide a concise summary of part {} of {} of the following Wikipedia article:\n\nTitle: {}\n\n{}",
+                i + 1, chunks.len(), title, chunk
+            );
+            partial_summaries.push(self.generate_with_stops(&prompt).await?);
+        }
+
+        // Reduce: merge the partial summaries into one
         let prompt = format!(
-            "Please provide a concise summary of the following Wikipedia article:\n\nTitle: {}\n\n{}",
-            title, content
+            "The following are summaries of consecutive parts of the Wikipedia article \"{}\". \
+             Please combine them into a single concise summary:\n\n{}",
+            title,
+            partial_summaries.join("\n\n")
         );
-        self.generate_text(&prompt).await
+        self.generate_with_stops(&prompt).await
     }
 
-    pub async fn answer_question(&self, article_title: &str, article_content: &str, question: &str) -> WikiResult<String> {
+    /// Answer a question about an article, citing the sentences that support the answer
+    pub async fn answer_question(&self, article_title: &str, article_content: &str, question: &str) -> WikiResult<CitedAnswer> {
+        let sources: Vec<Citation> = article_content
+            .unicode_sentences()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|sentence| Citation {
+                source_title: article_title.to_string(),
+                snippet: sentence.to_string(),
+            })
+            .collect();
+
+        self.answer_with_sources(question, &sources).await
+    }
+
+    /// Answer a question from numbered source passages and map the cited numbers back to them
+    pub async fn answer_with_sources(&self, question: &str, sources: &[Citation]) -> WikiResult<CitedAnswer> {
+        let numbered_sources = sources
+            .iter()
+            .enumerate()
+            .map(|(i, source)| format!("[{}] ({}) {}", i + 1, source.source_title, source.snippet))
+            .collect::<Vec<_>>()
+            .join("\n");
+
         let prompt = format!(
-            "Based on the following Wikipedia article, please answer the question.\n\nArticle Title: {}\n\nArticle Content: {}\n\nQuestion: {}\n\nAnswer:",
-            article_title, article_content, question
+            "Answer the question using only the numbered sources below. \
+             After each statement, cite the sources that support it by their number in square brackets, e.g. [1].\n\n\
+             Sources:\n{}\n\nQuestion: {}\n\nAnswer:",
+            numbered_sources, question
         );
-        self.generate_text(&prompt).await
+
+        let answer = self.generate_with_stops(&prompt).await?;
+        let citations = parse_citations(&answer, sources);
+
+        Ok(CitedAnswer { answer, citations })
     }
 }
 
+/// Map `[n]` markers in an answer to the corresponding sources, in order of first use
+fn parse_citations(answer: &str, sources: &[Citation]) -> Vec<Citation> {
+    let mut seen = Vec::new();
+    for caps in CITATION_RE.captures_iter(answer) {
+        match caps[1].parse::<usize>() {
+            Ok(n) if n >= 1 && n <= sources.len() => {
+                if !seen.contains(&n) {
+                    seen.push(n);
+                }
+            }
+            _ => warn!("Ignoring citation to unknown source {}", &caps[0]),
+        }
+    }
+
+    seen.into_iter().map(|n| sources[n - 1].clone()).collect()
+}
+
+/// Split text on word boundaries into chunks of roughly `max_tokens` tokens
+fn split_into_chunks(text: &str, max_tokens: usize) -> Vec<String> {
+    let max_chars = max_tokens.saturating_mul(CHARS_PER_TOKEN).max(1);
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+
+    for word in text.split_whitespace() {
+        if !current.is_empty() && current.len() + 1 + word.len() > max_chars {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+
+    chunks
+}
+
+/// Strong ETag for a summary of `content` generated with the options of `llm`
+pub fn summary_etag(content: &str, llm: &LlmService) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(content.as_bytes());
+    hasher.update([0u8]);
+    hasher.update(llm.summary_options_key().as_bytes());
+    format!("\"{:x}\"", hasher.finalize())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -94,7 +325,7 @@ mod tests {
             .with_body(r#"{"model":"llama2","response":"This is a test response."}"#)
             .create();
 
-        let llm = LlmService::new(&server_url(), Some("llama2"));
+        let llm = LlmService::new(&server_url(), Some("llama2"))?;
         let response = llm.generate_text("Test prompt").await?;
 
         assert_eq!(response, "This is a test response.");
@@ -102,4 +333,90 @@ mod tests {
 
         Ok(())
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_split_into_chunks() {
+        let text = "one two three four five six";
+        let chunks = split_into_chunks(text, 3); // ~12 characters per chunk
+        assert_eq!(chunks, vec!["one two", "three four", "five six"]);
+        assert_eq!(split_into_chunks(text, 1000), vec![text.to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_summarize_long_article_uses_map_reduce() -> WikiResult<()> {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server.mock("POST", "/api/generate")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"model":"llama2","response":"Summary."}"#)
+            .expect(3)
+            .create_async()
+            .await;
+
+        let llm = LlmService::new(&server.url(), Some("llama2"))?.with_summary_chunk_tokens(10);
+        let content = "word ".repeat(16); // 80 characters, two chunks of ~40
+        let summary = llm.summarize_article("Long Article", &content).await?;
+
+        assert_eq!(summary, "Summary.");
+        mock.assert_async().await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_answer_question_with_citations() -> WikiResult<()> {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server.mock("POST", "/api/generate")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"model":"llama2","response":"Paris is the capital [2]. It lies on the Seine [3][2]. See also [7]."}"#)
+            .create_async()
+            .await;
+
+        let llm = LlmService::new(&server.url(), Some("llama2"))?;
+        let content = "France is a country in Europe. Its capital is Paris. Paris lies on the Seine.";
+        let answer = llm.answer_question("France", content, "What is the capital of France?").await?;
+
+        assert!(answer.answer.starts_with("Paris is the capital"));
+        assert_eq!(answer.citations, vec![
+            Citation { source_title: "France".to_string(), snippet: "Its capital is Paris.".to_string() },
+            Citation { source_title: "France".to_string(), snippet: "Paris lies on the Seine.".to_string() },
+        ]);
+        mock.assert_async().await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_summary_request_sets_stop_sequences() -> WikiResult<()> {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server.mock("POST", "/api/generate")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "stop": ["\nNote:", "\nQuestion:", "\nTitle:", "\nSources:"]
+            })))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"model":"llama2","response":"Summary."}"#)
+            .create_async()
+            .await;
+
+        let llm = LlmService::new(&server.url(), Some("llama2"))?;
+        llm.summarize_article("Test", "Short article.").await?;
+        mock.assert_async().await;
+
+        let mock = server.mock("POST", "/api/generate")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({ "stop": ["END"] })))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"model":"llama2","response":"Summary."}"#)
+            .create_async()
+            .await;
+
+        let llm = LlmService::new(&server.url(), Some("llama2"))?
+            .with_stop_sequences(vec!["END".to_string()]);
+        llm.summarize_article("Test", "Short article.").await?;
+        mock.assert_async().await;
+
+        Ok(())
+    }
+}
@@ -1,11 +1,14 @@
 use rusqlite::Connection;
 use std::path::PathBuf;
-use tracing::{info, error};
+use tracing::{info, warn, error};
 use tokio;
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use davinci3_wiki::{
-    error_handling::WikiResult,
-    installer::{InstallConfig, InstallManager},
+    error_handling::{
+        init_production_logging_with_format, init_stderr_logging_with_format, LogFormat, WikiError, WikiResult,
+    },
+    db::{export_archive_to_path, DatabaseManager},
+    installer::{wikidump_url, InstallConfig, InstallEvent, InstallManager},
 };
 
 use crate::db::{init_database, DatabaseReader, DatabaseWriter};
@@ -22,6 +25,10 @@ mod image;
 mod vector;
 mod llm;
 mod installer;
+mod http;
+
+/// Install events buffered for the progress renderer before progress updates are dropped
+const PROGRESS_CHANNEL_CAPACITY: usize = 256;
 
 /// Davinci3 Wiki - An offline Wikipedia system with semantic search and LLM integration
 #[derive(Parser)]
@@ -31,6 +38,14 @@ struct Cli {
     #[arg(short, long, value_name = "DIRECTORY")]
     config_dir: Option<String>,
 
+    /// Log output format: pretty, compact or json (defaults to $DAVINCI3_LOG_FORMAT)
+    #[arg(long, value_name = "FORMAT")]
+    log_format: Option<LogFormat>,
+
+    /// User-Agent sent with outbound HTTP requests (Wikimedia asks for contact details)
+    #[arg(long, value_name = "AGENT")]
+    user_agent: Option<String>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -62,6 +77,42 @@ enum Commands {
         /// Custom Ollama URL
         #[arg(long, value_name = "URL")]
         ollama_url: Option<String>,
+        
+        /// Only import the first N articles of the dump
+        #[arg(long, value_name = "N")]
+        max_articles: Option<usize>,
+        
+        /// Print what would be installed and exit without writing anything
+        #[arg(long)]
+        dry_run: bool,
+        
+        /// Report the most common templates stripped from article text
+        #[arg(long)]
+        template_report: bool,
+        
+        /// Install the dump published on this date (YYYYMMDD) instead of the latest
+        #[arg(long, value_name = "YYYYMMDD")]
+        dump_date: Option<String>,
+        
+        /// Wikipedia language code of the dump (e.g. simple, en)
+        #[arg(long, value_name = "CODE")]
+        dump_language: Option<String>,
+        
+        /// Only import articles in this category (repeatable)
+        #[arg(long = "include-category", value_name = "CATEGORY")]
+        include_categories: Vec<String>,
+        
+        /// Skip articles in this category (repeatable, wins over --include-category)
+        #[arg(long = "exclude-category", value_name = "CATEGORY")]
+        exclude_categories: Vec<String>,
+        
+        /// Print progress and the final report as JSON lines instead of text
+        #[arg(long)]
+        progress_json: bool,
+        
+        /// Read the dump (plain, gzip or bz2 XML) from stdin instead of downloading it
+        #[arg(long)]
+        stdin: bool,
     },
     
     /// Update the system with latest Wikipedia dump
@@ -73,6 +124,10 @@ enum Commands {
         /// Skip generating embeddings
         #[arg(long)]
         skip_embeddings: bool,
+        
+        /// Print what would be updated and exit without writing anything
+        #[arg(long)]
+        dry_run: bool,
     },
     
     /// Uninstall the Davinci3 Wiki system
@@ -93,17 +148,83 @@ enum Commands {
         host: String,
     },
     
+    /// Regenerate article embeddings
+    Reembed {
+        /// Only regenerate vectors made with a different embedding model
+        #[arg(long)]
+        only_stale: bool,
+
+        /// Embedding model to use
+        #[arg(long, value_name = "MODEL")]
+        model: Option<String>,
+
+        /// Most embedding requests sent to Ollama at once
+        #[arg(long, value_name = "N")]
+        concurrency: Option<usize>,
+    },
+    
+    /// Export the corpus for migration
+    Export {
+        /// Export format
+        #[arg(long, value_enum, default_value_t = ExportFormat::Archive)]
+        format: ExportFormat,
+
+        /// File to write
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+    },
+    
+    /// Rebuild the full-text search index from the stored articles
+    RebuildIndex,
+    
+    /// Merge the search index, vacuum the database and compact the vector store
+    Optimize,
+    
+    /// Generate ahead of time what the server would otherwise compute on first request
+    Warmup {
+        /// Generate and store article summaries
+        #[arg(long)]
+        summaries: bool,
+
+        /// Only the N most viewed articles (topped up in title order) instead of all
+        #[arg(long, value_name = "N")]
+        top: Option<usize>,
+
+        /// Most summary requests sent to Ollama at once
+        #[arg(long, value_name = "N")]
+        concurrency: Option<usize>,
+    },
+    
     /// Show status information about the installation
-    Status,
+    Status {
+        /// Print the status as a single JSON object
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    /// Gzip-compressed JSON lines of all articles, categories and redirects
+    Archive,
 }
 
 #[tokio::main]
 async fn main() -> WikiResult<()> {
-    // Initialize logging
-    tracing_subscriber::fmt::init();
-
     // Parse command line arguments
     let cli = Cli::parse();
+
+    // Initialize logging; JSON status and progress own stdout, so their logs go to stderr
+    let log_format = cli.log_format.unwrap_or_else(LogFormat::from_env);
+    let json_on_stdout = matches!(
+        cli.command,
+        Some(Commands::Status { json: true }) | Some(Commands::Install { progress_json: true, .. })
+    );
+    if json_on_stdout {
+        init_stderr_logging_with_format(log_format)?;
+    } else {
+        init_production_logging_with_format(log_format)?;
+    }
     
     // Create default config
     let mut config = InstallConfig::default();
@@ -115,11 +236,15 @@ async fn main() -> WikiResult<()> {
         config.cache_dir = base_path.join("cache");
         config.vector_store_dir = base_path.join("vectors");
     }
+
+    if let Some(user_agent) = cli.user_agent {
+        config.user_agent = user_agent;
+    }
     
     info!("Starting Davinci3 Wiki...");
     
-    // Create installer
-    let installer = InstallManager::new(config);
+    // Create installer; subcommands that adjust the config build their own from the copy kept here
+    let installer = InstallManager::new(config.clone())?;
     
     // Handle commands
     match cli.command {
@@ -130,6 +255,15 @@ async fn main() -> WikiResult<()> {
             cache_dir,
             vector_dir,
             ollama_url,
+            max_articles,
+            dry_run,
+            template_report,
+            dump_date,
+            dump_language,
+            include_categories,
+            exclude_categories,
+            progress_json,
+            stdin,
         }) => {
             info!("Installing Davinci3 Wiki...");
             
@@ -146,17 +280,67 @@ async fn main() -> WikiResult<()> {
             if let Some(url) = ollama_url {
                 config.ollama_url = url;
             }
+            config.max_articles = max_articles;
+            config.template_report = template_report;
+            if let Some(language) = dump_language {
+                config.dump_language = language;
+            }
+            config.dump_date = dump_date;
+            config.include_categories = include_categories;
+            config.exclude_categories = exclude_categories;
+            // Reject a malformed date or language before doing any work
+            wikidump_url(&config.dump_language, config.dump_date.as_deref())?;
+            
+            if dry_run {
+                println!("{}", InstallManager::new(config)?.plan().await?);
+                return Ok(());
+            }
+            
+            // Render progress as the installer reports it
+            let (events, mut receiver) = tokio::sync::mpsc::channel::<InstallEvent>(PROGRESS_CHANNEL_CAPACITY);
+            let renderer = tokio::spawn(async move {
+                while let Some(event) = receiver.recv().await {
+                    if progress_json {
+                        match serde_json::to_string(&event) {
+                            Ok(line) => println!("{}", line),
+                            Err(e) => warn!("Failed to encode install event: {}", e),
+                        }
+                    } else {
+                        println!("{}", event);
+                    }
+                }
+            });
             
             // Create installer with updated config
-            let installer = InstallManager::new(config);
+            let installer = InstallManager::new(config)?.with_events(events);
             
             // Run installation
             // For now, the skip flags are not used, but they can be implemented in the installer
-            installer.install().await?;
+            let report = if stdin {
+                installer.install_from_reader(std::io::stdin()).await
+            } else {
+                installer.install().await
+            };
+            // Dropping the installer closes the channel so the renderer drains and stops
+            drop(installer);
+            let _ = renderer.await;
+            let report = report?;
             info!("Installation completed successfully!");
+            if progress_json {
+                let report = serde_json::to_string(&report)
+                    .map_err(|e| WikiError::OperationFailed(format!("Failed to encode install report: {}", e)))?;
+                println!("{}", report);
+            } else {
+                println!("{}", report);
+            }
         },
         
-        Some(Commands::Update { skip_download, skip_embeddings }) => {
+        Some(Commands::Update { skip_download, skip_embeddings, dry_run }) => {
+            if dry_run {
+                println!("{}", installer.plan().await?);
+                return Ok(());
+            }
+            
             info!("Updating Davinci3 Wiki...");
             // TODO: Implement update functionality
             installer.install().await?;
@@ -190,43 +374,68 @@ async fn main() -> WikiResult<()> {
             info!("Shutting down server...");
         },
         
-        Some(Commands::Status) => {
-            info!("Checking Davinci3 Wiki status...");
-            
-            // Check if directories exist
-            println!("Configuration:");
-            println!(" - Data directory: {}", config.data_dir.display());
-            println!(" - Cache directory: {}", config.cache_dir.display());
-            println!(" - Vector store directory: {}", config.vector_store_dir.display());
-            println!(" - Ollama URL: {}", config.ollama_url);
-            
-            println!("\nInstallation Status:");
-            println!(" - Data directory exists: {}", config.data_dir.exists());
-            println!(" - Cache directory exists: {}", config.cache_dir.exists());
-            println!(" - Vector store directory exists: {}", config.vector_store_dir.exists());
-            
-            // Check if database exists
-            let db_path = config.data_dir.join("wiki.db");
-            println!(" - Database exists: {}", db_path.exists());
-            
-            // Check if Ollama is installed
-            let ollama_installed = installer.check_ollama_installed().await?;
-            println!(" - Ollama installed: {}", ollama_installed);
-            
-            // Check number of articles if database exists
-            if db_path.exists() {
-                match Connection::open(&db_path) {
-                    Ok(conn) => {
-                        match conn.query_row("SELECT COUNT(*) FROM articles", [], |row| row.get::<_, i64>(0)) {
-                            Ok(count) => println!(" - Articles count: {}", count),
-                            Err(e) => println!(" - Error counting articles: {}", e),
-                        }
-                    },
-                    Err(e) => println!(" - Error opening database: {}", e),
+        Some(Commands::Reembed { only_stale, model, concurrency }) => {
+            if let Some(model) = model {
+                config.embedding_model = model;
+            }
+            if let Some(concurrency) = concurrency {
+                config.embedding_concurrency = concurrency;
+            }
+            let installer = InstallManager::new(config)?;
+            let count = installer.reembed(only_stale).await?;
+            info!("Re-embedded {} articles", count);
+        },
+        
+        Some(Commands::Export { format, output }) => {
+            let conn = Connection::open(config.data_dir.join("wiki.db"))?;
+            match format {
+                ExportFormat::Archive => {
+                    let report = export_archive_to_path(&conn, &output)?;
+                    println!(
+                        "Exported {} articles, {} categories and {} redirects to {}",
+                        report.articles, report.categories, report.redirects, output.display()
+                    );
                 }
             }
         },
         
+        Some(Commands::RebuildIndex) => {
+            let db_path = config.data_dir.join("wiki.db");
+            let manager = DatabaseManager::new(&db_path.to_string_lossy())?;
+            let count = manager.rebuild_fts()?;
+            println!("Rebuilt the search index over {} articles", count);
+        },
+        
+        Some(Commands::Optimize) => {
+            let report = installer.optimize().await?;
+            println!("{}", report);
+        },
+        
+        Some(Commands::Warmup { summaries, top, concurrency }) => {
+            if !summaries {
+                println!("Nothing to warm up; pass --summaries to generate article summaries");
+                return Ok(());
+            }
+            if let Some(concurrency) = concurrency {
+                config.summary_concurrency = concurrency;
+            }
+            let installer = InstallManager::new(config)?;
+            let report = installer.warmup_summaries(top).await?;
+            println!("{}", report);
+        },
+        
+        Some(Commands::Status { json }) => {
+            info!("Checking Davinci3 Wiki status...");
+            let status = installer.status().await;
+            if json {
+                let status = serde_json::to_string(&status)
+                    .map_err(|e| WikiError::OperationFailed(format!("Failed to encode status: {}", e)))?;
+                println!("{}", status);
+            } else {
+                println!("{}", status);
+            }
+        },
+        
         None => {
             // Default behavior if no command is provided
             println!("Davinci3 Wiki - An offline Wikipedia system with semantic search and LLM integration");
@@ -1,16 +1,95 @@
 pub mod models;
+mod pipeline;
 mod text;
 mod xml;
 
 use std::fs::File;
-use std::io::{self, BufReader, BufWriter};
+use std::io::{self, BufReader, BufWriter, Read};
 use std::path::Path;
 use flate2::read::GzDecoder;
 use crate::error_handling::{WikiError, WikiResult};
 use tracing::{info, debug, error};
 
-pub use models::{WikiArticle, WikiCategory, WikiDumpMetadata, WikiImage};
-pub use xml::WikiXmlParser;
+pub use models::{TemplateReport, WikiArticle, WikiArticleBuilder, WikiCategory, WikiDumpMetadata, WikiImage, WikiLangLink, WikiReference, WikiSection};
+pub use text::{clean_wiki_text, CleanOptions, CleanStep};
+pub use xml::{WikiXmlParser, ARTICLE_NAMESPACE};
+pub use pipeline::{DecompressedStream, DECOMPRESS_CHANNEL_CAPACITY, DECOMPRESS_CHUNK_BYTES};
+
+/// Leading bytes that identify a compressed dump
+const MAGIC_BYTES: usize = 3;
+
+/// Alternative names for the dump parser used across the codebase
+pub type XmlParser = WikiXmlParser;
+pub type WikiDumpParser = WikiXmlParser;
+/// Shorthand for [`WikiArticle`]
+pub type Article = WikiArticle;
+
+/// Compression used by a dump file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpFormat {
+    Plain,
+    Gzip,
+    Bzip2,
+}
+
+impl DumpFormat {
+    /// Detect the format from the file's magic bytes, falling back to its extension
+    pub fn detect<P: AsRef<Path>>(path: P) -> WikiResult<Self> {
+        let path = path.as_ref();
+        let mut magic = [0u8; MAGIC_BYTES];
+        let read = File::open(path)?.read(&mut magic)?;
+
+        if let Some(format) = Self::from_magic(&magic[..read]) {
+            return Ok(format);
+        }
+
+        Ok(match path.extension().and_then(|e| e.to_str()) {
+            Some("bz2") => DumpFormat::Bzip2,
+            Some("gz") => DumpFormat::Gzip,
+            _ => DumpFormat::Plain,
+        })
+    }
+
+    /// The compression announced by the first bytes of a dump, if any
+    pub fn from_magic(magic: &[u8]) -> Option<Self> {
+        if magic.starts_with(b"BZh") {
+            Some(DumpFormat::Bzip2)
+        } else if magic.starts_with(&[0x1f, 0x8b]) {
+            Some(DumpFormat::Gzip)
+        } else {
+            None
+        }
+    }
+
+    /// Wrap a reader over the raw file with the matching decoder
+    pub fn decoder<R: Read + Send + 'static>(self, reader: R) -> Box<dyn Read + Send> {
+        match self {
+            DumpFormat::Plain => Box::new(reader),
+            DumpFormat::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+            // Wikimedia publishes multistream bz2 dumps
+            DumpFormat::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(reader)),
+        }
+    }
+}
+
+/// Open a dump file and return a reader over its decompressed XML
+pub fn open_dump<P: AsRef<Path>>(path: P) -> WikiResult<Box<dyn Read + Send>> {
+    let format = DumpFormat::detect(&path)?;
+    debug!("Opening {} as {:?}", path.as_ref().display(), format);
+    Ok(format.decoder(BufReader::new(File::open(path)?)))
+}
+
+/// Wrap a reader over a raw dump (e.g. stdin) with the decoder its first bytes call for;
+/// anything that is not gzip or bz2 is read as plain XML
+pub fn open_dump_reader<R: Read + Send + 'static>(reader: R) -> WikiResult<Box<dyn Read + Send>> {
+    let mut reader = BufReader::new(reader);
+    // A pipe may hand over fewer bytes than the magic needs, so read until it is complete or the stream ends
+    let mut magic = Vec::with_capacity(MAGIC_BYTES);
+    (&mut reader).take(MAGIC_BYTES as u64).read_to_end(&mut magic)?;
+    let format = DumpFormat::from_magic(&magic).unwrap_or(DumpFormat::Plain);
+    debug!("Reading dump stream as {:?}", format);
+    Ok(format.decoder(io::Cursor::new(magic).chain(reader)))
+}
 
 /// Extract a gzipped file to a destination path
 pub fn extract_gzip<P: AsRef<Path>>(source: P, dest: P) -> WikiResult<()> {
@@ -47,6 +126,7 @@ pub fn extract_gzip<P: AsRef<Path>>(source: P, dest: P) -> WikiResult<()> {
 mod tests {
     use super::*;
     use std::fs;
+    use std::io::Write;
     use tempfile::tempdir;
 
     #[test]
@@ -70,4 +150,58 @@ mod tests {
         let content = fs::read_to_string(&dest).unwrap();
         assert_eq!(content, "<test>content</test>");
     }
+
+    #[test]
+    fn test_detect_dump_format() {
+        let dir = tempdir().unwrap();
+        let xml = b"<mediawiki></mediawiki>";
+
+        let plain = dir.path().join("dump.xml");
+        fs::write(&plain, xml).unwrap();
+
+        let gzip = dir.path().join("dump.xml.gz");
+        let mut encoder = flate2::write::GzEncoder::new(File::create(&gzip).unwrap(), flate2::Compression::default());
+        encoder.write_all(xml).unwrap();
+        encoder.finish().unwrap();
+
+        // Magic bytes win over a misleading extension
+        let bzip2 = dir.path().join("dump.bin");
+        let mut encoder = bzip2::write::BzEncoder::new(File::create(&bzip2).unwrap(), bzip2::Compression::default());
+        encoder.write_all(xml).unwrap();
+        encoder.finish().unwrap();
+
+        assert_eq!(DumpFormat::detect(&plain).unwrap(), DumpFormat::Plain);
+        assert_eq!(DumpFormat::detect(&gzip).unwrap(), DumpFormat::Gzip);
+        assert_eq!(DumpFormat::detect(&bzip2).unwrap(), DumpFormat::Bzip2);
+
+        for path in [&plain, &gzip, &bzip2] {
+            let mut content = String::new();
+            open_dump(path).unwrap().read_to_string(&mut content).unwrap();
+            assert_eq!(content.as_bytes(), xml);
+        }
+    }
+
+    /// Hands over one byte per read, like a slow pipe
+    struct OneByteReader(io::Cursor<Vec<u8>>);
+
+    impl Read for OneByteReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = buf.len().min(1);
+            self.0.read(&mut buf[..len])
+        }
+    }
+
+    #[test]
+    fn test_open_dump_reader_detects_format_from_short_reads() {
+        let xml = b"<mediawiki></mediawiki>";
+        let mut encoder = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+        encoder.write_all(xml).unwrap();
+        let bzip2 = encoder.finish().unwrap();
+
+        for (raw, expected) in [(bzip2, &xml[..]), (xml.to_vec(), &xml[..]), (b"<m".to_vec(), &b"<m"[..])] {
+            let mut content = Vec::new();
+            open_dump_reader(OneByteReader(io::Cursor::new(raw))).unwrap().read_to_end(&mut content).unwrap();
+            assert_eq!(content, expected);
+        }
+    }
 } 
\ No newline at end of file
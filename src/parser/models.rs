@@ -1,9 +1,27 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+
+use crate::error_handling::{WikiError, WikiResult};
+
+/// Reading speed used to estimate reading time
+pub const WORDS_PER_MINUTE: usize = 200;
+
+/// Number of whitespace-separated words in `text`
+pub fn count_words(text: &str) -> usize {
+    text.split_whitespace().count()
+}
+
+/// Minutes needed to read `word_count` words, rounded up
+pub fn reading_time_minutes(word_count: usize) -> usize {
+    (word_count + WORDS_PER_MINUTE - 1) / WORDS_PER_MINUTE
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WikiArticle {
+    /// Page id from the dump; articles stored without one read back with their database rowid
+    #[serde(default)]
+    pub id: Option<i64>,
     pub title: String,
     pub content: String,
     pub categories: HashSet<String>,
@@ -11,6 +29,38 @@ pub struct WikiArticle {
     pub size: usize,
     pub redirect_to: Option<String>,
     pub images: Vec<WikiImage>,
+    /// Original wikitext before cleaning, when retained
+    pub raw_wikitext: Option<String>,
+    /// Citations from `<ref>` tags, in the order they appear
+    #[serde(default)]
+    pub references: Vec<WikiReference>,
+    /// Interlanguage links like `[[fr:Chat]]`, one per language
+    #[serde(default)]
+    pub langlinks: Vec<WikiLangLink>,
+    /// Words in `content`, kept up to date by `update_size`
+    #[serde(default)]
+    pub word_count: usize,
+    /// Estimated minutes to read `content` at [`WORDS_PER_MINUTE`], kept up to date by `update_size`
+    #[serde(default)]
+    pub reading_minutes: usize,
+}
+
+/// A citation taken from a `<ref>` tag
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WikiReference {
+    /// The `name` attribute, used by later `<ref name="..." />` reuses
+    pub name: Option<String>,
+    /// Wikitext between the opening and closing tags
+    pub content: String,
+}
+
+/// The same article in another language edition, from an interlanguage link
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WikiLangLink {
+    /// Language code of the edition, e.g. `fr` or `zh-min-nan`
+    pub lang: String,
+    /// Title of the article in that edition
+    pub target: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +71,33 @@ pub struct WikiImage {
     pub mime_type: String,
     pub hash: String,
     pub caption: Option<String>,
+    /// Pixel dimensions, known once the image has been downloaded
+    #[serde(default)]
+    pub width: Option<u32>,
+    #[serde(default)]
+    pub height: Option<u32>,
+}
+
+/// A section of an article, delimited by `== Heading ==` lines
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WikiSection {
+    /// Heading text; empty for the lead section
+    pub heading: String,
+    /// Heading level (number of `=`); 0 for the lead section
+    pub level: usize,
+    pub body: String,
+}
+
+impl WikiSection {
+    /// URL-friendly anchor for the heading
+    pub fn slug(&self) -> String {
+        self.heading
+            .to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("-")
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,15 +109,45 @@ pub struct WikiCategory {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WikiDumpMetadata {
-    pub dump_date: DateTime<Utc>,
+    /// The `timestamp` of the dump's `<mediawiki>` tag, which many dumps lack
+    pub dump_date: Option<DateTime<Utc>>,
     pub version: String,
     pub lang: String,
     pub article_count: usize,
 }
 
+/// Counts of the templates stripped from article text while parsing
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TemplateReport {
+    counts: HashMap<String, usize>,
+}
+
+impl TemplateReport {
+    /// Count every template in a page's wikitext
+    pub fn record(&mut self, wikitext: &str) {
+        for name in super::text::template_names(wikitext) {
+            *self.counts.entry(name).or_insert(0) += 1;
+        }
+    }
+
+    /// Total number of templates stripped
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    /// The `n` most common template names with their counts, most common first
+    pub fn most_common(&self, n: usize) -> Vec<(String, usize)> {
+        let mut counts: Vec<_> = self.counts.iter().map(|(name, count)| (name.clone(), *count)).collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts.truncate(n);
+        counts
+    }
+}
+
 impl WikiArticle {
     pub fn new(title: String, content: String) -> Self {
         Self {
+            id: None,
             title,
             content,
             categories: HashSet::new(),
@@ -48,9 +155,19 @@ impl WikiArticle {
             size: 0,
             redirect_to: None,
             images: Vec::new(),
+            raw_wikitext: None,
+            references: Vec::new(),
+            langlinks: Vec::new(),
+            word_count: 0,
+            reading_minutes: 0,
         }
     }
 
+    /// Start building an article that is validated on [`WikiArticleBuilder::build`]
+    pub fn builder() -> WikiArticleBuilder {
+        WikiArticleBuilder::default()
+    }
+
     pub fn is_redirect(&self) -> bool {
         self.redirect_to.is_some()
     }
@@ -65,6 +182,65 @@ impl WikiArticle {
 
     pub fn update_size(&mut self) {
         self.size = self.content.len();
+        self.word_count = count_words(&self.content);
+        self.reading_minutes = reading_time_minutes(self.word_count);
+    }
+
+    /// Split the content into its lead and headed sections
+    pub fn sections(&self) -> Vec<WikiSection> {
+        super::text::split_sections(&self.content)
+    }
+}
+
+/// Builder for [`WikiArticle`] that checks the title and computes the size
+#[derive(Debug, Default)]
+pub struct WikiArticleBuilder {
+    title: Option<String>,
+    content: String,
+    categories: HashSet<String>,
+    images: Vec<WikiImage>,
+    redirect_to: Option<String>,
+}
+
+impl WikiArticleBuilder {
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn content(mut self, content: impl Into<String>) -> Self {
+        self.content = content.into();
+        self
+    }
+
+    pub fn category(mut self, category: impl Into<String>) -> Self {
+        self.categories.insert(category.into());
+        self
+    }
+
+    pub fn image(mut self, image: WikiImage) -> Self {
+        self.images.push(image);
+        self
+    }
+
+    pub fn redirect_to(mut self, target: impl Into<String>) -> Self {
+        self.redirect_to = Some(target.into());
+        self
+    }
+
+    /// Build the article, failing when the title is missing or blank
+    pub fn build(self) -> WikiResult<WikiArticle> {
+        let title = match self.title {
+            Some(title) if !title.trim().is_empty() => title,
+            _ => return Err(WikiError::Parse("Article title must not be empty".to_string())),
+        };
+
+        let mut article = WikiArticle::new(title, self.content);
+        article.categories = self.categories;
+        article.images = self.images;
+        article.redirect_to = self.redirect_to;
+        article.update_size();
+        Ok(article)
     }
 }
 
@@ -77,6 +253,8 @@ impl WikiImage {
             mime_type,
             hash,
             caption: None,
+            width: None,
+            height: None,
         }
     }
 
@@ -89,4 +267,35 @@ impl WikiImage {
         self.size = size;
         self
     }
-} 
\ No newline at end of file
+
+    pub fn with_dimensions(mut self, width: u32, height: u32) -> Self {
+        self.width = Some(width);
+        self.height = Some(height);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_article_builder() -> WikiResult<()> {
+        assert!(WikiArticle::builder().content("No title.").build().is_err());
+        assert!(WikiArticle::builder().title("  ").build().is_err());
+
+        let article = WikiArticle::builder()
+            .title("Built")
+            .content("Built content.")
+            .category("Examples")
+            .build()?;
+        assert_eq!(article.title, "Built");
+        assert_eq!(article.size, "Built content.".len());
+        assert!(article.categories.contains("Examples"));
+        assert_eq!(article.id, None);
+        assert!(!article.is_redirect());
+        assert!(article.images.is_empty());
+        assert!(article.references.is_empty());
+        Ok(())
+    }
+}
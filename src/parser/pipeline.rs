@@ -0,0 +1,199 @@
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::thread;
+use tracing::debug;
+
+use crate::error_handling::{WikiError, WikiResult};
+use super::{open_dump, open_dump_reader};
+
+/// Bytes of decompressed XML handed to the parser at a time
+pub const DECOMPRESS_CHUNK_BYTES: usize = 1024 * 1024;
+/// Chunks the decompressor may run ahead of the parser
+pub const DECOMPRESS_CHANNEL_CAPACITY: usize = 16;
+
+/// Decompressed dump bytes produced on a background thread.
+///
+/// Reading from the stream overlaps decompression with parsing; the decompressor
+/// blocks once `capacity` chunks are waiting, so memory use stays bounded.
+pub struct DecompressedStream {
+    chunks: Receiver<io::Result<Vec<u8>>>,
+    current: Vec<u8>,
+    pos: usize,
+}
+
+impl DecompressedStream {
+    /// Start decompressing the dump at `path` (plain, gzip or bz2) on its own thread
+    pub fn spawn(path: impl Into<PathBuf>, chunk_bytes: usize, capacity: usize) -> WikiResult<Self> {
+        let path = path.into();
+        let decoder = open_dump(&path)?;
+        Self::spawn_with(move || Ok(decoder), path.display().to_string(), chunk_bytes, capacity)
+    }
+
+    /// Start decompressing a dump read from `reader`, e.g. stdin, with the default chunk size
+    /// and channel capacity. The compression is detected from the first bytes on the
+    /// decompression thread, so a slow pipe never blocks the caller.
+    pub fn from_reader<R: Read + Send + 'static>(reader: R) -> WikiResult<Self> {
+        Self::spawn_with(
+            move || open_dump_reader(reader),
+            "reader".to_string(),
+            DECOMPRESS_CHUNK_BYTES,
+            DECOMPRESS_CHANNEL_CAPACITY,
+        )
+    }
+
+    fn spawn_with<F>(open: F, source: String, chunk_bytes: usize, capacity: usize) -> WikiResult<Self>
+    where
+        F: FnOnce() -> WikiResult<Box<dyn Read + Send>> + Send + 'static,
+    {
+        let (sender, chunks) = sync_channel(capacity.max(1));
+        let chunk_bytes = chunk_bytes.max(1);
+
+        thread::Builder::new()
+            .name("dump-decompress".to_string())
+            .spawn(move || {
+                let mut decoder = match open() {
+                    Ok(decoder) => decoder,
+                    Err(e) => {
+                        let _ = sender.send(Err(io::Error::other(e.to_string())));
+                        return;
+                    }
+                };
+                let mut total = 0u64;
+                loop {
+                    let mut chunk = Vec::with_capacity(chunk_bytes);
+                    match decoder.by_ref().take(chunk_bytes as u64).read_to_end(&mut chunk) {
+                        Ok(0) => break,
+                        Ok(n) => {
+                            total += n as u64;
+                            // The parser hung up; stop decompressing
+                            if sender.send(Ok(chunk)).is_err() {
+                                return;
+                            }
+                        }
+                        Err(e) => {
+                            let _ = sender.send(Err(e));
+                            return;
+                        }
+                    }
+                }
+                debug!("Decompressed {} bytes from {}", total, source);
+            })
+            .map_err(|e| WikiError::OperationFailed(format!("Failed to start decompression: {}", e)))?;
+
+        Ok(Self {
+            chunks,
+            current: Vec::new(),
+            pos: 0,
+        })
+    }
+
+    /// Decompress with the default chunk size and channel capacity
+    pub fn open(path: impl Into<PathBuf>) -> WikiResult<Self> {
+        Self::spawn(path, DECOMPRESS_CHUNK_BYTES, DECOMPRESS_CHANNEL_CAPACITY)
+    }
+}
+
+impl Read for DecompressedStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.current.len() {
+            match self.chunks.recv() {
+                Ok(chunk) => {
+                    self.current = chunk?;
+                    self.pos = 0;
+                }
+                // The decompressor finished and dropped its sender
+                Err(_) => return Ok(0),
+            }
+        }
+
+        let n = buf.len().min(self.current.len() - self.pos);
+        buf[..n].copy_from_slice(&self.current[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::WikiXmlParser;
+    use std::io::{BufReader, Write};
+    use std::time::Instant;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_bz2_dump_parses_through_pipeline() -> WikiResult<()> {
+        let dir = tempdir()?;
+        let mut xml = String::from("<mediawiki>");
+        for i in 0..200 {
+            xml.push_str(&format!(
+                "<page><title>Page {}</title><revision><text>Text of page {}.</text></revision></page>",
+                i, i
+            ));
+        }
+        xml.push_str("</mediawiki>");
+
+        let path = dir.path().join("dump.xml.bz2");
+        let mut encoder = bzip2::write::BzEncoder::new(std::fs::File::create(&path)?, bzip2::Compression::default());
+        encoder.write_all(xml.as_bytes())?;
+        encoder.finish()?;
+
+        // Small chunks and a tiny channel force many hand-offs between the threads
+        let stream = DecompressedStream::spawn(&path, 97, 2)?;
+        let mut parser = WikiXmlParser::from_reader(BufReader::new(stream));
+        let mut titles = Vec::new();
+        parser.parse_articles(|article| {
+            titles.push(article.title);
+            Ok(())
+        })?;
+
+        assert_eq!(titles.len(), 200);
+        assert_eq!(titles[0], "Page 0");
+        assert_eq!(titles[199], "Page 199");
+        Ok(())
+    }
+
+    // Timing comparison with the old read-to-string import; run with
+    // `cargo test --release -- --ignored --nocapture test_pipeline_timing`
+    #[test]
+    #[ignore]
+    fn test_pipeline_timing_against_read_to_string() -> WikiResult<()> {
+        let dir = tempdir()?;
+        let mut xml = String::from("<mediawiki><siteinfo><sitename>Wikipedia</sitename></siteinfo>");
+        for i in 0..20_000 {
+            xml.push_str(&format!(
+                "<page><title>Page {}</title><ns>0</ns><revision><text>{{{{Infobox|name=Page {}}}}} \
+                 ''Page {}'' links to [[Page {}|another page]]. {}\n[[Category:Pages]]</text></revision></page>",
+                i, i, i, i + 1, "Some filler text for the article body. ".repeat(40)
+            ));
+        }
+        xml.push_str("</mediawiki>");
+        let path = dir.path().join("dump.xml.bz2");
+        let mut encoder = bzip2::write::BzEncoder::new(std::fs::File::create(&path)?, bzip2::Compression::default());
+        encoder.write_all(xml.as_bytes())?;
+        encoder.finish()?;
+
+        let count = |parser: &mut WikiXmlParser| parser.parse_articles(|_| Ok(()));
+
+        // Before: decompress the whole dump into memory, then parse it
+        let started = Instant::now();
+        let mut content = String::new();
+        crate::parser::open_dump(&path)?.read_to_string(&mut content)?;
+        let before = count(&mut WikiXmlParser::from_string(&content))?;
+        let read_to_string = started.elapsed();
+
+        // After: decompress on a background thread while parsing
+        let started = Instant::now();
+        let stream = DecompressedStream::open(&path)?;
+        let after = count(&mut WikiXmlParser::from_reader(BufReader::new(stream)))?;
+        let streamed = started.elapsed();
+
+        println!(
+            "{} MB of XML, {} pages: read_to_string {:.2?}, streamed {:.2?}",
+            xml.len() / (1024 * 1024), after, read_to_string, streamed
+        );
+        assert_eq!(before, after);
+        Ok(())
+    }
+}
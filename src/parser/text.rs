@@ -1,51 +1,305 @@
 use lazy_static::lazy_static;
 use regex::Regex;
+use std::borrow::Cow;
 use std::collections::HashSet;
+use std::ops::Range;
 use url::Url;
 
+use super::models::{WikiLangLink, WikiReference, WikiSection};
+
 lazy_static! {
     static ref REDIRECT_RE: Regex = Regex::new(r"#REDIRECT\s*\[\[([^\]]+)\]\]").unwrap();
     static ref CATEGORY_RE: Regex = Regex::new(r"\[\[Category:([^\]]+)\]\]").unwrap();
-    static ref IMAGE_RE: Regex = Regex::new(r"\[\[File:([^\]|]+)(?:\|([^\]]+))?\]\]").unwrap();
-    static ref INTERNAL_LINK_RE: Regex = Regex::new(r"\[\[([^\]|]+)(?:\|([^\]]+))?\]\]").unwrap();
+    static ref IMAGE_RE: Regex = Regex::new(r"\[\[(?:File|Image):([^\]|]+)(?:\|([^\]]+))?\]\]").unwrap();
+    // Targets and labels exclude `[` so only the innermost of nested links matches
+    static ref INTERNAL_LINK_RE: Regex = Regex::new(r"\[\[([^\[\]|]+)(?:\|([^\[\]]+))?\]\]").unwrap();
+    // Lowercase prefixes like `fr` or `zh-min-nan`, checked against `LANGUAGE_CODES`;
+    // a leading `:` makes an inline link instead
+    static ref LANGLINK_RE: Regex = Regex::new(r"\[\[([a-z]+(?:-[a-z0-9]+)*):([^\[\]|]+)\]\]").unwrap();
     static ref EXTERNAL_LINK_RE: Regex = Regex::new(r"\[([^\s\]]+)(?:\s+([^\]]+))?\]").unwrap();
     static ref HTML_TAG_RE: Regex = Regex::new(r"<[^>]+>").unwrap();
-    static ref TEMPLATE_RE: Regex = Regex::new(r"\{\{[^\}]+\}\}").unwrap();
+    static ref REF_RE: Regex = Regex::new(r"(?s)<ref[^>/]*/>|<ref[^>]*>.*?</ref>").unwrap();
+    static ref REF_CONTENT_RE: Regex = Regex::new(r"(?s)<ref(\s[^>]*[^/>])?\s*>(.*?)</ref>").unwrap();
+    static ref REF_NAME_RE: Regex = Regex::new(r#"name\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'/>]+))"#).unwrap();
+    static ref BLANK_LINES_RE: Regex = Regex::new(r"\n{3,}").unwrap();
+    static ref HEADING_RE: Regex = Regex::new(r"^(={2,6})\s*(.+?)\s*={2,6}\s*$").unwrap();
 }
 
-pub fn clean_wiki_text(text: &str) -> String {
-    let mut cleaned = text.to_string();
+/// Prefixes of Wikipedia language editions, including aliases like `nb` and `zh-yue`,
+/// sorted for binary search. Other prefixes such as `wikt:` or `doi:` are interwiki or
+/// external links, not interlanguage ones
+const LANGUAGE_CODES: &[&str] = &[
+    "aa", "ab", "ace", "ady", "af", "als", "alt", "am", "ami", "an", "ang", "anp", "ar", "arc",
+    "ary", "arz", "as", "ast", "atj", "av", "avk", "awa", "ay", "az", "azb", "ba", "ban", "bar",
+    "bat-smg", "bbc", "bcl", "bdr", "be", "be-tarask", "be-x-old", "bew", "bg", "bh", "bi", "bjn",
+    "blk", "bm", "bn", "bo", "bpy", "br", "bs", "btm", "bug", "bxr", "ca", "cbk-zam", "cdo", "ce",
+    "ceb", "ch", "cho", "chr", "chy", "ckb", "co", "cr", "crh", "cs", "csb", "cu", "cv", "cy", "da",
+    "dag", "de", "dga", "din", "diq", "dsb", "dtp", "dty", "dv", "dz", "ee", "el", "eml", "en",
+    "eo", "es", "et", "eu", "ext", "fa", "fat", "ff", "fi", "fiu-vro", "fj", "fo", "fon", "fr",
+    "frp", "frr", "fur", "fy", "ga", "gag", "gan", "gcr", "gd", "gl", "glk", "gn", "gom", "gor",
+    "got", "gpe", "gsw", "gu", "guc", "gur", "guw", "gv", "ha", "hak", "haw", "he", "hi", "hif",
+    "ho", "hr", "hsb", "ht", "hu", "hy", "hyw", "hz", "ia", "iba", "id", "ie", "ig", "igl", "ii",
+    "ik", "ilo", "inh", "io", "is", "it", "iu", "ja", "jam", "jbo", "jv", "ka", "kaa", "kab", "kbd",
+    "kbp", "kcg", "kg", "kge", "ki", "kj", "kk", "kl", "km", "kn", "knc", "ko", "koi", "kr", "krc",
+    "ks", "ksh", "ku", "kus", "kv", "kw", "ky", "la", "lad", "lb", "lbe", "lez", "lfn", "lg", "li",
+    "lij", "lld", "lmo", "ln", "lo", "lrc", "lt", "ltg", "lv", "lzh", "mad", "mai", "map-bms",
+    "mdf", "mg", "mh", "mhr", "mi", "min", "mk", "ml", "mn", "mni", "mnw", "mos", "mr", "mrj", "ms",
+    "mt", "mus", "mwl", "my", "myv", "mzn", "na", "nah", "nap", "nb", "nds", "nds-nl", "ne", "new",
+    "ng", "nia", "nl", "nn", "no", "nov", "nqo", "nr", "nrm", "nso", "nup", "nv", "ny", "oc", "olo",
+    "om", "or", "os", "pa", "pag", "pam", "pap", "pcd", "pcm", "pdc", "pfl", "pi", "pih", "pl",
+    "pms", "pnb", "pnt", "ps", "pt", "pwn", "qu", "rm", "rmy", "rn", "ro", "roa-rup", "roa-tara",
+    "rsk", "ru", "rue", "rup", "rw", "sa", "sah", "sat", "sc", "scn", "sco", "sd", "se", "sg",
+    "sgs", "sh", "shi", "shn", "shy", "si", "simple", "sk", "skr", "sl", "sm", "smn", "sn", "so",
+    "sq", "sr", "srn", "ss", "st", "stq", "su", "sv", "sw", "syl", "szl", "szy", "ta", "tay", "tcy",
+    "tdd", "te", "tet", "tg", "th", "ti", "tig", "tk", "tl", "tly", "tn", "to", "tpi", "tr", "trv",
+    "ts", "tt", "tum", "tw", "ty", "tyv", "udm", "ug", "uk", "ur", "uz", "ve", "vec", "vep", "vi",
+    "vls", "vo", "vro", "wa", "war", "wo", "wuu", "xal", "xh", "xmf", "yi", "yo", "yue", "za",
+    "zea", "zgh", "zh", "zh-classical", "zh-min-nan", "zh-yue", "zu",
+];
+
+/// Whether `prefix` names a Wikipedia language edition
+fn is_language_code(prefix: &str) -> bool {
+    LANGUAGE_CODES.binary_search(&prefix).is_ok()
+}
+
+/// Deepest link nesting flattened, e.g. a link inside an image caption; deeper brackets are kept
+const MAX_LINK_NESTING: usize = 8;
+
+/// Byte ranges of the outermost balanced `{{...}}` templates in `text`.
+///
+/// Nested templates are covered by their enclosing one. An unclosed `{{` or stray `}}`
+/// is left in place without hiding the templates around it, and the scan is linear
+/// however the braces are arranged.
+fn template_spans(text: &str) -> Vec<Range<usize>> {
+    let bytes = text.as_bytes();
+    let mut open: Vec<usize> = Vec::new();
+    let mut spans: Vec<Range<usize>> = Vec::new();
+    let mut i = 0;
+    while i + 1 < bytes.len() {
+        match (bytes[i], bytes[i + 1]) {
+            (b'{', b'{') => {
+                open.push(i);
+                i += 2;
+            }
+            (b'}', b'}') if !open.is_empty() => {
+                let start = open.pop().unwrap_or_default();
+                // Templates closed inside this one are now part of it
+                while spans.last().is_some_and(|span| span.start >= start) {
+                    spans.pop();
+                }
+                spans.push(start..i + 2);
+                i += 2;
+            }
+            _ => i += 1,
+        }
+    }
+    spans
+}
 
-    // Remove templates
-    cleaned = TEMPLATE_RE.replace_all(&cleaned, "").to_string();
+/// Remove every balanced template from `text`
+fn strip_templates(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut last = 0;
+    for span in template_spans(text) {
+        stripped.push_str(&text[last..span.start]);
+        last = span.end;
+    }
+    stripped.push_str(&text[last..]);
+    stripped
+}
 
-    // Convert internal links to text
-    cleaned = INTERNAL_LINK_RE
-        .replace_all(&cleaned, |caps: &regex::Captures| {
+/// Replace internal links with their display text, innermost first, up to [`MAX_LINK_NESTING`] deep
+fn flatten_internal_links(text: &str) -> String {
+    let mut text = text.to_string();
+    for _ in 0..MAX_LINK_NESTING {
+        let flattened = INTERNAL_LINK_RE.replace_all(&text, |caps: &regex::Captures| {
             caps.get(2).map_or_else(
                 || caps[1].to_string(),
                 |m| m.as_str().to_string(),
             )
+        });
+        match flattened {
+            Cow::Borrowed(_) => break,
+            Cow::Owned(flattened) => text = flattened,
+        }
+    }
+    text
+}
+
+/// A single transformation applied while cleaning wikitext
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CleanStep {
+    /// Remove `{{...}}` templates
+    StripTemplates,
+    /// Remove `<ref>` citations together with their contents
+    StripRefs,
+    /// Replace internal and external links with their display text, dropping interlanguage links
+    FlattenLinks,
+    /// Remove HTML tags, keeping their inner text
+    StripHtml,
+    /// Collapse runs of blank lines and trim the result
+    CollapseWhitespace,
+}
+
+impl CleanStep {
+    pub fn apply(self, text: &str) -> String {
+        match self {
+            CleanStep::StripTemplates => strip_templates(text),
+            CleanStep::StripRefs => REF_RE.replace_all(text, "").to_string(),
+            CleanStep::FlattenLinks => {
+                // Interlanguage links point at other editions and are not part of the prose
+                let text = LANGLINK_RE.replace_all(text, |caps: &regex::Captures| {
+                    if is_language_code(&caps[1]) {
+                        String::new()
+                    } else {
+                        caps[0].to_string()
+                    }
+                });
+
+                // Convert internal links to text
+                let text = flatten_internal_links(&text);
+
+                // Convert external links to text
+                EXTERNAL_LINK_RE
+                    .replace_all(&text, |caps: &regex::Captures| {
+                        caps.get(2).map_or_else(
+                            || caps[1].to_string(),
+                            |m| m.as_str().to_string(),
+                        )
+                    })
+                    .to_string()
+            }
+            CleanStep::StripHtml => HTML_TAG_RE.replace_all(text, "").to_string(),
+            CleanStep::CollapseWhitespace => BLANK_LINES_RE.replace_all(text, "\n\n").trim().to_string(),
+        }
+    }
+}
+
+/// Which cleaning steps to run; the default matches [`clean_wiki_text`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CleanOptions {
+    pub strip_templates: bool,
+    pub strip_refs: bool,
+    pub flatten_links: bool,
+    pub strip_html: bool,
+    pub collapse_whitespace: bool,
+}
+
+impl Default for CleanOptions {
+    fn default() -> Self {
+        Self {
+            strip_templates: true,
+            strip_refs: false,
+            flatten_links: true,
+            strip_html: true,
+            collapse_whitespace: true,
+        }
+    }
+}
+
+impl CleanOptions {
+    /// The enabled steps, in the order they run
+    pub fn steps(&self) -> Vec<CleanStep> {
+        [
+            (self.strip_templates, CleanStep::StripTemplates),
+            // Citations go before HTML stripping, which would otherwise keep their text
+            (self.strip_refs, CleanStep::StripRefs),
+            (self.flatten_links, CleanStep::FlattenLinks),
+            (self.strip_html, CleanStep::StripHtml),
+            (self.collapse_whitespace, CleanStep::CollapseWhitespace),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, step)| enabled.then_some(step))
+        .collect()
+    }
+
+    /// Run the enabled steps over `text`
+    pub fn clean(&self, text: &str) -> String {
+        self.steps()
+            .into_iter()
+            .fold(text.to_string(), |text, step| step.apply(&text))
+    }
+}
+
+pub fn clean_wiki_text(text: &str) -> String {
+    CleanOptions::default().clean(text)
+}
+
+/// Names of the templates in `text`, in the order they appear, as `clean_wiki_text` would strip them
+pub fn template_names(text: &str) -> Vec<String> {
+    template_spans(text)
+        .into_iter()
+        .filter_map(|span| {
+            let inner = &text[span.start + 2..span.end - 2];
+            let name = inner.split('|').next()?.trim().replace('_', " ");
+            (!name.is_empty()).then_some(name)
         })
-        .to_string();
+        .collect()
+}
 
-    // Convert external links to text
-    cleaned = EXTERNAL_LINK_RE
-        .replace_all(&cleaned, |caps: &regex::Captures| {
-            caps.get(2).map_or_else(
-                || caps[1].to_string(),
-                |m| m.as_str().to_string(),
-            )
+/// Citations from `<ref>...</ref>` blocks; self-closing reuses of a named ref are skipped
+pub fn extract_references(text: &str) -> Vec<WikiReference> {
+    REF_CONTENT_RE
+        .captures_iter(text)
+        .filter_map(|caps| {
+            let content = caps[2].trim().to_string();
+            if content.is_empty() {
+                return None;
+            }
+            let name = caps.get(1).and_then(|attrs| {
+                REF_NAME_RE.captures(attrs.as_str()).and_then(|name| {
+                    name.get(1).or_else(|| name.get(2)).or_else(|| name.get(3))
+                        .map(|m| m.as_str().to_string())
+                })
+            });
+            Some(WikiReference { name, content })
+        })
+        .collect()
+}
+
+/// Interlanguage links like `[[fr:Chat]]`; only the first link per language is kept
+pub fn extract_langlinks(text: &str) -> Vec<WikiLangLink> {
+    let mut langs = HashSet::new();
+    LANGLINK_RE
+        .captures_iter(text)
+        .filter_map(|caps| {
+            let lang = &caps[1];
+            let target = caps[2].trim();
+            if !is_language_code(lang) || target.is_empty() || !langs.insert(lang.to_string()) {
+                return None;
+            }
+            Some(WikiLangLink { lang: lang.to_string(), target: target.to_string() })
         })
-        .to_string();
+        .collect()
+}
 
-    // Remove HTML tags
-    cleaned = HTML_TAG_RE.replace_all(&cleaned, "").to_string();
+/// Split text into sections at `== Heading ==` lines; the first section is the lead
+pub fn split_sections(text: &str) -> Vec<WikiSection> {
+    let mut sections = Vec::new();
+    let mut current = WikiSection {
+        heading: String::new(),
+        level: 0,
+        body: String::new(),
+    };
 
-    // Remove multiple newlines
-    cleaned = cleaned.replace("\n\n\n+", "\n\n");
+    for line in text.lines() {
+        if let Some(caps) = HEADING_RE.captures(line.trim()) {
+            current.body = current.body.trim().to_string();
+            sections.push(current);
+            current = WikiSection {
+                heading: caps[2].to_string(),
+                level: caps[1].len(),
+                body: String::new(),
+            };
+        } else {
+            current.body.push_str(line);
+            current.body.push('\n');
+        }
+    }
 
-    cleaned.trim().to_string()
+    current.body = current.body.trim().to_string();
+    sections.push(current);
+    sections
 }
 
 pub fn extract_redirect(text: &str) -> Option<String> {
@@ -55,7 +309,9 @@ pub fn extract_redirect(text: &str) -> Option<String> {
 pub fn extract_categories(text: &str) -> HashSet<String> {
     CATEGORY_RE
         .captures_iter(text)
-        .map(|caps| caps[1].trim().to_string())
+        // Drop the sort key in `[[Category:Name|key]]`
+        .map(|caps| caps[1].split('|').next().unwrap_or("").trim().to_string())
+        .filter(|name| !name.is_empty())
         .collect()
 }
 
@@ -104,20 +360,155 @@ mod tests {
 
     #[test]
     fn test_extract_categories() {
-        let text = "[[Category:Test1]]\n[[Category:Test2]]";
+        let text = "[[Category:Test1]]\n[[Category:Test2|Sort key]]";
         let categories = extract_categories(text);
         assert!(categories.contains("Test1"));
         assert!(categories.contains("Test2"));
+        assert_eq!(categories.len(), 2);
     }
 
     #[test]
     fn test_extract_images() {
-        let text = "[[File:image1.jpg|thumb|Caption1]]\n[[File:image2.jpg]]";
+        let text = "[[File:image1.jpg|thumb|Caption1]]\n[[File:image2.jpg]] and [[Image:image3.png|Caption3]]";
         let images = extract_images(text);
-        assert_eq!(images.len(), 2);
+        assert_eq!(images.len(), 3);
         assert_eq!(images[0].0, "image1.jpg");
         assert_eq!(images[0].1, Some("thumb|Caption1".to_string()));
         assert_eq!(images[1].0, "image2.jpg");
         assert_eq!(images[1].1, None);
+        assert_eq!(images[2].0, "image3.png");
+    }
+
+    #[test]
+    fn test_split_sections() {
+        let text = "Lead text.\n== History ==\nOld times.\n=== Early ===\nVery old.\n== See also ==\nOther.";
+        let sections = split_sections(text);
+        assert_eq!(sections.len(), 4);
+        assert_eq!(sections[0].heading, "");
+        assert_eq!(sections[0].body, "Lead text.");
+        assert_eq!(sections[1].heading, "History");
+        assert_eq!(sections[1].level, 2);
+        assert_eq!(sections[1].body, "Old times.");
+        assert_eq!(sections[2].level, 3);
+        assert_eq!(sections[3].slug(), "see-also");
+    }
+
+    #[test]
+    fn test_template_names() {
+        let text = "{{Infobox person|name=Ada}} text {{convert|5|km}} {{ Cite_web |url=x}}";
+        assert_eq!(template_names(text), vec!["Infobox person", "convert", "Cite web"]);
+
+        // Only the outermost of nested templates is named
+        assert_eq!(template_names("{{Infobox|born={{birth date|1815}}}}"), vec!["Infobox"]);
+    }
+
+    #[test]
+    fn test_nested_and_unbalanced_markup() {
+        assert_eq!(clean_wiki_text("A {{outer|x={{inner}}|y}} B"), "A  B");
+        assert_eq!(clean_wiki_text("A {{open {{closed}} B"), "A {{open  B");
+        assert_eq!(clean_wiki_text("A }} {{t}} B"), "A }}  B");
+        assert_eq!(
+            clean_wiki_text("[[File:Map.png|thumb|Map of [[Europe]] in [[1815|the year 1815]]]]"),
+            "thumb|Map of Europe in the year 1815"
+        );
+        assert_eq!(clean_wiki_text("[[a [[b]] c"), "[[a b c");
+    }
+
+    /// Random wikitext built from markup fragments that stress the cleaner's patterns
+    fn adversarial_wikitext(rng: &mut impl rand::Rng, len: usize) -> String {
+        const FRAGMENTS: &[&str] = &[
+            "{{", "}}", "{", "}", "[[", "]]", "[", "]", "|", "<ref", "<ref>", "</ref>", "/>", "<", ">",
+            "=", "==", "\n", "\n\n\n", " ", "Category:", "File:", "http://x", "a", "é", "名",
+        ];
+        (0..len).map(|_| FRAGMENTS[rng.gen_range(0..FRAGMENTS.len())]).collect()
+    }
+
+    #[test]
+    fn test_cleaner_is_fast_on_adversarial_input() {
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1733);
+        let options = CleanOptions { strip_refs: true, ..Default::default() };
+
+        let mut inputs: Vec<String> = (0..200).map(|_| adversarial_wikitext(&mut rng, 400)).collect();
+        inputs.push("{{".repeat(100_000));
+        inputs.push("}}".repeat(100_000));
+        inputs.push(format!("{}x{}", "[[".repeat(50_000), "]]".repeat(50_000)));
+        inputs.push(format!("{}x{}", "{{".repeat(50_000), "}}".repeat(50_000)));
+        inputs.push("[[a|".repeat(50_000));
+        inputs.push("<ref>".repeat(50_000));
+
+        let started = std::time::Instant::now();
+        for input in &inputs {
+            let cleaned = options.clean(input);
+            assert!(cleaned.len() <= input.len());
+            template_names(input);
+            extract_references(input);
+            extract_langlinks(input);
+            split_sections(input);
+        }
+        assert!(started.elapsed() < std::time::Duration::from_secs(10), "took {:?}", started.elapsed());
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_flatten_links_toggle() {
+        let text = "See [[Rust (language)|Rust]] and [https://example.com the site].";
+
+        let flattened = CleanOptions::default().clean(text);
+        assert_eq!(flattened, "See Rust and the site.");
+
+        let options = CleanOptions { flatten_links: false, ..Default::default() };
+        assert!(!options.steps().contains(&CleanStep::FlattenLinks));
+        let preserved = options.clean(text);
+        assert_eq!(preserved, text);
+    }
+
+    #[test]
+    fn test_strip_refs_removes_citation_text() {
+        let text = "Fact.<ref name=\"a\">Source, p. 1</ref> More.<ref name=\"a\" />\n\n\n\nEnd";
+        assert_eq!(clean_wiki_text(text), "Fact.Source, p. 1 More.\n\nEnd");
+
+        let options = CleanOptions { strip_refs: true, ..Default::default() };
+        assert_eq!(options.clean(text), "Fact. More.\n\nEnd");
+    }
+
+    #[test]
+    fn test_extract_references() {
+        let text = "Rust is fast.<ref name=\"bench\">{{cite web|url=https://example.com|title=Benchmarks}}</ref> \
+                    It is safe.<ref>Jung et al., ''RustBelt'', 2018.</ref> Again.<ref name=\"bench\" /> <ref >Third</ref>";
+        let references = extract_references(text);
+        assert_eq!(references.len(), 3);
+        assert_eq!(references[0].name.as_deref(), Some("bench"));
+        assert_eq!(references[0].content, "{{cite web|url=https://example.com|title=Benchmarks}}");
+        assert_eq!(references[1].name, None);
+        assert_eq!(references[1].content, "Jung et al., ''RustBelt'', 2018.");
+        // A self-closing reuse does not swallow the following citation
+        assert_eq!(references[2].content, "Third");
+    }
+
+    #[test]
+    fn test_extract_langlinks() {
+        let text = "A cat.\n[[Category:Cats]]\n[[fr:Chat]]\n[[zh-min-nan:Niau]]\n[[fr:Chat domestique]]\n\
+                    See [[:de:Katze]], [[mw:Help:Links]], [[wikt:cat]], [[doi:10.1000/182]] and [[rfc:2616]].";
+        let langlinks = extract_langlinks(text);
+        assert_eq!(
+            langlinks,
+            vec![
+                WikiLangLink { lang: "fr".to_string(), target: "Chat".to_string() },
+                WikiLangLink { lang: "zh-min-nan".to_string(), target: "Niau".to_string() },
+            ]
+        );
+
+        // Flattening drops them from the prose rather than leaving "fr:Chat" behind
+        let cleaned = clean_wiki_text("A cat.[[fr:Chat]] See [[Dog|dogs]].");
+        assert_eq!(cleaned, "A cat. See dogs.");
+        // Interwiki links are ordinary prose links and keep their text
+        assert!(clean_wiki_text("See [[wikt:cat]] and [[doi:10.1000/182]].").contains("wikt:cat"));
+    }
+
+    #[test]
+    fn test_language_codes_are_sorted() {
+        assert!(LANGUAGE_CODES.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(is_language_code("zh-min-nan"));
+        assert!(!is_language_code("wikt"));
+    }
+}
@@ -1,59 +1,161 @@
-use quick_xml::events::Event;
+use quick_xml::events::{BytesStart, BytesText, Event};
 use quick_xml::reader::Reader;
+use std::collections::HashSet;
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufRead, BufReader, Cursor};
 use std::path::Path;
+use tokio::io::AsyncBufRead;
 use tracing::{debug, info, warn};
 use chrono::{DateTime, Utc};
 
 use crate::error_handling::{WikiError, WikiResult};
-use super::models::{WikiArticle, WikiDumpMetadata, WikiImage};
-use super::text::clean_wiki_text;
+use super::models::{TemplateReport, WikiArticle, WikiDumpMetadata, WikiImage};
+use super::text::{extract_categories, extract_images, extract_langlinks, extract_references, CleanOptions};
+
+type DumpReader = Box<dyn BufRead + Send>;
+
+/// Bytes read looking for `</siteinfo>` before giving up; real siteinfo blocks are a few KB
+const MAX_SITEINFO_BYTES: usize = 1024 * 1024;
+
+/// Namespace of encyclopedia articles, the only pages parsed by default
+pub const ARTICLE_NAMESPACE: i32 = 0;
+
+fn default_namespaces() -> HashSet<i32> {
+    HashSet::from([ARTICLE_NAMESPACE])
+}
+
+fn no_dump_attached() -> WikiError {
+    WikiError::Parse("No dump attached; create the parser with from_file, from_reader or from_string".to_string())
+}
 
 pub struct WikiXmlParser {
-    reader: Reader<BufReader<File>>,
+    /// Dump being parsed; `None` for a parser from [`WikiXmlParser::new`] until one is
+    /// attached by [`WikiXmlParser::parse_reader`]
+    reader: Option<Reader<DumpReader>>,
     buf: Vec<u8>,
     metadata: Option<WikiDumpMetadata>,
+    max_articles: Option<usize>,
+    template_report: Option<TemplateReport>,
+    /// Keep each article's original wikitext in `raw_wikitext`
+    keep_raw_wikitext: bool,
+    clean_options: CleanOptions,
+    /// Namespaces whose pages are parsed; pages in any other namespace are skipped
+    namespaces: HashSet<i32>,
+    /// `parse_metadata` stopped on the first `<page>` tag, which `parse_articles` must replay
+    page_started: bool,
+}
+
+impl Default for WikiXmlParser {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl WikiXmlParser {
+    /// A parser with no dump attached; it opens nothing, so it works without a null device
     pub fn new() -> Self {
         Self {
-            reader: Reader::from_reader(BufReader::new(File::open("/dev/null").unwrap_or_else(|_| {
-                // Fallback for Windows
-                File::open("NUL").unwrap_or_else(|_| {
-                    panic!("Could not open null device")
-                })
-            }))),
+            reader: None,
             buf: Vec::new(),
             metadata: None,
+            max_articles: None,
+            template_report: None,
+            keep_raw_wikitext: false,
+            clean_options: CleanOptions::default(),
+            namespaces: default_namespaces(),
+            page_started: false,
         }
     }
 
     pub fn from_file<P: AsRef<Path>>(path: P) -> WikiResult<Self> {
         let file = File::open(path).map_err(WikiError::from)?;
-        let reader = Reader::from_reader(BufReader::new(file));
+        let reader: DumpReader = Box::new(BufReader::new(file));
         Ok(Self {
-            reader,
+            reader: Some(Reader::from_reader(reader)),
             buf: Vec::new(),
             metadata: None,
+            max_articles: None,
+            template_report: None,
+            keep_raw_wikitext: false,
+            clean_options: CleanOptions::default(),
+            namespaces: default_namespaces(),
+            page_started: false,
         })
     }
     
+    /// Parse a dump from any buffered reader, e.g. a [`super::DecompressedStream`]
+    pub fn from_reader<R: BufRead + Send + 'static>(reader: R) -> Self {
+        let reader: DumpReader = Box::new(reader);
+        Self {
+            reader: Some(Reader::from_reader(reader)),
+            buf: Vec::new(),
+            metadata: None,
+            max_articles: None,
+            template_report: None,
+            keep_raw_wikitext: false,
+            clean_options: CleanOptions::default(),
+            namespaces: default_namespaces(),
+            page_started: false,
+        }
+    }
+
     pub fn from_string(content: &str) -> Self {
-        let reader = Reader::from_str(content);
+        let reader: DumpReader = Box::new(Cursor::new(content.as_bytes().to_vec()));
         Self {
-            reader: Reader::from_reader(BufReader::new(File::open("/dev/null").unwrap_or_else(|_| {
-                // Fallback for Windows
-                File::open("NUL").unwrap_or_else(|_| {
-                    panic!("Could not open null device")
-                })
-            }))),
+            reader: Some(Reader::from_reader(reader)),
             buf: Vec::new(),
             metadata: None,
+            max_articles: None,
+            template_report: None,
+            keep_raw_wikitext: false,
+            clean_options: CleanOptions::default(),
+            namespaces: default_namespaces(),
+            page_started: false,
         }
     }
 
+    /// Stop parsing once this many articles have been produced
+    pub fn with_max_articles(mut self, max_articles: Option<usize>) -> Self {
+        self.max_articles = max_articles;
+        self
+    }
+
+    /// Choose which cleaning steps are applied to article text
+    pub fn with_clean_options(mut self, options: CleanOptions) -> Self {
+        self.clean_options = options;
+        self
+    }
+
+    /// Parse pages in these namespaces instead of articles (namespace 0) only, e.g.
+    /// `{0, 10, 14}` to include templates and categories. Pages without an `<ns>` are articles.
+    pub fn with_namespaces(mut self, allowed: HashSet<i32>) -> Self {
+        self.namespaces = allowed;
+        self
+    }
+
+    /// Count the templates stripped from each article, see [`WikiXmlParser::template_report`]
+    pub fn with_template_report(mut self, enabled: bool) -> Self {
+        self.template_report = enabled.then(TemplateReport::default);
+        self
+    }
+
+    /// Keep each article's original wikitext in [`WikiArticle::raw_wikitext`]
+    /// (off by default, since it doubles the text held per article)
+    pub fn with_raw_wikitext(mut self, enabled: bool) -> Self {
+        self.keep_raw_wikitext = enabled;
+        self
+    }
+
+    /// Templates stripped so far, when the report is enabled
+    pub fn template_report(&self) -> Option<&TemplateReport> {
+        self.template_report.as_ref()
+    }
+
+    /// Read the `<siteinfo>` header.
+    ///
+    /// A header missing its closing tag ends at the first `<page>`, or after
+    /// `MAX_SITEINFO_BYTES`, keeping whatever was read so far rather than scanning
+    /// the whole dump.
     pub fn parse_metadata(&mut self) -> WikiResult<WikiDumpMetadata> {
         if let Some(ref metadata) = self.metadata {
             return Ok(metadata.clone());
@@ -65,13 +167,23 @@ impl WikiXmlParser {
         let mut in_lang = false;
         let mut generator = String::new();
         let mut lang = String::new();
-        let mut dump_date = Utc::now();
+        let mut dump_date = None;
+        let reader = self.reader.as_mut().ok_or_else(no_dump_attached)?;
 
         loop {
             self.buf.clear();
-            match self.reader.read_event_into(&mut self.buf) {
+            match reader.read_event_into(&mut self.buf) {
                 Ok(Event::Start(ref e)) => match e.name().as_ref() {
                     b"siteinfo" => in_siteinfo = true,
+                    b"page" => {
+                        if in_siteinfo {
+                            warn!("Dump has no </siteinfo> before its first page");
+                            // The unclosed tag would otherwise fail the dump's final </mediawiki>
+                            reader.check_end_names(false);
+                        }
+                        self.page_started = true;
+                        break;
+                    }
                     b"sitename" => in_sitename = true,
                     b"generator" => in_generator = true,
                     b"lang" => in_lang = true,
@@ -80,7 +192,7 @@ impl WikiXmlParser {
                         for attr in e.attributes().flatten() {
                             if attr.key.as_ref() == b"timestamp" {
                                 if let Ok(ts) = String::from_utf8_lossy(&attr.value).parse::<DateTime<Utc>>() {
-                                    dump_date = ts;
+                                    dump_date = Some(ts);
                                 }
                             }
                         }
@@ -100,9 +212,9 @@ impl WikiXmlParser {
                 Ok(Event::Text(e)) => {
                     if in_siteinfo {
                         if in_generator {
-                            generator = String::from_utf8_lossy(&e).into_owned();
+                            generator = decode_text(&e);
                         } else if in_lang {
-                            lang = String::from_utf8_lossy(&e).into_owned();
+                            lang = decode_text(&e);
                         }
                     }
                 }
@@ -110,6 +222,15 @@ impl WikiXmlParser {
                 Err(e) => return Err(WikiError::Parse(e.to_string())),
                 _ => (),
             }
+
+            if reader.buffer_position() > MAX_SITEINFO_BYTES {
+                warn!(
+                    "No </siteinfo> in the first {} bytes of the dump, using the metadata read so far",
+                    MAX_SITEINFO_BYTES
+                );
+                reader.check_end_names(false);
+                break;
+            }
         }
 
         let metadata = WikiDumpMetadata {
@@ -126,114 +247,67 @@ impl WikiXmlParser {
     pub fn parse_articles<F>(&mut self, mut callback: F) -> WikiResult<usize>
     where
         F: FnMut(WikiArticle) -> WikiResult<()>,
+    {
+        self.parse_articles_while(|article| callback(article).map(|_| true))
+    }
+
+    /// Like [`parse_articles`](Self::parse_articles), but stops reading the dump as soon
+    /// as `callback` returns `Ok(false)`
+    pub fn parse_articles_while<F>(&mut self, mut callback: F) -> WikiResult<usize>
+    where
+        F: FnMut(WikiArticle) -> WikiResult<bool>,
     {
         let mut count = 0;
-        let mut current_article: Option<WikiArticle> = None;
-        let mut in_page = false;
-        let mut in_title = false;
-        let mut in_text = false;
-        let mut in_redirect = false;
-        let mut current_text = String::new();
+        // The template report reads the wikitext, so it is held until the report has seen it
+        let mut state = PageState::new(
+            self.clean_options.clone(),
+            self.namespaces.clone(),
+            self.keep_raw_wikitext || self.template_report.is_some(),
+        );
 
         // Ensure we have metadata
         if self.metadata.is_none() {
             self.parse_metadata()?;
         }
 
+        let reader = self.reader.as_mut().ok_or_else(no_dump_attached)?;
+        if std::mem::take(&mut self.page_started) {
+            state.handle_event(&Event::Start(BytesStart::new("page")));
+        }
+
         loop {
+            // Checked before reading on, so a limit of 0 reads no pages
+            if self.max_articles.is_some_and(|max| count >= max) {
+                info!("Reached limit of {} articles, stopping", count);
+                break;
+            }
+
             self.buf.clear();
-            match self.reader.read_event_into(&mut self.buf) {
-                Ok(Event::Start(ref e)) => match e.name().as_ref() {
-                    b"page" => {
-                        in_page = true;
-                        current_article = None;
-                    }
-                    b"title" => in_title = true,
-                    b"text" => in_text = true,
-                    b"redirect" => {
-                        in_redirect = true;
-                        if let Some(ref mut article) = current_article {
-                            // Get redirect target from attributes
-                            for attr in e.attributes().flatten() {
-                                if attr.key.as_ref() == b"title" {
-                                    article.redirect_to = Some(String::from_utf8_lossy(&attr.value).into_owned());
-                                }
-                            }
+            match reader.read_event_into(&mut self.buf) {
+                Ok(Event::Eof) => break,
+                Ok(event) => {
+                    if let Some(mut article) = state.handle_event(&event) {
+                        if let (Some(report), Some(wikitext)) = (&mut self.template_report, &article.raw_wikitext) {
+                            report.record(wikitext);
                         }
-                    }
-                    _ => (),
-                },
-                Ok(Event::End(ref e)) => match e.name().as_ref() {
-                    b"page" => {
-                        if let Some(mut article) = current_article.take() {
-                            article.content = clean_wiki_text(&current_text);
-                            article.update_size();
-                            
-                            // Extract categories from content
-                            let content = article.content.clone();
-                            for line in content.lines() {
-                                if line.starts_with("[[Category:") {
-                                    let cat = line.trim_start_matches("[[Category:")
-                                        .trim_end_matches("]]")
-                                        .trim()
-                                        .to_string();
-                                    article.add_category(cat);
-                                }
-                            }
-
-                            // Extract images from content
-                            let content = article.content.clone();
-                            for line in content.lines() {
-                                if line.starts_with("[[File:") || line.starts_with("[[Image:") {
-                                    let img = line.trim_start_matches("[[File:")
-                                        .trim_start_matches("[[Image:")
-                                        .trim_end_matches("]]")
-                                        .trim();
-                                    let parts: Vec<&str> = img.split('|').collect();
-                                    if !parts.is_empty() {
-                                        let filename = parts[0].to_string();
-                                        let caption = parts.get(1).map(|s| s.to_string());
-                                        let image = WikiImage::new(
-                                            filename.clone(),
-                                            format!("/images/{}", filename),
-                                            "image/unknown".to_string(),
-                                            "".to_string(),
-                                        ).with_caption(caption.unwrap_or_default());
-                                        article.add_image(image);
-                                    }
-                                }
-                            }
-
-                            callback(article)?;
-                            count += 1;
-                            if count % 1000 == 0 {
-                                info!("Processed {} articles", count);
-                            }
+                        if !self.keep_raw_wikitext {
+                            article.raw_wikitext = None;
                         }
-                        in_page = false;
-                        current_text.clear();
-                    }
-                    b"title" => in_title = false,
-                    b"text" => in_text = false,
-                    b"redirect" => in_redirect = false,
-                    _ => (),
-                },
-                Ok(Event::Text(e)) => {
-                    if in_page {
-                        if in_title {
-                            let title = String::from_utf8_lossy(&e).into_owned();
-                            current_article = Some(WikiArticle::new(title, String::new()));
-                        } else if in_text {
-                            current_text.push_str(&String::from_utf8_lossy(&e).into_owned());
+                        let keep_going = callback(article)?;
+                        count += 1;
+                        if count % 1000 == 0 {
+                            info!("Processed {} articles", count);
+                        }
+                        if !keep_going {
+                            info!("Stopping after {} articles", count);
+                            break;
                         }
                     }
                 }
-                Ok(Event::Eof) => break,
                 Err(e) => {
                     warn!("Error parsing XML: {}", e);
                     return Err(WikiError::Parse(e.to_string()));
                 }
-                _ => (),
             }
         }
 
@@ -246,9 +320,31 @@ impl WikiXmlParser {
         Ok(count)
     }
 
-    pub fn parse(&mut self, content: &str) -> WikiResult<Vec<WikiArticle>> {
+    /// Attach `reader` as the dump and stream its articles to `callback` as each
+    /// `</page>` is reached, with this parser's limits and cleaning options.
+    ///
+    /// Only the page being parsed is held in memory, so a decompressing reader
+    /// such as a `BzDecoder` can feed a dump of any size.
+    pub fn parse_reader<R, F>(&mut self, reader: R, callback: F) -> WikiResult<usize>
+    where
+        R: BufRead + Send + 'static,
+        F: FnMut(WikiArticle) -> WikiResult<()>,
+    {
+        let reader: DumpReader = Box::new(reader);
+        self.reader = Some(Reader::from_reader(reader));
+        self.metadata = None;
+        self.page_started = false;
+        self.parse_articles(callback)
+    }
+
+    /// Parse every article of an in-memory dump with this parser's limits and cleaning options
+    pub fn parse(&self, content: &str) -> WikiResult<Vec<WikiArticle>> {
         let mut articles = Vec::new();
-        let mut parser = WikiXmlParser::from_string(content);
+        let mut parser = WikiXmlParser::from_string(content)
+            .with_max_articles(self.max_articles)
+            .with_clean_options(self.clean_options.clone())
+            .with_namespaces(self.namespaces.clone())
+            .with_raw_wikitext(self.keep_raw_wikitext);
         
         parser.parse_articles(|article| {
             articles.push(article);
@@ -257,6 +353,236 @@ impl WikiXmlParser {
         
         Ok(articles)
     }
+
+    /// Stream articles from an async reader, keeping those accepted by `filter`
+    pub async fn parse_async<R, F>(&self, reader: R, filter: F) -> WikiResult<Vec<WikiArticle>>
+    where
+        R: AsyncBufRead + Unpin,
+        F: Fn(&WikiArticle) -> bool,
+    {
+        let mut reader = Reader::from_reader(reader);
+        let mut buf = Vec::new();
+        let mut state = PageState::new(self.clean_options.clone(), self.namespaces.clone(), self.keep_raw_wikitext);
+        let mut articles = Vec::new();
+        let mut count = 0;
+
+        loop {
+            buf.clear();
+            match reader.read_event_into_async(&mut buf).await {
+                Ok(Event::Eof) => break,
+                Ok(event) => {
+                    if let Some(article) = state.handle_event(&event) {
+                        count += 1;
+                        if count % 1000 == 0 {
+                            info!("Processed {} articles", count);
+                        }
+                        if filter(&article) {
+                            articles.push(article);
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!("Error parsing XML: {}", e);
+                    return Err(WikiError::Parse(e.to_string()));
+                }
+            }
+        }
+
+        info!("Finished processing {} articles, kept {}", count, articles.len());
+        Ok(articles)
+    }
+}
+
+/// Tracks the `<page>` element currently being parsed
+#[derive(Default)]
+struct PageState {
+    current_article: Option<WikiArticle>,
+    in_page: bool,
+    in_title: bool,
+    in_text: bool,
+    in_redirect: bool,
+    /// Inside `<revision>`, whose own `<id>` elements are not the page id
+    in_revision: bool,
+    in_page_id: bool,
+    in_timestamp: bool,
+    in_ns: bool,
+    /// Namespace from the page's `<ns>`, if it has one
+    namespace: Option<i32>,
+    current_text: String,
+    /// Text of the latest `<revision><timestamp>`, applied once the page ends
+    timestamp: Option<String>,
+    clean_options: CleanOptions,
+    namespaces: HashSet<i32>,
+    keep_raw_wikitext: bool,
+}
+
+impl PageState {
+    fn new(clean_options: CleanOptions, namespaces: HashSet<i32>, keep_raw_wikitext: bool) -> Self {
+        Self {
+            clean_options,
+            namespaces,
+            keep_raw_wikitext,
+            ..Default::default()
+        }
+    }
+
+    /// Feed an XML event, returning the article once its `</page>` is reached
+    fn handle_event(&mut self, event: &Event) -> Option<WikiArticle> {
+        match event {
+            Event::Start(e) => match e.name().as_ref() {
+                b"page" => {
+                    self.in_page = true;
+                    self.current_article = None;
+                    self.timestamp = None;
+                    self.namespace = None;
+                }
+                b"title" => self.in_title = true,
+                b"text" => self.in_text = true,
+                b"revision" => self.in_revision = true,
+                b"id" if self.in_page && !self.in_revision => self.in_page_id = true,
+                b"timestamp" if self.in_revision => self.in_timestamp = true,
+                b"ns" if self.in_page => self.in_ns = true,
+                b"redirect" => {
+                    self.in_redirect = true;
+                    self.record_redirect(e);
+                }
+                _ => (),
+            },
+            // Dumps write redirects as a self-closing `<redirect title="..." />`
+            Event::Empty(e) if e.name().as_ref() == b"redirect" => self.record_redirect(e),
+            Event::End(e) => match e.name().as_ref() {
+                b"page" => {
+                    let timestamp = self.timestamp.take();
+                    let namespace = self.namespace.take().unwrap_or(ARTICLE_NAMESPACE);
+                    let skipped = !self.namespaces.contains(&namespace);
+                    if skipped {
+                        if let Some(ref article) = self.current_article {
+                            debug!("Skipping '{}' in namespace {}", article.title, namespace);
+                        }
+                    }
+                    let article = self.current_article.take().filter(|_| !skipped).map(|mut article| {
+                        Self::apply_timestamp(&mut article, timestamp.as_deref());
+                        if article.is_redirect() {
+                            Self::finish_redirect(article)
+                        } else {
+                            Self::finish_article(article, &self.current_text, &self.clean_options, self.keep_raw_wikitext)
+                        }
+                    });
+                    self.in_page = false;
+                    self.current_text.clear();
+                    return article;
+                }
+                b"title" => self.in_title = false,
+                b"text" => self.in_text = false,
+                b"revision" => self.in_revision = false,
+                b"id" => self.in_page_id = false,
+                b"timestamp" => self.in_timestamp = false,
+                b"ns" => self.in_ns = false,
+                b"redirect" => self.in_redirect = false,
+                _ => (),
+            },
+            Event::Text(e) => {
+                if self.in_page {
+                    if self.in_title {
+                        let title = decode_text(e);
+                        self.current_article = Some(WikiArticle::new(title, String::new()));
+                    } else if self.in_text {
+                        self.current_text.push_str(&decode_text(e));
+                    } else if self.in_page_id {
+                        if let Some(ref mut article) = self.current_article {
+                            article.id = decode_text(e).trim().parse().ok();
+                        }
+                    } else if self.in_timestamp {
+                        self.timestamp = Some(decode_text(e));
+                    } else if self.in_ns {
+                        self.namespace = decode_text(e).trim().parse().ok();
+                    }
+                }
+            }
+            // Text may arrive as several fragments, some of them CDATA sections
+            Event::CData(e) => {
+                if self.in_page && self.in_text {
+                    self.current_text.push_str(&String::from_utf8_lossy(e));
+                }
+            }
+            _ => (),
+        }
+
+        None
+    }
+
+    /// Take the redirect target from the `title` attribute of a `<redirect>` element
+    fn record_redirect(&mut self, e: &BytesStart) {
+        if let Some(ref mut article) = self.current_article {
+            for attr in e.attributes().flatten() {
+                if attr.key.as_ref() == b"title" {
+                    let target = attr
+                        .unescape_value()
+                        .map(|value| value.into_owned())
+                        .unwrap_or_else(|_| String::from_utf8_lossy(&attr.value).into_owned());
+                    article.redirect_to = Some(target);
+                }
+            }
+        }
+    }
+
+    /// Date the article from its revision timestamp, keeping the import time when it has none
+    fn apply_timestamp(article: &mut WikiArticle, timestamp: Option<&str>) {
+        match timestamp.map(|ts| DateTime::parse_from_rfc3339(ts.trim())) {
+            Some(Ok(ts)) => article.last_modified = ts.with_timezone(&Utc),
+            Some(Err(e)) => warn!("Invalid revision timestamp for '{}', using the current time: {}", article.title, e),
+            None => warn!("No revision timestamp for '{}', using the current time", article.title),
+        }
+    }
+
+    /// Redirect pages only record their target; the `#REDIRECT` text is not content
+    fn finish_redirect(mut article: WikiArticle) -> WikiArticle {
+        article.content.clear();
+        article.update_size();
+        article
+    }
+
+    fn finish_article(mut article: WikiArticle, text: &str, clean_options: &CleanOptions, keep_raw_wikitext: bool) -> WikiArticle {
+        // Citations are taken from the raw text since cleaning may strip them
+        article.references = extract_references(text);
+        article.langlinks = extract_langlinks(text);
+        article.content = clean_options.clean(text);
+        if keep_raw_wikitext {
+            article.raw_wikitext = Some(text.to_string());
+        }
+        article.update_size();
+        
+        // Categories come from the raw text since flattening links rewrites them
+        for category in extract_categories(text) {
+            article.add_category(category);
+        }
+
+        // Images too, since cleaning removes the `[[File:...]]` links
+        for (filename, options) in extract_images(text) {
+            let mut image = WikiImage::new(
+                filename.clone(),
+                format!("/images/{}", filename),
+                "image/unknown".to_string(),
+                "".to_string(),
+            );
+            // The caption is the last of the `|`-separated options
+            if let Some(caption) = options.as_deref().and_then(|options| options.rsplit('|').next()) {
+                image = image.with_caption(caption.trim().to_string());
+            }
+            article.add_image(image);
+        }
+
+        article
+    }
+}
+
+/// Text content with XML entities (`&amp;`, `&lt;`, ...) decoded, falling back
+/// to the raw text when it contains an unknown entity
+fn decode_text(e: &BytesText) -> String {
+    match e.unescape() {
+        Ok(text) => text.into_owned(),
+        Err(_) => String::from_utf8_lossy(e).into_owned(),
+    }
 }
 
 #[cfg(test)]
@@ -287,6 +613,7 @@ mod tests {
         
         assert_eq!(metadata.lang, "en");
         assert!(metadata.version.contains("MediaWiki"));
+        assert_eq!(metadata.dump_date.map(|date| date.to_rfc3339()).as_deref(), Some("2024-03-11T00:00:00+00:00"));
 
         let mut articles = Vec::new();
         parser.parse_articles(|article| {
@@ -297,7 +624,9 @@ mod tests {
         assert_eq!(articles.len(), 1);
         assert_eq!(articles[0].title, "Test Article");
         assert!(!articles[0].categories.is_empty());
-        assert!(!articles[0].images.is_empty());
+        assert_eq!(articles[0].images.len(), 1);
+        assert_eq!(articles[0].images[0].filename, "Test.jpg");
+        assert_eq!(articles[0].images[0].caption.as_deref(), Some("Test image"));
         Ok(())
     }
 
@@ -315,7 +644,7 @@ mod tests {
         let mut temp_file = NamedTempFile::new().unwrap();
         write!(temp_file, "{}", xml_content).unwrap();
 
-        let mut parser = WikiXmlParser::new(temp_file.path())?;
+        let mut parser = WikiXmlParser::from_file(temp_file.path())?;
         let mut articles = Vec::new();
 
         parser.parse_articles(|article| {
@@ -328,4 +657,468 @@ mod tests {
         assert_eq!(articles[0].redirect_to.as_deref(), Some("Target Article"));
         Ok(())
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_redirect_page_has_no_content() -> WikiResult<()> {
+        let xml_content = r#"
+        <mediawiki>
+            <siteinfo><sitename>Wikipedia</sitename><lang>en</lang></siteinfo>
+            <page>
+                <title>Old Name</title>
+                <redirect title="New Name" />
+                <revision>
+                    <text>#REDIRECT [[New Name]]
+[[Category:Redirects from moves]]
+[[File:Logo.png|thumb]]</text>
+                </revision>
+            </page>
+            <page>
+                <title>New Name</title>
+                <revision><text>Real article text.</text></revision>
+            </page>
+        </mediawiki>"#;
+
+        let mut parser = WikiXmlParser::from_string(xml_content);
+        let mut articles = Vec::new();
+        parser.parse_articles(|article| {
+            articles.push(article);
+            Ok(())
+        })?;
+
+        assert_eq!(articles.len(), 2);
+        let redirect = &articles[0];
+        assert!(redirect.is_redirect());
+        assert_eq!(redirect.redirect_to.as_deref(), Some("New Name"));
+        assert!(redirect.content.is_empty());
+        assert_eq!(redirect.size, 0);
+        assert!(redirect.categories.is_empty());
+        assert!(redirect.images.is_empty());
+
+        assert!(!articles[1].is_redirect());
+        assert_eq!(articles[1].content, "Real article text.");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_async_parse_with_filter() -> WikiResult<()> {
+        let mut xml_content = String::from("<mediawiki>");
+        for i in 0..6 {
+            xml_content.push_str(&format!(
+                "<page><title>Page {}</title><text>Content {}</text></page>",
+                i, i
+            ));
+        }
+        xml_content.push_str("</mediawiki>");
+
+        let parser = WikiXmlParser::new();
+        let reader = std::io::Cursor::new(xml_content.into_bytes());
+        let articles = parser.parse_async(reader, |article| {
+            article.title
+                .trim_start_matches("Page ")
+                .parse::<usize>()
+                .map(|i| i % 2 == 0)
+                .unwrap_or(false)
+        }).await?;
+
+        let titles: Vec<&str> = articles.iter().map(|a| a.title.as_str()).collect();
+        assert_eq!(titles, vec!["Page 0", "Page 2", "Page 4"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_unattached_parser_needs_no_device() -> WikiResult<()> {
+        // Nothing is opened, so construction cannot fail where /dev/null is missing
+        let mut parser = WikiXmlParser::default();
+        assert!(parser.reader.is_none());
+
+        let articles = parser.parse("<mediawiki><siteinfo><lang>en</lang></siteinfo><page><title>Solo</title><text>Alone.</text></page></mediawiki>")?;
+        assert_eq!(articles.len(), 1);
+        assert_eq!(articles[0].title, "Solo");
+
+        // Streaming without a dump is an error rather than a panic or silent no-op
+        assert!(matches!(parser.parse_metadata(), Err(WikiError::Parse(_))));
+        assert!(matches!(parser.parse_articles(|_| Ok(())), Err(WikiError::Parse(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_applies_parser_options() -> WikiResult<()> {
+        let xml_content = "<mediawiki><siteinfo><lang>en</lang></siteinfo>\
+            <page><title>Talk:One</title><ns>1</ns><text>Discussion.</text></page>\
+            <page><title>Two</title><ns>0</ns><text>Second.</text></page>\
+            <page><title>Three</title><ns>0</ns><text>Third.</text></page></mediawiki>";
+
+        let parser = WikiXmlParser::new().with_max_articles(Some(1));
+        let articles = parser.parse(xml_content)?;
+        let titles: Vec<&str> = articles.iter().map(|a| a.title.as_str()).collect();
+        assert_eq!(titles, vec!["Two"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_unclosed_siteinfo_is_not_read_to_eof() -> WikiResult<()> {
+        // Namespaces keep the header going past the budget without ever closing it
+        let mut xml_content = String::from("<mediawiki><siteinfo><sitename>Wikipedia</sitename><lang>en</lang><namespaces>");
+        while xml_content.len() < 4 * MAX_SITEINFO_BYTES {
+            xml_content.push_str("<namespace key=\"0\" case=\"first-letter\" />");
+        }
+        xml_content.push_str("</namespaces></mediawiki>");
+
+        let mut parser = WikiXmlParser::from_string(&xml_content);
+        let metadata = parser.parse_metadata()?;
+        assert_eq!(metadata.lang, "en");
+        assert_eq!(metadata.dump_date, None);
+        let position = parser.reader.as_ref().unwrap().buffer_position();
+        assert!(position < 2 * MAX_SITEINFO_BYTES, "read {} of {} bytes", position, xml_content.len());
+
+        // A header cut short by the first page keeps that page
+        let xml_content = "<mediawiki><siteinfo><lang>de</lang>\
+            <page><title>Erste</title><text>Eins.</text></page>\
+            <page><title>Zweite</title><text>Zwei.</text></page></mediawiki>";
+        let mut parser = WikiXmlParser::from_string(xml_content);
+        let mut titles = Vec::new();
+        parser.parse_articles(|article| {
+            titles.push(article.title);
+            Ok(())
+        })?;
+        assert_eq!(titles, vec!["Erste", "Zweite"]);
+        assert_eq!(parser.parse_metadata()?.lang, "de");
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_stops_at_max_articles() -> WikiResult<()> {
+        let mut xml_content = String::from(
+            "<mediawiki><siteinfo><sitename>Wikipedia</sitename><lang>en</lang></siteinfo>"
+        );
+        for i in 0..20 {
+            xml_content.push_str(&format!(
+                "<page><title>Page {}</title><text>Content {}</text></page>",
+                i, i
+            ));
+        }
+        xml_content.push_str("</mediawiki>");
+
+        let mut temp_file = NamedTempFile::new().unwrap();
+        write!(temp_file, "{}", xml_content).unwrap();
+
+        let mut parser = WikiXmlParser::from_file(temp_file.path())?.with_max_articles(Some(2));
+        let mut articles = Vec::new();
+        let count = parser.parse_articles(|article| {
+            articles.push(article);
+            Ok(())
+        })?;
+
+        assert_eq!(count, 2);
+        assert_eq!(articles.len(), 2);
+        assert_eq!(articles[1].title, "Page 1");
+        // The reader should not have consumed the rest of the dump
+        assert!((parser.reader.as_ref().unwrap().buffer_position() as usize) < xml_content.len() / 2);
+
+        let mut parser = WikiXmlParser::from_file(temp_file.path())?.with_max_articles(Some(0));
+        assert_eq!(parser.parse_articles(|_| Ok(()))?, 0);
+
+        // The callback can stop the parse as well
+        let mut parser = WikiXmlParser::from_file(temp_file.path())?;
+        let count = parser.parse_articles_while(|article| Ok(article.title != "Page 2"))?;
+        assert_eq!(count, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_template_report_counts_stripped_templates() -> WikiResult<()> {
+        let mut xml = String::from("<mediawiki><siteinfo><sitename>Wikipedia</sitename></siteinfo>");
+        for i in 0..5 {
+            xml.push_str(&format!(
+                "<page><title>Page {}</title><revision><text>{{{{Infobox settlement|name=Town {}}}}} Body. {{{{convert|{}|km}}}}</text></revision></page>",
+                i, i, i
+            ));
+        }
+        xml.push_str("</mediawiki>");
+
+        let mut parser = WikiXmlParser::from_string(&xml).with_template_report(true);
+        let count = parser.parse_articles(|article| {
+            assert!(!article.content.contains("{{"));
+            // The report reads the wikitext without keeping it on the article
+            assert!(article.raw_wikitext.is_none());
+            Ok(())
+        })?;
+        assert_eq!(count, 5);
+
+        let report = parser.template_report().unwrap();
+        assert_eq!(report.total(), 10);
+        assert_eq!(report.most_common(1), vec![("Infobox settlement".to_string(), 5)]);
+
+        // Disabled by default
+        assert!(WikiXmlParser::from_string(&xml).template_report().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_raw_wikitext_is_kept_only_when_enabled() -> WikiResult<()> {
+        let xml_content = "<mediawiki><page><title>Raw</title><revision><text>{{Infobox}} Body [[text]]</text></revision></page></mediawiki>";
+
+        let articles = WikiXmlParser::new().parse(xml_content)?;
+        assert_eq!(articles[0].raw_wikitext, None);
+
+        let articles = WikiXmlParser::new().with_raw_wikitext(true).parse(xml_content)?;
+        assert_eq!(articles[0].raw_wikitext.as_deref(), Some("{{Infobox}} Body [[text]]"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_cdata_text() -> WikiResult<()> {
+        let xml_content = r#"
+        <mediawiki>
+            <page>
+                <title>Markup</title>
+                <text>Before. <![CDATA[Inside <b>CDATA</b>.]]> After.</text>
+            </page>
+        </mediawiki>"#;
+
+        let mut parser = WikiXmlParser::from_string(xml_content);
+        let mut articles = Vec::new();
+        parser.parse_articles(|article| {
+            articles.push(article);
+            Ok(())
+        })?;
+
+        assert_eq!(articles.len(), 1);
+        assert!(articles[0].content.contains("Before."));
+        assert!(articles[0].content.contains("CDATA"));
+        assert!(articles[0].content.contains("After."));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_decodes_entities() -> WikiResult<()> {
+        let xml_content = r#"
+        <mediawiki>
+            <page>
+                <title>Special &amp; Characters</title>
+                <redirect title="Tom &amp; Jerry" />
+            </page>
+            <page>
+                <title>Q&amp;A</title>
+                <text>Uses &lt;special&gt; characters &amp; symbols.</text>
+            </page>
+        </mediawiki>"#;
+
+        let mut parser = WikiXmlParser::from_string(xml_content)
+            .with_clean_options(CleanOptions { strip_html: false, ..Default::default() });
+        let mut articles = Vec::new();
+        parser.parse_articles(|article| {
+            articles.push(article);
+            Ok(())
+        })?;
+
+        assert_eq!(articles.len(), 2);
+        assert_eq!(articles[0].title, "Special & Characters");
+        assert_eq!(articles[0].redirect_to.as_deref(), Some("Tom & Jerry"));
+        assert_eq!(articles[1].title, "Q&A");
+        assert_eq!(articles[1].content, "Uses <special> characters & symbols.");
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_special_characters() -> WikiResult<()> {
+        let xml_content = r#"<mediawiki>
+  <page>
+    <title>Special &amp; Characters</title>
+    <ns>0</ns>
+    <id>1</id>
+    <revision>
+      <text>This article contains &lt;special&gt; characters &amp; symbols.</text>
+    </revision>
+  </page>
+</mediawiki>"#;
+        let parse = |options: CleanOptions| -> WikiResult<Vec<WikiArticle>> {
+            let mut parser = WikiXmlParser::from_string(xml_content).with_clean_options(options);
+            let mut articles = Vec::new();
+            parser.parse_articles(|article| {
+                articles.push(article);
+                Ok(())
+            })?;
+            Ok(articles)
+        };
+
+        // Decoded entities survive when the markup is kept
+        let articles = parse(CleanOptions { strip_html: false, ..Default::default() })?;
+        assert_eq!(articles.len(), 1);
+        assert_eq!(articles[0].title, "Special & Characters");
+        assert_eq!(articles[0].content, "This article contains <special> characters & symbols.");
+
+        // The default cleaning strips the tag they spelled out
+        let articles = parse(CleanOptions::default())?;
+        assert!(articles[0].content.starts_with("This article contains"));
+        assert!(articles[0].content.ends_with("characters & symbols."));
+        assert!(!articles[0].content.contains("<special>"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_keeps_only_allowed_namespaces() -> WikiResult<()> {
+        let xml_content = "<mediawiki><siteinfo><lang>en</lang></siteinfo>\
+            <page><title>Article</title><ns>0</ns><text>An article.</text></page>\
+            <page><title>Talk:Article</title><ns>1</ns><text>A discussion.</text></page>\
+            <page><title>User:Someone</title><ns>2</ns><text>A user page.</text></page>\
+            <page><title>Untagged</title><text>No namespace given.</text></page></mediawiki>";
+        let parse_titles = |parser: WikiXmlParser| -> WikiResult<Vec<String>> {
+            let mut parser = parser;
+            let mut titles = Vec::new();
+            parser.parse_articles(|article| {
+                titles.push(article.title);
+                Ok(())
+            })?;
+            Ok(titles)
+        };
+
+        // Articles only by default; a page without <ns> is an article
+        let titles = parse_titles(WikiXmlParser::from_string(xml_content))?;
+        assert_eq!(titles, vec!["Article", "Untagged"]);
+
+        // Talk pages (namespace 1) when asked for
+        let parser = WikiXmlParser::from_string(xml_content).with_namespaces(HashSet::from([0, 1]));
+        let titles = parse_titles(parser)?;
+        assert_eq!(titles, vec!["Article", "Talk:Article", "Untagged"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_page_id() -> WikiResult<()> {
+        let xml_content = r#"
+        <mediawiki>
+            <page>
+                <title>Numbered</title>
+                <ns>0</ns>
+                <id>42</id>
+                <revision>
+                    <id>9001</id>
+                    <contributor><id>7</id></contributor>
+                    <text>Has an id.</text>
+                </revision>
+            </page>
+        </mediawiki>"#;
+
+        let mut parser = WikiXmlParser::from_string(xml_content);
+        let mut articles = Vec::new();
+        parser.parse_articles(|article| {
+            articles.push(article);
+            Ok(())
+        })?;
+
+        assert_eq!(articles.len(), 1);
+        assert_eq!(articles[0].id, Some(42));
+        Ok(())
+    }
+
+    /// Dump of `pages` small pages generated as it is read, so the whole document never exists
+    struct SyntheticDump {
+        pages: usize,
+        next_page: usize,
+        pending: Vec<u8>,
+        offset: usize,
+        finished: bool,
+        produced: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl std::io::Read for SyntheticDump {
+        fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+            if self.offset == self.pending.len() {
+                self.pending.clear();
+                self.offset = 0;
+                if self.next_page < self.pages {
+                    if self.next_page == 0 {
+                        self.pending.extend_from_slice(b"<mediawiki><siteinfo><lang>en</lang></siteinfo>");
+                    }
+                    let page = format!(
+                        "<page><title>Page {0}</title><ns>0</ns><id>{0}</id><revision><text>Body of page {0}.</text></revision></page>",
+                        self.next_page
+                    );
+                    self.pending.extend_from_slice(page.as_bytes());
+                    self.next_page += 1;
+                } else if !self.finished {
+                    self.pending.extend_from_slice(b"</mediawiki>");
+                    self.finished = true;
+                }
+            }
+            let n = out.len().min(self.pending.len() - self.offset);
+            out[..n].copy_from_slice(&self.pending[self.offset..self.offset + n]);
+            self.offset += n;
+            self.produced.fetch_add(n, std::sync::atomic::Ordering::Relaxed);
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_parse_reader_streams_large_dump() -> WikiResult<()> {
+        use std::sync::atomic::Ordering;
+
+        let pages = 50_000;
+        let produced = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let dump = SyntheticDump {
+            pages,
+            next_page: 0,
+            pending: Vec::new(),
+            offset: 0,
+            finished: false,
+            produced: produced.clone(),
+        };
+
+        let mut parser = WikiXmlParser::new();
+        let mut count = 0;
+        let mut produced_at_first = 0;
+        let parsed = parser.parse_reader(BufReader::new(dump), |article| {
+            if count == 0 {
+                produced_at_first = produced.load(Ordering::Relaxed);
+            }
+            assert_eq!(article.title, format!("Page {}", count));
+            count += 1;
+            Ok(())
+        })?;
+
+        let total = produced.load(Ordering::Relaxed);
+        assert_eq!((parsed, count), (pages, pages));
+        // The first article arrives after a buffer's worth of input, not the whole dump
+        assert!(produced_at_first < 64 * 1024);
+        assert!(total > 5 * 1024 * 1024);
+        // The event buffer only ever held a single element
+        assert!(parser.buf.capacity() < 4 * 1024);
+        assert_eq!(parser.parse_metadata()?.lang, "en");
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_revision_timestamp() -> WikiResult<()> {
+        let xml_content = r#"
+        <mediawiki>
+            <page>
+                <title>Dated</title>
+                <revision>
+                    <timestamp>2021-05-01T12:00:00Z</timestamp>
+                    <text>Has a timestamp.</text>
+                </revision>
+            </page>
+            <page>
+                <title>Garbled</title>
+                <revision>
+                    <timestamp>yesterday</timestamp>
+                    <text>Has a bad timestamp.</text>
+                </revision>
+            </page>
+        </mediawiki>"#;
+
+        let before = Utc::now();
+        let mut parser = WikiXmlParser::from_string(xml_content);
+        let mut articles = Vec::new();
+        parser.parse_articles(|article| {
+            articles.push(article);
+            Ok(())
+        })?;
+
+        let expected = DateTime::parse_from_rfc3339("2021-05-01T12:00:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(articles[0].last_modified, expected);
+        assert!(articles[1].last_modified >= before);
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+//! Similarity helpers over plain `f32` slices, for callers working with
+//! embeddings outside the [`VectorStore`](super::VectorStore)
+
+use std::cmp::Ordering;
+
+use crate::error_handling::{WikiError, WikiResult};
+
+fn check_lengths(a: &[f32], b: &[f32]) -> WikiResult<()> {
+    if a.len() != b.len() {
+        return Err(WikiError::InvalidVectorDimension(a.len(), b.len()));
+    }
+    Ok(())
+}
+
+/// Dot product of two vectors of the same length
+///
+/// ```
+/// use davinci3_wiki::vector::math::dot;
+///
+/// assert_eq!(dot(&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]).unwrap(), 32.0);
+/// assert!(dot(&[1.0], &[1.0, 2.0]).is_err());
+/// ```
+pub fn dot(a: &[f32], b: &[f32]) -> WikiResult<f32> {
+    check_lengths(a, b)?;
+    Ok(a.iter().zip(b).map(|(x, y)| x * y).sum())
+}
+
+/// Cosine of the angle between two vectors, in `[-1, 1]`; 0 when either is all zeros
+///
+/// ```
+/// use davinci3_wiki::vector::math::cosine_similarity;
+///
+/// let similarity = cosine_similarity(&[1.0, 1.0], &[2.0, 2.0]).unwrap();
+/// assert!((similarity - 1.0).abs() < 1e-6);
+/// ```
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> WikiResult<f32> {
+    let product = dot(a, b)?;
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 {
+        return Ok(0.0);
+    }
+    Ok(product / norms)
+}
+
+/// Scale a vector to unit length; an all-zero vector is returned unchanged
+///
+/// ```
+/// use davinci3_wiki::vector::math::normalize;
+///
+/// assert_eq!(normalize(&[3.0, 4.0]), vec![0.6, 0.8]);
+/// ```
+pub fn normalize(v: &[f32]) -> Vec<f32> {
+    let length = norm(v);
+    if length == 0.0 {
+        return v.to_vec();
+    }
+    v.iter().map(|x| x / length).collect()
+}
+
+/// The `k` candidates most similar to `query` by cosine similarity, best first
+///
+/// ```
+/// use davinci3_wiki::vector::math::top_k;
+///
+/// let candidates = vec![("x", vec![1.0, 0.0]), ("y", vec![0.0, 1.0]), ("xy", vec![1.0, 1.0])];
+/// let best = top_k(&[1.0, 0.1], &candidates, 2).unwrap();
+/// assert_eq!(best.iter().map(|(key, _)| *key).collect::<Vec<_>>(), vec!["x", "xy"]);
+/// ```
+pub fn top_k<K: Clone>(query: &[f32], candidates: &[(K, Vec<f32>)], k: usize) -> WikiResult<Vec<(K, f32)>> {
+    let mut scored = candidates
+        .iter()
+        .map(|(key, vector)| Ok((key.clone(), cosine_similarity(query, vector)?)))
+        .collect::<WikiResult<Vec<_>>>()?;
+    scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+    scored.truncate(k);
+    Ok(scored)
+}
+
+fn norm(v: &[f32]) -> f32 {
+    v.iter().map(|x| x * x).sum::<f32>().sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cosine_similarity_of_special_cases() -> WikiResult<()> {
+        let a = [1.0, 2.0, 3.0];
+        assert!((cosine_similarity(&a, &a)? - 1.0).abs() < 1e-6);
+        assert!((cosine_similarity(&a, &[-1.0, -2.0, -3.0])? + 1.0).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0])?, 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0])?, 0.0);
+
+        match cosine_similarity(&a, &[1.0, 2.0]) {
+            Err(WikiError::InvalidVectorDimension(3, 2)) => {}
+            other => panic!("expected a dimension error, got {:?}", other.map(|_| ())),
+        }
+        assert!(top_k(&a, &[("short", vec![1.0])], 1).is_err());
+        Ok(())
+    }
+}
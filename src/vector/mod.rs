@@ -1,15 +1,62 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
 use std::path::{Path, PathBuf};
-use heed::{Database, Env, EnvOpenOptions};
+use std::sync::Arc;
+use heed::{CompactionOption, Database, Env, EnvOpenOptions};
 use ndarray::{Array1, ArrayView1};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use tracing::{debug, info, warn};
 use rayon::prelude::*;
+use tokio::sync::Semaphore;
 
 use crate::error_handling::{WikiError, WikiResult};
 
+pub mod math;
+
 const VECTOR_SIZE: usize = 1536; // OpenAI embedding size
 const MAX_BATCH_SIZE: usize = 32;
+/// Below this many stored vectors the scan stays on one thread
+const PARALLEL_SCAN_THRESHOLD: usize = 1024;
+/// Vectors decoded at a time by a similarity scan, bounding its memory to one chunk plus the results
+const SCAN_CHUNK_SIZE: usize = 4096;
+
+/// Model used to generate embeddings unless configured otherwise
+pub const DEFAULT_EMBEDDING_MODEL: &str = "llama2";
+
+/// Characters of input sent to the embedding model; roughly 2k tokens, within llama2's context
+pub const DEFAULT_MAX_EMBEDDING_CHARS: usize = 8192;
+
+/// Name of the LMDB data file inside the store directory
+const DATA_FILE_NAME: &str = "data.mdb";
+/// Compacted copy written next to the data file before it replaces it
+const COMPACTED_FILE_NAME: &str = "data.mdb.compact";
+
+/// LMDB map size used when the corpus size is not known
+pub const DEFAULT_MAP_SIZE: usize = 10 * 1024 * 1024 * 1024; // 10GB
+const MIN_MAP_SIZE: usize = 64 * 1024 * 1024; // 64MB
+/// Allowance per entry for the title key, the model tag and LMDB node headers
+const ENTRY_OVERHEAD_BYTES: usize = 512;
+/// Headroom for B-tree page fill and free pages
+const MAP_SIZE_HEADROOM: usize = 2;
+const MAP_SIZE_ALIGNMENT: usize = 1024 * 1024;
+
+/// Estimate the LMDB map size needed for `article_count` embeddings of `dimension` floats.
+///
+/// An explicit `override_size` always wins. The estimate is rounded up to a whole MiB
+/// (a multiple of any OS page size) and never goes below 64MB.
+pub fn estimate_map_size(article_count: usize, dimension: usize, override_size: Option<usize>) -> usize {
+    if let Some(size) = override_size {
+        return size;
+    }
+
+    let per_entry = dimension.saturating_mul(std::mem::size_of::<f32>()) + ENTRY_OVERHEAD_BYTES;
+    let needed = article_count
+        .saturating_mul(per_entry)
+        .saturating_mul(MAP_SIZE_HEADROOM)
+        .max(MIN_MAP_SIZE);
+    needed.div_ceil(MAP_SIZE_ALIGNMENT).saturating_mul(MAP_SIZE_ALIGNMENT)
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EmbeddingRequest {
@@ -27,42 +74,188 @@ pub struct Embedding {
     embedding: Vec<f32>,
 }
 
+/// How stored vectors are ranked against a query; higher scores rank first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SimilarityMetric {
+    /// Cosine of the angle between the vectors
+    #[default]
+    Cosine,
+    /// Raw dot product; equivalent to cosine for normalized embeddings, and cheaper
+    Dot,
+    /// Negated L2 distance, so closer vectors score higher
+    Euclidean,
+}
+
+impl SimilarityMetric {
+    pub fn score(&self, a: &ArrayView1<f32>, b: &ArrayView1<f32>) -> f32 {
+        match self {
+            // Views over stored vectors are contiguous; a vector of another dimension is unrelated
+            SimilarityMetric::Cosine => match (a.as_slice(), b.as_slice()) {
+                (Some(a), Some(b)) => math::cosine_similarity(a, b).unwrap_or(0.0),
+                _ => math::cosine_similarity(&a.to_vec(), &b.to_vec()).unwrap_or(0.0),
+            },
+            SimilarityMetric::Dot => a.dot(b),
+            SimilarityMetric::Euclidean => {
+                -a.iter().zip(b.iter()).map(|(x, y)| (x - y) * (x - y)).sum::<f32>().sqrt()
+            }
+        }
+    }
+}
+
+/// Bounds on a nearest-neighbour lookup, trading recall for precision
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimilarityOptions {
+    /// Most neighbours returned, best first
+    pub max_candidates: usize,
+    /// Drop neighbours scoring below this, in the units of the store's metric
+    pub min_score: Option<f32>,
+}
+
+impl SimilarityOptions {
+    /// The best `max_candidates` neighbours, however weak
+    pub fn new(max_candidates: usize) -> Self {
+        Self { max_candidates, min_score: None }
+    }
+
+    /// Only keep neighbours scoring at least `min_score`
+    pub fn with_min_score(mut self, min_score: f32) -> Self {
+        self.min_score = Some(min_score);
+        self
+    }
+
+    fn accepts(&self, score: f32) -> bool {
+        self.min_score.is_none_or(|min_score| score >= min_score)
+    }
+}
+
+/// The first `max_chars` characters of `text`
+fn truncate_chars(text: &str, max_chars: usize) -> &str {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
+type VectorDb = Database<heed::types::Str, heed::types::SerdeBincode<Vec<f32>>>;
+/// Title -> name of the model that produced the stored vector
+type ModelDb = Database<heed::types::Str, heed::types::Str>;
+
 pub struct VectorStore {
     env: Env,
-    db: Database<heed::types::Str, heed::types::SerdeBincode<Vec<f32>>>,
+    db: VectorDb,
+    models: ModelDb,
     client: Client,
     ollama_url: String,
+    embedding_model: String,
+    metric: SimilarityMetric,
+    max_input_chars: usize,
+    /// Caps embedding requests in flight to Ollama; unlimited when unset
+    embedding_permits: Option<(usize, Arc<Semaphore>)>,
 }
 
 impl VectorStore {
-    pub fn new<P: AsRef<Path>>(path: P, ollama_url: &str) -> WikiResult<Self> {
+    pub async fn new<P: AsRef<Path>>(path: P, ollama_url: &str) -> WikiResult<Self> {
+        Self::with_map_size(path, ollama_url, DEFAULT_MAP_SIZE).await
+    }
+
+    /// Open the store with an explicit LMDB map size, e.g. from `estimate_map_size`.
+    /// Fails on an `ollama_url` without an http(s) scheme.
+    pub async fn with_map_size<P: AsRef<Path>>(path: P, ollama_url: &str, map_size: usize) -> WikiResult<Self> {
+        let ollama_url = crate::http::normalize_ollama_url(ollama_url)?;
         let path = path.as_ref().to_path_buf();
-        std::fs::create_dir_all(&path)?;
-
-        // LMDB operations require unsafe
-        let env = unsafe {
-            EnvOpenOptions::new()
-                .map_size(10 * 1024 * 1024 * 1024) // 10GB
-                .max_dbs(1)
-                .open(path)?
-        };
 
-        let mut wtxn = env.write_txn()?;
-        let db = env.create_database(&mut wtxn, Some("vectors"))?;
-        wtxn.commit()?;
+        // Opening the LMDB environment is blocking, keep it off the async runtime
+        let (env, db, models) = tokio::task::spawn_blocking(move || -> WikiResult<_> {
+            std::fs::create_dir_all(&path)?;
+
+            // LMDB operations require unsafe
+            let env = unsafe {
+                EnvOpenOptions::new()
+                    .map_size(map_size)
+                    .max_dbs(2)
+                    .open(path)?
+            };
+
+            let mut wtxn = env.write_txn()?;
+            let db = env.create_database(&mut wtxn, Some("vectors"))?;
+            let models = env.create_database(&mut wtxn, Some("models"))?;
+            wtxn.commit()?;
+
+            Ok((env, db, models))
+        })
+        .await
+        .map_err(|e| WikiError::VectorStore(format!("Failed to open vector store: {}", e)))??;
 
         Ok(Self {
             env,
             db,
-            client: Client::new(),
-            ollama_url: ollama_url.to_string(),
+            models,
+            client: crate::http::default_client(),
+            ollama_url,
+            embedding_model: DEFAULT_EMBEDDING_MODEL.to_string(),
+            metric: SimilarityMetric::default(),
+            max_input_chars: DEFAULT_MAX_EMBEDDING_CHARS,
+            embedding_permits: None,
         })
     }
 
+    /// Identify embedding requests with this User-Agent
+    pub fn with_user_agent(mut self, user_agent: &str) -> WikiResult<Self> {
+        self.client = crate::http::client_with_user_agent(user_agent)?;
+        Ok(self)
+    }
+
+    /// Rank `find_similar` results with this metric (cosine by default)
+    pub fn with_similarity_metric(mut self, metric: SimilarityMetric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    /// Generate embeddings with this model; stored vectors are tagged with it
+    pub fn with_embedding_model(mut self, model: &str) -> Self {
+        self.embedding_model = model.to_string();
+        self
+    }
+
+    /// Truncate embedding input to this many characters so it fits the model's context
+    pub fn with_max_input_chars(mut self, max_input_chars: usize) -> Self {
+        self.max_input_chars = max_input_chars;
+        self
+    }
+
+    /// Allow at most `max_concurrent` embedding requests in flight at once (at least one)
+    pub fn with_max_concurrent_embeddings(mut self, max_concurrent: usize) -> Self {
+        let max_concurrent = max_concurrent.max(1);
+        self.embedding_permits = Some((max_concurrent, Arc::new(Semaphore::new(max_concurrent))));
+        self
+    }
+
+    /// The limit set by `with_max_concurrent_embeddings`, if any
+    pub fn max_concurrent_embeddings(&self) -> Option<usize> {
+        self.embedding_permits.as_ref().map(|(max_concurrent, _)| *max_concurrent)
+    }
+
+    pub fn embedding_model(&self) -> &str {
+        &self.embedding_model
+    }
+
     pub async fn generate_embedding(&self, text: &str) -> WikiResult<Vec<f32>> {
+        let input = truncate_chars(text, self.max_input_chars);
+        if input.len() < text.len() {
+            debug!("Truncated embedding input from {} to {} bytes", text.len(), input.len());
+        }
+
         let request = EmbeddingRequest {
-            model: "llama2".to_string(),
-            input: text.to_string(),
+            model: self.embedding_model.clone(),
+            input: input.to_string(),
+        };
+
+        // Held until the response is read, so waiting callers queue here rather than at Ollama
+        let _permit = match &self.embedding_permits {
+            Some((_, permits)) => Some(permits.acquire().await.map_err(|e| {
+                WikiError::VectorStore(format!("Embedding limiter closed: {}", e))
+            })?),
+            None => None,
         };
 
         let response = self.client
@@ -92,53 +285,328 @@ impl VectorStore {
     }
 
     pub fn store_embedding(&self, key: &str, embedding: &[f32]) -> WikiResult<()> {
-        let mut wtxn = self.env.write_txn()?;
-        self.db.put(&mut wtxn, key, &embedding.to_vec())?;
-        wtxn.commit()?;
-        Ok(())
+        put_embedding(&self.env, self.db, self.models, key, embedding, &self.embedding_model)
+    }
+
+    /// Store an embedding tagged with an explicit model name
+    pub fn store_embedding_with_model(&self, key: &str, embedding: &[f32], model: &str) -> WikiResult<()> {
+        put_embedding(&self.env, self.db, self.models, key, embedding, model)
+    }
+
+    /// Model that produced the stored embedding; `None` for untagged or missing vectors
+    pub fn embedding_model_of(&self, key: &str) -> WikiResult<Option<String>> {
+        let rtxn = self.env.read_txn()?;
+        Ok(self.models.get(&rtxn, key)?.map(|model| model.to_string()))
+    }
+
+    /// Keys whose vector was produced by a different (or unknown) model than the current one
+    pub fn stale_keys(&self) -> WikiResult<Vec<String>> {
+        let rtxn = self.env.read_txn()?;
+        let mut stale = Vec::new();
+        for item in self.db.iter(&rtxn)? {
+            let (key, _) = item?;
+            if self.models.get(&rtxn, key)? != Some(self.embedding_model.as_str()) {
+                stale.push(key.to_string());
+            }
+        }
+        Ok(stale)
     }
 
     pub fn get_embedding(&self, key: &str) -> WikiResult<Option<Vec<f32>>> {
+        read_embedding(&self.env, self.db, key)
+    }
+
+    /// Remove an embedding, returning whether one was stored under `key`
+    pub fn delete_embedding(&self, key: &str) -> WikiResult<bool> {
+        remove_embedding(&self.env, self.db, self.models, key)
+    }
+
+    /// Number of stored embeddings
+    pub fn len(&self) -> WikiResult<usize> {
         let rtxn = self.env.read_txn()?;
-        Ok(self.db.get(&rtxn, key)?)
+        Ok(self.db.len(&rtxn)? as usize)
+    }
+
+    pub fn is_empty(&self) -> WikiResult<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Bytes the store currently takes on disk, including free pages
+    pub fn disk_size(&self) -> WikiResult<u64> {
+        Ok(self.env.real_disk_size()?)
+    }
+
+    /// Rewrite the store without the free pages left behind by updates and deletes.
+    ///
+    /// A compacted copy is written next to the data file, the environment is closed and
+    /// the copy renamed over the original, so an interruption leaves one of the two intact.
+    /// Other processes must not have the store open while it is compacted.
+    pub async fn compact(self) -> WikiResult<Self> {
+        let Self { env, db: _, models: _, client, ollama_url, embedding_model, metric, max_input_chars, embedding_permits } = self;
+        let map_size = env.info().map_size;
+
+        let (path, before, after) = tokio::task::spawn_blocking(move || -> WikiResult<_> {
+            let path = env.path().to_path_buf();
+            let before = env.real_disk_size()?;
+            let compacted = path.join(COMPACTED_FILE_NAME);
+            // A copy left by an interrupted compaction is incomplete
+            if compacted.exists() {
+                std::fs::remove_file(&compacted)?;
+            }
+            let after = env.copy_to_file(&compacted, CompactionOption::Enabled)?.metadata()?.len();
+
+            // The environment must be closed before its data file is replaced
+            env.prepare_for_closing().wait();
+            std::fs::rename(&compacted, path.join(DATA_FILE_NAME))?;
+            Ok((path, before, after))
+        })
+        .await
+        .map_err(|e| WikiError::VectorStore(format!("Failed to compact vector store: {}", e)))??;
+        info!("Compacted vector store from {} to {} bytes", before, after);
+
+        let reopened = Self::with_map_size(&path, &ollama_url, map_size).await?;
+        Ok(Self { client, embedding_model, metric, max_input_chars, embedding_permits, ..reopened })
+    }
+
+    /// Id of the last committed write transaction; changes whenever any process writes
+    /// to the store, e.g. a reembed run from the CLI
+    pub fn generation(&self) -> usize {
+        self.env.info().last_txn_id
+    }
+
+    pub fn has_embedding(&self, key: &str) -> WikiResult<bool> {
+        Ok(self.get_embedding(key)?.is_some())
     }
 
     pub fn find_similar(&self, query_embedding: &[f32], limit: usize) -> WikiResult<Vec<(String, f32)>> {
-        let rtxn = self.env.read_txn()?;
-        let query_array = ArrayView1::from(query_embedding);
-        
-        let mut results: Vec<_> = self.db
-            .iter(&rtxn)?
-            .filter_map(|item| item.ok())
-            .map(|(key, embedding)| {
-                let similarity = cosine_similarity(&query_array, &ArrayView1::from(&embedding));
-                (key.to_string(), similarity)
-            })
-            .collect();
-        
-        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-        results.truncate(limit);
-        
-        Ok(results)
+        self.find_similar_with(query_embedding, SimilarityOptions::new(limit))
+    }
+
+    /// Like `find_similar`, also dropping neighbours below `options.min_score`
+    pub fn find_similar_with(&self, query_embedding: &[f32], options: SimilarityOptions) -> WikiResult<Vec<(String, f32)>> {
+        scan_similar(&self.env, self.db, self.metric, query_embedding, options)
+    }
+
+    /// Async variant of `store_embedding` that runs the LMDB write on the blocking pool.
+    pub async fn store_embedding_async(&self, key: &str, embedding: &[f32]) -> WikiResult<()> {
+        let (env, db, models) = (self.env.clone(), self.db, self.models);
+        let key = key.to_string();
+        let embedding = embedding.to_vec();
+        let model = self.embedding_model.clone();
+        run_blocking(move || put_embedding(&env, db, models, &key, &embedding, &model)).await
+    }
+
+    /// Async variant of `get_embedding` that runs the LMDB read on the blocking pool.
+    pub async fn get_embedding_async(&self, key: &str) -> WikiResult<Option<Vec<f32>>> {
+        let (env, db) = (self.env.clone(), self.db);
+        let key = key.to_string();
+        run_blocking(move || read_embedding(&env, db, &key)).await
+    }
+
+    /// Async variant of `delete_embedding` that runs the LMDB write on the blocking pool.
+    pub async fn delete_embedding_async(&self, key: &str) -> WikiResult<bool> {
+        let (env, db, models) = (self.env.clone(), self.db, self.models);
+        let key = key.to_string();
+        run_blocking(move || remove_embedding(&env, db, models, &key)).await
+    }
+
+    /// Async variant of `find_similar`. The linear scan runs on the blocking pool
+    /// so large stores don't stall the tokio workers serving other requests.
+    pub async fn find_similar_async(&self, query_embedding: &[f32], limit: usize) -> WikiResult<Vec<(String, f32)>> {
+        self.find_similar_with_async(query_embedding, SimilarityOptions::new(limit)).await
     }
+
+    /// Async variant of `find_similar_with`
+    pub async fn find_similar_with_async(
+        &self,
+        query_embedding: &[f32],
+        options: SimilarityOptions,
+    ) -> WikiResult<Vec<(String, f32)>> {
+        let (env, db, metric) = (self.env.clone(), self.db, self.metric);
+        let query = query_embedding.to_vec();
+        run_blocking(move || scan_similar(&env, db, metric, &query, options)).await
+    }
+}
+
+async fn run_blocking<T, F>(f: F) -> WikiResult<T>
+where
+    F: FnOnce() -> WikiResult<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| WikiError::VectorStore(format!("Vector store task failed: {}", e)))?
 }
 
-fn cosine_similarity(a: &ArrayView1<f32>, b: &ArrayView1<f32>) -> f32 {
-    let dot_product = a.dot(b);
-    let norm_a = (a.dot(a)).sqrt();
-    let norm_b = (b.dot(b)).sqrt();
-    dot_product / (norm_a * norm_b)
+fn put_embedding(
+    env: &Env,
+    db: VectorDb,
+    models: ModelDb,
+    key: &str,
+    embedding: &[f32],
+    model: &str,
+) -> WikiResult<()> {
+    let mut wtxn = env.write_txn()?;
+    db.put(&mut wtxn, key, &embedding.to_vec())?;
+    models.put(&mut wtxn, key, model)?;
+    wtxn.commit()?;
+    Ok(())
+}
+
+fn remove_embedding(env: &Env, db: VectorDb, models: ModelDb, key: &str) -> WikiResult<bool> {
+    let mut wtxn = env.write_txn()?;
+    let deleted = db.delete(&mut wtxn, key)?;
+    models.delete(&mut wtxn, key)?;
+    wtxn.commit()?;
+    Ok(deleted)
+}
+
+fn read_embedding(env: &Env, db: VectorDb, key: &str) -> WikiResult<Option<Vec<f32>>> {
+    let rtxn = env.read_txn()?;
+    Ok(db.get(&rtxn, key)?)
+}
+
+fn scan_similar(
+    env: &Env,
+    db: VectorDb,
+    metric: SimilarityMetric,
+    query_embedding: &[f32],
+    options: SimilarityOptions,
+) -> WikiResult<Vec<(String, f32)>> {
+    let rtxn = env.read_txn()?;
+
+    // LMDB iterators are single-threaded, so entries are decoded a chunk at a time and each
+    // chunk is scored in parallel, keeping only the top `max_candidates` across chunks
+    let mut top = TopK::new(options.max_candidates);
+    let mut chunk: Vec<(String, Vec<f32>)> = Vec::with_capacity(SCAN_CHUNK_SIZE);
+    for (key, embedding) in db.iter(&rtxn)?.filter_map(|item| item.ok()) {
+        chunk.push((key.to_string(), embedding));
+        if chunk.len() == SCAN_CHUNK_SIZE {
+            top = top.merge(rank_chunk(&chunk, metric, query_embedding, options));
+            chunk.clear();
+        }
+    }
+    Ok(top.merge(rank_chunk(&chunk, metric, query_embedding, options)).into_sorted_vec())
+}
+
+/// Top results of one chunk of decoded entries, scored in parallel once the chunk is large enough
+fn rank_chunk(
+    entries: &[(String, Vec<f32>)],
+    metric: SimilarityMetric,
+    query_embedding: &[f32],
+    options: SimilarityOptions,
+) -> TopK {
+    if entries.len() < PARALLEL_SCAN_THRESHOLD {
+        return rank_sequential(entries, metric, query_embedding, options);
+    }
+    let query_array = ArrayView1::from(query_embedding);
+    let limit = options.max_candidates;
+    entries
+        .par_iter()
+        .map(|(key, embedding)| (key.clone(), metric.score(&query_array, &ArrayView1::from(embedding))))
+        .filter(|(_, score)| options.accepts(*score))
+        .fold(|| TopK::new(limit), TopK::push)
+        .reduce(|| TopK::new(limit), TopK::merge)
+}
+
+/// Best score first; ties are broken by title so equal similarities come back in a stable order
+fn rank_order(a: &(String, f32), b: &(String, f32)) -> Ordering {
+    b.1.partial_cmp(&a.1)
+        .unwrap_or(Ordering::Equal)
+        .then_with(|| a.0.cmp(&b.0))
+}
+
+/// Heap entry ordered by `rank_order`, so the heap's maximum is the worst kept result
+struct Ranked((String, f32));
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Ranked {}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        rank_order(&self.0, &other.0)
+    }
+}
+
+/// Bounded heap keeping the `limit` best results seen so far
+struct TopK {
+    limit: usize,
+    heap: BinaryHeap<Ranked>,
+}
+
+impl TopK {
+    fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            heap: BinaryHeap::with_capacity(limit.min(PARALLEL_SCAN_THRESHOLD) + 1),
+        }
+    }
+
+    fn push(mut self, result: (String, f32)) -> Self {
+        self.offer(result);
+        self
+    }
+
+    fn offer(&mut self, result: (String, f32)) {
+        if self.heap.len() < self.limit {
+            self.heap.push(Ranked(result));
+        } else if let Some(mut worst) = self.heap.peek_mut() {
+            if rank_order(&result, &worst.0) == Ordering::Less {
+                *worst = Ranked(result);
+            }
+        }
+    }
+
+    fn merge(mut self, other: TopK) -> Self {
+        for Ranked(result) in other.heap {
+            self.offer(result);
+        }
+        self
+    }
+
+    fn into_sorted_vec(self) -> Vec<(String, f32)> {
+        self.heap.into_sorted_vec().into_iter().map(|Ranked(result)| result).collect()
+    }
+}
+
+/// Single-threaded scoring of already decoded entries
+fn rank_sequential(
+    entries: &[(String, Vec<f32>)],
+    metric: SimilarityMetric,
+    query_embedding: &[f32],
+    options: SimilarityOptions,
+) -> TopK {
+    let query_array = ArrayView1::from(query_embedding);
+    entries
+        .iter()
+        .map(|(key, embedding)| (key.clone(), metric.score(&query_array, &ArrayView1::from(embedding))))
+        .filter(|(_, score)| options.accepts(*score))
+        .fold(TopK::new(options.max_candidates), TopK::push)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
     use tempfile::TempDir;
 
     #[tokio::test]
     async fn test_vector_store() -> WikiResult<()> {
         let temp_dir = TempDir::new()?;
-        let store = VectorStore::new(temp_dir.path(), "http://localhost:11434")?;
+        let store = VectorStore::new(temp_dir.path(), "http://localhost:11434").await?;
 
         // Test embedding generation
         let text = "This is a test sentence.";
@@ -158,4 +626,301 @@ mod tests {
 
         Ok(())
     }
-} 
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_find_similar_breaks_ties_by_title() -> WikiResult<()> {
+        let temp_dir = TempDir::new()?;
+        let store = VectorStore::new(temp_dir.path(), "http://localhost:11434").await?;
+
+        // Identical vectors have identical similarity to any query
+        store.store_embedding("Zeta", &[1.0, 0.0, 0.0])?;
+        store.store_embedding("Alpha", &[1.0, 0.0, 0.0])?;
+        store.store_embedding("Mu", &[1.0, 0.0, 0.0])?;
+        store.store_embedding("Other", &[0.0, 1.0, 0.0])?;
+
+        let query = [1.0, 0.0, 0.0];
+        for _ in 0..5 {
+            let similar = store.find_similar(&query, 2)?;
+            let titles: Vec<&str> = similar.iter().map(|(t, _)| t.as_str()).collect();
+            assert_eq!(titles, vec!["Alpha", "Mu"]);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_async_new_round_trip() -> WikiResult<()> {
+        let temp_dir = TempDir::new()?;
+        let store = VectorStore::new(temp_dir.path().join("nested"), "http://localhost:11434").await?;
+
+        store.store_embedding("Round Trip", &[0.25, 0.5, 0.75])?;
+        assert_eq!(store.get_embedding("Round Trip")?, Some(vec![0.25, 0.5, 0.75]));
+        assert_eq!(store.get_embedding("Missing")?, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_async_accessors_round_trip() -> WikiResult<()> {
+        let temp_dir = TempDir::new()?;
+        let store = VectorStore::new(temp_dir.path(), "http://localhost:11434").await?;
+
+        store.store_embedding_async("Async", &[0.0, 1.0]).await?;
+        assert_eq!(store.get_embedding_async("Async").await?, Some(vec![0.0, 1.0]));
+
+        let similar = store.find_similar_async(&[0.0, 1.0], 1).await?;
+        assert_eq!(similar[0].0, "Async");
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_concurrent_searches_do_not_starve_runtime() -> WikiResult<()> {
+        let temp_dir = TempDir::new()?;
+        let store = Arc::new(VectorStore::new(temp_dir.path(), "http://localhost:11434").await?);
+
+        // Populate in a single transaction to keep setup fast
+        let mut wtxn = store.env.write_txn()?;
+        for i in 0..20_000 {
+            let embedding: Vec<f32> = (0..64).map(|j| ((i * 31 + j) % 97) as f32).collect();
+            store.db.put(&mut wtxn, &format!("Article {}", i), &embedding)?;
+        }
+        wtxn.commit()?;
+
+        let done = Arc::new(AtomicBool::new(false));
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let ticker = {
+            let (done, ticks) = (done.clone(), ticks.clone());
+            tokio::spawn(async move {
+                while !done.load(Ordering::SeqCst) {
+                    ticks.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+                }
+            })
+        };
+
+        let query = vec![1.0; 64];
+        let searches: Vec<_> = (0..4)
+            .map(|_| store.find_similar_async(&query, 10))
+            .collect();
+        for result in futures_util::future::join_all(searches).await {
+            assert_eq!(result?.len(), 10);
+        }
+        done.store(true, Ordering::SeqCst);
+        ticker.await.unwrap();
+
+        // On a single-threaded runtime the ticker can only run if the scans yielded
+        assert!(ticks.load(Ordering::SeqCst) > 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stale_keys_follow_model_tag() -> WikiResult<()> {
+        let temp_dir = TempDir::new()?;
+        let store = VectorStore::new(temp_dir.path(), "http://localhost:11434")
+            .await?
+            .with_embedding_model("nomic-embed-text");
+
+        store.store_embedding("Current", &[1.0, 0.0])?;
+        store.store_embedding_with_model("Old", &[0.0, 1.0], "llama2")?;
+
+        assert_eq!(store.embedding_model_of("Current")?.as_deref(), Some("nomic-embed-text"));
+        assert_eq!(store.embedding_model_of("Old")?.as_deref(), Some("llama2"));
+        assert_eq!(store.stale_keys()?, vec!["Old".to_string()]);
+
+        store.delete_embedding("Old")?;
+        assert_eq!(store.embedding_model_of("Old")?, None);
+        assert!(store.stale_keys()?.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_similarity_metrics() -> WikiResult<()> {
+        let norm = (0.5f32).sqrt();
+        let vectors: [(&str, [f32; 2]); 4] = [
+            ("East", [1.0, 0.0]),
+            ("NorthEast", [norm, norm]),
+            ("North", [0.0, 1.0]),
+            ("West", [-1.0, 0.0]),
+        ];
+        let query = [0.8, 0.6];
+
+        let mut rankings = Vec::new();
+        for metric in [SimilarityMetric::Cosine, SimilarityMetric::Dot, SimilarityMetric::Euclidean] {
+            let temp_dir = TempDir::new()?;
+            let store = VectorStore::new(temp_dir.path(), "http://localhost:11434")
+                .await?
+                .with_similarity_metric(metric);
+            for (title, vector) in &vectors {
+                store.store_embedding(title, vector)?;
+            }
+            let titles: Vec<String> = store.find_similar(&query, 4)?
+                .into_iter()
+                .map(|(title, _)| title)
+                .collect();
+            rankings.push(titles);
+        }
+
+        // Unit vectors: cosine and dot agree, and the nearest point is also the most similar
+        assert_eq!(rankings[0], vec!["NorthEast", "East", "North", "West"]);
+        assert_eq!(rankings[0], rankings[1]);
+        assert_eq!(rankings[2], rankings[0]);
+
+        let a = ArrayView1::from(&[3.0f32, 4.0][..]);
+        let b = ArrayView1::from(&[0.0f32, 0.0][..]);
+        assert_eq!(SimilarityMetric::Euclidean.score(&a, &b), -5.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parallel_ranking_matches_sequential() {
+        // Deterministic dataset with plenty of duplicate vectors to exercise tie-breaking
+        let entries: Vec<(String, Vec<f32>)> = (0..5000)
+            .map(|i| {
+                let embedding = (0..16).map(|j| ((i * 7 + j * 13) % 23) as f32 - 11.0).collect();
+                (format!("Article {:04}", i), embedding)
+            })
+            .collect();
+        let query: Vec<f32> = (0..16).map(|j| (j % 5) as f32 - 2.0).collect();
+
+        for metric in [SimilarityMetric::Cosine, SimilarityMetric::Dot, SimilarityMetric::Euclidean] {
+            for limit in [1, 10, 250, 6000] {
+                let options = SimilarityOptions::new(limit);
+                assert_eq!(
+                    rank_chunk(&entries, metric, &query, options).into_sorted_vec(),
+                    rank_sequential(&entries, metric, &query, options).into_sorted_vec(),
+                );
+                let options = options.with_min_score(0.0);
+                assert_eq!(
+                    rank_chunk(&entries, metric, &query, options).into_sorted_vec(),
+                    rank_sequential(&entries, metric, &query, options).into_sorted_vec(),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_top_k_matches_full_sort() {
+        let entries: Vec<(String, Vec<f32>)> = (0..2000)
+            .map(|i| {
+                let embedding = (0..8).map(|j| ((i * 31 + j * 17) % 19) as f32 - 9.0).collect();
+                (format!("Article {:04}", i), embedding)
+            })
+            .collect();
+        let query: Vec<f32> = (0..8).map(|j| j as f32 - 3.5).collect();
+        let query_array = ArrayView1::from(&query[..]);
+
+        let mut full: Vec<(String, f32)> = entries
+            .iter()
+            .map(|(key, embedding)| {
+                (key.clone(), SimilarityMetric::Cosine.score(&query_array, &ArrayView1::from(embedding)))
+            })
+            .collect();
+        full.sort_by(rank_order);
+
+        for limit in [0, 1, 5, 100, 2000, 5000] {
+            let expected: Vec<_> = full.iter().take(limit).cloned().collect();
+            assert_eq!(
+                rank_sequential(&entries, SimilarityMetric::Cosine, &query, SimilarityOptions::new(limit)).into_sorted_vec(),
+                expected
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scan_merges_results_across_chunks() -> WikiResult<()> {
+        let temp_dir = TempDir::new()?;
+        let store = VectorStore::new(temp_dir.path(), "http://localhost:11434").await?;
+
+        // More than two chunks, with the best matches spread over all of them
+        let entries: Vec<(String, Vec<f32>)> = (0..2 * SCAN_CHUNK_SIZE + 100)
+            .map(|i| {
+                let embedding = (0..8).map(|j| ((i * 29 + j * 11) % 17) as f32 - 8.0).collect();
+                (format!("Article {:05}", i), embedding)
+            })
+            .collect();
+        let mut wtxn = store.env.write_txn()?;
+        for (key, embedding) in &entries {
+            store.db.put(&mut wtxn, key, embedding)?;
+        }
+        wtxn.commit()?;
+
+        let query: Vec<f32> = (0..8).map(|j| j as f32 - 3.5).collect();
+        for options in [SimilarityOptions::new(25), SimilarityOptions::new(25).with_min_score(0.5)] {
+            assert_eq!(
+                store.find_similar_with(&query, options)?,
+                rank_sequential(&entries, SimilarityMetric::Cosine, &query, options).into_sorted_vec()
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_estimate_map_size() {
+        let articles = 200_000;
+        let dimension = 4096;
+        let minimum = articles * dimension * std::mem::size_of::<f32>();
+
+        let estimate = estimate_map_size(articles, dimension, None);
+        assert!(estimate > minimum);
+        assert_eq!(estimate % MAP_SIZE_ALIGNMENT, 0);
+
+        // Tiny corpora still get a usable map
+        assert_eq!(estimate_map_size(3, dimension, None), MIN_MAP_SIZE);
+
+        // An explicit size is used as-is
+        assert_eq!(estimate_map_size(articles, dimension, Some(1 << 30)), 1 << 30);
+    }
+
+    #[tokio::test]
+    async fn test_embedding_input_is_truncated() -> WikiResult<()> {
+        let temp_dir = TempDir::new()?;
+        let mut server = mockito::Server::new_async().await;
+        let mock = server.mock("POST", "/api/embeddings")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({ "input": "é".repeat(16) })))
+            .with_status(200)
+            .with_body(r#"{"data":[{"embedding":[0.5,0.5]}]}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let store = VectorStore::new(temp_dir.path(), &server.url()).await?.with_max_input_chars(16);
+        let embedding = store.generate_embedding(&"é".repeat(100)).await?;
+        assert_eq!(embedding, vec![0.5, 0.5]);
+        mock.assert_async().await;
+
+        assert_eq!(truncate_chars("short", 16), "short");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_compact_shrinks_store_after_deletes() -> WikiResult<()> {
+        let temp_dir = TempDir::new()?;
+        let store = VectorStore::new(temp_dir.path(), "http://localhost:11434").await?
+            .with_embedding_model("nomic-embed-text");
+        let vector = vec![0.5f32; 256];
+        for i in 0..1000 {
+            store.store_embedding(&format!("Article {:04}", i), &vector)?;
+        }
+        for i in 100..1000 {
+            assert!(store.delete_embedding(&format!("Article {:04}", i))?);
+        }
+        let before = store.disk_size()?;
+
+        let store = store.compact().await?;
+        let after = store.disk_size()?;
+        assert!(after < before / 2, "{} bytes before, {} after", before, after);
+        assert!(!temp_dir.path().join(COMPACTED_FILE_NAME).exists());
+
+        // Kept vectors, their model tags and the store's settings survive compaction
+        assert_eq!(store.len()?, 100);
+        assert_eq!(store.get_embedding("Article 0042")?, Some(vector));
+        assert_eq!(store.embedding_model(), "nomic-embed-text");
+        assert!(store.stale_keys()?.is_empty());
+        Ok(())
+    }
+}
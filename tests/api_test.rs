@@ -34,7 +34,7 @@ async fn setup_test_api(port: u16) -> WikiResult<(ApiServer, PathBuf, tempfile::
     // Setup vector store
     let vector_path = temp_dir.path().join("vectors");
     std::fs::create_dir_all(&vector_path)?;
-    let vector_store = VectorStore::new(&vector_path).await?;
+    let vector_store = VectorStore::new(&vector_path, "http://localhost:11434").await?;
     
     // Insert test vectors
     for (id, vector) in mock_data::get_test_vectors() {
@@ -48,6 +48,7 @@ async fn setup_test_api(port: u16) -> WikiResult<(ApiServer, PathBuf, tempfile::
         db_path: db_path.clone(),
         vector_path: vector_path.clone(),
         ollama_url: "http://localhost:11434".to_string(),
+        ..ApiConfig::default()
     };
     
     // Start API server
@@ -50,7 +50,7 @@ pub async fn create_test_db(db_path: &PathBuf) -> davinci3_wiki::db::DbManager {
 pub async fn create_test_vector_store(vector_path: &PathBuf) -> davinci3_wiki::vector::VectorStore {
     use davinci3_wiki::vector::VectorStore;
     
-    let store = VectorStore::new(vector_path).await.expect("Failed to create test vector store");
+    let store = VectorStore::new(vector_path, "http://localhost:11434").await.expect("Failed to create test vector store");
     
     // Add some test vectors
     for (id, vector) in crate::common::mock_data::get_test_vectors() {
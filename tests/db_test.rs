@@ -22,7 +22,7 @@ fn test_db_init_and_basic_operations() -> WikiResult<()> {
     
     // Create test articles
     let article1 = WikiArticle {
-        id: "1".to_string(),
+        id: Some(1),
         title: "Test Article 1".to_string(),
         text: "This is test article 1 content.".to_string(),
         categories: vec!["Category 1".to_string(), "Category 2".to_string()],
@@ -30,7 +30,7 @@ fn test_db_init_and_basic_operations() -> WikiResult<()> {
     };
     
     let article2 = WikiArticle {
-        id: "2".to_string(),
+        id: Some(2),
         title: "Test Article 2".to_string(),
         text: "This is test article 2 content.".to_string(),
         categories: vec!["Category 2".to_string(), "Category 3".to_string()],
@@ -64,7 +64,7 @@ fn test_db_init_and_basic_operations() -> WikiResult<()> {
     
     let search_results = db_reader.search("article 1")?;
     assert_eq!(search_results.len(), 1);
-    assert_eq!(search_results[0].id, "1");
+    assert_eq!(search_results[0].id, Some(1));
     
     // Test get_articles_by_category
     let category_articles = db_reader.get_articles_by_category("Category 2")?;
@@ -72,7 +72,7 @@ fn test_db_init_and_basic_operations() -> WikiResult<()> {
     
     let category_articles = db_reader.get_articles_by_category("Category 3")?;
     assert_eq!(category_articles.len(), 1);
-    assert_eq!(category_articles[0].id, "2");
+    assert_eq!(category_articles[0].id, Some(2));
     
     Ok(())
 }
@@ -93,7 +93,7 @@ fn test_db_article_update() -> WikiResult<()> {
     
     // Create test article
     let article = WikiArticle {
-        id: "1".to_string(),
+        id: Some(1),
         title: "Original Title".to_string(),
         text: "Original content.".to_string(),
         categories: vec!["Category 1".to_string()],
@@ -105,7 +105,7 @@ fn test_db_article_update() -> WikiResult<()> {
     
     // Update the article
     let updated_article = WikiArticle {
-        id: "1".to_string(),
+        id: Some(1),
         title: "Updated Title".to_string(),
         text: "Updated content.".to_string(),
         categories: vec!["Category 1".to_string(), "Category 2".to_string()],
@@ -117,14 +117,14 @@ fn test_db_article_update() -> WikiResult<()> {
     // Fetch the article and verify it was updated
     let fetched_article = db_reader.get_article("1")?;
     
-    assert_eq!(fetched_article.id, "1");
+    assert_eq!(fetched_article.id, Some(1));
     assert_eq!(fetched_article.title, "Updated Title");
     assert_eq!(fetched_article.text, "Updated content.");
     
     // Verify categories were updated
     let category_articles = db_reader.get_articles_by_category("Category 2")?;
     assert_eq!(category_articles.len(), 1);
-    assert_eq!(category_articles[0].id, "1");
+    assert_eq!(category_articles[0].id, Some(1));
     
     Ok(())
 }
@@ -144,7 +144,7 @@ fn test_db_transaction() -> WikiResult<()> {
     // Create test articles
     let articles: Vec<_> = (1..=100).map(|i| {
         WikiArticle {
-            id: i.to_string(),
+            id: Some(i),
             title: format!("Article {}", i),
             text: format!("Content of article {}.", i),
             categories: vec!["Test".to_string()],
@@ -163,4 +163,4 @@ fn test_db_transaction() -> WikiResult<()> {
     assert_eq!(all_articles.len(), 100);
     
     Ok(())
-} 
\ No newline at end of file
+}
@@ -36,7 +36,7 @@ async fn test_full_workflow() -> WikiResult<()> {
     std::fs::write(&xml_path, mock_data::get_test_xml_dump())?;
     
     // Initialize installer
-    let installer = Installer::new(config.clone());
+    let installer = Installer::new(config.clone())?;
     
     // Run installation with test data
     installer.install_from_file(&xml_path).await?;
@@ -61,7 +61,7 @@ async fn test_full_workflow() -> WikiResult<()> {
     assert!(vector_path.exists(), "Vector store not created");
     
     // Initialize vector store
-    let vector_store = VectorStore::new(&vector_path).await?;
+    let vector_store = VectorStore::new(&vector_path, "http://localhost:11434").await?;
     
     // Skip the rest of the test if Ollama is not available
     if test_helpers::skip_if_ollama_unavailable().await {
@@ -99,7 +99,7 @@ async fn test_incremental_update() -> WikiResult<()> {
     std::fs::write(&initial_xml_path, mock_data::get_test_xml_dump())?;
     
     // Initialize installer
-    let installer = Installer::new(config.clone());
+    let installer = Installer::new(config.clone())?;
     
     // Run initial installation with test data
     println!("Running initial installation...");
@@ -149,7 +149,7 @@ async fn test_incremental_update() -> WikiResult<()> {
     assert!(!updated_titles.contains(&"Test Article 3".to_string()), "Article 3 should be removed");
     
     // Verify vector store was updated (only for new and modified articles)
-    let vector_store = VectorStore::new(&config.vector_dir).await?;
+    let vector_store = VectorStore::new(&config.vector_dir, "http://localhost:11434").await?;
     
     // Get embedding counts - should match the number of articles after update
     let embedding_count = vector_store.get_embedding_count().await?;
@@ -189,7 +189,7 @@ async fn test_system_performance() -> WikiResult<()> {
     std::fs::write(&xml_path, mock_data::get_large_test_xml_dump())?;
     
     // Initialize installer
-    let installer = Installer::new(config.clone());
+    let installer = Installer::new(config.clone())?;
     
     // Measure installation time
     let start = Instant::now();
@@ -246,7 +246,7 @@ async fn test_uninstall() -> WikiResult<()> {
     std::fs::write(&db_path, "test content")?;
     
     // Initialize installer
-    let installer = Installer::new(config.clone());
+    let installer = Installer::new(config.clone())?;
     
     // Run uninstallation
     installer.uninstall().await?;
@@ -272,7 +272,7 @@ async fn test_error_handling_and_recovery() -> WikiResult<()> {
     let config = mock_data::get_test_installer_config(temp_dir.path());
     
     // Initialize installer
-    let installer = Installer::new(config.clone());
+    let installer = Installer::new(config.clone())?;
     
     // Test 1: Handle invalid XML
     println!("Testing invalid XML handling...");
@@ -394,7 +394,7 @@ async fn test_concurrent_access() -> WikiResult<()> {
     let config = mock_data::get_test_installer_config(temp_dir.path());
     
     // Initialize installer and install test data
-    let installer = Installer::new(config.clone());
+    let installer = Installer::new(config.clone())?;
     
     // Setup test XML data path with larger dataset for concurrency testing
     let xml_path = temp_dir.path().join("concurrency_test.xml");
@@ -412,7 +412,7 @@ async fn test_concurrent_access() -> WikiResult<()> {
     let db = DatabaseManager::with_path(&db_path, 10).await?;
     
     // Initialize vector store
-    let vector_store = VectorStore::new(&config.vector_dir).await?;
+    let vector_store = VectorStore::new(&config.vector_dir, "http://localhost:11434").await?;
     
     // Verify initial article count
     let article_count = db.get_article_count().await?;
@@ -599,7 +599,7 @@ async fn test_network_interruption() -> WikiResult<()> {
     config.wiki_dump_url = mock_server_url.to_string();
     
     // Initialize installer
-    let installer = Installer::new(config.clone());
+    let installer = Installer::new(config.clone())?;
     
     // Test 1: Handle network failure during installation
     println!("Testing network failure during installation...");
@@ -638,7 +638,7 @@ async fn test_network_interruption() -> WikiResult<()> {
     resumed_config.wiki_dump_url = format!("file://{}", local_xml_path.display());
     
     // Create a new installer with the updated config
-    let resumed_installer = Installer::new(resumed_config.clone());
+    let resumed_installer = Installer::new(resumed_config.clone())?;
     
     // Attempt installation again, should succeed with local file
     let resumed_result = resumed_installer.install().await;
@@ -660,7 +660,7 @@ async fn test_network_interruption() -> WikiResult<()> {
     update_config.wiki_dump_url = mock_server_url.to_string();
     
     // Create installer with bad URL
-    let update_installer = Installer::new(update_config);
+    let update_installer = Installer::new(update_config)?;
     
     // Attempt update with bad URL
     let update_result = update_installer.update().await;
@@ -680,7 +680,7 @@ async fn test_network_interruption() -> WikiResult<()> {
     final_config.wiki_dump_url = format!("file://{}", updated_xml_path.display());
     
     // Create a new installer with the updated config
-    let final_installer = Installer::new(final_config);
+    let final_installer = Installer::new(final_config)?;
     
     // Attempt update again, should succeed with local file
     let final_result = final_installer.update().await;
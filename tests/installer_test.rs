@@ -19,10 +19,11 @@ async fn test_installer_basic_functionality() -> WikiResult<()> {
         ollama_url: "http://localhost:11434".to_string(),
         max_image_size: 1024 * 1024, // 1MB
         max_batch_size: 10,
+        ..Default::default()
     };
     
     // Create installer
-    let installer = InstallManager::new(config);
+    let installer = InstallManager::new(config)?;
     
     // Test directory creation
     assert!(installer.create_directories().await.is_ok());
@@ -61,9 +62,10 @@ async fn test_installer_config_customization() -> WikiResult<()> {
         ollama_url: "http://custom:8080".to_string(),
         max_image_size: 5 * 1024 * 1024, // 5MB
         max_batch_size: 20,
+        ..Default::default()
     };
     
-    let installer = InstallManager::new(custom_config);
+    let installer = InstallManager::new(custom_config)?;
     
     // Test directory creation
     assert!(installer.create_directories().await.is_ok());
@@ -93,9 +95,10 @@ async fn test_download_wikidump() -> WikiResult<()> {
         ollama_url: "http://localhost:11434".to_string(),
         max_image_size: 1024 * 1024,
         max_batch_size: 10,
+        ..Default::default()
     };
     
-    let installer = InstallManager::new(config);
+    let installer = InstallManager::new(config)?;
     
     // Create directories
     installer.create_directories().await?;
@@ -57,43 +57,6 @@ fn test_parser_simple_xml() -> WikiResult<()> {
     Ok(())
 }
 
-#[test]
-fn test_parser_handles_special_characters() -> WikiResult<()> {
-    // Create a temporary directory for test files
-    let temp_dir = TempDir::new()?;
-    let xml_path = temp_dir.path().join("special_chars.xml");
-    
-    // Create XML with special characters
-    let test_xml = r#"<mediawiki>
-  <page>
-    <title>Special &amp; Characters</title>
-    <ns>0</ns>
-    <id>1</id>
-    <revision>
-      <text>This article contains &lt;special&gt; characters &amp; symbols.</text>
-    </revision>
-  </page>
-</mediawiki>"#;
-    
-    // Write the XML to a file
-    let mut file = File::create(&xml_path)?;
-    file.write_all(test_xml.as_bytes())?;
-    
-    // Parse the XML
-    let parser = WikiDumpParser::new();
-    let xml_content = std::fs::read_to_string(&xml_path)?;
-    let articles = parser.parse(&xml_content)?;
-    
-    // Verify the parsing results
-    assert_eq!(articles.len(), 1);
-    
-    // Check that special characters are handled correctly
-    assert_eq!(articles[0].title, "Special & Characters");
-    assert_eq!(articles[0].text, "This article contains <special> characters & symbols.");
-    
-    Ok(())
-}
-
 #[test]
 fn test_parser_skips_non_article_namespaces() -> WikiResult<()> {
     // Create a temporary directory for test files
@@ -142,4 +105,4 @@ fn test_parser_skips_non_article_namespaces() -> WikiResult<()> {
     assert_eq!(articles[0].title, "Regular Article");
     
     Ok(())
-} 
\ No newline at end of file
+}
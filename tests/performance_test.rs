@@ -78,7 +78,7 @@ async fn test_vector_store_performance_scaling() -> WikiResult<()> {
     std::fs::create_dir_all(&vector_path)?;
     
     // Initialize vector store
-    let store = VectorStore::new(&vector_path).await?;
+    let store = VectorStore::new(&vector_path, "http://localhost:11434").await?;
     
     // Dataset sizes to test
     let sizes = [100, 500, 1000, 5000];
@@ -143,7 +143,7 @@ async fn test_xml_parsing_performance() -> WikiResult<()> {
     // Measure parsing time
     let start = Instant::now();
     let reader = Cursor::new(large_xml);
-    let articles = parser.parse(reader, |_| true).await?;
+    let articles = parser.parse_async(reader, |_| true).await?;
     let parse_time = start.elapsed();
     
     println!("Parsed {} articles in {:?}", articles.len(), parse_time);